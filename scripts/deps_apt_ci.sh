@@ -35,4 +35,5 @@ sudo apt-get -y install \
   clang \
   libopencv-dev \
   wait-for-it \
-  docker-compose
+  docker-compose \
+  fonts-dejavu-core
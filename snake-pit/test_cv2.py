@@ -2841,3 +2841,512 @@ def test_vconcat_video_vf():
 
 
 # =============================================================================
+# Blur tests
+# =============================================================================
+
+
+def _blur_canvas(cv2, width=100, height=80):
+    """A canvas with sharp edges to blur"""
+    if cv2 is ocv_cv2:
+        canvas = np.zeros((height, width, 3), dtype=np.uint8)
+    else:
+        canvas = cv2.zeros((height, width, 3))
+    cv2.rectangle(canvas, (20, 20), (60, 50), (255, 128, 0), -1)
+    cv2.circle(canvas, (75, 40), 10, (0, 255, 255), -1)
+    return canvas
+
+
+@pytest.mark.parametrize(
+    "ksize,sigmaX,sigmaY",
+    [((5, 5), 0, None), ((9, 3), 2.0, None), ((0, 0), 3.0, 1.5)],
+)
+def test_GaussianBlur(ksize, sigmaX, sigmaY):
+    kwargs = {} if sigmaY is None else {"sigmaY": sigmaY}
+    blurred_ocv = ocv_cv2.GaussianBlur(_blur_canvas(ocv_cv2), ksize, sigmaX, **kwargs)
+    blurred_vf = vf_cv2.GaussianBlur(
+        _blur_canvas(vf_cv2), ksize, sigmaX, **kwargs
+    ).numpy()
+
+    assert blurred_vf.shape == blurred_ocv.shape
+    assert np.allclose(blurred_ocv, blurred_vf, atol=1)
+
+
+@pytest.mark.parametrize("ksize", [3, 5, 7])
+def test_medianBlur(ksize):
+    blurred_ocv = ocv_cv2.medianBlur(_blur_canvas(ocv_cv2), ksize)
+    blurred_vf = vf_cv2.medianBlur(_blur_canvas(vf_cv2), ksize).numpy()
+
+    assert np.allclose(blurred_ocv, blurred_vf, atol=1)
+
+
+@pytest.mark.parametrize(
+    "ksize,anchor,borderType",
+    [
+        ((5, 5), None, None),
+        ((7, 3), (0, 0), None),
+        ((3, 3), None, ocv_cv2.BORDER_REPLICATE),
+    ],
+)
+def test_blur(ksize, anchor, borderType):
+    kwargs = {}
+    if anchor is not None:
+        kwargs["anchor"] = anchor
+    if borderType is not None:
+        kwargs["borderType"] = borderType
+    blurred_ocv = ocv_cv2.blur(_blur_canvas(ocv_cv2), ksize, **kwargs)
+    blurred_vf = vf_cv2.blur(_blur_canvas(vf_cv2), ksize, **kwargs).numpy()
+
+    assert np.allclose(blurred_ocv, blurred_vf, atol=1)
+
+
+def test_bilateralFilter():
+    blurred_ocv = ocv_cv2.bilateralFilter(_blur_canvas(ocv_cv2), 9, 75, 75)
+    blurred_vf = vf_cv2.bilateralFilter(_blur_canvas(vf_cv2), 9, 75, 75).numpy()
+
+    assert np.allclose(blurred_ocv, blurred_vf, atol=1)
+
+
+def test_blurRegion_rect():
+    x, y, w, h = 15, 15, 30, 20
+    canvas = _blur_canvas(ocv_cv2)
+    blurred = ocv_cv2.GaussianBlur(canvas, (11, 11), 0)
+    expected = canvas.copy()
+    expected[y : y + h, x : x + w] = blurred[y : y + h, x : x + w]
+
+    actual = vf_cv2.blurRegion(_blur_canvas(vf_cv2), 11, rect=(x, y, w, h)).numpy()
+
+    # Only the region changes, and inside it matches blurring the whole frame
+    assert np.array_equal(actual[:y], canvas[:y])
+    assert np.array_equal(actual[:, x + w :], canvas[:, x + w :])
+    assert np.allclose(actual, expected, atol=1)
+
+
+def test_blurRegion_pts():
+    pts = [[[50, 10], [90, 10], [90, 70]]]
+    canvas = _blur_canvas(ocv_cv2)
+    mask = np.zeros(canvas.shape[:2], dtype=np.uint8)
+    ocv_cv2.fillPoly(mask, [np.array(pts[0], dtype=np.int32)], 255)
+    blurred = ocv_cv2.medianBlur(canvas, 5)
+    expected = np.where(mask[..., None] == 255, blurred, canvas)
+
+    actual = vf_cv2.blurRegion(_blur_canvas(vf_cv2), 5, pts=pts, method="median")
+
+    assert np.allclose(actual.numpy(), expected, atol=1)
+
+
+# =============================================================================
+# Warp tests
+# =============================================================================
+
+
+@pytest.mark.parametrize(
+    "flags,borderMode",
+    [
+        (None, None),
+        (ocv_cv2.INTER_NEAREST, ocv_cv2.BORDER_REPLICATE),
+        (ocv_cv2.INTER_CUBIC | ocv_cv2.WARP_INVERSE_MAP, ocv_cv2.BORDER_REFLECT),
+    ],
+)
+def test_warpAffine(flags, borderMode):
+    M = ocv_cv2.getRotationMatrix2D((50.0, 40.0), 30, 0.8)
+    assert np.allclose(M, vf_cv2.getRotationMatrix2D((50.0, 40.0), 30, 0.8))
+
+    kwargs = {}
+    if flags is not None:
+        kwargs["flags"] = flags
+    if borderMode is not None:
+        kwargs["borderMode"] = borderMode
+    warped_ocv = ocv_cv2.warpAffine(_blur_canvas(ocv_cv2), M, (120, 90), **kwargs)
+    warped_vf = vf_cv2.warpAffine(_blur_canvas(vf_cv2), M, (120, 90), **kwargs)
+
+    assert warped_vf._fmt["width"] == 120 and warped_vf._fmt["height"] == 90
+    assert np.allclose(warped_ocv, warped_vf.numpy(), atol=1)
+
+
+def test_warpAffine_border_value():
+    M = np.float64([[1, 0, 15], [0, 1, -10]])
+    warped_ocv = ocv_cv2.warpAffine(
+        _blur_canvas(ocv_cv2), M, (100, 80), borderValue=(0, 0, 255)
+    )
+    warped_vf = vf_cv2.warpAffine(
+        _blur_canvas(vf_cv2), M, (100, 80), borderValue=(0, 0, 255)
+    )
+
+    assert np.array_equal(warped_ocv, warped_vf.numpy())
+
+
+def test_warpPerspective():
+    src = np.float32([[0, 0], [99, 0], [99, 79], [0, 79]])
+    dst = np.float32([[10, 5], [90, 15], [95, 75], [5, 70]])
+    M = ocv_cv2.getPerspectiveTransform(src, dst)
+
+    warped_ocv = ocv_cv2.warpPerspective(_blur_canvas(ocv_cv2), M, (100, 80))
+    warped_vf = vf_cv2.warpPerspective(_blur_canvas(vf_cv2), M, (100, 80))
+
+    assert np.allclose(warped_ocv, warped_vf.numpy(), atol=1)
+
+
+@pytest.mark.parametrize(
+    "dsize,fx,fy,interpolation",
+    [
+        ((50, 40), None, None, ocv_cv2.INTER_AREA),
+        ((0, 0), 1.5, 0.75, ocv_cv2.INTER_NEAREST),
+        ((160, 120), None, None, ocv_cv2.INTER_CUBIC),
+    ],
+)
+def test_resize_interpolation(dsize, fx, fy, interpolation):
+    kwargs = {"interpolation": interpolation}
+    if fx is not None:
+        kwargs["fx"] = fx
+        kwargs["fy"] = fy
+    resized_ocv = ocv_cv2.resize(_blur_canvas(ocv_cv2), dsize, **kwargs)
+    resized_vf = vf_cv2.resize(_blur_canvas(vf_cv2), dsize, **kwargs).numpy()
+
+    assert resized_ocv.shape == resized_vf.shape
+    assert np.allclose(resized_ocv, resized_vf, atol=1)
+
+
+# =============================================================================
+# Overlay tests
+# =============================================================================
+
+
+def _overlay_reference(img, ovl, x, y, alpha, blend="normal"):
+    """Composite a BGR overlay with per-pixel alpha in [0, 1] using numpy"""
+    out = img.astype(np.float32) / 255.0
+    h, w = ovl.shape[:2]
+    x0, y0 = max(x, 0), max(y, 0)
+    x1, y1 = min(x + w, img.shape[1]), min(y + h, img.shape[0])
+    bottom = out[y0:y1, x0:x1]
+    top = ovl[y0 - y : y1 - y, x0 - x : x1 - x].astype(np.float32) / 255.0
+    a = alpha[y0 - y : y1 - y, x0 - x : x1 - x, None]
+    if blend == "normal":
+        blended = top
+    elif blend == "multiply":
+        blended = bottom * top
+    elif blend == "screen":
+        blended = 1 - (1 - bottom) * (1 - top)
+    out[y0:y1, x0:x1] = bottom + (blended - bottom) * a
+    return np.round(out * 255).astype(np.uint8)
+
+
+def _overlay_logo(width=40, height=30):
+    """A BGRA logo with an alpha gradient"""
+    logo = np.zeros((height, width, 4), dtype=np.uint8)
+    logo[:, :, 0] = 200
+    logo[:, :, 1] = np.linspace(0, 255, width, dtype=np.uint8)[None, :]
+    logo[:, :, 2] = 50
+    logo[:, :, 3] = np.linspace(0, 255, height, dtype=np.uint8)[:, None]
+    return logo
+
+
+@pytest.mark.parametrize("x,y", [(10, 20), (-15, -10), (80, 60)])
+def test_overlay_bgra(x, y):
+    logo = _overlay_logo()
+    expected = _overlay_reference(
+        _blur_canvas(ocv_cv2), logo[:, :, :3], x, y, logo[:, :, 3] / 255.0
+    )
+
+    actual = vf_cv2.overlay(_blur_canvas(vf_cv2), logo, x, y).numpy()
+
+    assert actual.shape == expected.shape
+    assert np.allclose(actual, expected, atol=1)
+
+
+@pytest.mark.parametrize("blend", ["normal", "multiply", "screen"])
+def test_overlay_mask_opacity_blend(blend):
+    ovl = np.full((30, 50, 3), (30, 160, 240), dtype=np.uint8)
+    mask = np.zeros((30, 50, 1), dtype=np.uint8)
+    ocv_cv2.circle(mask, (25, 15), 12, 255, -1)
+    expected = _overlay_reference(
+        _blur_canvas(ocv_cv2), ovl, 30, 25, mask[:, :, 0] / 255.0 * 0.5, blend
+    )
+
+    actual = vf_cv2.overlay(
+        _blur_canvas(vf_cv2), ovl, 30, 25, mask=mask, opacity=0.5, blend=blend
+    ).numpy()
+
+    assert np.allclose(actual, expected, atol=1)
+
+
+def test_overlay_size():
+    ovl = _blur_canvas(ocv_cv2, width=60, height=40)
+    scaled = ocv_cv2.resize(ovl, (30, 20), interpolation=ocv_cv2.INTER_LINEAR)
+    expected = _overlay_reference(
+        _blur_canvas(ocv_cv2), scaled, 5, 5, np.ones((20, 30))
+    )
+
+    actual = vf_cv2.overlay(_blur_canvas(vf_cv2), ovl, 5, 5, size=(30, None))
+
+    assert np.allclose(actual.numpy(), expected, atol=1)
+
+
+# =============================================================================
+# Text tests
+# =============================================================================
+
+# Relative to the igni server's [fonts] service
+TEXT_FONT = "truetype/dejavu/DejaVuSans.ttf"
+
+
+def _text_extent(img):
+    """Bounding box (x0, y0, x1, y1) of the non-black pixels of an image"""
+    ys, xs = np.nonzero(img.any(axis=2))
+    return xs.min(), ys.min(), xs.max() + 1, ys.max() + 1
+
+
+def test_drawText():
+    canvas = vf_cv2.zeros((120, 300, 3))
+    vf_cv2.drawText(
+        canvas, "Hello مرحبا", TEXT_FONT, 24, org=(10, 20), color=(0, 255, 255)
+    )
+    out = canvas.numpy()
+
+    x0, y0, x1, y1 = _text_extent(out)
+    assert x0 >= 10 and y0 >= 20
+    assert y1 - y0 < 40
+    # Only the text color is drawn, antialiased
+    assert (out[:, :, 0] == 0).all()
+    assert np.array_equal(out[:, :, 1], out[:, :, 2])
+    assert (out[:, :, 1] == 255).any()
+
+
+def test_drawText_wrap_align():
+    text = "the quick brown fox jumps over the lazy dog"
+    wide = vf_cv2.zeros((200, 400, 3))
+    vf_cv2.drawText(wide, text, TEXT_FONT, 20, org=(0, 0))
+    narrow = vf_cv2.zeros((200, 400, 3))
+    vf_cv2.drawText(
+        narrow, text, TEXT_FONT, 20, rect=(100, 0, 200, 200), align="right"
+    )
+
+    wx0, wy0, wx1, wy1 = _text_extent(wide.numpy())
+    nx0, ny0, nx1, ny1 = _text_extent(narrow.numpy())
+    assert wy1 - wy0 < 30
+    assert ny1 - ny0 > 2 * (wy1 - wy0)
+    assert nx0 >= 100 and nx1 <= 300 and nx1 > 290
+
+
+def test_drawText_outline_shadow_background():
+    canvas = vf_cv2.zeros((100, 300, 3))
+    vf_cv2.drawText(
+        canvas,
+        "Subtitle",
+        TEXT_FONT,
+        30,
+        rect=(0, 0, 300, 100),
+        align="center",
+        valign="middle",
+        outline=2,
+        outlineColor=(0, 0, 255),
+        shadow=(3, 3),
+        background=(255, 0, 0),
+        padding=6,
+    )
+    out = canvas.numpy()
+
+    # The background box is blue, around the text and inside the rectangle
+    x0, y0, x1, y1 = _text_extent(out)
+    assert 0 < x0 and x1 < 300 and 0 < y0 and y1 < 100
+    assert (out[y0, x0] == [255, 0, 0]).all()
+    # The outline is red, the text white, and the shadow darkens the background
+    assert ((out[:, :, 2] == 255) & (out[:, :, 1] == 0)).any()
+    assert (out == 255).all(axis=2).any()
+    assert ((out[:, :, 0] > 0) & (out[:, :, 0] < 200) & (out[:, :, 2] < 50)).any()
+
+
+# =============================================================================
+# Color grading tests
+# =============================================================================
+
+
+def _color_canvas(width=80, height=60):
+    """A smooth, low-contrast BGR gradient"""
+    ys, xs = np.mgrid[0:height, 0:width]
+    canvas = np.stack([60 + xs, 80 + ys, 100 + (xs + ys) // 2], axis=2)
+    return canvas.astype(np.uint8)
+
+
+def _color_adjust_reference(img, brightness, contrast, gamma, saturation):
+    x = (img.astype(np.float64) / 255.0 - 0.5) * contrast + 0.5 + brightness
+    x = np.round(np.clip(x, 0.0, 1.0) ** (1.0 / gamma) * 255.0)
+    luma = 0.0722 * x[:, :, 0] + 0.7152 * x[:, :, 1] + 0.2126 * x[:, :, 2]
+    out = luma[:, :, None] + (x - luma[:, :, None]) * saturation
+    return np.clip(np.round(out), 0, 255).astype(np.uint8)
+
+
+@pytest.mark.parametrize(
+    "brightness,contrast,gamma,saturation",
+    [
+        (0.1, 1.0, 1.0, 1.0),
+        (0.0, 1.5, 1.0, 1.0),
+        (0.0, 1.0, 2.2, 1.0),
+        (0.0, 1.0, 1.0, 0.0),
+        (-0.05, 1.2, 0.8, 1.6),
+    ],
+)
+def test_colorAdjust(brightness, contrast, gamma, saturation):
+    img = _color_canvas()
+    expected = _color_adjust_reference(img, brightness, contrast, gamma, saturation)
+
+    actual = vf_cv2.colorAdjust(
+        img,
+        brightness=brightness,
+        contrast=contrast,
+        gamma=gamma,
+        saturation=saturation,
+    ).numpy()
+
+    assert np.allclose(actual, expected, atol=1)
+
+
+def test_equalizeHist():
+    gray = ocv_cv2.cvtColor(_color_canvas(), ocv_cv2.COLOR_BGR2GRAY)
+    expected = ocv_cv2.equalizeHist(gray)
+
+    actual = vf_cv2.equalizeHist(gray).numpy()
+
+    assert np.array_equal(actual.reshape(expected.shape), expected)
+
+
+def test_createCLAHE():
+    gray = ocv_cv2.cvtColor(_color_canvas(), ocv_cv2.COLOR_BGR2GRAY)
+    expected = ocv_cv2.createCLAHE(2.0, (4, 4)).apply(gray)
+
+    clahe = vf_cv2.createCLAHE(clipLimit=2.0, tileGridSize=(4, 4))
+    assert clahe.getClipLimit() == 2.0
+    actual = clahe.apply(gray).numpy()
+
+    assert np.array_equal(actual.reshape(expected.shape), expected)
+
+
+def test_equalizeHist_color():
+    img = _color_canvas()
+    ycrcb = ocv_cv2.cvtColor(img, ocv_cv2.COLOR_BGR2YCrCb)
+    ycrcb[:, :, 0] = ocv_cv2.equalizeHist(ycrcb[:, :, 0])
+    expected = ocv_cv2.cvtColor(ycrcb, ocv_cv2.COLOR_YCrCb2BGR)
+
+    actual = vf_cv2.equalizeHist(img).numpy()
+
+    assert np.allclose(actual, expected, atol=3)
+
+
+def _green_screen(width=80, height=60):
+    """A red subject on a green backdrop, and a blue background, in BGR"""
+    fg = np.zeros((height, width, 3), dtype=np.uint8)
+    fg[:, :] = (0, 255, 0)
+    fg[15:45, 20:60] = (0, 0, 255)
+    bg = np.zeros((height, width, 3), dtype=np.uint8)
+    bg[:, :] = (255, 0, 0)
+    return fg, bg
+
+
+def test_chromaKey():
+    fg, bg = _green_screen()
+    expected = bg.copy()
+    expected[15:45, 20:60] = fg[15:45, 20:60]
+
+    actual = vf_cv2.chromaKey(fg, bg).numpy()
+
+    assert np.array_equal(actual, expected)
+
+
+def test_chromaKey_mask_overlay():
+    fg, bg = _green_screen()
+    expected_mask = np.zeros(fg.shape[:2], dtype=np.uint8)
+    expected_mask[15:45, 20:60] = 255
+
+    mask = vf_cv2.chromaKey(fg, output="mask")
+    assert mask.shape == (60, 80, 1)
+    assert np.array_equal(mask.numpy().reshape(expected_mask.shape), expected_mask)
+
+    # Both the mask and the rgba output composite the subject with overlay()
+    keyed = vf_cv2.chromaKey(fg, output="rgba")
+    with_mask = vf_cv2.overlay(bg, fg, mask=mask).numpy()
+    with_alpha = vf_cv2.overlay(bg, keyed).numpy()
+    expected = vf_cv2.chromaKey(fg, bg).numpy()
+    assert np.array_equal(with_mask, expected)
+    assert np.array_equal(with_alpha, expected)
+
+
+def test_chromaKey_spill():
+    fg, bg = _green_screen()
+    # A green-tinged, opaque edge around the subject, just outside the key's similarity
+    fg[14, 20:60] = (110, 190, 150)
+
+    unsuppressed = vf_cv2.chromaKey(fg, bg, smoothness=0.05, spill=0.0).numpy()
+    suppressed = vf_cv2.chromaKey(fg, bg, smoothness=0.05, spill=1.0).numpy()
+
+    assert (suppressed[14, 20:60, 1] < unsuppressed[14, 20:60, 1]).all()
+    assert np.array_equal(suppressed[20:40], unsuppressed[20:40])
+
+
+# =============================================================================
+# Transition tests
+# =============================================================================
+
+
+@pytest.mark.parametrize(
+    "kind,direction",
+    [
+        ("crossfade", "left"),
+        ("wipe", "right"),
+        ("slide", "up"),
+        ("push", "left"),
+        ("iris", "left"),
+        ("dip", "left"),
+    ],
+)
+def test_transition_endpoints(kind, direction):
+    a = _color_canvas()
+    b = 255 - a
+
+    start = vf_cv2.transition(a, b, 0.0, kind, direction=direction, softness=0.1)
+    end = vf_cv2.transition(a, b, 1.0, kind, direction=direction, softness=0.1)
+
+    assert np.array_equal(start.numpy(), a)
+    assert np.array_equal(end.numpy(), b)
+
+
+def test_transition_crossfade():
+    a = _color_canvas()
+    b = 255 - a
+    expected = ocv_cv2.addWeighted(a, 0.7, b, 0.3, 0)
+
+    actual = vf_cv2.transition(a, b, 0.3).numpy()
+
+    assert np.allclose(actual, expected, atol=1)
+
+
+def test_transition_wipe_and_slide():
+    a = _color_canvas()
+    b = 255 - a
+
+    # The edge moves left, revealing b from the right
+    wipe = vf_cv2.transition(a, b, 0.25, "wipe", direction="left").numpy()
+    assert np.array_equal(wipe[:, :60], a[:, :60])
+    assert np.array_equal(wipe[:, 60:], b[:, 60:])
+
+    # b enters from the right over a
+    slide = vf_cv2.transition(a, b, 0.25, "slide", direction="left").numpy()
+    assert np.array_equal(slide[:, :60], a[:, :60])
+    assert np.array_equal(slide[:, 60:], b[:, :20])
+
+    # a moves out to the left along with b
+    push = vf_cv2.transition(a, b, 0.25, "push", direction="left").numpy()
+    assert np.array_equal(push[:, :60], a[:, 20:])
+    assert np.array_equal(push[:, 60:], b[:, :20])
+
+
+def test_transition_iris_and_dip():
+    a = _color_canvas()
+    b = 255 - a
+
+    iris = vf_cv2.transition(a, b, 0.5, "iris").numpy()
+    assert np.array_equal(iris[30, 40], b[30, 40])
+    assert np.array_equal(iris[0, 0], a[0, 0])
+
+    black = vf_cv2.transition(a, b, 0.5, "dip").numpy()
+    white = vf_cv2.transition(a, b, 0.5, "dip", color=(255, 255, 255)).numpy()
+    assert (black == 0).all()
+    assert (white == 255).all()
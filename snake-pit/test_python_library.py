@@ -1,4 +1,5 @@
 from fractions import Fraction
+import os
 import pytest
 
 import vidformer as vf
@@ -87,6 +88,51 @@ def test_create_spec(ttl):
     assert isinstance(spec_id, vf.Spec)
 
 
+def test_spec_subtitles():
+    server = vf.Server(ENDPOINT, API_KEY)
+    tos = server.create_source("../tos_720p.mp4", 0, "fs", {"root": "."})
+    with open("/tmp/vf_test_subtitles.srt", "w") as f:
+        f.write("1\n00:00:00,000 --> 00:00:01,000\nHello\n")
+    subtitles = {
+        "en": {
+            "path": "vf_test_subtitles.srt",
+            "storage_service": "fs",
+            "storage_config": {"root": "/tmp"},
+        }
+    }
+    spec = server.create_spec(
+        1280, 720, "rgb24", Fraction(2, 1), Fraction(30, 1), subtitles=subtitles
+    )
+
+    scale = vf.Filter("Scale")
+    subtitle = vf.Filter("Subtitle")
+    frames = []
+    for i in range(30):
+        t = Fraction(i, 30)
+        f = scale(tos.iloc[i], pix_fmt="rgb24")
+        f = subtitle(
+            f,
+            "en",
+            [t.numerator, t.denominator],
+            font="truetype/dejavu/DejaVuSans.ttf",
+        )
+        frames.append((t, f))
+    server.push_spec_part(spec, 0, frames, True)
+
+    path = "/tmp/vf_test_subtitles.raw"
+    server.export_spec(spec.id(), path, encoder="rawvideo", format="rawvideo")
+    assert os.path.getsize(path) == 30 * 1280 * 720 * 3
+    os.remove(path)
+
+    # Missing subtitle files are rejected when the spec is created
+    subtitles["en"]["path"] = "vf_test_missing.srt"
+    with pytest.raises(Exception):
+        server.create_spec(
+            1280, 720, "rgb24", Fraction(2, 1), Fraction(30, 1), subtitles=subtitles
+        )
+    os.remove("/tmp/vf_test_subtitles.srt")
+
+
 def test_list_specs():
     server = vf.Server(ENDPOINT, API_KEY)
     spec = server.create_spec(1920, 1080, "yuv420p", Fraction(2, 1), Fraction(30, 1))
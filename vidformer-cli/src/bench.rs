@@ -8,6 +8,9 @@ use vidformer::run;
 struct DveBench {
     spec: String,
     sources: Vec<(String, String, usize)>,
+    /// Subtitle tracks as (name, path) pairs, drawn with fonts from the working directory
+    #[serde(default)]
+    subtitles: Vec<(String, String)>,
     config: vidformer::Config,
 }
 
@@ -45,8 +48,33 @@ impl DveBench {
             })
             .collect::<Vec<_>>();
 
+        let subtitles = self
+            .subtitles
+            .iter()
+            .map(|(name, path)| {
+                Arc::new(
+                    vidformer::subtitle::SubtitleTrack::load(
+                        name,
+                        path,
+                        &vidformer::service::Service::default(),
+                    )
+                    .unwrap(),
+                )
+            })
+            .collect::<Vec<_>>();
+
         let filters = default_filters();
         let context = vidformer::Context::new(sources, filters, None);
+        let context = if subtitles.is_empty() {
+            context
+        } else {
+            context.with_subtitles(
+                subtitles,
+                Arc::new(vidformer::filter::text::FontStore::new(
+                    vidformer::service::Service::default(),
+                )),
+            )
+        };
 
         (Arc::new(spec), Arc::new(context), Arc::new(self.config))
     }
@@ -89,3 +117,9 @@ pub(crate) fn cmd_benchmark(opt: &BenchmarkCmd) {
 
     println!("Done!");
 }
+
+pub(crate) fn cmd_explain(opt: &ExplainCmd) {
+    let (spec, context, config) = DveBench::from_json_file(&opt.bench).split();
+    let plan = vidformer::explain(&spec, &context, &config, &None).unwrap();
+    println!("{}", serde_json::to_string_pretty(&plan).unwrap());
+}
@@ -79,13 +79,23 @@ struct BenchmarkCmd {
     out_path: String,
 }
 
+#[derive(Parser, Debug)]
+struct ExplainCmd {
+    /// A benchmark file, describing the spec, sources, and config to plan
+    #[clap(long)]
+    bench: String,
+}
+
 #[derive(Subcommand, Debug)]
 enum ArgCmd {
     Profile(ProfileCmd),
     X,
     Benchmark(BenchmarkCmd),
     Validate(ValidateCmd),
+    Explain(ExplainCmd),
     Codecs,
+    /// Print the signatures of the built-in filters as JSON
+    Filters,
 }
 
 fn cmd_profile(opt: &ProfileCmd) {
@@ -153,9 +163,11 @@ fn cmd_x() {
 
     let dve_config = vidformer::Config {
         decode_pool_size: 10000,
+        decode_pool_bytes: None,
         decoder_view: usize::MAX,
         decoders: 2,
         filterers: 2,
+        filter_cache_bytes: 256 * 1024 * 1024,
 
         output_width: 1280,
         output_height: 720,
@@ -188,6 +200,14 @@ fn default_filters() -> BTreeMap<String, Box<dyn filter::Filter>> {
     let mut filters: BTreeMap<String, Box<dyn filter::Filter>> = BTreeMap::new();
     filters.extend(vidformer::filter::builtin::filters());
     filters.extend(vidformer::filter::cv2::filters());
+    filters.extend(vidformer::filter::color::filters());
+    filters.extend(vidformer::filter::transition::filters());
+    filters.extend(vidformer::filter::color::lut_filters(std::sync::Arc::new(
+        vidformer::filter::color::LutStore::new(vidformer::service::Service::default()),
+    )));
+    filters.extend(vidformer::filter::text::filters(std::sync::Arc::new(
+        vidformer::filter::text::FontStore::new(vidformer::service::Service::default()),
+    )));
     filters
 }
 
@@ -227,6 +247,11 @@ fn cmd_codecs() {
     }
 }
 
+fn cmd_filters() {
+    let signatures = filter::signatures(&default_filters());
+    println!("{}", serde_json::to_string_pretty(&signatures).unwrap());
+}
+
 fn main() {
     pretty_env_logger::init();
     vidformer::init();
@@ -238,6 +263,8 @@ fn main() {
         ArgCmd::Validate(opt) => cmd_validate(&opt),
         ArgCmd::X => cmd_x(),
         ArgCmd::Benchmark(opt) => bench::cmd_benchmark(&opt),
+        ArgCmd::Explain(opt) => bench::cmd_explain(&opt),
         ArgCmd::Codecs => cmd_codecs(),
+        ArgCmd::Filters => cmd_filters(),
     }
 }
@@ -52,7 +52,8 @@ CREATE TABLE spec (
     ready_hook TEXT,
     steer_hook TEXT,
     created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
-    expires_at TIMESTAMPTZ
+    expires_at TIMESTAMPTZ,
+    subtitles JSONB
 );
 
 -- spec_t table
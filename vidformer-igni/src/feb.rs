@@ -309,6 +309,9 @@ impl FrameBlock {
                             source_frac_idx: self.source_fracs.len() as u32 - 2,
                         }
                     }
+                    vidformer::sir::IndexConst::Timecode(_) => {
+                        return Err("Timecode frame references are not supported".to_string());
+                    }
                 };
                 self.exprs.push(source_expr.to_int());
                 Ok(self.exprs.len() - 1)
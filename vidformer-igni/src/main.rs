@@ -515,6 +515,7 @@ async fn cmd_spec_add(pool: sqlx::Pool<sqlx::Postgres>, opt: SpecAddOpt) -> Resu
         ready_hook,
         steer_hook,
         None,
+        None,
     )
     .await?;
 
@@ -102,6 +102,40 @@ pub(crate) async fn profile_source(
     Ok(profile)
 }
 
+/// Where a spec's subtitle track is stored, as given when creating the spec
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
+pub(crate) struct SubtitleSource {
+    pub(crate) path: String,
+    pub(crate) storage_service: String,
+    pub(crate) storage_config: serde_json::Value,
+}
+
+/// Load the subtitle tracks stored in a spec's `subtitles` column
+pub(crate) async fn load_subtitles(
+    subtitles: &serde_json::Value,
+) -> Result<Vec<std::sync::Arc<vidformer::subtitle::SubtitleTrack>>, IgniError> {
+    let subtitles: std::collections::BTreeMap<String, SubtitleSource> =
+        serde_json::from_value(subtitles.clone())
+            .map_err(|e| IgniError::General(format!("Failed to parse subtitles JSON: {}", e)))?;
+
+    let mut tracks = Vec::with_capacity(subtitles.len());
+    for (name, subtitle) in subtitles {
+        let storage_config_json = serde_json::to_string(&subtitle.storage_config).unwrap();
+        let storage = parse_storage_config(&storage_config_json)?;
+        let service = vidformer::service::Service::new(subtitle.storage_service, storage.1);
+
+        // subtitle files are read with blocking IO
+        let track = tokio::task::spawn_blocking(move || {
+            vidformer::subtitle::SubtitleTrack::load(&name, &subtitle.path, &service)
+        })
+        .await
+        .map_err(|e| IgniError::General(format!("Failed to join blocking thread: {}", e)))??;
+        tracks.push(std::sync::Arc::new(track));
+    }
+
+    Ok(tracks)
+}
+
 pub(crate) async fn add_spec(
     pool: &sqlx::Pool<sqlx::Postgres>,
     user_id: &uuid::Uuid,
@@ -113,10 +147,11 @@ pub(crate) async fn add_spec(
     ready_hook: Option<String>,
     steer_hook: Option<String>,
     ttl: Option<i64>,
+    subtitles: Option<serde_json::Value>,
 ) -> Result<uuid::Uuid, IgniError> {
     let spec_id = uuid::Uuid::new_v4();
 
-    sqlx::query("INSERT INTO spec (id, user_id, width, height, pix_fmt, vod_segment_length_num, vod_segment_length_denom, frame_rate_num, frame_rate_denom, pos_discontinuity, closed, ready_hook, steer_hook, expires_at) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)")
+    sqlx::query("INSERT INTO spec (id, user_id, width, height, pix_fmt, vod_segment_length_num, vod_segment_length_denom, frame_rate_num, frame_rate_denom, pos_discontinuity, closed, ready_hook, steer_hook, expires_at, subtitles) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)")
         .bind(spec_id)
         .bind(user_id)
         .bind(width)
@@ -131,6 +166,7 @@ pub(crate) async fn add_spec(
         .bind(ready_hook)
         .bind(steer_hook)
         .bind(ttl.map(|ttl| chrono::Utc::now() + chrono::Duration::seconds(ttl)))
+        .bind(subtitles)
         .execute(pool)
         .await?;
 
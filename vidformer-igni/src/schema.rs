@@ -26,6 +26,9 @@ pub struct SpecRow {
     pub steer_hook: Option<String>,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    // Missing on databases which haven't applied upgrade.sql
+    #[sqlx(default)]
+    pub subtitles: Option<serde_json::Value>,
 }
 
 #[derive(sqlx::FromRow)]
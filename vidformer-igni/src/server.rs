@@ -14,6 +14,42 @@ mod vod;
 struct IgniServerGlobal {
     config: ServerConfig,
     pool: sqlx::Pool<sqlx::Postgres>,
+    frame_cache: Option<std::sync::Arc<vidformer::frame_cache::LruFrameCache>>,
+    decoder_registry: Option<std::sync::Arc<vidformer::decoder_registry::DecoderRegistry>>,
+    filter_plugins: Vec<vidformer::filter::plugin::FilterPlugin>,
+    /// Fonts and LUTs read through the configured services, shared by every context
+    fonts: Option<std::sync::Arc<vidformer::filter::text::FontStore>>,
+    luts: Option<std::sync::Arc<vidformer::filter::color::LutStore>>,
+    spec_subtitles: std::sync::Mutex<SpecSubtitleCache>,
+}
+
+type SubtitleTracks = Vec<std::sync::Arc<vidformer::subtitle::SubtitleTrack>>;
+
+/// Subtitle tracks of recently rendered specs, so a spec's segments don't each reload its tracks
+///
+/// A spec's subtitles are fixed when it's created, so entries never go stale; the oldest are dropped past a limit.
+#[derive(Default)]
+struct SpecSubtitleCache {
+    tracks: std::collections::BTreeMap<uuid::Uuid, SubtitleTracks>,
+    order: std::collections::VecDeque<uuid::Uuid>,
+}
+
+const SPEC_SUBTITLE_CACHE_SIZE: usize = 256;
+
+impl SpecSubtitleCache {
+    fn get(&self, spec_id: &uuid::Uuid) -> Option<SubtitleTracks> {
+        self.tracks.get(spec_id).cloned()
+    }
+
+    fn insert(&mut self, spec_id: uuid::Uuid, tracks: SubtitleTracks) {
+        if self.tracks.insert(spec_id, tracks).is_none() {
+            self.order.push_back(spec_id);
+        }
+        while self.order.len() > SPEC_SUBTITLE_CACHE_SIZE {
+            let oldest = self.order.pop_front().unwrap();
+            self.tracks.remove(&oldest);
+        }
+    }
 }
 
 impl IgniServerGlobal {
@@ -26,6 +62,66 @@ impl IgniServerGlobal {
             None => None,
         }
     }
+
+    /// All filters available on this server: the VOD filters, plugin filters, and filters which need a configured service
+    fn filters(&self) -> std::collections::BTreeMap<String, Box<dyn vidformer::filter::Filter>> {
+        let mut filters = vod::filters();
+        for plugin in &self.filter_plugins {
+            filters.extend(plugin.filters());
+        }
+        if let Some(fonts) = &self.fonts {
+            filters.extend(vidformer::filter::text::filters(fonts.clone()));
+        }
+        if let Some(luts) = &self.luts {
+            filters.extend(vidformer::filter::color::lut_filters(luts.clone()));
+        }
+        filters
+    }
+
+    fn context(
+        &self,
+        sources: Vec<vidformer::source::SourceVideoStreamMeta>,
+    ) -> vidformer::Context {
+        let context = vidformer::Context::new(sources, self.filters(), self.io_wrapper());
+        let context = match &self.frame_cache {
+            Some(frame_cache) => context.with_frame_cache(frame_cache.clone()),
+            None => context,
+        };
+        match &self.decoder_registry {
+            Some(decoder_registry) => context.with_decoder_registry(decoder_registry.clone()),
+            None => context,
+        }
+    }
+
+    /// A context for rendering a spec, with the spec's subtitle tracks attached
+    async fn spec_context(
+        &self,
+        sources: Vec<vidformer::source::SourceVideoStreamMeta>,
+        spec: &schema::SpecRow,
+    ) -> Result<vidformer::Context, IgniError> {
+        let context = self.context(sources);
+        match (&spec.subtitles, &self.fonts) {
+            (None, _) => Ok(context),
+            (Some(subtitles), Some(fonts)) => {
+                let cached = self.spec_subtitles.lock().unwrap().get(&spec.id);
+                let tracks = match cached {
+                    Some(tracks) => tracks,
+                    None => {
+                        let tracks = crate::ops::load_subtitles(subtitles).await?;
+                        self.spec_subtitles
+                            .lock()
+                            .unwrap()
+                            .insert(spec.id, tracks.clone());
+                        tracks
+                    }
+                };
+                Ok(context.with_subtitles(tracks, fonts.clone()))
+            }
+            (Some(_), None) => Err(IgniError::General(
+                "Spec has subtitles but no fonts service is configured".to_string(),
+            )),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -370,6 +466,24 @@ struct ServerConfig {
     io_cache_valkey_url: Option<String>,
     io_cache_block_size: usize,
     enable_export: bool,
+    /// Max bytes of decoded frames each spec run keeps in its decode pool
+    #[serde(default)]
+    decode_pool_bytes: Option<usize>,
+    /// Max bytes of decoded frames to share between spec runs
+    #[serde(default)]
+    frame_cache_bytes: Option<usize>,
+    /// Max decoders to keep open between spec runs so sequential segments can resume them
+    #[serde(default)]
+    parked_decoders: Option<usize>,
+    /// Shared libraries of filters to load at startup
+    #[serde(default)]
+    filter_plugins: Vec<String>,
+    /// Where the Text filter reads fonts from; without it, the Text filter is unavailable
+    #[serde(default)]
+    fonts: Option<vidformer::service::Service>,
+    /// Where the LUT3D filter reads .cube files from; without it, the LUT3D filter is unavailable
+    #[serde(default)]
+    luts: Option<vidformer::service::Service>,
 }
 
 pub(crate) async fn cmd_server(
@@ -380,7 +494,44 @@ pub(crate) async fn cmd_server(
     use hyper_util::rt::TokioIo;
 
     let config = load_config(&opt.config)?;
-    let global = std::sync::Arc::new(IgniServerGlobal { config, pool });
+    let frame_cache = config
+        .frame_cache_bytes
+        .map(|bytes| std::sync::Arc::new(vidformer::frame_cache::LruFrameCache::new(bytes)));
+    let decoder_registry = config.parked_decoders.map(|max_parked| {
+        std::sync::Arc::new(vidformer::decoder_registry::DecoderRegistry::new(
+            max_parked,
+        ))
+    });
+    let mut filter_plugins = Vec::new();
+    for path in &config.filter_plugins {
+        // Plugins are configured by the operator, so they're trusted like the server itself
+        let plugin = unsafe { vidformer::filter::plugin::FilterPlugin::load(path) }
+            .map_err(|e| IgniError::General(e.to_string()))?;
+        info!(
+            "Loaded filter plugin {} with filters {:?}",
+            path,
+            plugin.filters().keys().collect::<Vec<_>>()
+        );
+        filter_plugins.push(plugin);
+    }
+    let fonts = config
+        .fonts
+        .clone()
+        .map(|fonts| std::sync::Arc::new(vidformer::filter::text::FontStore::new(fonts)));
+    let luts = config
+        .luts
+        .clone()
+        .map(|luts| std::sync::Arc::new(vidformer::filter::color::LutStore::new(luts)));
+    let global = std::sync::Arc::new(IgniServerGlobal {
+        config,
+        pool,
+        frame_cache,
+        decoder_registry,
+        filter_plugins,
+        fonts,
+        luts,
+        spec_subtitles: std::sync::Mutex::new(SpecSubtitleCache::default()),
+    });
     let addr: std::net::SocketAddr = format!("[::]:{}", opt.port).parse().unwrap();
     let listener = tokio::net::TcpListener::bind(&addr)
         .await
@@ -609,6 +760,10 @@ async fn igni_http_req_api(
         => {
             api::auth(req, global, &user_auth).await
         }
+        (hyper::Method::GET, "/v2/filter") // /v2/filter (list, with signatures)
+        => {
+            api::list_filters(req, global, &user_auth).await
+        }
         (hyper::Method::GET, "/v2/source") // /v2/source (list)
         => {
             if let Some(res) = user_auth.permissions.flag_err("source:list") {
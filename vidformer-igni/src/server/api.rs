@@ -34,6 +34,20 @@ pub(crate) async fn auth(
         )))?)
 }
 
+pub(crate) async fn list_filters(
+    _req: hyper::Request<impl hyper::body::Body>,
+    global: std::sync::Arc<IgniServerGlobal>,
+    _user: &super::UserAuth,
+) -> Result<hyper::Response<http_body_util::Full<hyper::body::Bytes>>, IgniError> {
+    let res = vidformer::filter::signatures(&global.filters());
+
+    Ok(hyper::Response::builder()
+        .header("Content-Type", "application/json")
+        .body(http_body_util::Full::new(hyper::body::Bytes::from(
+            serde_json::to_string(&res).unwrap(),
+        )))?)
+}
+
 pub(crate) async fn list_sources(
     _req: hyper::Request<impl hyper::body::Body>,
     global: std::sync::Arc<IgniServerGlobal>,
@@ -534,6 +548,7 @@ pub(crate) async fn push_spec(
         ready_hook: Option<String>,
         steer_hook: Option<String>,
         ttl: Option<i64>,
+        subtitles: Option<std::collections::BTreeMap<String, crate::ops::SubtitleSource>>,
     }
 
     let mut req: RequestContent = match serde_json::from_slice(&req) {
@@ -600,6 +615,37 @@ pub(crate) async fn push_spec(
         }
     }
 
+    let subtitles = match &req.subtitles {
+        None => None,
+        Some(subtitles) => {
+            for subtitle in subtitles.values() {
+                if let Some(err) = user
+                    .permissions
+                    .valset_err("source:storage_service", &subtitle.storage_service)
+                {
+                    return Ok(err);
+                }
+            }
+            if global.fonts.is_none() {
+                return Ok(hyper::Response::builder()
+                    .status(hyper::StatusCode::BAD_REQUEST)
+                    .body(http_body_util::Full::new(hyper::body::Bytes::from(
+                        "Subtitles require a fonts service to be configured",
+                    )))?);
+            }
+            let subtitles = serde_json::to_value(subtitles).unwrap();
+            // Load the tracks once so a missing or malformed file is reported now rather than on playback
+            if let Err(err) = crate::ops::load_subtitles(&subtitles).await {
+                return Ok(hyper::Response::builder()
+                    .status(hyper::StatusCode::BAD_REQUEST)
+                    .body(http_body_util::Full::new(hyper::body::Bytes::from(
+                        format!("Bad request: {}", err),
+                    )))?);
+            }
+            Some(subtitles)
+        }
+    };
+
     let spec = crate::ops::add_spec(
         &global.pool,
         &user.user_id,
@@ -611,6 +657,7 @@ pub(crate) async fn push_spec(
         req.ready_hook,
         req.steer_hook,
         req.ttl,
+        subtitles,
     )
     .await;
 
@@ -925,6 +972,13 @@ async fn push_frame_req(
                 vidformer::sir::IndexConst::T(t) => {
                     ref_by_ts.push((source_id, t));
                 }
+                vidformer::sir::IndexConst::Timecode(_) => {
+                    return Ok(hyper::Response::builder()
+                        .status(hyper::StatusCode::BAD_REQUEST)
+                        .body(http_body_util::Full::new(hyper::body::Bytes::from(
+                            "Timecode frame references are not supported",
+                        )))?);
+                }
             }
         }
 
@@ -1298,6 +1352,13 @@ pub(crate) async fn get_frame(
                 vidformer::sir::IndexConst::T(t) => {
                     ref_by_ts.push((source_id, *t));
                 }
+                vidformer::sir::IndexConst::Timecode(_) => {
+                    return Ok(hyper::Response::builder()
+                        .status(hyper::StatusCode::BAD_REQUEST)
+                        .body(http_body_util::Full::new(hyper::body::Bytes::from(
+                            "Timecode frame references are not supported",
+                        )))?);
+                }
             }
         }
 
@@ -1420,6 +1481,7 @@ pub(crate) async fn get_frame(
                 ts,
                 keys,
                 fuid: Some(source_id.to_string()),
+                timecode: None,
             });
         }
 
@@ -1452,16 +1514,16 @@ pub(crate) async fn get_frame(
     let spec = IgniSpec { frame: frame_expr };
     let spec = std::sync::Arc::new(std::boxed::Box::new(spec) as Box<dyn vidformer::spec::Spec>);
 
-    let io_wrapper = global.io_wrapper();
-    let filters = crate::server::vod::filters();
-    let context = vidformer::Context::new(sources, filters, io_wrapper);
+    let context = global.context(sources);
     let context: std::sync::Arc<vidformer::Context> = std::sync::Arc::new(context);
 
     let dve_config: vidformer::Config = vidformer::Config {
         decode_pool_size: 50,
+        decode_pool_bytes: global.config.decode_pool_bytes,
         decoder_view: 50,
         decoders: u16::MAX as usize,
         filterers: 8,
+        filter_cache_bytes: 256 * 1024 * 1024,
         output_width: req.width as usize,
         output_height: req.height as usize,
         output_pix_fmt: req.pix_fmt.clone(),
@@ -1707,6 +1769,7 @@ pub(crate) async fn export_spec(
                 ts,
                 keys,
                 fuid: Some(source_id.to_string()),
+                timecode: None,
             });
         }
 
@@ -1714,17 +1777,16 @@ pub(crate) async fn export_spec(
     };
     transaction.commit().await?;
 
-    let io_wrapper = global.io_wrapper();
-
-    let filters = crate::server::vod::filters();
-    let context = vidformer::Context::new(sources, filters, io_wrapper);
+    let context = global.spec_context(sources, &spec_db).await?;
     let context = std::sync::Arc::new(context);
 
     let dve_config: vidformer::Config = vidformer::Config {
         decode_pool_size: 50,
+        decode_pool_bytes: global.config.decode_pool_bytes,
         decoder_view: 50,
         decoders: u16::MAX as usize,
         filterers: 8,
+        filter_cache_bytes: 256 * 1024 * 1024,
         output_width: spec_db.width as usize,
         output_height: spec_db.height as usize,
         output_pix_fmt: spec_db.pix_fmt,
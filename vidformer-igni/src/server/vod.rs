@@ -11,6 +11,8 @@ pub fn filters() -> BTreeMap<String, Box<dyn vidformer::filter::Filter>> {
     let mut filters: BTreeMap<String, Box<dyn vidformer::filter::Filter>> = BTreeMap::new();
     filters.extend(vidformer::filter::builtin::filters());
     filters.extend(vidformer::filter::cv2::filters());
+    filters.extend(vidformer::filter::color::filters());
+    filters.extend(vidformer::filter::transition::filters());
     filters
 }
 
@@ -444,6 +446,7 @@ pub(crate) async fn get_segment(
                 ts,
                 keys,
                 fuid: Some(source_id.to_string()),
+                timecode: None,
             });
         }
 
@@ -451,17 +454,16 @@ pub(crate) async fn get_segment(
     };
     transaction.commit().await?;
 
-    let io_wrapper = global.io_wrapper();
-
-    let filters = filters();
-    let context = vidformer::Context::new(sources, filters, io_wrapper);
+    let context = global.spec_context(sources, &spec_db).await?;
     let context = std::sync::Arc::new(context);
 
     let dve_config: vidformer::Config = vidformer::Config {
         decode_pool_size: 50,
+        decode_pool_bytes: global.config.decode_pool_bytes,
         decoder_view: 50,
         decoders: u16::MAX as usize,
         filterers: 8,
+        filter_cache_bytes: 256 * 1024 * 1024,
         output_width: spec_db.width as usize,
         output_height: spec_db.height as usize,
         output_pix_fmt: spec_db.pix_fmt,
@@ -0,0 +1,5 @@
+-- Brings a database created by an older init/setup.sql up to date
+-- Every statement is idempotent, so this is safe to run on any database
+
+-- Subtitle tracks attached to specs
+ALTER TABLE spec ADD COLUMN IF NOT EXISTS subtitles JSONB;
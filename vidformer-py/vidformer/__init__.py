@@ -499,6 +499,17 @@ class Server:
         response = response.json()
         return Source(response["id"], response)
 
+    def list_filters(self) -> dict:
+        """The signatures of the server's filters, by filter name, for filters which declare one."""
+        response = self._session.get(
+            f"{self._endpoint}/v2/filter",
+            headers={"Authorization": f"Bearer {self._api_key}"},
+        )
+        if not response.ok:
+            raise Exception(response.text)
+        response = response.json()
+        return response
+
     def list_sources(self) -> list[str]:
         response = self._session.get(
             f"{self._endpoint}/v2/source",
@@ -617,6 +628,7 @@ class Server:
         ready_hook=None,
         steer_hook=None,
         ttl=None,
+        subtitles=None,
     ) -> Spec:
         assert type(width) is int
         assert type(height) is int
@@ -626,6 +638,8 @@ class Server:
         assert type(ready_hook) is str or ready_hook is None
         assert type(steer_hook) is str or steer_hook is None
         assert ttl is None or type(ttl) is int
+        # subtitles maps a track name to {"path", "storage_service", "storage_config"}
+        assert subtitles is None or type(subtitles) is dict
 
         req = {
             "width": width,
@@ -639,6 +653,7 @@ class Server:
             "ready_hook": ready_hook,
             "steer_hook": steer_hook,
             "ttl": ttl,
+            "subtitles": subtitles,
         }
         response = self._session.post(
             f"{self._endpoint}/v2/spec",
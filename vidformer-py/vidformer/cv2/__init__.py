@@ -63,6 +63,8 @@ INTER_LANCOZOS4 = 4
 INTER_LINEAR_EXACT = 5
 INTER_NEAREST_EXACT = 6
 INTER_MAX = 7
+WARP_FILL_OUTLIERS = 8
+WARP_INVERSE_MAP = 16
 
 # Rotation flags
 ROTATE_90_CLOCKWISE = 0
@@ -106,6 +108,21 @@ _filter_rotate = vf.Filter("cv2.rotate")
 _filter_copyMakeBorder = vf.Filter("cv2.copyMakeBorder")
 _filter_hconcat = vf.Filter("cv2.hconcat")
 _filter_vconcat = vf.Filter("cv2.vconcat")
+_filter_GaussianBlur = vf.Filter("cv2.GaussianBlur")
+_filter_medianBlur = vf.Filter("cv2.medianBlur")
+_filter_blur = vf.Filter("cv2.blur")
+_filter_bilateralFilter = vf.Filter("cv2.bilateralFilter")
+_blur_region = vf.Filter("_blur_region")
+_filter_warpAffine = vf.Filter("cv2.warpAffine")
+_filter_warpPerspective = vf.Filter("cv2.warpPerspective")
+_filter_resize = vf.Filter("cv2.resize")
+_overlay = vf.Filter("_overlay")
+_filter_text = vf.Filter("Text")
+_filter_color_adjust = vf.Filter("ColorAdjust")
+_filter_equalize = vf.Filter("Equalize")
+_filter_lut3d = vf.Filter("LUT3D")
+_filter_chroma_key = vf.Filter("ChromaKey")
+_filter_transition = vf.Filter("Transition")
 
 
 def _ts_to_fps(timestamps):
@@ -162,9 +179,18 @@ _PIX_FMT_MAP = {
     "yuvj420p": "rgb24",
     "yuvj422p": "rgb24",
     "yuvj444p": "rgb24",
+    "rgba": "rgb24",
+    "bgra": "rgb24",
+    "argb": "rgb24",
+    "abgr": "rgb24",
+    "yuva420p": "rgb24",
     "gray": "gray",
 }
 
+# Pixel formats with an alpha channel; like cv2.imread, frames in these formats
+# drop their alpha once modified, but overlay() can still use it
+_ALPHA_PIX_FMTS = ["rgba", "bgra", "argb", "abgr", "yuva420p"]
+
 
 def _top_level_pix_fmt(pix_fmt):
     if pix_fmt in _PIX_FMT_MAP:
@@ -373,18 +399,26 @@ class Frame:
 def _inline_frame(arr):
     if arr.dtype != np.uint8:
         raise Exception("Only uint8 arrays are supported")
+    if len(arr.shape) == 2:
+        arr = arr[:, :, None]
     if len(arr.shape) != 3:
-        raise Exception("Only 3D arrays are supported")
-    if arr.shape[2] != 3:
-        raise Exception("To inline a frame, the array must have 3 channels")
+        raise Exception("Only 2D and 3D arrays are supported")
+    if arr.shape[2] not in [1, 3, 4]:
+        raise Exception("To inline a frame, the array must have 1, 3, or 4 channels")
 
-    arr = arr[:, :, ::-1]
+    if arr.shape[2] == 1:
+        pix_fmt = "gray"
+    elif arr.shape[2] == 3:
+        arr = arr[:, :, ::-1]
+        pix_fmt = "rgb24"
+    else:
+        arr = arr[:, :, [2, 1, 0, 3]]  # BGRA to RGBA
+        pix_fmt = "rgba"
     if not arr.flags["C_CONTIGUOUS"]:
         arr = np.ascontiguousarray(arr)
 
     width = arr.shape[1]
     height = arr.shape[0]
-    pix_fmt = "rgb24"
 
     data_gzip = zlib.compress(memoryview(arr), level=1)
 
@@ -745,20 +779,50 @@ def solid(shape, color, dtype=np.uint8):
     return Frame(f, fmt)
 
 
-def resize(src, dsize, interpolation=None):
+def resize(src, dsize, dst=None, fx=None, fy=None, interpolation=None):
+    """
+    cv.resize(src, dsize[, dst[, fx[, fy[, interpolation]]]]) -> dst
+
+    Resizes an image.
+
+    Parameters:
+        src: input array
+        dsize: output image size (width, height); if (0, 0), it is computed from fx and fy
+        fx: scale factor along the horizontal axis
+        fy: scale factor along the vertical axis
+        interpolation: interpolation method (cv2.INTER_NEAREST, cv2.INTER_LINEAR, etc.)
+
+    Without fx, fy, or interpolation, the frame is scaled with FFmpeg's swscale and keeps its pixel format.
+    """
+    if dst is not None:
+        raise NotImplementedError("dst is not supported")
     src = frameify(src)
     src._mut()
 
     assert isinstance(dsize, tuple) or isinstance(dsize, list)
     assert len(dsize) == 2
+    dsize = [int(dsize[0]), int(dsize[1])]
     width, height = dsize
 
-    # TODO: We don't do anything with interpolation yet
+    if interpolation is None and fx is None and fy is None:
+        f = _filter_scale(src._f, width=width, height=height)
+        fmt = {"width": width, "height": height, "pix_fmt": src._fmt["pix_fmt"]}
+        return Frame(f, fmt)
+
     assert interpolation is None or (
-        interpolation >= INTER_NEAREST and interpolation <= INTER_MAX
+        interpolation >= INTER_NEAREST and interpolation < INTER_MAX
     )
-
-    f = _filter_scale(src._f, width=width, height=height)
+    fx = 0.0 if fx is None else float(fx)
+    fy = 0.0 if fy is None else float(fy)
+    if width == 0 and height == 0:
+        # Matches OpenCV's rounding of the scaled size
+        width = int(np.rint(src._fmt["width"] * fx))
+        height = int(np.rint(src._fmt["height"] * fy))
+
+    kwargs = {"fx": fx, "fy": fy}
+    if interpolation is not None:
+        kwargs["interpolation"] = interpolation
+    f = _filter_resize(src._f, dsize, **kwargs)
     fmt = {"width": width, "height": height, "pix_fmt": src._fmt["pix_fmt"]}
     return Frame(f, fmt)
 
@@ -1468,3 +1532,650 @@ def vconcat(src):
     f = _filter_vconcat([frame for frame in frames])
     fmt = {"width": width, "height": total_height, "pix_fmt": pix_fmt}
     return Frame(f, fmt)
+
+
+def GaussianBlur(src, ksize, sigmaX, dst=None, sigmaY=None, borderType=None):
+    """
+    cv.GaussianBlur(src, ksize, sigmaX[, dst[, sigmaY[, borderType]]]) -> dst
+
+    Blurs an image using a Gaussian filter.
+
+    Parameters:
+        src: input array
+        ksize: Gaussian kernel size (width, height); each must be odd and positive, or zero to compute it from sigma
+        sigmaX: Gaussian kernel standard deviation in the X direction
+        sigmaY: Gaussian kernel standard deviation in the Y direction (default: sigmaX)
+        borderType: pixel extrapolation method (default: cv2.BORDER_DEFAULT)
+    """
+    if dst is not None:
+        raise NotImplementedError("dst is not supported")
+    src = frameify(src)
+    src._mut()
+
+    assert len(ksize) == 2
+    ksize = [int(ksize[0]), int(ksize[1])]
+    kwargs = {}
+    if sigmaY is not None:
+        kwargs["sigmaY"] = float(sigmaY)
+    if borderType is not None:
+        assert isinstance(borderType, int)
+        kwargs["borderType"] = borderType
+
+    f = _filter_GaussianBlur(src._f, ksize, float(sigmaX), **kwargs)
+    return Frame(f, src._fmt.copy())
+
+
+def medianBlur(src, ksize, dst=None):
+    """
+    cv.medianBlur(src, ksize[, dst]) -> dst
+
+    Blurs an image using the median filter.
+
+    Parameters:
+        src: input array
+        ksize: aperture linear size; it must be odd and greater than 1
+    """
+    if dst is not None:
+        raise NotImplementedError("dst is not supported")
+    src = frameify(src)
+    src._mut()
+
+    assert isinstance(ksize, int)
+
+    f = _filter_medianBlur(src._f, ksize)
+    return Frame(f, src._fmt.copy())
+
+
+def blur(src, ksize, dst=None, anchor=None, borderType=None):
+    """
+    cv.blur(src, ksize[, dst[, anchor[, borderType]]]) -> dst
+
+    Blurs an image using the normalized box filter.
+
+    Parameters:
+        src: input array
+        ksize: blurring kernel size (width, height)
+        anchor: anchor point (default: (-1, -1), the kernel center)
+        borderType: border mode used to extrapolate pixels outside of the image (default: cv2.BORDER_DEFAULT)
+    """
+    if dst is not None:
+        raise NotImplementedError("dst is not supported")
+    src = frameify(src)
+    src._mut()
+
+    assert len(ksize) == 2
+    ksize = [int(ksize[0]), int(ksize[1])]
+    kwargs = {}
+    if anchor is not None:
+        assert len(anchor) == 2
+        kwargs["anchor"] = [int(anchor[0]), int(anchor[1])]
+    if borderType is not None:
+        assert isinstance(borderType, int)
+        kwargs["borderType"] = borderType
+
+    f = _filter_blur(src._f, ksize, **kwargs)
+    return Frame(f, src._fmt.copy())
+
+
+def bilateralFilter(src, d, sigmaColor, sigmaSpace, dst=None, borderType=None):
+    """
+    cv.bilateralFilter(src, d, sigmaColor, sigmaSpace[, dst[, borderType]]) -> dst
+
+    Applies the bilateral filter to an image.
+
+    Parameters:
+        src: input array
+        d: diameter of each pixel neighborhood; if non-positive, it is computed from sigmaSpace
+        sigmaColor: filter sigma in the color space
+        sigmaSpace: filter sigma in the coordinate space
+        borderType: border mode used to extrapolate pixels outside of the image (default: cv2.BORDER_DEFAULT)
+    """
+    if dst is not None:
+        raise NotImplementedError("dst is not supported")
+    src = frameify(src)
+    src._mut()
+
+    assert isinstance(d, int)
+    kwargs = {}
+    if borderType is not None:
+        assert isinstance(borderType, int)
+        kwargs["borderType"] = borderType
+
+    f = _filter_bilateralFilter(
+        src._f, d, float(sigmaColor), float(sigmaSpace), **kwargs
+    )
+    return Frame(f, src._fmt.copy())
+
+
+def blurRegion(src, ksize, rect=None, pts=None, method="gaussian"):
+    """
+    Blurs only inside a region of an image, e.g., to anonymize faces or license plates (not in cv2).
+
+    Parameters:
+        src: input array
+        ksize: kernel size; it must be odd and positive
+        rect: region to blur as (x, y, width, height)
+        pts: region to blur as a list of polygons, like cv2.fillPoly
+        method: "gaussian", "median", or "box"
+
+    Exactly one of rect and pts must be given.
+    """
+    src = frameify(src)
+    src._mut()
+
+    assert isinstance(ksize, int)
+    assert (rect is None) != (pts is None), "Exactly one of rect and pts is required"
+    assert method in ["gaussian", "median", "box"]
+
+    kwargs = {"method": method}
+    if rect is not None:
+        assert len(rect) == 4
+        kwargs["rect"] = [int(x) for x in rect]
+    else:
+        kwargs["pts"] = _convert_polygon_list(pts)
+
+    f = _blur_region(src._f, ksize, **kwargs)
+    return Frame(f, src._fmt.copy())
+
+
+def _convert_matrix(M, rows):
+    """Convert a transformation matrix to the internal format (a list of rows of floats)."""
+    M = np.asarray(M, dtype=np.float64)
+    assert M.shape == (rows, 3), f"Expected a {rows}x3 matrix"
+    return [[float(v) for v in row] for row in M]
+
+
+def _warp_kwargs(flags, borderMode, borderValue):
+    kwargs = {}
+    if flags is not None:
+        assert isinstance(flags, int)
+        kwargs["flags"] = flags
+    if borderMode is not None:
+        assert isinstance(borderMode, int)
+        kwargs["borderMode"] = borderMode
+    if borderValue is not None:
+        assert len(borderValue) == 3 or len(borderValue) == 4
+        borderValue = [float(x) for x in borderValue]
+        if len(borderValue) == 3:
+            borderValue.append(0.0)
+        kwargs["borderValue"] = borderValue
+    return kwargs
+
+
+def warpAffine(src, M, dsize, dst=None, flags=None, borderMode=None, borderValue=None):
+    """
+    cv.warpAffine(src, M, dsize[, dst[, flags[, borderMode[, borderValue]]]]) -> dst
+
+    Applies an affine transformation to an image.
+
+    Parameters:
+        src: input array
+        M: 2x3 transformation matrix
+        dsize: size of the output image (width, height)
+        flags: interpolation method, optionally combined with cv2.WARP_INVERSE_MAP (default: cv2.INTER_LINEAR)
+        borderMode: pixel extrapolation method (default: cv2.BORDER_CONSTANT)
+        borderValue: value used with a constant border (default: black)
+    """
+    if dst is not None:
+        raise NotImplementedError("dst is not supported")
+    src = frameify(src)
+    src._mut()
+
+    assert len(dsize) == 2
+    width, height = int(dsize[0]), int(dsize[1])
+
+    f = _filter_warpAffine(
+        src._f,
+        _convert_matrix(M, 2),
+        [width, height],
+        **_warp_kwargs(flags, borderMode, borderValue),
+    )
+    fmt = {"width": width, "height": height, "pix_fmt": src._fmt["pix_fmt"]}
+    return Frame(f, fmt)
+
+
+def warpPerspective(
+    src, M, dsize, dst=None, flags=None, borderMode=None, borderValue=None
+):
+    """
+    cv.warpPerspective(src, M, dsize[, dst[, flags[, borderMode[, borderValue]]]]) -> dst
+
+    Applies a perspective transformation to an image.
+
+    Parameters:
+        src: input array
+        M: 3x3 transformation matrix
+        dsize: size of the output image (width, height)
+        flags: interpolation method, optionally combined with cv2.WARP_INVERSE_MAP (default: cv2.INTER_LINEAR)
+        borderMode: pixel extrapolation method (default: cv2.BORDER_CONSTANT)
+        borderValue: value used with a constant border (default: black)
+    """
+    if dst is not None:
+        raise NotImplementedError("dst is not supported")
+    src = frameify(src)
+    src._mut()
+
+    assert len(dsize) == 2
+    width, height = int(dsize[0]), int(dsize[1])
+
+    f = _filter_warpPerspective(
+        src._f,
+        _convert_matrix(M, 3),
+        [width, height],
+        **_warp_kwargs(flags, borderMode, borderValue),
+    )
+    fmt = {"width": width, "height": height, "pix_fmt": src._fmt["pix_fmt"]}
+    return Frame(f, fmt)
+
+
+def getRotationMatrix2D(center, angle, scale):
+    """
+    cv.getRotationMatrix2D(center, angle, scale) -> retval
+
+    Calculates an affine matrix of 2D rotation, for use with warpAffine.
+
+    Parameters:
+        center: center of the rotation in the source image
+        angle: rotation angle in degrees; positive values mean counter-clockwise rotation
+        scale: isotropic scale factor
+    """
+    angle = np.deg2rad(angle)
+    alpha = np.cos(angle) * scale
+    beta = np.sin(angle) * scale
+    cx, cy = center
+    return np.array(
+        [
+            [alpha, beta, (1 - alpha) * cx - beta * cy],
+            [-beta, alpha, beta * cx + (1 - alpha) * cy],
+        ],
+        dtype=np.float64,
+    )
+
+
+def getAffineTransform(*args, **kwargs):
+    """
+    cv.getAffineTransform(src, dst) -> retval
+    """
+    _check_opencv2("getAffineTransform")
+    return _opencv2.getAffineTransform(*args, **kwargs)
+
+
+def getPerspectiveTransform(*args, **kwargs):
+    """
+    cv.getPerspectiveTransform(src, dst[, solveMethod]) -> retval
+    """
+    _check_opencv2("getPerspectiveTransform")
+    return _opencv2.getPerspectiveTransform(*args, **kwargs)
+
+
+def overlay(
+    img,
+    ovl,
+    x=0,
+    y=0,
+    mask=None,
+    size=None,
+    opacity=1.0,
+    blend="normal",
+):
+    """
+    Composites an image onto another with transparency, e.g., a logo or a picture-in-picture (not in cv2).
+
+    Parameters:
+        img: base image
+        ovl: image to draw; a BGRA array or a frame with an alpha channel (e.g., a PNG) is blended with its alpha
+        x: column of the overlay's left edge in img; it may be negative
+        y: row of the overlay's top edge in img; it may be negative
+        mask: optional 1-channel image the size of ovl, multiplied into its alpha
+        size: optional (width, height) to scale ovl (and mask) to; either may be None to keep the aspect ratio
+        opacity: global opacity in [0, 1]
+        blend: "normal", "multiply", "screen", "overlay", "add", "darken", "lighten", or "difference"
+
+    Parts of the overlay outside img are clipped. Returns a new image.
+    """
+    img = frameify(img, "img")
+    img._mut()
+
+    ovl = frameify(ovl, "ovl")
+    if not ovl._modified and ovl._fmt["pix_fmt"] in _ALPHA_PIX_FMTS:
+        ovl_f = ovl._f
+        if ovl._fmt["pix_fmt"] != "rgba":
+            ovl_f = _filter_scale(ovl_f, pix_fmt="rgba")
+    else:
+        ovl._mut()
+        ovl_f = ovl._f
+
+    assert 0.0 <= opacity <= 1.0
+    kwargs = {"opacity": float(opacity), "blend": blend}
+    if mask is not None:
+        mask = frameify(mask, "mask")
+        mask._mut()
+        assert mask.shape[2] == 1, "mask must have 1 channel"
+        assert mask.shape[:2] == ovl.shape[:2], "mask must be the same size as ovl"
+        kwargs["mask"] = mask._f
+    if size is not None:
+        assert len(size) == 2
+        if size[0] is not None:
+            kwargs["width"] = int(size[0])
+        if size[1] is not None:
+            kwargs["height"] = int(size[1])
+
+    f = _overlay(img._f, ovl_f, int(x), int(y), **kwargs)
+    return Frame(f, img._fmt.copy())
+
+
+def _convert_text_color(color):
+    """Convert a (B, G, R) or (B, G, R, A) color to the Text filter's [r, g, b, a]."""
+    assert len(color) == 3 or len(color) == 4
+    color = [float(x) for x in color]
+    return [color[2], color[1], color[0], color[3] if len(color) == 4 else 255.0]
+
+
+def drawText(
+    img,
+    text,
+    font,
+    size,
+    org=None,
+    rect=None,
+    color=(255, 255, 255),
+    align="left",
+    valign="top",
+    lineSpacing=1.0,
+    outline=0,
+    outlineColor=(0, 0, 0),
+    shadow=None,
+    shadowColor=(0, 0, 0, 128),
+    background=None,
+    padding=4,
+    fontIndex=0,
+):
+    """
+    Draws text with a TrueType or OpenType font, in any script the font covers (not in cv2).
+
+    Parameters:
+        img: image to draw on
+        text: text to draw; newlines start new lines
+        font: path of a .ttf or .otf font, in the server's font storage
+        size: font size in pixels
+        org: (x, y) of the top left of the text
+        rect: (x, y, width, height) to wrap and align the text within
+        color: (B, G, R) or (B, G, R, A) text color, where A is its opacity in [0, 255]
+        align: "left", "center", or "right"
+        valign: "top", "middle", or "bottom" of rect
+        lineSpacing: multiple of the font's line height between lines
+        outline: outline width in pixels
+        outlineColor: (B, G, R) or (B, G, R, A) outline color
+        shadow: (dx, dy) offset of a drop shadow
+        shadowColor: (B, G, R) or (B, G, R, A) shadow color
+        background: (B, G, R) or (B, G, R, A) color of a box behind the text, e.g., for subtitles
+        padding: space in pixels between the text and its background box
+        fontIndex: index of the font within a font collection (.ttc)
+
+    Exactly one of org and rect must be given.
+    """
+    img = frameify(img)
+    img._mut()
+
+    assert isinstance(text, str)
+    assert isinstance(font, str)
+    assert (org is None) != (rect is None), "Exactly one of org and rect is required"
+    assert align in ["left", "center", "right"]
+    assert valign in ["top", "middle", "bottom"]
+
+    kwargs = {
+        "font": font,
+        "font_index": int(fontIndex),
+        "size": float(size),
+        "align": align,
+        "valign": valign,
+        "line_spacing": float(lineSpacing),
+        "color": _convert_text_color(color),
+        "outline": int(outline),
+        "outline_color": _convert_text_color(outlineColor),
+        "shadow_color": _convert_text_color(shadowColor),
+        "padding": int(padding),
+    }
+    if org is not None:
+        assert len(org) == 2
+        kwargs["org"] = [int(x) for x in org]
+    else:
+        assert len(rect) == 4
+        kwargs["rect"] = [int(x) for x in rect]
+    if shadow is not None:
+        assert len(shadow) == 2
+        kwargs["shadow"] = [int(x) for x in shadow]
+    if background is not None:
+        kwargs["background"] = _convert_text_color(background)
+
+    img._f = _filter_text(img._f, text, **kwargs)
+
+
+def colorAdjust(src, brightness=0.0, contrast=1.0, gamma=1.0, saturation=1.0):
+    """
+    Adjusts brightness, contrast, gamma, and saturation (not in cv2).
+
+    Parameters:
+        src: input image
+        brightness: added to each channel, from -1 to 1 of full scale
+        contrast: scale around mid gray; 0 is flat gray, 1 unchanged
+        gamma: above 1 brightens midtones, below 1 darkens them
+        saturation: 0 is grayscale, 1 unchanged, above 1 more saturated
+    """
+    src = frameify(src)
+    src._mut()
+    assert src.shape[2] == 3, "colorAdjust requires a 3-channel image"
+
+    f = _filter_color_adjust(
+        src._f,
+        brightness=float(brightness),
+        contrast=float(contrast),
+        gamma=float(gamma),
+        saturation=float(saturation),
+    )
+    return Frame(f, src._fmt.copy())
+
+
+def equalizeHist(src, dst=None):
+    """
+    cv.equalizeHist(src[, dst]) -> dst
+
+    Equalizes the histogram of a grayscale image.
+
+    Parameters:
+        src: input image; 3-channel images have their luma equalized, keeping their chroma
+    """
+    if dst is not None:
+        raise NotImplementedError("dst is not supported")
+    src = frameify(src)
+    src._mut()
+
+    f = _filter_equalize(src._f)
+    return Frame(f, src._fmt.copy())
+
+
+class CLAHE:
+    """Contrast Limited Adaptive Histogram Equalization, as returned by cv2.createCLAHE"""
+
+    def __init__(self, clipLimit, tileGridSize):
+        self._clip_limit = float(clipLimit)
+        self._tile_grid_size = (int(tileGridSize[0]), int(tileGridSize[1]))
+
+    def apply(self, src, dst=None):
+        """
+        Equalizes the histogram of a grayscale image using CLAHE.
+
+        Parameters:
+            src: input image; 3-channel images have their luma equalized, keeping their chroma
+        """
+        if dst is not None:
+            raise NotImplementedError("dst is not supported")
+        src = frameify(src)
+        src._mut()
+
+        f = _filter_equalize(
+            src._f,
+            clip_limit=self._clip_limit,
+            tile_grid=list(self._tile_grid_size),
+        )
+        return Frame(f, src._fmt.copy())
+
+    def getClipLimit(self):
+        return self._clip_limit
+
+    def setClipLimit(self, clipLimit):
+        self._clip_limit = float(clipLimit)
+
+    def getTilesGridSize(self):
+        return self._tile_grid_size
+
+    def setTilesGridSize(self, tileGridSize):
+        self._tile_grid_size = (int(tileGridSize[0]), int(tileGridSize[1]))
+
+
+def createCLAHE(clipLimit=40.0, tileGridSize=(8, 8)):
+    """
+    cv.createCLAHE([, clipLimit[, tileGridSize]]) -> retval
+
+    Creates a CLAHE object.
+
+    Parameters:
+        clipLimit: threshold for contrast limiting
+        tileGridSize: size of the grid of tiles the image is divided into, as (columns, rows)
+    """
+    return CLAHE(clipLimit, tileGridSize)
+
+
+_LUT_PIX_FMTS = [
+    "yuv420p",
+    "yuv422p",
+    "yuv444p",
+    "yuvj420p",
+    "yuvj422p",
+    "yuvj444p",
+]
+
+
+def applyLUT3D(src, lut, strength=1.0):
+    """
+    Applies a 3D LUT from a .cube file with trilinear interpolation (not in cv2).
+
+    Parameters:
+        src: input image
+        lut: path of the .cube file, relative to the server's [luts] service
+        strength: blend between the original (0) and the graded image (1)
+
+    Frames still in their source's planar YUV format are graded without converting them to RGB.
+    """
+    src = frameify(src)
+    if src._modified or src._fmt["pix_fmt"] not in _LUT_PIX_FMTS:
+        src._mut()
+        assert src.shape[2] == 3, "applyLUT3D requires a 3-channel image"
+
+    assert isinstance(lut, str)
+    assert 0.0 <= strength <= 1.0
+
+    f = _filter_lut3d(src._f, lut, strength=float(strength))
+    return Frame(f, src._fmt.copy())
+
+
+def chromaKey(
+    fg,
+    bg=None,
+    keyColor=(0, 255, 0),
+    similarity=0.4,
+    smoothness=0.08,
+    spill=0.1,
+    output="composite",
+):
+    """
+    Keys out a backdrop color, e.g., a green screen (not in cv2).
+
+    Parameters:
+        fg: foreground image shot in front of the key color
+        bg: background image the same size as fg; required for the composite output
+        keyColor: (b, g, r) of the backdrop
+        similarity: chroma distance from keyColor within which pixels are fully transparent
+        smoothness: chroma distance over which edges fade from transparent to opaque
+        spill: chroma distance beyond similarity within which keyColor is desaturated from fg
+        output: "composite" for fg over bg, "mask" for fg's 1-channel alpha, or "rgba" for fg with its alpha
+
+    The "mask" and "rgba" outputs can be passed to overlay() as its mask or ovl. Returns a new image.
+    """
+    fg = frameify(fg, "fg")
+    fg._mut()
+    assert fg.shape[2] == 3, "chromaKey requires a 3-channel fg"
+
+    assert len(keyColor) == 3
+    assert similarity >= 0.0 and smoothness >= 0.0 and spill >= 0.0
+    kwargs = {
+        "key_color": [int(c) for c in keyColor[::-1]],
+        "similarity": float(similarity),
+        "smoothness": float(smoothness),
+        "spill": float(spill),
+        "output": output,
+    }
+
+    fmt = fg._fmt.copy()
+    if output == "composite":
+        assert bg is not None, "chromaKey requires bg for the composite output"
+    elif output == "mask":
+        fmt["pix_fmt"] = "gray"
+    elif output == "rgba":
+        fmt["pix_fmt"] = "rgba"
+    else:
+        raise ValueError(f"Unknown output {output}")
+
+    if bg is not None:
+        bg = frameify(bg, "bg")
+        bg._mut()
+        assert bg.shape == fg.shape, "bg must be the same size as fg"
+        f = _filter_chroma_key(fg._f, bg._f, **kwargs)
+    else:
+        f = _filter_chroma_key(fg._f, **kwargs)
+    return Frame(f, fmt)
+
+
+def transition(
+    a,
+    b,
+    progress,
+    kind="crossfade",
+    direction="left",
+    softness=0.0,
+    center=None,
+    color=(0, 0, 0),
+):
+    """
+    Renders a frame of a transition from one image to another (not in cv2).
+
+    Parameters:
+        a: image transitioned from
+        b: image transitioned to, the same size as a
+        progress: from 0 (just a) to 1 (just b)
+        kind: "crossfade", "wipe", "slide" (b moves in over a), "push" (b pushes a out), "iris", or "dip"
+        direction: "left", "right", "up", or "down"; the direction a wipe's edge or a sliding image moves in
+        softness: width of a wipe's or iris's feathered edge, as a fraction of the transition
+        center: optional (x, y) an iris grows from; defaults to the center of the image
+        color: (b, g, r) a dip fades through, e.g., (0, 0, 0) to dip to black or (255, 255, 255) to white
+
+    Returns a new image.
+    """
+    a = frameify(a, "a")
+    a._mut()
+    b = frameify(b, "b")
+    b._mut()
+    assert a.shape[2] == 3 and b.shape[2] == 3, "transition requires 3-channel images"
+    assert a.shape == b.shape, "a and b must be the same size"
+    assert 0.0 <= progress <= 1.0
+
+    kwargs = {"direction": direction, "softness": float(softness)}
+    if center is not None:
+        assert len(center) == 2
+        kwargs["center"] = [int(center[0]), int(center[1])]
+    if kind == "dip":
+        assert len(color) == 3
+        kwargs["color"] = [int(c) for c in color[::-1]]
+
+    f = _filter_transition(a._f, b._f, float(progress), kind, **kwargs)
+    return Frame(f, a._fmt.copy())
@@ -0,0 +1,47 @@
+use std::path::PathBuf;
+
+/// Builds the filter plugins loaded by the plugin tests, one per ABI version they cover
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=include/vidformer_filter_plugin.h");
+    println!("cargo:rerun-if-changed=tests/plugins/test_plugin.c");
+
+    let out_dir = PathBuf::from(std::env::var("OUT_DIR").unwrap());
+    for (env_var, abi_version) in [
+        ("VIDFORMER_TEST_PLUGIN", None),
+        ("VIDFORMER_TEST_PLUGIN_BAD_ABI", Some("99")),
+    ] {
+        let path = out_dir.join(format!(
+            "{}{}{}",
+            std::env::consts::DLL_PREFIX,
+            env_var.to_lowercase(),
+            std::env::consts::DLL_SUFFIX
+        ));
+        let mut build = cc::Build::new();
+        build.include("include").warnings(true);
+        if let Some(abi_version) = abi_version {
+            build.define("TEST_ABI_VERSION", abi_version);
+        }
+        let status = build
+            .get_compiler()
+            .to_command()
+            .args(["-shared", "-fPIC", "-o"])
+            .arg(&path)
+            .arg("tests/plugins/test_plugin.c")
+            .status();
+
+        // Only the tests need these, so a missing C compiler shouldn't fail the build
+        match status {
+            Ok(status) if status.success() => {
+                println!("cargo:rustc-env={}={}", env_var, path.display());
+            }
+            Ok(status) => {
+                println!(
+                    "cargo:warning=Failed to build test filter plugin: {}",
+                    status
+                )
+            }
+            Err(e) => println!("cargo:warning=Failed to build test filter plugin: {}", e),
+        }
+    }
+}
@@ -0,0 +1,74 @@
+/*
+ * vidformer filter plugin ABI
+ *
+ * A plugin is a shared library exporting vidformer_filter_plugin(), which returns a
+ * VfPlugin describing its filters. See vidformer::filter::plugin for the calling rules.
+ *
+ * Plugin filters have no declared signature: vidformer can't check or document their
+ * arguments, so filter_type must validate them and report problems through err.
+ */
+
+#ifndef VIDFORMER_FILTER_PLUGIN_H
+#define VIDFORMER_FILTER_PLUGIN_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#define VF_ABI_VERSION 1
+
+#define VF_VAL_BOOL 0
+#define VF_VAL_INT 1
+#define VF_VAL_FLOAT 2
+#define VF_VAL_STRING 3
+#define VF_VAL_BYTES 4
+#define VF_VAL_LIST 5
+#define VF_VAL_FRAME 6
+#define VF_VAL_FRAME_TYPE 7
+
+typedef struct VfFrameType {
+    int32_t width;
+    int32_t height;
+    int32_t format; /* AVPixelFormat */
+} VfFrameType;
+
+typedef struct VfFrame {
+    VfFrameType frame_type;
+    uint8_t *data[8];
+    int32_t linesize[8];
+} VfFrame;
+
+typedef struct VfVal {
+    uint32_t tag;
+    int64_t int_val;   /* BOOL, INT */
+    double float_val;  /* FLOAT */
+    const void *data;  /* STRING, BYTES: bytes; LIST: const VfVal * */
+    size_t len;
+    VfFrame frame;     /* FRAME, FRAME_TYPE */
+} VfVal;
+
+typedef struct VfArg {
+    const char *name; /* NULL for positional arguments */
+    VfVal val;
+} VfArg;
+
+typedef int32_t (*VfFilterTypeFn)(void *state, const VfArg *args, size_t n_args,
+                                  VfFrameType *out, char *err, size_t err_cap);
+typedef int32_t (*VfFilterFn)(void *state, const VfArg *args, size_t n_args,
+                              VfFrame *out, char *err, size_t err_cap);
+
+typedef struct VfFilterDef {
+    const char *name;
+    void *state;
+    VfFilterTypeFn filter_type;
+    VfFilterFn filter;
+} VfFilterDef;
+
+typedef struct VfPlugin {
+    uint32_t abi_version; /* VF_ABI_VERSION */
+    const VfFilterDef *filters;
+    size_t filter_count;
+} VfPlugin;
+
+const VfPlugin *vidformer_filter_plugin(void);
+
+#endif
@@ -0,0 +1,103 @@
+//! Computing data from frames
+//!
+//! [`analyze`] runs a [`DataSpec`] much like [`run`](crate::run) runs a spec: source frames are decoded through the same
+//! decode pool and decoders, and any frame filters are rendered by the filter threads.
+//! Instead of being encoded, each timestamp's top-level [`DataFilter`](crate::filter::DataFilter) call is evaluated and
+//! its value collected.
+
+use crate::dve::{
+    check_workers, exec_context, type_frame, Config, Context, Error, Range, StatRunner, Stats,
+};
+use crate::filter::Val;
+use crate::sir::{DataExpr, Expr, FilterExpr, FrameExpr, ProcessSpan};
+use crate::spec::{DataSpec, Spec, SpecContext};
+use num_rational::Rational64;
+use rayon::prelude::*;
+use std::sync::Arc;
+
+/// The values computed by [`analyze`]
+#[derive(Debug)]
+pub struct Analysis {
+    /// The value at each timestamp, in order
+    pub rows: Vec<(Rational64, Val)>,
+    pub stats: Stats,
+}
+
+/// Presents a data spec as a spec of frames, so it can be planned like one
+struct DataSpecFrames<'a>(&'a dyn DataSpec);
+
+impl Spec for DataSpecFrames<'_> {
+    fn timestamps(&self, context: &dyn SpecContext) -> Vec<Rational64> {
+        self.0.timestamps(context)
+    }
+
+    fn render(&self, context: &dyn SpecContext, t: &Rational64) -> FrameExpr {
+        FrameExpr::Filter(self.0.render(context, t))
+    }
+}
+
+/// Compute a data spec, or a range of one
+///
+/// The config's output size and format are ignored, but the other settings apply as they do for [`run`](crate::run).
+pub fn analyze(
+    spec: &Arc<Box<dyn DataSpec>>,
+    context: &Arc<Context>,
+    config: &Arc<Config>,
+    range: &Option<Range>,
+) -> Result<Analysis, Error> {
+    check_workers(config)?;
+    let stat = Arc::new(StatRunner::new());
+
+    let spec = DataSpecFrames(spec.as_ref().as_ref());
+    let mut process_span = ProcessSpan::create(&spec, context, range);
+
+    // Check each call, reporting the error of the first bad one
+    let err = process_span
+        .frames
+        .par_iter()
+        .find_map_first(|frame| check_data_call(context, config, frame).err());
+    if let Some(err) = err {
+        return Err(err);
+    }
+    crate::optimizer::optimize(&mut process_span, context, config)?;
+
+    let mut exec = exec_context(stat, process_span, "", Some(Vec::new()), context, config)?;
+    let stats = exec.run()?;
+    Ok(Analysis {
+        rows: exec.take_data_rows(),
+        stats,
+    })
+}
+
+/// Check that a rendered data spec frame calls a data filter, and that any frames passed to it type check
+fn check_data_call(context: &Context, config: &Config, frame: &FrameExpr) -> Result<(), Error> {
+    let call: &FilterExpr = match frame {
+        FrameExpr::Filter(call) => call,
+        FrameExpr::Source(_) => {
+            return Err(Error::InvalidSpec(format!(
+                "Data spec must call a data filter, got {}",
+                frame
+            )))
+        }
+    };
+    if !context.data_filters.contains_key(&call.name) {
+        return Err(Error::Unknown(format!(
+            "Data filter `{}` not found",
+            call.name
+        )));
+    }
+
+    fn check_expr(context: &Context, config: &Config, expr: &Expr) -> Result<(), Error> {
+        match expr {
+            Expr::Frame(frame) => type_frame(context, config, frame).map(|_| ()),
+            Expr::Data(DataExpr::List(list)) => list
+                .iter()
+                .try_for_each(|item| check_expr(context, config, item)),
+            Expr::Data(_) => Ok(()),
+        }
+    }
+    call.args
+        .iter()
+        .chain(call.kwargs.values())
+        .try_for_each(|arg| check_expr(context, config, arg))
+}
@@ -0,0 +1,267 @@
+//! Picking [`Config`] performance knobs from the shape of a spec
+//!
+//! [`Config::auto`] looks at which source frames each output frame depends on, how long the GOPs holding them are,
+//! and whether the spec applies any filters, then sizes the decode pool, decoder view, decoders, and filterers
+//! for a given core count and memory limit.
+
+use crate::dve::{type_frame, Config, Context, Error, IFrameRef, SourceRef};
+use crate::sir::ProcessSpan;
+use crate::spec::Spec;
+use crate::util;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+const MIB: usize = 1024 * 1024;
+const DEFAULT_FILTER_CACHE_BYTES: usize = 256 * MIB;
+
+/// The machine resources a run may use, for [`Config::auto`]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Resources {
+    /// How many CPU cores to use
+    pub cores: usize,
+    /// The max bytes of frames to hold in memory, if limited
+    pub memory_bytes: Option<usize>,
+}
+
+/// The dependency structure of a rendered spec
+struct SpecShape {
+    frames: usize,
+    sources: usize,
+    /// Most source frames any one output frame depends on
+    max_fan_in: usize,
+    /// Mean source frames per output frame, rounded up
+    mean_fan_in: usize,
+    /// Distinct source frames used
+    used_frames: usize,
+    /// Source frames used by more than one output frame
+    reused_frames: usize,
+    /// Distinct GOPs holding used frames
+    gops: usize,
+    /// Longest GOP holding a used frame
+    max_gop_len: usize,
+    /// Largest decoded source frame
+    source_frame_bytes: usize,
+    filtered: bool,
+}
+
+impl SpecShape {
+    fn new(process_span: &ProcessSpan, context: &Context) -> Result<Self, Error> {
+        let deps_per_oframe: Vec<BTreeSet<IFrameRef>> = process_span
+            .frames
+            .par_iter()
+            .map(|oframe| {
+                let mut frame_deps = BTreeSet::new();
+                oframe.add_source_deps(&mut frame_deps);
+                frame_deps
+                    .into_iter()
+                    .map(|dep| {
+                        let (sourceref, pts) = context.resolve_frame_source(dep)?;
+                        Ok(IFrameRef { sourceref, pts })
+                    })
+                    .collect::<Result<_, Error>>()
+            })
+            .collect::<Result<_, Error>>()?;
+
+        let mut uses: BTreeMap<&IFrameRef, usize> = BTreeMap::new();
+        for deps in &deps_per_oframe {
+            for dep in deps {
+                *uses.entry(dep).or_default() += 1;
+            }
+        }
+
+        let mut gops: BTreeSet<(&SourceRef, usize)> = BTreeSet::new();
+        let mut max_gop_len = 0;
+        let mut source_frame_bytes = 0;
+        for iframe in uses.keys() {
+            let source = &context.sources[&iframe.sourceref];
+            let gop_idx = match source.keys.binary_search(&iframe.pts) {
+                Ok(i) => i,
+                Err(i) => i.saturating_sub(1),
+            };
+            if gops.insert((&iframe.sourceref, gop_idx)) {
+                let gop_len = context.get_gop_frames(&iframe.sourceref, gop_idx).len();
+                max_gop_len = max_gop_len.max(gop_len);
+                let (width, height) = source.resolution;
+                let bytes = util::image_size_bytes(&source.pix_fmt, width, height)
+                    .unwrap_or(width * height * 4);
+                source_frame_bytes = source_frame_bytes.max(bytes);
+            }
+        }
+
+        let total_deps: usize = deps_per_oframe.iter().map(|deps| deps.len()).sum();
+        Ok(SpecShape {
+            frames: process_span.frames.len(),
+            sources: gops
+                .iter()
+                .map(|(sourceref, _)| *sourceref)
+                .collect::<BTreeSet<_>>()
+                .len(),
+            max_fan_in: deps_per_oframe
+                .iter()
+                .map(|deps| deps.len())
+                .max()
+                .unwrap_or(0),
+            mean_fan_in: total_deps.div_ceil(process_span.frames.len()),
+            used_frames: uses.len(),
+            reused_frames: uses.values().filter(|count| **count > 1).count(),
+            gops: gops.len(),
+            max_gop_len,
+            source_frame_bytes,
+            filtered: process_span
+                .frames
+                .iter()
+                .any(|frame| matches!(frame, crate::sir::FrameExpr::Filter(_))),
+        })
+    }
+}
+
+impl Config {
+    /// Pick a config for running a spec with the given resources
+    ///
+    /// The output size and pixel format come from the spec's first frame, and no encoder or format is set.
+    /// Returns the config along with a short explanation of why each knob was set the way it was.
+    pub fn auto(
+        spec: &dyn Spec,
+        context: &Context,
+        resources: &Resources,
+    ) -> Result<(Config, String), Error> {
+        if resources.cores == 0 {
+            return Err(Error::ConfigError(
+                "cores must be greater than 0".to_string(),
+            ));
+        }
+
+        let process_span = ProcessSpan::create(spec, context, &None);
+        if process_span.frames.is_empty() {
+            return Err(Error::InvalidSpec("spec has no frames".to_string()));
+        }
+        let shape = SpecShape::new(&process_span, context)?;
+
+        let mut config = Config {
+            decode_pool_size: 1,
+            decode_pool_bytes: None,
+            decoder_view: 1,
+            decoders: 1,
+            filterers: 1,
+            filter_cache_bytes: DEFAULT_FILTER_CACHE_BYTES,
+            output_width: 0,
+            output_height: 0,
+            output_pix_fmt: String::new(),
+            encoder: None,
+            format: None,
+        };
+
+        let output_type = type_frame(context, &config, &process_span.frames[0])?;
+        config.output_width = output_type.width;
+        config.output_height = output_type.height;
+        config.output_pix_fmt = util::av_pix_fmt_to_pixel_fmt_str(output_type.format)
+            .ok_or(Error::InvalidOutputFrameType)?;
+        let output_frame_bytes = util::image_size_bytes(
+            &config.output_pix_fmt,
+            output_type.width,
+            output_type.height,
+        )
+        .unwrap_or(output_type.width * output_type.height * 4);
+
+        let mut rationale = format!(
+            "{} output frames use {} frames from {} GOPs across {} sources, up to {} per output frame ({} reused, GOPs up to {} frames long). ",
+            shape.frames,
+            shape.used_frames,
+            shape.gops,
+            shape.sources,
+            shape.max_fan_in,
+            shape.reused_frames,
+            shape.max_gop_len,
+        );
+
+        // Each decoder works through one GOP on one thread, so there's no use in more decoders than GOPs
+        config.decoders = resources.cores.min(shape.gops).max(1);
+        // Filterers are idle unless there's something to filter
+        config.filterers = if shape.filtered { resources.cores } else { 1 };
+        // Keep enough output frames in flight for the filterers to stay busy while decoders fill the pool
+        config.decoder_view = 2 * config.filterers;
+        // Room for the inputs of every active output frame, plus a GOP per decoder so frames decoded on the way
+        // to a needed frame can be kept for later output frames
+        config.decode_pool_size = (config.decoder_view * shape.mean_fan_in
+            + config.decoders * shape.max_gop_len)
+            .min(shape.used_frames + config.decoders)
+            .max(shape.max_fan_in)
+            .max(1);
+        write!(
+            rationale,
+            "Using {} decoders (one per core, at most one per GOP), {} filterers ({}), a decoder view of {} (two output frames per filterer), and a decode pool of {} frames (the view's inputs plus a GOP per decoder).",
+            config.decoders,
+            config.filterers,
+            if shape.filtered { "one per core" } else { "the spec applies no filters" },
+            config.decoder_view,
+            config.decode_pool_size,
+        )
+        .unwrap();
+
+        if let Some(memory_bytes) = resources.memory_bytes {
+            config.filter_cache_bytes = (memory_bytes / 8).min(DEFAULT_FILTER_CACHE_BYTES);
+            let frame_memory = memory_bytes - config.filter_cache_bytes;
+            let frames_bytes = |config: &Config| {
+                (config.decoders + config.decode_pool_size) * shape.source_frame_bytes
+                    + config.decoder_view * output_frame_bytes
+            };
+
+            let mut shrunk = Vec::new();
+            if frames_bytes(&config) > frame_memory {
+                let fixed = config.decoders * shape.source_frame_bytes
+                    + config.decoder_view * output_frame_bytes;
+                let fit = frame_memory.saturating_sub(fixed) / shape.source_frame_bytes.max(1);
+                config.decode_pool_size = fit.max(shape.max_fan_in);
+                shrunk.push("the decode pool");
+            }
+            if frames_bytes(&config) > frame_memory {
+                let fixed = (config.decoders + config.decode_pool_size) * shape.source_frame_bytes;
+                let fit = frame_memory.saturating_sub(fixed) / output_frame_bytes.max(1);
+                config.decoder_view = fit.max(1);
+                shrunk.push("the decoder view");
+            }
+            if frames_bytes(&config) > frame_memory {
+                let fixed = config.decode_pool_size * shape.source_frame_bytes
+                    + config.decoder_view * output_frame_bytes;
+                let fit = frame_memory.saturating_sub(fixed) / shape.source_frame_bytes.max(1);
+                config.decoders = fit.max(1);
+                shrunk.push("decoders");
+            }
+            if frames_bytes(&config) > frame_memory {
+                return Err(Error::ConfigError(format!(
+                    "{} bytes of memory is not enough to run this spec, which needs at least {} bytes",
+                    memory_bytes,
+                    frames_bytes(&config) + config.filter_cache_bytes
+                )));
+            }
+
+            config.decode_pool_bytes =
+                Some(frame_memory - config.decoder_view * output_frame_bytes);
+            write!(
+                rationale,
+                " To fit in {} MiB, the filter cache gets {} MiB and decoded frames are limited to {} MiB",
+                memory_bytes / MIB,
+                config.filter_cache_bytes / MIB,
+                config.decode_pool_bytes.unwrap() / MIB,
+            )
+            .unwrap();
+            if shrunk.is_empty() {
+                rationale.push('.');
+            } else {
+                write!(
+                    rationale,
+                    "; {} shrunk to {} pool frames, a view of {}, and {} decoders.",
+                    shrunk.join(", "),
+                    config.decode_pool_size,
+                    config.decoder_view,
+                    config.decoders,
+                )
+                .unwrap();
+            }
+        }
+
+        Ok((config, rationale))
+    }
+}
@@ -11,6 +11,7 @@ struct IoCtx {
     size: u64,
     reader: Box<dyn crate::io::ReadSeek>,
     err: Option<std::io::Error>,
+    bytes_read: u64,
 }
 
 unsafe extern "C" fn vidformer_avio_read_packet(
@@ -29,6 +30,7 @@ unsafe extern "C" fn vidformer_avio_read_packet(
                 debug_assert!(io_ctx.reader.stream_position().unwrap() == io_ctx.size);
                 ffi::AVERROR_EOF
             } else {
+                io_ctx.bytes_read += read as u64;
                 read as i32
             }
         }
@@ -78,7 +80,7 @@ unsafe extern "C" fn vidformer_avio_seek(
 pub struct Demuxer {
     pub format_context: *mut ffi::AVFormatContext,
     avio_context: *mut ffi::AVIOContext,
-    #[allow(unused)] // We need to keep this alive since libav keeps it as an opaque pointer
+    // We need to keep this alive since libav keeps it as an opaque pointer
     io_ctx: std::pin::Pin<std::boxed::Box<IoCtx>>,
     pub time_base: Rational64,
     pub codec: *const ffi::AVCodec,
@@ -135,6 +137,7 @@ impl Demuxer {
             size: file_size,
             reader: Box::new(reader),
             err: None,
+            bytes_read: 0,
         };
         let io_ctx = Box::pin(io_ctx);
         let io_ctx_ptr =
@@ -280,6 +283,11 @@ impl Demuxer {
         Ok(())
     }
 
+    /// Bytes libav has read from the file so far
+    pub fn bytes_read(&self) -> u64 {
+        self.io_ctx.bytes_read
+    }
+
     pub fn read_packet(&mut self, packet: *mut ffi::AVPacket) -> Option<()> {
         loop {
             if unsafe { ffi::av_read_frame(self.format_context, packet) } >= 0 {
@@ -87,6 +87,10 @@ impl FrameSource {
         }
     }
 
+    pub fn bytes_read(&self) -> u64 {
+        self.demuxer.bytes_read()
+    }
+
     pub fn time_base(&self) -> &Rational64 {
         &self.demuxer.time_base
     }
@@ -96,6 +100,9 @@ impl FrameSource {
     }
 }
 
+// A FrameSource is only ever used by one thread at a time, but may be handed between threads by a decoder registry
+unsafe impl Send for FrameSource {}
+
 impl Drop for FrameSource {
     fn drop(&mut self) {
         if self.frames_needs_unref {
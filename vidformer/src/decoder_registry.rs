@@ -0,0 +1,115 @@
+//! Decoders kept open between runs
+//!
+//! Rendering a spec in consecutive ranges (e.g., HLS segments during playback) often stops a decoder partway through a GOP
+//! which the next range picks up right where it left off.
+//! With a [`DecoderRegistry`] set on a [`Context`](crate::Context), decoders which stop before the end of their GOP are parked
+//! in the registry instead of closed, and later decoders on the same GOP resume a parked one rather than seeking to the
+//! keyframe and decoding from there again.
+//!
+//! Parked decoders are keyed by the source's fuid, so only sources with a fuid use the registry.
+
+use crate::av::framesource::FrameSource;
+use num_rational::Rational64;
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+
+struct ParkedDecoder {
+    fuid: String,
+    gop_idx: usize,
+    /// The timestamp of the decoded frame the decoder is holding, which it produces first when resumed
+    next_pts: Rational64,
+    framesource: FrameSource,
+}
+
+/// Holds decoders parked by finished runs so later runs can resume them
+///
+/// Decoders in the registry read from their sources on the registry's own I/O runtime, since they outlive the run which opened them.
+pub struct DecoderRegistry {
+    max_parked: usize,
+    /// Parked decoders, least recently parked first
+    parked: Mutex<VecDeque<ParkedDecoder>>,
+    /// Only taken when dropping the registry
+    io_runtime: Option<tokio::runtime::Runtime>,
+}
+
+impl DecoderRegistry {
+    /// Create a registry which keeps up to `max_parked` decoders, closing the least recently parked beyond that
+    pub fn new(max_parked: usize) -> Self {
+        DecoderRegistry {
+            max_parked,
+            parked: Mutex::new(VecDeque::new()),
+            io_runtime: Some(
+                tokio::runtime::Builder::new_multi_thread()
+                    .worker_threads(2)
+                    .thread_name("decoder-registry-io")
+                    .enable_all()
+                    .build()
+                    .unwrap(),
+            ),
+        }
+    }
+
+    /// The number of decoders currently parked
+    pub fn len(&self) -> usize {
+        self.parked.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub(crate) fn io_runtime_handle(&self) -> &tokio::runtime::Handle {
+        self.io_runtime.as_ref().unwrap().handle()
+    }
+
+    pub(crate) fn park(
+        &self,
+        fuid: &str,
+        gop_idx: usize,
+        next_pts: Rational64,
+        framesource: FrameSource,
+    ) {
+        let mut parked = self.parked.lock();
+        parked.push_back(ParkedDecoder {
+            fuid: fuid.to_string(),
+            gop_idx,
+            next_pts,
+            framesource,
+        });
+        while parked.len() > self.max_parked {
+            parked.pop_front();
+        }
+    }
+
+    /// Take the parked decoder on a GOP which is furthest along without having passed `pts`
+    ///
+    /// Returns the timestamp of the frame it is holding, along with the decoder.
+    pub(crate) fn resume(
+        &self,
+        fuid: &str,
+        gop_idx: usize,
+        pts: &Rational64,
+    ) -> Option<(Rational64, FrameSource)> {
+        let mut parked = self.parked.lock();
+        let idx = parked
+            .iter()
+            .enumerate()
+            .filter(|(_, decoder)| {
+                decoder.fuid == fuid && decoder.gop_idx == gop_idx && decoder.next_pts <= *pts
+            })
+            .max_by_key(|(_, decoder)| decoder.next_pts)
+            .map(|(idx, _)| idx)?;
+        let decoder = parked.remove(idx).unwrap();
+        Some((decoder.next_pts, decoder.framesource))
+    }
+}
+
+impl Drop for DecoderRegistry {
+    fn drop(&mut self) {
+        // Close the parked decoders while their runtime is still up, and don't block if dropped from async code
+        self.parked.lock().clear();
+        if let Some(io_runtime) = self.io_runtime.take() {
+            io_runtime.shutdown_background();
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use crate::av;
 use crate::filter;
 use crate::filter::Frame;
+use crate::filter_cache::{FilterCache, FrameMemo};
 use crate::pool::Pool;
 use crate::sir;
 
@@ -12,6 +13,7 @@ use num::integer::Roots;
 use num_rational::Rational64;
 use parking_lot::Condvar;
 use parking_lot::Mutex;
+use rayon::prelude::*;
 use rusty_ffmpeg::ffi;
 use serde::Deserialize;
 use serde::Serialize;
@@ -28,6 +30,8 @@ pub enum Error {
     SourceNotFound(String),
     #[error("Index `{1:?}` out of bounds on source `{0}`")]
     IndexOutOfBounds(String, sir::IndexConst),
+    #[error("Invalid timecode on source `{0}`: {1}")]
+    InvalidTimecode(String, String),
     #[error("Missing filter arg")]
     MissingFilterArg,
     #[error("Invalid filter arg type on `{0}`. Expected `{1}`, got `{2}`")]
@@ -40,6 +44,10 @@ pub enum Error {
     InvalidOutputFrameType,
     #[error("Config error: {0}")]
     ConfigError(String),
+    #[error("Invalid subtitles `{0}`: {1}")]
+    InvalidSubtitles(String, String),
+    #[error("Invalid spec: {0}")]
+    InvalidSpec(String),
     #[error("AV error: {0}")]
     AVError(String),
     #[error("IO error: {0}")]
@@ -76,6 +84,10 @@ pub struct Context {
     pub(crate) sources: BTreeMap<SourceRef, crate::source::SourceVideoStreamMeta>,
     pub(crate) filters: BTreeMap<String, Box<dyn crate::filter::Filter>>,
     pub(crate) io_wrapper: Option<Box<dyn IoWrapper>>,
+    pub(crate) frame_cache: Option<Arc<dyn crate::frame_cache::FrameCache>>,
+    pub(crate) decoder_registry: Option<Arc<crate::decoder_registry::DecoderRegistry>>,
+    pub(crate) data_filters: BTreeMap<String, Box<dyn crate::filter::DataFilter>>,
+    pub(crate) subtitles: BTreeMap<String, Arc<crate::subtitle::SubtitleTrack>>,
 }
 
 #[derive(Debug)]
@@ -89,6 +101,34 @@ impl AVFrame {
         let new_frame = unsafe { ffi::av_frame_clone(frame) };
         AVFrame { inner: new_frame }
     }
+
+    /// The size of the buffers backing the frame, including any padding
+    ///
+    /// Frames without buffers, like explain's placeholders, are sized by their unpadded image data.
+    pub(crate) fn size_bytes(&self) -> usize {
+        let frame = unsafe { &*self.inner };
+        if frame.buf[0].is_null() {
+            let size = unsafe {
+                ffi::av_image_get_buffer_size(frame.format, frame.width, frame.height, 1)
+            };
+            return size.max(0) as usize;
+        }
+
+        let extended_buf: &[*mut ffi::AVBufferRef] = if frame.extended_buf.is_null() {
+            &[]
+        } else {
+            unsafe {
+                std::slice::from_raw_parts(frame.extended_buf, frame.nb_extended_buf as usize)
+            }
+        };
+        frame
+            .buf
+            .iter()
+            .chain(extended_buf)
+            .filter(|buf| !buf.is_null())
+            .map(|buf| unsafe { (**buf).size })
+            .sum()
+    }
 }
 
 unsafe impl Send for AVFrame {}
@@ -117,7 +157,61 @@ impl Context {
             sources,
             filters,
             io_wrapper,
+            frame_cache: None,
+            decoder_registry: None,
+            data_filters: BTreeMap::new(),
+            subtitles: BTreeMap::new(),
+        }
+    }
+
+    /// Share decoded source frames with other runs through a [`FrameCache`](crate::frame_cache::FrameCache)
+    pub fn with_frame_cache(
+        mut self,
+        frame_cache: Arc<dyn crate::frame_cache::FrameCache>,
+    ) -> Context {
+        self.frame_cache = Some(frame_cache);
+        self
+    }
+
+    /// Park decoders which stop partway through a GOP in a [`DecoderRegistry`](crate::decoder_registry::DecoderRegistry) so later runs can resume them
+    pub fn with_decoder_registry(
+        mut self,
+        decoder_registry: Arc<crate::decoder_registry::DecoderRegistry>,
+    ) -> Context {
+        self.decoder_registry = Some(decoder_registry);
+        self
+    }
+
+    /// Add filters which compute data values, for use by [`analyze`](crate::analyze)
+    pub fn with_data_filters(
+        mut self,
+        data_filters: BTreeMap<String, Box<dyn crate::filter::DataFilter>>,
+    ) -> Context {
+        self.data_filters.extend(data_filters);
+        self
+    }
+
+    /// Register subtitle tracks, by name, and add the `Subtitle` filter which draws them with fonts from `fonts`
+    ///
+    /// Tracks and fonts are shared, so contexts created for the same spec can reuse them without reloading.
+    ///
+    /// A spec draws a track by passing each output frame's timestamp to the filter, e.g., `Subtitle(frame, "en", [t_num, t_den], font="DejaVuSans.ttf")`.
+    pub fn with_subtitles(
+        mut self,
+        tracks: Vec<Arc<crate::subtitle::SubtitleTrack>>,
+        fonts: Arc<crate::filter::text::FontStore>,
+    ) -> Context {
+        for track in tracks {
+            self.subtitles.insert(track.name.clone(), track);
         }
+        self.filters.insert(
+            "Subtitle".to_string(),
+            Box::new(crate::filter::subtitle::Subtitle::new(
+                self.subtitles.clone(),
+                fonts,
+            )),
+        );
+        self
     }
 
     pub fn spec_ctx(&self) -> impl crate::spec::SpecContext {
@@ -184,6 +278,40 @@ impl Context {
                         ))
                     }
                 }
+                crate::sir::IndexConst::Timecode(tc) => {
+                    let source_tc = match &source.timecode {
+                        Some(source_tc) => source_tc,
+                        None => {
+                            return Err(Error::InvalidTimecode(
+                                source_name.clone(),
+                                "source has no timecode".to_string(),
+                            ))
+                        }
+                    };
+                    let invalid_tc = |e| Error::InvalidTimecode(source_name.clone(), e);
+                    let start = source_tc
+                        .start
+                        .frame_number(&source_tc.rate)
+                        .map_err(invalid_tc)?;
+                    let mut offset = tc.frame_number(&source_tc.rate).map_err(invalid_tc)? - start;
+                    if offset < 0 {
+                        // Timecodes wrap around at midnight
+                        offset += crate::sir::Timecode::frames_per_day(
+                            &source_tc.rate,
+                            source_tc.start.drop_frame,
+                        )
+                        .map_err(invalid_tc)?;
+                    }
+
+                    if let Some(t) = source.ts.get(offset as usize) {
+                        Ok((SourceRef::new(&frame_source.video), *t))
+                    } else {
+                        Err(Error::IndexOutOfBounds(
+                            source_name.clone(),
+                            frame_source.index.clone(),
+                        ))
+                    }
+                }
             }
         } else {
             Err(Error::SourceNotFound(source_name.clone()))
@@ -207,6 +335,7 @@ pub(crate) fn run_decoder(
     pool: &Arc<(Mutex<Pool>, Condvar)>,
     decoder_id: String,
     io_runtime_handle: &tokio::runtime::Handle,
+    parked: Option<av::framesource::FrameSource>,
 ) -> Result<(), Error> {
     let stream_meta = &context.sources.get(source).unwrap();
     let stream_service = &stream_meta.service;
@@ -225,19 +354,38 @@ pub(crate) fn run_decoder(
         }
         None => None,
     };
-    let mut framesource = av::framesource::FrameSource::new(
-        &stream_meta.file_path,
-        stream_meta.stream_idx,
-        &stream_meta.keys[gop_idx],
-        stream_service,
-        stream_meta.file_size,
-        io_runtime_handle,
-        io_cache,
-    )?;
+    let mut busy_start = std::time::Instant::now();
+    let (mut framesource, mut resumed) = match parked {
+        Some(framesource) => (framesource, true),
+        None => {
+            let framesource = av::framesource::FrameSource::new(
+                &stream_meta.file_path,
+                stream_meta.stream_idx,
+                &stream_meta.keys[gop_idx],
+                stream_service,
+                stream_meta.file_size,
+                io_runtime_handle,
+                io_cache,
+            )?;
+            (framesource, false)
+        }
+    };
+    let mut bytes_read = framesource.bytes_read();
 
-    while framesource.next_frame()?.is_some() {
-        stat.frames_decoded
-            .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+    loop {
+        // A resumed decoder is still holding the frame it was parked with
+        if !std::mem::take(&mut resumed) {
+            let next_frame = framesource.next_frame()?;
+            stat.decode.busy(busy_start);
+            stat.add_bytes_read(source, framesource.bytes_read() - bytes_read);
+            bytes_read = framesource.bytes_read();
+            if next_frame.is_none() {
+                break;
+            }
+
+            stat.frames_decoded
+                .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        }
         unsafe {
             debug!(
                 "DECODE - Frame (type={}, size={} bytes, pts={} key_frame={})",
@@ -254,9 +402,24 @@ pub(crate) fn run_decoder(
             pts: frame_t,
         };
 
+        let avframe = framesource.as_avframe();
+        if let (Some(frame_cache), Some(fuid)) = (&context.frame_cache, &stream_meta.fuid) {
+            frame_cache.insert(fuid, &frame_t, filter::Frame::new_arc(avframe.clone()));
+        }
+
+        // Stopping before the end of the GOP leaves the decoder holding this frame, so a later run may pick up from here
+        let park = |framesource: av::framesource::FrameSource| {
+            if let (Some(registry), Some(fuid)) = (&context.decoder_registry, &stream_meta.fuid) {
+                registry.park(fuid, gop_idx, iframeref.pts, framesource);
+            }
+        };
+
+        let mut stalled = false;
         loop {
             let mut pool_ref = pool.0.lock();
             if pool_ref.terminate_decoders {
+                drop(pool_ref);
+                park(framesource);
                 return Ok(());
             }
 
@@ -279,21 +442,33 @@ pub(crate) fn run_decoder(
 
             if pool_ref.should_stall(&decoder_id) {
                 if pool_ref.should_decoder_abandon(&decoder_id) {
+                    stat.decoders_abandoned
+                        .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
                     pool_ref.decoders.remove(&decoder_id);
                     pool_ref
                         .finished_unjoined_decoders
                         .insert(decoder_id.clone());
                     pool.1.notify_all();
+                    drop(pool_ref);
+                    park(framesource);
                     return Ok(());
                 } else {
+                    if !stalled {
+                        stalled = true;
+                        stat.decoder_stalls
+                            .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                    }
+                    let wait_start = std::time::Instant::now();
                     pool.1.wait(&mut pool_ref);
+                    stat.decode.wait(wait_start);
                     continue;
                 }
             }
 
-            let avframe = framesource.as_avframe();
             debug!("Adding frame {}:{} to pool", source, iframeref.pts);
-            pool_ref.decoded(&decoder_id, iframeref.clone(), avframe);
+            pool_ref.decoded(&decoder_id, iframeref.clone(), avframe.clone());
+            stat.max_pool_bytes
+                .fetch_max(pool_ref.member_bytes(), std::sync::atomic::Ordering::SeqCst);
 
             let decoder_state = pool_ref.decoders.get_mut(&decoder_id).unwrap();
             decoder_state.future_frames.remove(&iframeref.pts);
@@ -312,11 +487,22 @@ pub(crate) fn run_decoder(
                 break;
             }
         }
+        busy_start = std::time::Instant::now();
     }
 
     Err(Error::AVError("Decoder ran out of frames".to_string()))
 }
 
+/// Most output frames sent to a filter thread at once
+const MAX_FILTER_BATCH: usize = 16;
+
+fn top_level_filter(frame: &crate::sir::FrameExpr) -> Option<&str> {
+    match frame {
+        crate::sir::FrameExpr::Filter(f) => Some(&f.name),
+        crate::sir::FrameExpr::Source(_) => None,
+    }
+}
+
 struct FilterTask {
     gen: usize,
     oframe_expr: crate::sir::FrameExpr,
@@ -325,33 +511,43 @@ struct FilterTask {
 
 struct FilterTaskResult {
     gen: usize,
-    oframe: Arc<AVFrame>,
+    output: Rendered,
+}
+
+/// What a filter thread rendered for one output frame
+enum Rendered {
+    Frame(Arc<AVFrame>),
+    /// The value of the top-level data filter, when analyzing
+    Data(filter::Val),
 }
 
 fn run_filter(
     context: &Context,
     config: &Config,
-    _stat: &StatRunner,
-    input_channel: crossbeam_channel::Receiver<Option<FilterTask>>,
+    stat: &StatRunner,
+    filter_cache: &FilterCache,
+    analysis: bool,
+    input_channel: crossbeam_channel::Receiver<Option<Vec<FilterTask>>>,
     output_channel: crossbeam_channel::Sender<FilterTaskResult>,
 ) -> Result<(), Error> {
     loop {
-        match input_channel.recv() {
-            Ok(Some(filter_task)) => {
-                debug!("Filtering gen {}", filter_task.gen);
-                let oframe = render_frame(
-                    context,
-                    config,
-                    &filter_task.oframe_expr,
-                    &filter_task.dep_frames,
-                )?;
-
-                let result = FilterTaskResult {
-                    gen: filter_task.gen,
-                    oframe,
-                };
+        let wait_start = std::time::Instant::now();
+        let batch = input_channel.recv();
+        stat.filter.wait(wait_start);
+        match batch {
+            Ok(Some(batch)) => {
+                debug!(
+                    "Filtering gens {}..={}",
+                    batch.first().unwrap().gen,
+                    batch.last().unwrap().gen
+                );
+                let busy_start = std::time::Instant::now();
+                let results = render_batch(context, config, stat, filter_cache, analysis, &batch)?;
+                stat.filter.busy(busy_start);
 
-                output_channel.send(result).unwrap();
+                for result in results {
+                    output_channel.send(result).unwrap();
+                }
             }
             Ok(None) => {
                 debug!("Filter received kill signal");
@@ -364,6 +560,110 @@ fn run_filter(
     }
 }
 
+/// Render a batch of output frames, calling the top-level filter they share once for the whole batch
+///
+/// When analyzing, the top-level filters are data filters, which are called once per output frame.
+fn render_batch(
+    context: &Context,
+    config: &Config,
+    stat: &StatRunner,
+    filter_cache: &FilterCache,
+    analysis: bool,
+    batch: &[FilterTask],
+) -> Result<Vec<FilterTaskResult>, Error> {
+    let mut outputs: Vec<Option<Rendered>> = (0..batch.len()).map(|_| None).collect();
+    let mut memos = Vec::with_capacity(batch.len());
+    let mut filter_name = None;
+    let mut calls = Vec::new();
+    // Index in the batch and cache key of each call
+    let mut call_targets = Vec::new();
+
+    for (idx, task) in batch.iter().enumerate() {
+        let mut memo = filter_cache.frame_memo(task.gen);
+        match &task.oframe_expr {
+            crate::sir::FrameExpr::Filter(f) if analysis => {
+                // Data values aren't cached, but the memo still has to step over the node
+                if let Some(memo) = memo.as_mut() {
+                    memo.enter();
+                }
+                let data_filter = context.data_filters.get(&f.name).unwrap();
+                let (args, kwargs) =
+                    render_filter_args(context, config, stat, f, &task.dep_frames, &mut memo)?;
+                let filter_start = std::time::Instant::now();
+                let val = data_filter.compute(&args, &kwargs)?;
+                stat.filter_called(&f.name, filter_start);
+                outputs[idx] = Some(Rendered::Data(val));
+            }
+            crate::sir::FrameExpr::Filter(f) => {
+                let cache_key = memo.as_mut().and_then(|memo| memo.enter());
+                let cached = cache_key.and_then(|key| memo.as_mut().unwrap().lookup(key));
+                if let Some(cached) = cached {
+                    outputs[idx] = Some(Rendered::Frame(cached));
+                } else {
+                    calls.push(render_filter_args(
+                        context,
+                        config,
+                        stat,
+                        f,
+                        &task.dep_frames,
+                        &mut memo,
+                    )?);
+                    call_targets.push((idx, cache_key));
+                    filter_name = Some(&f.name);
+                }
+            }
+            crate::sir::FrameExpr::Source(_) => {
+                outputs[idx] = Some(Rendered::Frame(render_frame(
+                    context,
+                    config,
+                    stat,
+                    &task.oframe_expr,
+                    &task.dep_frames,
+                    &mut memo,
+                )?));
+            }
+        }
+        memos.push(memo);
+    }
+
+    if let Some(filter_name) = filter_name {
+        let filter = context.filters.get(filter_name).unwrap();
+        let filter_start = std::time::Instant::now();
+        let frames = filter.filter_batch(&calls)?;
+        stat.filter_batch_called(filter_name, calls.len(), filter_start);
+        if frames.len() != calls.len() {
+            return Err(Error::FilterInternalError(format!(
+                "Filter `{}` returned {} frames for a batch of {} calls",
+                filter_name,
+                frames.len(),
+                calls.len()
+            )));
+        }
+
+        for ((idx, cache_key), frame) in call_targets.into_iter().zip(frames) {
+            let oframe = frame.into_avframe();
+            if let Some(key) = cache_key {
+                memos[idx].as_mut().unwrap().store(key, &oframe);
+            }
+            outputs[idx] = Some(Rendered::Frame(oframe));
+        }
+    }
+
+    for memo in memos.into_iter().flatten() {
+        stat.filter_cache_hits
+            .fetch_add(memo.hits, std::sync::atomic::Ordering::SeqCst);
+    }
+
+    Ok(batch
+        .iter()
+        .zip(outputs)
+        .map(|(task, output)| FilterTaskResult {
+            gen: task.gen,
+            output: output.unwrap(),
+        })
+        .collect())
+}
+
 #[derive(Ord, Eq, PartialEq, PartialOrd, Clone, Debug)]
 pub struct IFrameRef {
     pub sourceref: SourceRef,
@@ -380,13 +680,20 @@ pub struct IFrameRef {
 pub struct Config {
     /// The number of frames which can be fit in the decode pool
     pub decode_pool_size: usize,
+    /// The max bytes of decoded frames in the decode pool, if any
+    /// Fewer decoders and output frames are kept active when this would be exceeded
+    #[serde(default)]
+    pub decode_pool_bytes: Option<usize>,
     /// How many output frames can be active at once
     /// This also limits how many frames can be in the filters + encode buffer at once
     pub decoder_view: usize,
     /// How many decoders can be active at once
     pub decoders: usize,
-    /// How many filter threads to run
+    /// How many filter threads to run (at least 1)
     pub filterers: usize,
+    /// How many bytes of filter outputs which are shared between output frames to keep for reuse (0 disables)
+    #[serde(default)]
+    pub filter_cache_bytes: usize,
 
     pub output_width: usize,
     pub output_height: usize,
@@ -442,30 +749,165 @@ pub struct Stats {
     pub decoders_created: usize,
     pub frames_written: usize,
     pub frames_decoded: usize,
+    pub filter_cache_hits: usize,
+    /// Source frames taken from the context's frame cache instead of being decoded
+    pub frame_cache_hits: usize,
+    /// Peak bytes of decoded frames resident in the decode pool
+    pub max_pool_bytes: usize,
+    /// Times a decoder had a frame ready but had to wait for room in the pool
+    pub decoder_stalls: usize,
+    /// Decoders stopped early to make way for a decoder on a sooner-needed GOP
+    pub decoders_abandoned: usize,
+    /// Decoders picked up from the context's decoder registry instead of seeking to a keyframe
+    pub decoders_resumed: usize,
+    /// Time spent decoding and waiting on the pool, summed over decoder threads
+    pub decode: StageStats,
+    /// Time spent rendering frames and waiting for work, summed over filter threads
+    pub filter: StageStats,
+    /// Time spent encoding and muxing and waiting on filtered frames
+    pub encode: StageStats,
+    /// Calls and latency of each filter, by filter name
+    pub filters: BTreeMap<String, FilterStats>,
+    /// Bytes read by decoders from each source, by source name
+    pub bytes_read: BTreeMap<String, u64>,
     pub runtime: std::time::Duration,
 }
 
+/// Busy and waiting time of one stage of a run
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct StageStats {
+    pub busy: std::time::Duration,
+    pub wait: std::time::Duration,
+}
+
+/// Number of buckets in [`FilterStats::latency_histogram`]
+pub const FILTER_LATENCY_BUCKETS: usize = 24;
+
+/// Call count and latency of one filter over a run
+#[derive(Debug, Serialize, Clone)]
+pub struct FilterStats {
+    pub calls: usize,
+    pub total_latency: std::time::Duration,
+    /// Calls by latency, where bucket `i` counts calls taking under 2^i microseconds (and at least 2^(i-1))
+    /// The last bucket also counts anything slower
+    pub latency_histogram: [usize; FILTER_LATENCY_BUCKETS],
+}
+
+impl FilterStats {
+    fn new() -> Self {
+        FilterStats {
+            calls: 0,
+            total_latency: std::time::Duration::ZERO,
+            latency_histogram: [0; FILTER_LATENCY_BUCKETS],
+        }
+    }
+
+    fn record(&mut self, latency: std::time::Duration) {
+        self.calls += 1;
+        self.total_latency += latency;
+        let micros = latency.as_micros() as u64;
+        let bucket = (u64::BITS - micros.leading_zeros()) as usize;
+        self.latency_histogram[bucket.min(FILTER_LATENCY_BUCKETS - 1)] += 1;
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct StageTimer {
+    busy_ns: std::sync::atomic::AtomicU64,
+    wait_ns: std::sync::atomic::AtomicU64,
+}
+
+impl StageTimer {
+    pub(crate) fn busy(&self, since: std::time::Instant) {
+        self.busy_ns
+            .fetch_add(since.elapsed().as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn wait(&self, since: std::time::Instant) {
+        self.wait_ns
+            .fetch_add(since.elapsed().as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    fn stats(&self) -> StageStats {
+        StageStats {
+            busy: std::time::Duration::from_nanos(self.busy_ns.load(Ordering::SeqCst)),
+            wait: std::time::Duration::from_nanos(self.wait_ns.load(Ordering::SeqCst)),
+        }
+    }
+}
+
 pub(crate) struct StatRunner {
     max_decoder_count: std::sync::atomic::AtomicUsize,
     max_encode_buffer_size: std::sync::atomic::AtomicUsize,
     decoders_created: std::sync::atomic::AtomicUsize,
     frames_written: std::sync::atomic::AtomicUsize,
     frames_decoded: std::sync::atomic::AtomicUsize,
+    filter_cache_hits: std::sync::atomic::AtomicUsize,
+    frame_cache_hits: std::sync::atomic::AtomicUsize,
+    max_pool_bytes: std::sync::atomic::AtomicUsize,
+    decoder_stalls: std::sync::atomic::AtomicUsize,
+    decoders_abandoned: std::sync::atomic::AtomicUsize,
+    decoders_resumed: std::sync::atomic::AtomicUsize,
+    decode: StageTimer,
+    filter: StageTimer,
+    encode: StageTimer,
+    filters: Mutex<BTreeMap<String, FilterStats>>,
+    bytes_read: Mutex<BTreeMap<String, u64>>,
     start_time: std::time::Instant,
 }
 
 impl StatRunner {
-    fn new() -> Self {
+    pub(crate) fn new() -> Self {
         StatRunner {
             max_decoder_count: std::sync::atomic::AtomicUsize::new(0),
             max_encode_buffer_size: std::sync::atomic::AtomicUsize::new(0),
             decoders_created: std::sync::atomic::AtomicUsize::new(0),
             frames_written: std::sync::atomic::AtomicUsize::new(0),
             frames_decoded: std::sync::atomic::AtomicUsize::new(0),
+            filter_cache_hits: std::sync::atomic::AtomicUsize::new(0),
+            frame_cache_hits: std::sync::atomic::AtomicUsize::new(0),
+            max_pool_bytes: std::sync::atomic::AtomicUsize::new(0),
+            decoder_stalls: std::sync::atomic::AtomicUsize::new(0),
+            decoders_abandoned: std::sync::atomic::AtomicUsize::new(0),
+            decoders_resumed: std::sync::atomic::AtomicUsize::new(0),
+            decode: StageTimer::default(),
+            filter: StageTimer::default(),
+            encode: StageTimer::default(),
+            filters: Mutex::new(BTreeMap::new()),
+            bytes_read: Mutex::new(BTreeMap::new()),
             start_time: std::time::Instant::now(),
         }
     }
 
+    fn filter_called(&self, name: &str, since: std::time::Instant) {
+        let latency = since.elapsed();
+        self.filters
+            .lock()
+            .entry(name.to_string())
+            .or_insert_with(FilterStats::new)
+            .record(latency);
+    }
+
+    /// Record a batch of `calls` calls to a filter, splitting its latency evenly between them
+    fn filter_batch_called(&self, name: &str, calls: usize, since: std::time::Instant) {
+        let latency = since.elapsed() / calls as u32;
+        let mut filters = self.filters.lock();
+        let filter_stats = filters
+            .entry(name.to_string())
+            .or_insert_with(FilterStats::new);
+        for _ in 0..calls {
+            filter_stats.record(latency);
+        }
+    }
+
+    fn add_bytes_read(&self, source: &SourceRef, bytes: u64) {
+        *self
+            .bytes_read
+            .lock()
+            .entry(source.video.clone())
+            .or_default() += bytes;
+    }
+
     fn stats(&self) -> Stats {
         Stats {
             max_decoder_count: self
@@ -483,6 +925,29 @@ impl StatRunner {
             frames_decoded: self
                 .frames_decoded
                 .load(std::sync::atomic::Ordering::SeqCst),
+            filter_cache_hits: self
+                .filter_cache_hits
+                .load(std::sync::atomic::Ordering::SeqCst),
+            frame_cache_hits: self
+                .frame_cache_hits
+                .load(std::sync::atomic::Ordering::SeqCst),
+            max_pool_bytes: self
+                .max_pool_bytes
+                .load(std::sync::atomic::Ordering::SeqCst),
+            decoder_stalls: self
+                .decoder_stalls
+                .load(std::sync::atomic::Ordering::SeqCst),
+            decoders_abandoned: self
+                .decoders_abandoned
+                .load(std::sync::atomic::Ordering::SeqCst),
+            decoders_resumed: self
+                .decoders_resumed
+                .load(std::sync::atomic::Ordering::SeqCst),
+            decode: self.decode.stats(),
+            filter: self.filter.stats(),
+            encode: self.encode.stats(),
+            filters: self.filters.lock().clone(),
+            bytes_read: self.bytes_read.lock().clone(),
             runtime: std::time::Instant::now() - self.start_time,
         }
     }
@@ -556,6 +1021,9 @@ pub(crate) fn type_frame(
                 }
             }
 
+            if let Some(signature) = filter.signature() {
+                signature.check(&f.name, &args, &kwargs)?;
+            }
             Ok(filter.filter_type(&args, &kwargs)?)
         }
     }
@@ -566,7 +1034,9 @@ fn data_expr_to_val_for_render(
     data: &crate::sir::DataExpr,
     context: &Context,
     _config: &Config,
+    stat: &StatRunner,
     loaded_frames: &BTreeMap<IFrameRef, Arc<AVFrame>>,
+    memo: &mut Option<FrameMemo<'_>>,
 ) -> Result<filter::Val, Error> {
     match data {
         crate::sir::DataExpr::Bool(b) => Ok(filter::Val::Bool(*b)),
@@ -579,7 +1049,8 @@ fn data_expr_to_val_for_render(
             for item in list {
                 match item {
                     crate::sir::Expr::Frame(frame) => {
-                        let rendered = render_frame(context, _config, frame, loaded_frames)?;
+                        let rendered =
+                            render_frame(context, _config, stat, frame, loaded_frames, memo)?;
                         result.push(filter::Val::Frame(Frame::new_arc(rendered)));
                     }
                     crate::sir::Expr::Data(d) => {
@@ -587,7 +1058,9 @@ fn data_expr_to_val_for_render(
                             d,
                             context,
                             _config,
+                            stat,
                             loaded_frames,
+                            memo,
                         )?);
                     }
                 }
@@ -600,8 +1073,10 @@ fn data_expr_to_val_for_render(
 fn render_frame(
     context: &Context,
     _config: &Config,
+    stat: &StatRunner,
     frame: &crate::sir::FrameExpr,
     loaded_frames: &BTreeMap<IFrameRef, Arc<AVFrame>>,
+    memo: &mut Option<FrameMemo<'_>>,
 ) -> Result<Arc<AVFrame>, Error> {
     info!("Rendering frame {}", frame);
     match frame {
@@ -615,47 +1090,76 @@ fn render_frame(
             Ok(frame.clone())
         }
         crate::sir::FrameExpr::Filter(f) => {
-            let filter = context.filters.get(&f.name).unwrap();
-            let mut args = Vec::new();
-            let mut kwargs = BTreeMap::new();
-
-            // TODO: Very easy to parallelize these two loops
-            for arg in &f.args {
-                match arg {
-                    crate::sir::Expr::Frame(frame) => {
-                        let frame = render_frame(context, _config, frame, loaded_frames)?;
-                        args.push(crate::filter::Val::Frame(Frame::new_arc(frame)));
-                    }
-                    crate::sir::Expr::Data(data) => {
-                        args.push(data_expr_to_val_for_render(
-                            data,
-                            context,
-                            _config,
-                            loaded_frames,
-                        )?);
-                    }
+            let cache_key = memo.as_mut().and_then(|memo| memo.enter());
+            if let Some(key) = cache_key {
+                if let Some(frame) = memo.as_mut().unwrap().lookup(key) {
+                    return Ok(frame);
                 }
             }
-            for (k, v) in &f.kwargs {
-                match v {
-                    crate::sir::Expr::Frame(frame) => {
-                        let frame = render_frame(context, _config, frame, loaded_frames)?;
-                        kwargs.insert(k.clone(), crate::filter::Val::Frame(Frame::new_arc(frame)));
-                    }
-                    crate::sir::Expr::Data(data) => {
-                        kwargs.insert(
-                            k.clone(),
-                            data_expr_to_val_for_render(data, context, _config, loaded_frames)?,
-                        );
-                    }
-                }
+
+            let filter = context.filters.get(&f.name).unwrap();
+            let (args, kwargs) =
+                render_filter_args(context, _config, stat, f, loaded_frames, memo)?;
+
+            let filter_start = std::time::Instant::now();
+            let oframe = filter.filter(&args, &kwargs)?.into_avframe();
+            stat.filter_called(&f.name, filter_start);
+            if let Some(key) = cache_key {
+                memo.as_mut().unwrap().store(key, &oframe);
             }
 
-            let oframe = filter.filter(&args, &kwargs)?;
+            Ok(oframe)
+        }
+    }
+}
 
-            Ok(oframe.into_avframe())
+/// Render the arguments of a filter call
+fn render_filter_args(
+    context: &Context,
+    _config: &Config,
+    stat: &StatRunner,
+    f: &crate::sir::FilterExpr,
+    loaded_frames: &BTreeMap<IFrameRef, Arc<AVFrame>>,
+    memo: &mut Option<FrameMemo<'_>>,
+) -> Result<(Vec<filter::Val>, BTreeMap<String, filter::Val>), Error> {
+    let mut args = Vec::new();
+    let mut kwargs = BTreeMap::new();
+
+    // TODO: Very easy to parallelize these two loops
+    for arg in &f.args {
+        match arg {
+            crate::sir::Expr::Frame(frame) => {
+                let frame = render_frame(context, _config, stat, frame, loaded_frames, memo)?;
+                args.push(crate::filter::Val::Frame(Frame::new_arc(frame)));
+            }
+            crate::sir::Expr::Data(data) => {
+                args.push(data_expr_to_val_for_render(
+                    data,
+                    context,
+                    _config,
+                    stat,
+                    loaded_frames,
+                    memo,
+                )?);
+            }
         }
     }
+    for (k, v) in &f.kwargs {
+        match v {
+            crate::sir::Expr::Frame(frame) => {
+                let frame = render_frame(context, _config, stat, frame, loaded_frames, memo)?;
+                kwargs.insert(k.clone(), crate::filter::Val::Frame(Frame::new_arc(frame)));
+            }
+            crate::sir::Expr::Data(data) => {
+                kwargs.insert(
+                    k.clone(),
+                    data_expr_to_val_for_render(data, context, _config, stat, loaded_frames, memo)?,
+                );
+            }
+        }
+    }
+
+    Ok((args, kwargs))
 }
 
 /// Select whether the output timestamps are local to the ranged segment or the entire output
@@ -692,8 +1196,10 @@ impl EncodeBuffer {
     }
 }
 
-struct ExecContext {
+pub(crate) struct ExecContext {
     output_path: String,
+    /// Values of each output frame's data filter, when analyzing instead of encoding
+    data_rows: Option<Vec<(usize, filter::Val)>>,
     context: Arc<Context>,
     config: Arc<Config>,
 
@@ -702,10 +1208,11 @@ struct ExecContext {
 
     pool: Arc<(Mutex<Pool>, Condvar)>,
     output_time_base: num_rational::Ratio<i64>,
+    filter_cache: Arc<FilterCache>,
 
     to_filter_channel: (
-        crossbeam_channel::Sender<Option<FilterTask>>,
-        crossbeam_channel::Receiver<Option<FilterTask>>,
+        crossbeam_channel::Sender<Option<Vec<FilterTask>>>,
+        crossbeam_channel::Receiver<Option<Vec<FilterTask>>>,
     ),
     from_filter_channel: (
         crossbeam_channel::Sender<FilterTaskResult>,
@@ -724,8 +1231,18 @@ struct ExecContext {
 }
 
 impl ExecContext {
-    fn run(mut self) -> Result<Stats, Error> {
-        let enc_thread = {
+    /// The collected data values after an analysis run, by timestamp
+    pub(crate) fn take_data_rows(&mut self) -> Vec<(Rational64, filter::Val)> {
+        let mut rows = self.data_rows.take().unwrap_or_default();
+        rows.sort_by_key(|(gen, _)| *gen);
+        rows.into_iter()
+            .map(|(gen, val)| (self.process_span.ts[gen], val))
+            .collect()
+    }
+
+    pub(crate) fn run(&mut self) -> Result<Stats, Error> {
+        let analysis = self.data_rows.is_some();
+        let enc_thread = (!analysis).then(|| {
             let encode_buffer = self.encode_buffer.clone();
             let output_path = self.output_path.to_string();
             let config = self.config.clone();
@@ -746,16 +1263,26 @@ impl ExecContext {
                 debug!("Enc thread ended");
                 r
             })
-        };
+        });
 
         for _i in 0..self.config.filterers {
             let context = self.context.clone();
             let config = self.config.clone();
             let stat = self.stat.clone();
+            let filter_cache = self.filter_cache.clone();
             let receiver = self.to_filter_channel.1.clone();
             let sender = self.from_filter_channel.0.clone();
-            let filter_thread =
-                std::thread::spawn(move || run_filter(&context, &config, &stat, receiver, sender));
+            let filter_thread = std::thread::spawn(move || {
+                run_filter(
+                    &context,
+                    &config,
+                    &stat,
+                    &filter_cache,
+                    analysis,
+                    receiver,
+                    sender,
+                )
+            });
             self.filter_join_handles.push(filter_thread);
         }
 
@@ -797,7 +1324,7 @@ impl ExecContext {
 
             // Check if encoder finished
             {
-                if enc_thread.is_finished() {
+                if enc_thread.as_ref().is_some_and(|t| t.is_finished()) {
                     // This means the encoder had an error and we should stop
                     break;
                 }
@@ -913,7 +1440,7 @@ impl ExecContext {
             encode_buffer_ref.terminate_encoder = true;
             self.encode_buffer.1.notify_one();
         }
-        match enc_thread.join() {
+        match enc_thread.map_or(Ok(Ok(())), |t| t.join()) {
             Ok(enc_result) => {
                 match enc_result {
                     Ok(_) => {} // Thread finished successfully
@@ -930,6 +1457,10 @@ impl ExecContext {
         }
         debug!("Encoder finished");
 
+        self.stat
+            .frame_cache_hits
+            .store(self.pool.0.lock().frame_cache_hits, Ordering::SeqCst);
+
         match return_err {
             Some(e) => Err(e),
             None => Ok(self.stat.stats()),
@@ -944,7 +1475,6 @@ impl ExecContext {
                     debug_assert!(self.filtering_gens.contains(&result.gen));
                     self.filtering_gens.remove(&result.gen);
 
-                    let oframe = result.oframe;
                     let gen = result.gen;
 
                     {
@@ -954,15 +1484,20 @@ impl ExecContext {
                         self.pool.1.notify_all();
                     }
 
-                    {
-                        let mut encode_buffer_ref = self.encode_buffer.0.lock();
-                        encode_buffer_ref.members.push((gen, oframe));
-                        let encode_buffer_size = encode_buffer_ref.members.len();
-                        self.stat
-                            .max_encode_buffer_size
-                            .fetch_max(encode_buffer_size, Ordering::SeqCst);
-
-                        self.encode_buffer.1.notify_one();
+                    match result.output {
+                        Rendered::Frame(oframe) => {
+                            let mut encode_buffer_ref = self.encode_buffer.0.lock();
+                            encode_buffer_ref.members.push((gen, oframe));
+                            let encode_buffer_size = encode_buffer_ref.members.len();
+                            self.stat
+                                .max_encode_buffer_size
+                                .fetch_max(encode_buffer_size, Ordering::SeqCst);
+
+                            self.encode_buffer.1.notify_one();
+                        }
+                        Rendered::Data(val) => {
+                            self.data_rows.as_mut().unwrap().push((gen, val));
+                        }
                     }
 
                     self.frames_post_filtering += 1;
@@ -1016,14 +1551,29 @@ impl ExecContext {
             debug_assert!(self.decoder_count.load(Ordering::SeqCst) >= 0);
             let mut pool_ref = self.pool.0.lock();
             let new_decoder = pool_ref.new_decoder_gop();
-            if let Some((sourceref, gop_idx)) = new_decoder {
+            if let Some((sourceref, gop_idx, basis_pts)) = new_decoder {
                 let source = sourceref.clone();
                 let decoder_id = crate::util::rand_uuid();
                 let decoder_id_join_handle_copy = decoder_id.clone();
                 let pool = self.pool.clone();
                 let context = self.context.clone();
                 let stat = self.stat.clone();
-                let io_runtime_handle = self.io_runtime.handle().clone();
+                // Decoders which may be parked have to outlive this run's runtime
+                let io_runtime_handle = match &context.decoder_registry {
+                    Some(registry) => registry.io_runtime_handle().clone(),
+                    None => self.io_runtime.handle().clone(),
+                };
+
+                let mut future_frames = context.get_gop_frames(&source, gop_idx);
+                let parked = match (&context.decoder_registry, &context.sources[&source].fuid) {
+                    (Some(registry), Some(fuid)) => registry.resume(fuid, gop_idx, &basis_pts),
+                    _ => None,
+                };
+                let parked = parked.map(|(next_pts, framesource)| {
+                    future_frames = future_frames.split_off(&next_pts);
+                    stat.decoders_resumed.fetch_add(1, Ordering::SeqCst);
+                    framesource
+                });
 
                 let num_decoders = self.decoder_count.fetch_add(1, Ordering::SeqCst) + 1;
                 stat.max_decoder_count
@@ -1034,7 +1584,7 @@ impl ExecContext {
                 let decoder_state = DecoderState {
                     source: source.clone(),
                     gop_idx,
-                    future_frames: context.get_gop_frames(&source, gop_idx),
+                    future_frames,
                     past_frames: BTreeSet::new(),
                 };
                 pool_ref.decoders.insert(decoder_id.clone(), decoder_state);
@@ -1053,6 +1603,7 @@ impl ExecContext {
                             &pool,
                             decoder_id.clone(),
                             &io_runtime_handle,
+                            parked,
                         );
                         // TODO: Handle error
                         decoder_count.fetch_add(-1, Ordering::SeqCst);
@@ -1070,45 +1621,59 @@ impl ExecContext {
     }
 
     fn send_to_filters(&mut self) -> Result<(), Error> {
-        loop {
-            let gen_to_filter = {
-                let mut out = None;
-                let pool_ref = self.pool.0.lock();
-                for active_gen in pool_ref.active_gens() {
-                    if !self.filtering_gens.contains(&active_gen)
-                        && pool_ref.is_gen_ready(active_gen)
-                    {
-                        out = Some(active_gen);
-                        break;
-                    }
-                }
-                out
-            };
+        let ready_gens: Vec<usize> = {
+            let pool_ref = self.pool.0.lock();
+            pool_ref
+                .active_gens()
+                .into_iter()
+                .filter(|gen| !self.filtering_gens.contains(gen) && pool_ref.is_gen_ready(*gen))
+                .collect()
+        };
 
-            if gen_to_filter.is_none() {
-                break;
-            }
-            let gen_to_filter = gen_to_filter.unwrap();
-            self.filtering_gens.insert(gen_to_filter);
+        // Consecutive gens with the same top-level filter go out as one batch, but keep enough batches to go around the filterers
+        let max_batch = (ready_gens.len() / self.config.filterers).clamp(1, MAX_FILTER_BATCH);
+        let mut batch: Vec<FilterTask> = Vec::new();
+        for gen in ready_gens {
+            self.filtering_gens.insert(gen);
 
             let frame_deps = {
                 let pool_ref = self.pool.0.lock();
-                pool_ref.get_ready_gen_frames(gen_to_filter)
+                pool_ref.get_ready_gen_frames(gen)
             };
 
             let filter_task = FilterTask {
-                gen: gen_to_filter,
-                oframe_expr: self.process_span.frames[gen_to_filter].clone(),
+                gen,
+                oframe_expr: self.process_span.frames[gen].clone(),
                 dep_frames: frame_deps,
             };
 
-            debug!("Sending gen {} to filter", gen_to_filter);
-
-            self.to_filter_channel.0.send(Some(filter_task)).unwrap();
+            if let Some(last) = batch.last() {
+                if batch.len() == max_batch
+                    || last.gen + 1 != gen
+                    || top_level_filter(&last.oframe_expr).is_none()
+                    || top_level_filter(&last.oframe_expr)
+                        != top_level_filter(&filter_task.oframe_expr)
+                {
+                    self.send_batch(std::mem::take(&mut batch));
+                }
+            }
+            batch.push(filter_task);
+        }
+        if !batch.is_empty() {
+            self.send_batch(batch);
         }
 
         Ok(())
     }
+
+    fn send_batch(&self, batch: Vec<FilterTask>) {
+        debug!(
+            "Sending gens {}..={} to filter",
+            batch.first().unwrap().gen,
+            batch.last().unwrap().gen
+        );
+        self.to_filter_channel.0.send(Some(batch)).unwrap();
+    }
 }
 
 /// Execute a spec, or a range of a spec.
@@ -1119,12 +1684,7 @@ pub fn run(
     config: &Arc<Config>,
     range: &Option<Range>,
 ) -> Result<Stats, Error> {
-    if config.decoders > u16::MAX as usize {
-        // yes this is arbitrary, but bad things happen if do something like usize::MAX because we track counts with a i64 internally
-        return Err(Error::ConfigError(
-            "Decoders must be less than u16::MAX".to_string(),
-        ));
-    }
+    check_workers(config)?;
 
     // Make sure the encoder config is valid
     if let Some(enc_cfg) = &config.encoder {
@@ -1132,23 +1692,41 @@ pub fn run(
     }
 
     let stat = Arc::new(StatRunner::new());
-    let expected_output_type = config.expected_output_type();
 
-    let process_span = Arc::new(crate::sir::ProcessSpan::create(
-        spec.as_ref().as_ref(),
-        context,
-        range,
-    ));
-
-    // Type check frames
-    for oframe in &process_span.frames {
-        let frame_type = type_frame(context, config, oframe)?;
-        if frame_type != expected_output_type {
-            return Err(Error::InvalidOutputFrameType);
-        }
+    let mut process_span = create_checked_span(spec, context, config, range)?;
+    crate::optimizer::optimize(&mut process_span, context, config)?;
+
+    exec_context(stat, process_span, output_path, None, context, config)?.run()
+}
+
+pub(crate) fn check_workers(config: &Config) -> Result<(), Error> {
+    if config.decoders > u16::MAX as usize {
+        // yes this is arbitrary, but bad things happen if do something like usize::MAX because we track counts with a i64 internally
+        return Err(Error::ConfigError(
+            "Decoders must be less than u16::MAX".to_string(),
+        ));
     }
+    if config.filterers == 0 {
+        return Err(Error::ConfigError(
+            "Filterers must be at least 1".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Set up the execution of an optimized span, which encodes to `output_path` unless `data_rows` is given
+pub(crate) fn exec_context(
+    stat: Arc<StatRunner>,
+    process_span: sir::ProcessSpan,
+    output_path: &str,
+    data_rows: Option<Vec<(usize, filter::Val)>>,
+    context: &Arc<Context>,
+    config: &Arc<Config>,
+) -> Result<ExecContext, Error> {
+    let process_span = Arc::new(process_span);
 
-    let (pool, output_time_base) = build_pool(&process_span, config, context)?;
+    let filter_cache = Arc::new(plan_filter_cache(&process_span, context, config));
+    let (pool, output_time_base) = build_pool(&process_span, &filter_cache, config, context)?;
 
     let pool = Arc::new((Mutex::new(pool), Condvar::new()));
 
@@ -1166,14 +1744,16 @@ pub fn run(
         .build()
         .unwrap();
 
-    let exec_contex = ExecContext {
+    Ok(ExecContext {
         output_path: output_path.to_string(),
+        data_rows,
         context: context.clone(),
         config: config.clone(),
-        stat: stat.clone(),
+        stat,
         process_span,
         pool,
         output_time_base,
+        filter_cache,
         to_filter_channel: crossbeam_channel::unbounded(),
         from_filter_channel: crossbeam_channel::unbounded(),
         decoder_count,
@@ -1183,36 +1763,64 @@ pub fn run(
         encode_buffer,
         dec_join_handles,
         filter_join_handles,
-    };
-
-    exec_contex.run()
+    })
 }
 
-/// Validate that a spec can be run.
-pub fn validate(
+/// Render a spec and type check its frames
+pub(crate) fn create_checked_span(
     spec: &Arc<Box<dyn Spec>>,
     context: &Arc<Context>,
     config: &Arc<Config>,
-) -> Result<(), Error> {
+    range: &Option<Range>,
+) -> Result<sir::ProcessSpan, Error> {
     let expected_output_type = config.expected_output_type();
 
-    let process_span = Arc::new(crate::sir::ProcessSpan::create(
-        spec.as_ref().as_ref(),
-        context,
-        &None,
-    ));
-
-    // Type check frames
-    for oframe in &process_span.frames {
-        let frame_type = type_frame(context, config, oframe)?;
-        if frame_type != expected_output_type {
-            return Err(Error::InvalidOutputFrameType);
+    let process_span = crate::sir::ProcessSpan::create(spec.as_ref().as_ref(), context, range);
+
+    // Type check frames, reporting the error of the first bad frame
+    let err = process_span.frames.par_iter().find_map_first(|oframe| {
+        match type_frame(context, config, oframe) {
+            Ok(frame_type) if frame_type == expected_output_type => None,
+            Ok(_) => Some(Error::InvalidOutputFrameType),
+            Err(err) => Some(err),
         }
+    });
+    if let Some(err) = err {
+        return Err(err);
     }
 
+    Ok(process_span)
+}
+
+/// Validate that a spec can be run.
+pub fn validate(
+    spec: &Arc<Box<dyn Spec>>,
+    context: &Arc<Context>,
+    config: &Arc<Config>,
+) -> Result<(), Error> {
+    create_checked_span(spec, context, config, &None)?;
     Ok(())
 }
 
+/// Returns a spec's frames as they are executed, after optimization.
+///
+/// This is meant for debugging; the result can be printed or serialized like any other [`JsonSpec`](crate::spec::JsonSpec).
+pub fn optimized_spec(
+    spec: &Arc<Box<dyn Spec>>,
+    context: &Arc<Context>,
+    config: &Arc<Config>,
+) -> Result<crate::spec::JsonSpec, Error> {
+    let mut process_span = create_checked_span(spec, context, config, &None)?;
+    crate::optimizer::optimize(&mut process_span, context, config)?;
+    Ok(crate::spec::JsonSpec {
+        frames: process_span
+            .ts
+            .into_iter()
+            .zip(process_span.frames)
+            .collect(),
+    })
+}
+
 fn encoder_thread(
     config: Arc<Config>,
     stat: Arc<StatRunner>,
@@ -1258,6 +1866,7 @@ fn encoder_thread(
                 break;
             }
 
+            let wait_start = std::time::Instant::now();
             let mut encode_buffer_ref = encode_buffer.0.lock();
 
             if encode_buffer_ref.terminate_encoder {
@@ -1271,6 +1880,8 @@ fn encoder_thread(
 
             if let Some(target_index) = target_index {
                 let (gen, frame) = encode_buffer_ref.members.remove(target_index);
+                drop(encode_buffer_ref);
+                let busy_start = std::time::Instant::now();
 
                 let pts = match process_span.output_ts_offset {
                     Some(offset) => process_span.ts[gen] - offset,
@@ -1278,13 +1889,16 @@ fn encoder_thread(
                 };
                 encoder.encode(&pts, &frame)?;
                 oframe_next += 1;
+                stat.encode.busy(busy_start);
             } else {
                 encode_buffer.1.wait(&mut encode_buffer_ref);
+                stat.encode.wait(wait_start);
                 continue;
             }
         }
 
         // Mux frames
+        let busy_start = std::time::Instant::now();
         if let Some(packet) = encoder.get_packet() {
             unsafe {
                 (*packet).pts *= *encoder_to_muxer_ts_multiplier.numer();
@@ -1295,8 +1909,10 @@ fn encoder_thread(
                 .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
             muxer.mux_packet(packet)?;
         }
+        stat.encode.busy(busy_start);
     }
 
+    let busy_start = std::time::Instant::now();
     encoder.flush()?;
 
     // Mux frames
@@ -1313,6 +1929,7 @@ fn encoder_thread(
 
     encoder.close();
     muxer.close()?;
+    stat.encode.busy(busy_start);
 
     unsafe {
         ffi::avcodec_parameters_free(&mut encoder_codec_params);
@@ -1321,44 +1938,69 @@ fn encoder_thread(
     Ok(())
 }
 
-fn build_pool(
+/// Plan the filter cache for a span, pinning shared nodes by the size of their output type
+pub(crate) fn plan_filter_cache(
     process_span: &sir::ProcessSpan,
+    context: &Context,
+    config: &Config,
+) -> FilterCache {
+    FilterCache::plan(process_span, config.filter_cache_bytes, |node| {
+        let frame_type = type_frame(context, config, node).ok()?;
+        let size = unsafe {
+            ffi::av_image_get_buffer_size(
+                frame_type.format,
+                frame_type.width as i32,
+                frame_type.height as i32,
+                1,
+            )
+        };
+        usize::try_from(size).ok()
+    })
+}
+
+pub(crate) fn build_pool(
+    process_span: &sir::ProcessSpan,
+    filter_cache: &FilterCache,
     config: &Arc<Config>,
     context: &Arc<Context>,
 ) -> Result<(Pool, num_rational::Ratio<i64>), Error> {
-    let mut iframes_per_oframe: Vec<BTreeSet<IFrameRef>> =
-        Vec::<BTreeSet<IFrameRef>>::with_capacity(process_span.frames.len());
-    let mut iframe_refs_in_out_idx: BTreeMap<IFrameRef, BTreeSet<usize>> = BTreeMap::new();
-
-    for (oframe_i, oframe) in process_span.frames.iter().enumerate() {
-        let mut frame_deps = std::collections::BTreeSet::new();
-        oframe.add_source_deps(&mut frame_deps);
-        assert!(
-            frame_deps.len() <= config.decode_pool_size,
-            "OFrame {} has too many dependencies ({}) for decode pool size {}",
-            oframe_i,
-            frame_deps.len(),
-            config.decode_pool_size
-        );
-
-        let mut iframe_refs = BTreeSet::new();
-        for dep in &frame_deps {
-            let (source_ref, t) = context.resolve_frame_source(dep)?;
+    let iframes_per_oframe: Vec<BTreeSet<IFrameRef>> = process_span
+        .frames
+        .par_iter()
+        .enumerate()
+        .map(|(oframe_i, oframe)| {
+            let mut frame_deps = std::collections::BTreeSet::new();
+            filter_cache.add_source_deps(oframe_i, oframe, &mut frame_deps);
+            assert!(
+                frame_deps.len() <= config.decode_pool_size,
+                "OFrame {} has too many dependencies ({}) for decode pool size {}",
+                oframe_i,
+                frame_deps.len(),
+                config.decode_pool_size
+            );
 
-            let iframe_ref = IFrameRef {
-                sourceref: source_ref.clone(),
-                pts: t,
-            };
-            iframe_refs.insert(iframe_ref.clone());
+            let mut iframe_refs = BTreeSet::new();
+            for dep in &frame_deps {
+                let (source_ref, t) = context.resolve_frame_source(dep)?;
+                iframe_refs.insert(IFrameRef {
+                    sourceref: source_ref,
+                    pts: t,
+                });
+            }
+            Ok(iframe_refs)
+        })
+        .collect::<Result<_, Error>>()?;
+    debug_assert_eq!(iframes_per_oframe.len(), process_span.frames.len());
 
+    let mut iframe_refs_in_out_idx: BTreeMap<IFrameRef, BTreeSet<usize>> = BTreeMap::new();
+    for (oframe_i, iframe_refs) in iframes_per_oframe.iter().enumerate() {
+        for iframe_ref in iframe_refs {
             iframe_refs_in_out_idx
-                .entry(iframe_ref)
+                .entry(iframe_ref.clone())
                 .or_default()
                 .insert(oframe_i);
         }
-        iframes_per_oframe.push(iframe_refs);
     }
-    debug_assert_eq!(iframes_per_oframe.len(), process_span.frames.len());
 
     let output_time_base = {
         let mut lcm = *process_span.ts[0].denom();
@@ -1368,9 +2010,14 @@ fn build_pool(
         Rational64::new(1, lcm)
     };
 
+    let waits_on = (0..process_span.frames.len())
+        .map(|gen| filter_cache.waits_on(gen))
+        .collect();
+
     let pool = crate::pool::Pool::new(
         iframes_per_oframe,
         iframe_refs_in_out_idx,
+        waits_on,
         context.clone(),
         config.clone(),
     )?;
@@ -0,0 +1,263 @@
+//! Dry runs of a spec
+//!
+//! [`explain`] plans a run exactly as [`run`](crate::run) would, then steps the decode pool through it without opening any files.
+//! Decoders are advanced one frame at a time in lockstep and output frames are rendered as soon as they are ready, so the
+//! numbers describe the plan the scheduler follows rather than the timing of a real run.
+
+use crate::dve::{
+    build_pool, create_checked_span, plan_filter_cache, AVFrame, Config, Context, DecoderState,
+    Error, IFrameRef, Range, SourceRef,
+};
+use crate::filter_cache::FilterCache;
+use crate::sir::{DataExpr, Expr, FrameExpr, ProcessSpan};
+use crate::source::SourceVideoStreamMeta;
+use crate::spec::Spec;
+use rusty_ffmpeg::ffi;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Arc;
+
+/// The decode plan of a spec, as returned by [`explain`]
+#[derive(Debug, Serialize)]
+pub struct Plan {
+    /// Number of output frames
+    pub frames: usize,
+    /// Per-source breakdown, by source name
+    pub sources: BTreeMap<String, SourcePlan>,
+    /// Source frames decoded, including frames decoded only to reach a later frame in the GOP
+    pub frames_decoded: usize,
+    /// Distinct source frames the output depends on
+    pub frames_used: usize,
+    /// Most decoded frames held in the decode pool at once
+    pub max_pool_size: usize,
+    /// Most bytes of decoded frames held in the decode pool at once, from each source's expected frame size
+    pub max_pool_bytes: usize,
+    pub max_decoder_count: usize,
+    pub decoders_created: usize,
+    /// Source frames taken from the context's frame cache instead of being decoded
+    pub frame_cache_hits: usize,
+    /// How many times each filter is invoked, by filter name
+    pub filter_calls: BTreeMap<String, usize>,
+    /// Filter invocations saved by the filter cache, assuming it has room for every shared output
+    pub filter_cache_hits: usize,
+    /// Estimated bytes read from sources
+    pub bytes_read: u64,
+}
+
+/// The part of a [`Plan`] for a single source
+#[derive(Debug, Serialize, Default)]
+pub struct SourcePlan {
+    /// Index of each GOP a decoder is opened on, in the order they are opened
+    ///
+    /// A GOP appears more than once if it is evicted and decoded again.
+    pub gops: Vec<usize>,
+    pub frames_decoded: usize,
+    pub frames_used: usize,
+    /// Estimated bytes read, assuming each frame takes an equal share of the file
+    pub bytes_read: u64,
+}
+
+/// Plan a run of a spec, or a range of a spec, without decoding anything
+///
+/// This renders, type checks, and optimizes the spec like [`run`](crate::run), so it fails in the same cases.
+pub fn explain(
+    spec: &Arc<Box<dyn Spec>>,
+    context: &Arc<Context>,
+    config: &Arc<Config>,
+    range: &Option<Range>,
+) -> Result<Plan, Error> {
+    let mut process_span = create_checked_span(spec, context, config, range)?;
+    crate::optimizer::optimize(&mut process_span, context, config)?;
+    let filter_cache = plan_filter_cache(&process_span, context, config);
+    let (mut pool, _) = build_pool(&process_span, &filter_cache, config, context)?;
+
+    let mut plan = Plan {
+        frames: process_span.frames.len(),
+        sources: BTreeMap::new(),
+        frames_decoded: 0,
+        frames_used: 0,
+        max_pool_size: 0,
+        max_pool_bytes: 0,
+        max_decoder_count: 0,
+        decoders_created: 0,
+        frame_cache_hits: 0,
+        filter_calls: BTreeMap::new(),
+        filter_cache_hits: 0,
+        bytes_read: 0,
+    };
+
+    for iframe in pool.iframe_refs() {
+        plan.frames_used += 1;
+        plan.sources
+            .entry(iframe.sourceref.to_string())
+            .or_default()
+            .frames_used += 1;
+    }
+
+    // The pool only tracks which frames it holds and their sizes, so members from a source can share one bufferless frame
+    let placeholders: BTreeMap<SourceRef, Arc<AVFrame>> = context
+        .sources
+        .iter()
+        .map(|(sourceref, source)| (sourceref.clone(), placeholder_frame(source)))
+        .collect();
+
+    let mut frames_done = 0;
+    while frames_done < process_span.frames.len() {
+        let mut progress = false;
+
+        while let Some((sourceref, gop_idx, _)) = pool.new_decoder_gop() {
+            let future_frames = context.get_gop_frames(&sourceref, gop_idx);
+            let source = plan.sources.entry(sourceref.to_string()).or_default();
+            let stream_meta = &context.sources[&sourceref];
+            source.gops.push(gop_idx);
+            source.bytes_read +=
+                stream_meta.file_size * future_frames.len() as u64 / stream_meta.ts.len() as u64;
+
+            pool.decoders.insert(
+                plan.decoders_created.to_string(),
+                DecoderState {
+                    source: sourceref,
+                    gop_idx,
+                    future_frames,
+                    past_frames: Default::default(),
+                },
+            );
+            plan.decoders_created += 1;
+            progress = true;
+        }
+        plan.max_decoder_count = plan.max_decoder_count.max(pool.decoders.len());
+
+        let decoder_ids: Vec<String> = pool.decoders.keys().cloned().collect();
+        for decoder_id in decoder_ids {
+            let decoder = &pool.decoders[&decoder_id];
+            let source = plan.sources.get_mut(&decoder.source.to_string()).unwrap();
+            if pool.should_stall(&decoder_id) {
+                if pool.should_decoder_abandon(&decoder_id) {
+                    // The frame it was holding was still decoded
+                    source.frames_decoded += 1;
+                    pool.decoders.remove(&decoder_id);
+                    progress = true;
+                }
+                continue;
+            }
+
+            let pts = *decoder.future_frames.first().unwrap();
+            let iframe = IFrameRef {
+                sourceref: decoder.source.clone(),
+                pts,
+            };
+            let placeholder = placeholders[&decoder.source].clone();
+            pool.decoded(&decoder_id, iframe, placeholder);
+            source.frames_decoded += 1;
+            plan.max_pool_size = plan.max_pool_size.max(pool.member_count());
+            plan.max_pool_bytes = plan.max_pool_bytes.max(pool.member_bytes());
+
+            let decoder = pool.decoders.get_mut(&decoder_id).unwrap();
+            decoder.future_frames.remove(&pts);
+            decoder.past_frames.insert(pts);
+            if decoder.future_frames.is_empty() {
+                pool.decoders.remove(&decoder_id);
+            }
+            progress = true;
+        }
+
+        for gen in pool.active_gens() {
+            if pool.is_gen_ready(gen) {
+                pool.finish_gen(gen);
+                frames_done += 1;
+                progress = true;
+            }
+        }
+
+        if !progress {
+            return Err(Error::Unknown(format!(
+                "Decode plan stalled after {} of {} output frames",
+                frames_done,
+                process_span.frames.len()
+            )));
+        }
+    }
+
+    plan.frame_cache_hits = pool.frame_cache_hits;
+    for source in plan.sources.values() {
+        plan.frames_decoded += source.frames_decoded;
+        plan.bytes_read += source.bytes_read;
+    }
+
+    count_filter_calls(&process_span, &filter_cache, &mut plan);
+
+    Ok(plan)
+}
+
+/// A frame without data whose [`AVFrame::size_bytes`] is the source's expected frame size
+fn placeholder_frame(source: &SourceVideoStreamMeta) -> Arc<AVFrame> {
+    let (width, height) = source.resolution;
+    let format = match crate::util::pixel_fmt_str_to_av_pix_fmt(&source.pix_fmt) {
+        Ok(format) if crate::util::image_size_bytes(&source.pix_fmt, width, height).is_some() => {
+            format
+        }
+        // Matches the 4 bytes per pixel the pool assumes for unknown formats
+        _ => ffi::AVPixelFormat_AV_PIX_FMT_RGBA,
+    };
+
+    let f = unsafe { ffi::av_frame_alloc() };
+    if f.is_null() {
+        panic!("ERROR could not allocate frame");
+    }
+    unsafe {
+        (*f).width = width as i32;
+        (*f).height = height as i32;
+        (*f).format = format;
+    }
+    let frame = Arc::new(AVFrame { inner: f });
+    debug_assert_eq!(
+        frame.size_bytes(),
+        crate::pool::expected_frame_bytes(source)
+    );
+    frame
+}
+
+fn count_filter_calls(process_span: &ProcessSpan, filter_cache: &FilterCache, plan: &mut Plan) {
+    let mut rendered = HashSet::new();
+
+    for frame in &process_span.frames {
+        let hashes = frame.filter_hashes();
+        let mut names = Vec::with_capacity(hashes.len());
+        frame_filter_names(frame, &mut names);
+        debug_assert_eq!(hashes.len(), names.len());
+
+        let mut idx = 0;
+        while idx < hashes.len() {
+            let (hash, descendants) = hashes[idx];
+            if filter_cache.is_shared(hash) && !rendered.insert(hash) {
+                plan.filter_cache_hits += 1;
+                idx += descendants + 1;
+                continue;
+            }
+            *plan.filter_calls.entry(names[idx].to_string()).or_default() += 1;
+            idx += 1;
+        }
+    }
+}
+
+/// Filter names in the same order as [`FrameExpr::filter_hashes`]
+fn frame_filter_names<'a>(frame: &'a FrameExpr, out: &mut Vec<&'a str>) {
+    if let FrameExpr::Filter(filter) = frame {
+        out.push(&filter.name);
+        for arg in filter.args.iter().chain(filter.kwargs.values()) {
+            expr_filter_names(arg, out);
+        }
+    }
+}
+
+fn expr_filter_names<'a>(expr: &'a Expr, out: &mut Vec<&'a str>) {
+    match expr {
+        Expr::Frame(frame) => frame_filter_names(frame, out),
+        Expr::Data(DataExpr::List(list)) => {
+            for item in list {
+                expr_filter_names(item, out);
+            }
+        }
+        Expr::Data(_) => {}
+    }
+}
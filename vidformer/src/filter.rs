@@ -10,8 +10,16 @@ use std::collections::BTreeMap;
 use std::sync::Arc;
 
 pub mod builtin;
+pub mod color;
 pub mod cv2;
 mod filter_utils;
+pub mod plugin;
+mod signature;
+pub mod subtitle;
+pub mod text;
+pub mod transition;
+
+pub use signature::{signatures, Param, ParamKind, ParamType, Signature};
 
 /// A decoded video frame
 #[derive(Clone)]
@@ -135,7 +143,9 @@ impl Val {
 }
 
 /// A filter that can create a video frame from some inputs
-pub trait Filter: Send + Sync {
+///
+/// Filters are [`Any`](std::any::Any) so the optimizer can tell a builtin filter from another registered under the same name.
+pub trait Filter: Send + Sync + std::any::Any {
     /// Creates a video frame from some inputs
     ///
     /// The inputs are arbitrary and user provided. An input can either be
@@ -146,9 +156,43 @@ pub trait Filter: Send + Sync {
         kwargs: &BTreeMap<String, Val>,
     ) -> Result<Frame, crate::dve::Error>;
 
+    /// Creates a video frame for each of several sets of inputs
+    ///
+    /// Filter threads call this with consecutive output frames which share this filter at their top level,
+    /// so filters with costly per-call setup can do it once per batch.
+    /// Returns one frame per call, in order. By default this calls [`Filter::filter`] on each.
+    fn filter_batch(
+        &self,
+        calls: &[(Vec<Val>, BTreeMap<String, Val>)],
+    ) -> Result<Vec<Frame>, crate::dve::Error> {
+        calls
+            .iter()
+            .map(|(args, kwargs)| self.filter(args, kwargs))
+            .collect()
+    }
+
     fn filter_type(
         &self,
         args: &[Val],
         kwargs: &BTreeMap<String, Val>,
     ) -> Result<FrameType, crate::dve::Error>;
+
+    /// The arguments this filter accepts, if it declares them
+    ///
+    /// When declared, calls are checked against the signature before [`Filter::filter_type`] is called.
+    fn signature(&self) -> Option<Signature> {
+        None
+    }
+}
+
+/// A filter that computes a data value, such as a statistic of a frame, from some inputs
+///
+/// Data filters are called at the top level of a [`DataSpec`](crate::spec::DataSpec) when running [`analyze`](crate::analyze).
+/// They take the same kinds of inputs as a [`Filter`], so frames passed to them can be rendered by other filters first.
+pub trait DataFilter: Send + Sync {
+    fn compute(
+        &self,
+        args: &[Val],
+        kwargs: &BTreeMap<String, Val>,
+    ) -> Result<Val, crate::dve::Error>;
 }
@@ -28,6 +28,13 @@ pub fn filters() -> BTreeMap<String, std::boxed::Box<dyn Filter>> {
     filters
 }
 
+/// The builtin data filters, for [`analyze`](crate::analyze)
+pub fn data_filters() -> BTreeMap<String, std::boxed::Box<dyn DataFilter>> {
+    let mut filters: BTreeMap<String, std::boxed::Box<dyn DataFilter>> = BTreeMap::new();
+    filters.insert("MeanLuma".to_string(), std::boxed::Box::new(MeanLuma {}));
+    filters
+}
+
 pub struct PlaceholderFrame {}
 impl super::Filter for PlaceholderFrame {
     fn filter(
@@ -83,6 +90,16 @@ impl super::Filter for PlaceholderFrame {
             ffi::AVPixelFormat_AV_PIX_FMT_YUV420P,
         ))
     }
+
+    fn signature(&self) -> Option<Signature> {
+        Some(
+            Signature::new(vec![
+                Param::keyword_only("width", ParamType::Int),
+                Param::keyword_only("height", ParamType::Int),
+            ])
+            .with_doc("A test pattern frame"),
+        )
+    }
 }
 
 /// Scale & convert pixel format of a frame
@@ -266,6 +283,34 @@ impl super::Filter for Scale {
             _ => Err(Error::MissingFilterArg),
         }
     }
+
+    fn signature(&self) -> Option<Signature> {
+        Some(
+            Signature::new(vec![
+                Param::positional_only("frame", ParamType::Frame),
+                Param::keyword_only("width", ParamType::Int)
+                    .optional()
+                    .with_doc("Width of the output frame, required if `height` is given"),
+                Param::keyword_only("height", ParamType::Int)
+                    .optional()
+                    .with_doc("Height of the output frame, required if `width` is given"),
+                Param::keyword_only("pix_fmt", ParamType::String)
+                    .optional()
+                    .with_doc("Pixel format of the output frame"),
+            ])
+            .with_doc("Scale & convert pixel format of a frame"),
+        )
+    }
+}
+
+/// The pixel format and bytes per pixel of an [`InlineMat`] pixel format name
+fn inline_mat_pix_fmt(pix_fmt: &str) -> Option<(ffi::AVPixelFormat, usize)> {
+    match pix_fmt {
+        "rgb24" => Some((ffi::AVPixelFormat_AV_PIX_FMT_RGB24, 3)),
+        "rgba" => Some((ffi::AVPixelFormat_AV_PIX_FMT_RGBA, 4)),
+        "gray" => Some((ffi::AVPixelFormat_AV_PIX_FMT_GRAY8, 1)),
+        _ => None,
+    }
 }
 
 pub struct InlineMat;
@@ -285,6 +330,11 @@ impl super::Filter for InlineMat {
             _ => panic!("Expected int"),
         };
 
+        let (format, bytes_per_pixel) = match kwargs.get("pix_fmt") {
+            Some(Val::String(s)) => inline_mat_pix_fmt(s).expect("Invalid pixel format"),
+            _ => panic!("Expected string"),
+        };
+
         let compression = match kwargs.get("compression") {
             Some(Val::String(s)) => Option::Some(s),
             _ => Option::None,
@@ -303,7 +353,7 @@ impl super::Filter for InlineMat {
             use std::io::Read;
             decoder.read_to_end(&mut decompressed).unwrap();
             data = &decompressed;
-            if data.len() != width as usize * height as usize * 3 {
+            if data.len() != width as usize * height as usize * bytes_per_pixel {
                 return Err(Error::InvalidFilterArgValue(
                     format!("{:?}", data.len()),
                     "Invalid data length".to_string(),
@@ -319,7 +369,7 @@ impl super::Filter for InlineMat {
         unsafe {
             (*f).width = width as i32;
             (*f).height = height as i32;
-            (*f).format = ffi::AVPixelFormat_AV_PIX_FMT_RGB24;
+            (*f).format = format;
 
             if ffi::av_frame_get_buffer(f, 0) < 0 {
                 panic!("ERROR could not allocate frame data");
@@ -329,7 +379,7 @@ impl super::Filter for InlineMat {
         // check if ffmpeg wants padding
         let linesize = unsafe { (*f).linesize[0] as usize };
         let data_len = data.len();
-        let data_linesize = width as usize * 3;
+        let data_linesize = width as usize * bytes_per_pixel;
 
         if linesize != data_linesize {
             for i in 0..height as usize {
@@ -384,12 +434,15 @@ impl super::Filter for InlineMat {
             }
         }
 
-        if pix_fmt != "rgb24" {
-            return Err(Error::InvalidFilterArgValue(
-                pix_fmt.clone(),
-                "Invalid pixel format".to_string(),
-            ));
-        }
+        let (format, bytes_per_pixel) = match inline_mat_pix_fmt(pix_fmt) {
+            Some(pix_fmt) => pix_fmt,
+            None => {
+                return Err(Error::InvalidFilterArgValue(
+                    pix_fmt.clone(),
+                    "Invalid pixel format".to_string(),
+                ))
+            }
+        };
 
         if args.is_empty() {
             return Err(Error::MissingFilterArg);
@@ -408,7 +461,8 @@ impl super::Filter for InlineMat {
         };
 
         // check if data length matches width, height, and pix_fmt
-        if compression.is_none() && data.len() != width as usize * height as usize * 3 {
+        if compression.is_none() && data.len() != width as usize * height as usize * bytes_per_pixel
+        {
             return Err(Error::InvalidFilterArgValue(
                 format!("{:?}", data.len()),
                 "Invalid data length".to_string(),
@@ -418,9 +472,25 @@ impl super::Filter for InlineMat {
         Ok(FrameType {
             width: width as usize,
             height: height as usize,
-            format: ffi::AVPixelFormat_AV_PIX_FMT_RGB24,
+            format,
         })
     }
+
+    fn signature(&self) -> Option<Signature> {
+        Some(
+            Signature::new(vec![
+                Param::positional_only("data", ParamType::Bytes)
+                    .with_doc("The pixel data, row by row"),
+                Param::keyword_only("width", ParamType::Int),
+                Param::keyword_only("height", ParamType::Int),
+                Param::keyword_only("pix_fmt", ParamType::String).with_doc("rgb24, rgba, or gray"),
+                Param::keyword_only("compression", ParamType::String)
+                    .optional()
+                    .with_doc("zlib if `data` is compressed"),
+            ])
+            .with_doc("A frame from raw pixel data"),
+        )
+    }
 }
 
 pub struct Black;
@@ -522,6 +592,17 @@ impl super::Filter for Black {
             format: ff_pix_fmt,
         })
     }
+
+    fn signature(&self) -> Option<Signature> {
+        Some(
+            Signature::new(vec![
+                Param::keyword_only("width", ParamType::Int),
+                Param::keyword_only("height", ParamType::Int),
+                Param::keyword_only("pix_fmt", ParamType::String),
+            ])
+            .with_doc("A black frame"),
+        )
+    }
 }
 
 pub struct Solid;
@@ -692,6 +773,18 @@ impl super::Filter for Solid {
             format: ff_pix_fmt,
         })
     }
+
+    fn signature(&self) -> Option<Signature> {
+        Some(
+            Signature::new(vec![
+                Param::keyword_only("width", ParamType::Int),
+                Param::keyword_only("height", ParamType::Int),
+                Param::keyword_only("pix_fmt", ParamType::String).with_doc("rgb24 or gray"),
+                Param::keyword_only("color", ParamType::List).with_doc("The color as [r, g, b]"),
+            ])
+            .with_doc("A frame of a single color"),
+        )
+    }
 }
 
 // _slice_mat(frame, miny, maxy, minx, maxx)
@@ -818,6 +911,19 @@ impl super::Filter for SliceMat {
             format: frame.format,
         })
     }
+
+    fn signature(&self) -> Option<Signature> {
+        Some(
+            Signature::new(vec![
+                Param::positional_only("frame", ParamType::Frame),
+                Param::positional_only("miny", ParamType::Int),
+                Param::positional_only("maxy", ParamType::Int),
+                Param::positional_only("minx", ParamType::Int),
+                Param::positional_only("maxx", ParamType::Int),
+            ])
+            .with_doc("Crop a frame to rows `miny..maxy` and columns `minx..maxx`"),
+        )
+    }
 }
 
 // _slice_write_mat(f1, f2, miny, maxy, minx, maxx)
@@ -979,4 +1085,66 @@ impl super::Filter for SliceWriteMat {
             format: f1.format,
         })
     }
+
+    fn signature(&self) -> Option<Signature> {
+        Some(
+            Signature::new(vec![
+                Param::positional_only("f1", ParamType::Frame),
+                Param::positional_only("f2", ParamType::Frame),
+                Param::positional_only("miny", ParamType::Int),
+                Param::positional_only("maxy", ParamType::Int),
+                Param::positional_only("minx", ParamType::Int),
+                Param::positional_only("maxx", ParamType::Int),
+            ])
+            .with_doc("Write `f2` into rows `miny..maxy` and columns `minx..maxx` of `f1`"),
+        )
+    }
+}
+
+/// Mean brightness of a frame
+///
+/// Returns the mean of the luma plane as a float from 0 to 255.
+///
+/// # Arguments
+///
+/// * `frame` - A frame in a YUV or gray pixel format with 8-bit luma
+pub struct MeanLuma {}
+impl DataFilter for MeanLuma {
+    fn compute(&self, args: &[Val], _kwargs: &BTreeMap<String, Val>) -> Result<Val, Error> {
+        let frame = match args.first() {
+            Some(Val::Frame(frame)) => frame,
+            _ => return Err(Error::MissingFilterArg),
+        };
+        if !matches!(
+            frame.format,
+            ffi::AVPixelFormat_AV_PIX_FMT_YUV420P
+                | ffi::AVPixelFormat_AV_PIX_FMT_YUV422P
+                | ffi::AVPixelFormat_AV_PIX_FMT_YUV444P
+                | ffi::AVPixelFormat_AV_PIX_FMT_YUVJ420P
+                | ffi::AVPixelFormat_AV_PIX_FMT_YUVJ422P
+                | ffi::AVPixelFormat_AV_PIX_FMT_YUVJ444P
+                | ffi::AVPixelFormat_AV_PIX_FMT_NV12
+                | ffi::AVPixelFormat_AV_PIX_FMT_GRAY8
+        ) {
+            return Err(Error::InvalidFilterArgValue(
+                format!("{:?}", frame),
+                "MeanLuma needs a frame with 8-bit luma".to_string(),
+            ));
+        }
+
+        let avframe = frame.clone().into_avframe();
+        let (width, height) = (frame.width as usize, frame.height as usize);
+        let mut sum: u64 = 0;
+        for y in 0..height {
+            let row = unsafe {
+                std::slice::from_raw_parts(
+                    (*avframe.inner).data[0].add(y * (*avframe.inner).linesize[0] as usize),
+                    width,
+                )
+            };
+            sum += row.iter().map(|v| *v as u64).sum::<u64>();
+        }
+
+        Ok(Val::Float(sum as f64 / (width * height).max(1) as f64))
+    }
 }
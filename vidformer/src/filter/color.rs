@@ -0,0 +1,1166 @@
+//! Color grading filters
+//!
+//! [`ColorAdjust`] changes brightness, contrast, gamma, and saturation, [`Equalize`] equalizes a frame's histogram
+//! (globally or with CLAHE), and [`Lut3d`] applies a 3D LUT loaded from a `.cube` file through a [`Service`].
+//! [`ChromaKey`] keys out a backdrop color, such as a green screen.
+
+use super::filter_utils::{self, FrameArg};
+use super::{Filter, Frame, FrameType, Param, ParamType, Signature, Val};
+use crate::dve::{AVFrame, Error};
+use crate::service::Service;
+use opencv::imgproc;
+use opencv::prelude::{CLAHETrait, MatTraitConstManual, MatTraitManual};
+use rusty_ffmpeg::ffi;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// Color adjustment, equalization, and keying filters
+pub fn filters() -> BTreeMap<String, Box<dyn Filter>> {
+    let mut filters: BTreeMap<String, Box<dyn Filter>> = BTreeMap::new();
+    filters.insert("ColorAdjust".to_string(), Box::new(ColorAdjust {}));
+    filters.insert("Equalize".to_string(), Box::new(Equalize {}));
+    filters.insert("ChromaKey".to_string(), Box::new(ChromaKey {}));
+    filters
+}
+
+/// 3D LUT filters, loading `.cube` files through `luts`
+///
+/// LUT paths given to the filters are relative to the store's service, e.g., to its `root` for a filesystem service.
+pub fn lut_filters(luts: Arc<LutStore>) -> BTreeMap<String, Box<dyn Filter>> {
+    let mut filters: BTreeMap<String, Box<dyn Filter>> = BTreeMap::new();
+    filters.insert("LUT3D".to_string(), Box::new(Lut3d::new(luts)));
+    filters
+}
+
+/// Planar 8-bit YUV formats the LUT3D filter accepts, besides RGB24
+const YUV_PIX_FMTS: [ffi::AVPixelFormat; 6] = [
+    ffi::AVPixelFormat_AV_PIX_FMT_YUV420P,
+    ffi::AVPixelFormat_AV_PIX_FMT_YUV422P,
+    ffi::AVPixelFormat_AV_PIX_FMT_YUV444P,
+    ffi::AVPixelFormat_AV_PIX_FMT_YUVJ420P,
+    ffi::AVPixelFormat_AV_PIX_FMT_YUVJ422P,
+    ffi::AVPixelFormat_AV_PIX_FMT_YUVJ444P,
+];
+
+/// Map each 8-bit value through brightness, contrast, then gamma
+fn tone_table(brightness: f64, contrast: f64, gamma: f64) -> [u8; 256] {
+    let mut table = [0u8; 256];
+    for (v, out) in table.iter_mut().enumerate() {
+        let x = (v as f64 / 255.0 - 0.5) * contrast + 0.5 + brightness;
+        let x = x.clamp(0.0, 1.0).powf(1.0 / gamma);
+        *out = (x * 255.0).round() as u8;
+    }
+    table
+}
+
+/// Apply a tone table, then scale each pixel's distance from its luma by `saturation`
+fn adjust_rgb24(pixels: &mut [u8], table: &[u8; 256], saturation: f64) {
+    for px in pixels.chunks_exact_mut(3) {
+        let rgb = [
+            table[px[0] as usize],
+            table[px[1] as usize],
+            table[px[2] as usize],
+        ];
+        if saturation == 1.0 {
+            px.copy_from_slice(&rgb);
+            continue;
+        }
+        let luma = 0.2126 * rgb[0] as f64 + 0.7152 * rgb[1] as f64 + 0.0722 * rgb[2] as f64;
+        for (p, c) in px.iter_mut().zip(rgb) {
+            *p = (luma + (c as f64 - luma) * saturation)
+                .round()
+                .clamp(0.0, 255.0) as u8;
+        }
+    }
+}
+
+pub struct ColorAdjust {}
+
+struct ColorAdjustArgs {
+    img: FrameArg,
+    brightness: f64,
+    contrast: f64,
+    gamma: f64,
+    saturation: f64,
+}
+
+impl ColorAdjust {
+    fn sig() -> Signature {
+        Signature::new(vec![
+            Param::positional("img", ParamType::Frame),
+            Param::keyword_only("brightness", ParamType::Float)
+                .with_default(Val::Float(0.0))
+                .with_doc("Added to each channel, from -1 to 1 of full scale"),
+            Param::keyword_only("contrast", ParamType::Float)
+                .with_default(Val::Float(1.0))
+                .with_doc("Scale around mid gray; 0 is flat gray, 1 unchanged"),
+            Param::keyword_only("gamma", ParamType::Float)
+                .with_default(Val::Float(1.0))
+                .with_doc("Above 1 brightens midtones, below 1 darkens them"),
+            Param::keyword_only("saturation", ParamType::Float)
+                .with_default(Val::Float(1.0))
+                .with_doc("0 is grayscale, 1 unchanged, above 1 more saturated"),
+        ])
+        .with_doc("Adjust brightness, contrast, gamma, and saturation")
+    }
+
+    fn args(args: &[Val], kwargs: &BTreeMap<String, Val>) -> Result<ColorAdjustArgs, String> {
+        let parsed_args =
+            filter_utils::parse_arguments(&Self::sig(), args.to_vec(), kwargs.clone())?;
+
+        let img = filter_utils::get_frame(&parsed_args, "img")?;
+        let brightness = filter_utils::get_float(&parsed_args, "brightness")?;
+        let contrast = filter_utils::get_float(&parsed_args, "contrast")?;
+        let gamma = filter_utils::get_float(&parsed_args, "gamma")?;
+        let saturation = filter_utils::get_float(&parsed_args, "saturation")?;
+        if !(-1.0..=1.0).contains(&brightness) {
+            return Err("Expected 'brightness' to be between -1 and 1".into());
+        }
+        if contrast < 0.0 || saturation < 0.0 {
+            return Err("Expected 'contrast' and 'saturation' to be non-negative".into());
+        }
+        if gamma <= 0.0 {
+            return Err("Expected 'gamma' to be positive".into());
+        }
+
+        Ok(ColorAdjustArgs {
+            img,
+            brightness,
+            contrast,
+            gamma,
+            saturation,
+        })
+    }
+}
+
+impl Filter for ColorAdjust {
+    fn filter(&self, args: &[Val], kwargs: &BTreeMap<String, Val>) -> Result<Frame, Error> {
+        let opts = Self::args(args, kwargs).map_err(Error::AVError)?;
+
+        let img = opts.img.unwrap_frame();
+        let (width, height) = (img.width, img.height);
+        debug_assert_eq!(img.format, ffi::AVPixelFormat_AV_PIX_FMT_RGB24);
+        let mut mat = filter_utils::frame_to_mat_rgb24(&img, width, height);
+
+        let table = tone_table(opts.brightness, opts.contrast, opts.gamma);
+        adjust_rgb24(mat.data_bytes_mut().unwrap(), &table, opts.saturation);
+
+        let f = match filter_utils::mat_to_frame_rgb24(mat, width, height) {
+            Ok(value) => value,
+            Err(value) => return value,
+        };
+
+        Ok(Frame::new(AVFrame { inner: f }))
+    }
+
+    fn filter_type(
+        &self,
+        args: &[Val],
+        kwargs: &BTreeMap<String, Val>,
+    ) -> Result<FrameType, Error> {
+        let opts = Self::args(args, kwargs).map_err(Error::AVError)?;
+
+        let img = opts.img.unwrap_frame_type();
+        if img.format != ffi::AVPixelFormat_AV_PIX_FMT_RGB24 {
+            return Err(Error::AVError("Expected img to be an RGB24 frame".into()));
+        }
+
+        Ok(img)
+    }
+
+    fn signature(&self) -> Option<Signature> {
+        Some(Self::sig())
+    }
+}
+
+pub struct Equalize {}
+
+struct EqualizeArgs {
+    img: FrameArg,
+    clip_limit: Option<f64>,
+    tile_grid: (i32, i32),
+}
+
+impl Equalize {
+    fn sig() -> Signature {
+        Signature::new(vec![
+            Param::positional("img", ParamType::Frame),
+            Param::keyword_only("clip_limit", ParamType::Float)
+                .optional()
+                .with_doc(
+                    "Contrast limit of CLAHE; without it, the whole histogram is equalized at once",
+                ),
+            Param::keyword_only("tile_grid", ParamType::List)
+                .with_default(Val::List(vec![Val::Int(8), Val::Int(8)]))
+                .with_doc("[columns, rows] of the tiles CLAHE equalizes separately"),
+        ])
+        .with_doc("Equalize the histogram of a frame's luma, globally or with CLAHE")
+    }
+
+    fn args(args: &[Val], kwargs: &BTreeMap<String, Val>) -> Result<EqualizeArgs, String> {
+        let parsed_args =
+            filter_utils::parse_arguments(&Self::sig(), args.to_vec(), kwargs.clone())?;
+
+        let img = filter_utils::get_frame(&parsed_args, "img")?;
+        let clip_limit = if parsed_args.contains_key("clip_limit") {
+            let clip_limit = filter_utils::get_float(&parsed_args, "clip_limit")?;
+            if clip_limit <= 0.0 {
+                return Err("Expected 'clip_limit' to be positive".into());
+            }
+            Some(clip_limit)
+        } else {
+            None
+        };
+        let tile_grid = filter_utils::get_point(&parsed_args, "tile_grid")?;
+        if tile_grid.0 <= 0 || tile_grid.1 <= 0 {
+            return Err("Expected 'tile_grid' to be positive".into());
+        }
+
+        Ok(EqualizeArgs {
+            img,
+            clip_limit,
+            tile_grid,
+        })
+    }
+
+    fn equalize(opts: &EqualizeArgs, src: &opencv::core::Mat) -> opencv::core::Mat {
+        let mut dst = opencv::core::Mat::default();
+        match opts.clip_limit {
+            Some(clip_limit) => {
+                let mut clahe = imgproc::create_clahe(
+                    clip_limit,
+                    opencv::core::Size::new(opts.tile_grid.0, opts.tile_grid.1),
+                )
+                .unwrap();
+                clahe.apply(src, &mut dst).unwrap();
+            }
+            None => imgproc::equalize_hist(src, &mut dst).unwrap(),
+        }
+        dst
+    }
+}
+
+impl Filter for Equalize {
+    fn filter(&self, args: &[Val], kwargs: &BTreeMap<String, Val>) -> Result<Frame, Error> {
+        let opts = Self::args(args, kwargs).map_err(Error::AVError)?;
+
+        let img = opts.img.unwrap_frame();
+        let (width, height) = (img.width, img.height);
+
+        let f = if img.format == ffi::AVPixelFormat_AV_PIX_FMT_GRAY8 {
+            let mat = filter_utils::frame_to_mat_gray8(&img, width, height);
+            let mat = Self::equalize(&opts, &mat);
+            filter_utils::mat_to_frame_gray8(mat, width, height)
+        } else {
+            debug_assert_eq!(img.format, ffi::AVPixelFormat_AV_PIX_FMT_RGB24);
+            let mut mat = filter_utils::frame_to_mat_rgb24(&img, width, height);
+
+            // Equalize the luma (as in YCrCb), then shift each pixel by its luma's change, which keeps its chroma
+            let mut luma =
+                unsafe { opencv::core::Mat::new_rows_cols(height, width, opencv::core::CV_8UC1) }
+                    .unwrap();
+            for (y, px) in luma
+                .data_bytes_mut()
+                .unwrap()
+                .iter_mut()
+                .zip(mat.data_bytes().unwrap().chunks_exact(3))
+            {
+                *y = (0.299 * px[0] as f64 + 0.587 * px[1] as f64 + 0.114 * px[2] as f64).round()
+                    as u8;
+            }
+            let equalized = Self::equalize(&opts, &luma);
+            for ((px, y), y_eq) in mat
+                .data_bytes_mut()
+                .unwrap()
+                .chunks_exact_mut(3)
+                .zip(luma.data_bytes().unwrap())
+                .zip(equalized.data_bytes().unwrap())
+            {
+                let delta = *y_eq as i32 - *y as i32;
+                for c in px.iter_mut() {
+                    *c = (*c as i32 + delta).clamp(0, 255) as u8;
+                }
+            }
+            filter_utils::mat_to_frame_rgb24(mat, width, height)
+        };
+
+        let f = match f {
+            Ok(value) => value,
+            Err(value) => return value,
+        };
+
+        Ok(Frame::new(AVFrame { inner: f }))
+    }
+
+    fn filter_type(
+        &self,
+        args: &[Val],
+        kwargs: &BTreeMap<String, Val>,
+    ) -> Result<FrameType, Error> {
+        let opts = Self::args(args, kwargs).map_err(Error::AVError)?;
+
+        let img = opts.img.unwrap_frame_type();
+        if img.format != ffi::AVPixelFormat_AV_PIX_FMT_RGB24
+            && img.format != ffi::AVPixelFormat_AV_PIX_FMT_GRAY8
+        {
+            return Err(Error::AVError(
+                "Expected img to be an RGB24 or GRAY8 frame".into(),
+            ));
+        }
+
+        Ok(img)
+    }
+
+    fn signature(&self) -> Option<Signature> {
+        Some(Self::sig())
+    }
+}
+
+/// A lattice of `size`³ colors, indexed by red, then green, then blue, with red varying fastest
+#[derive(Debug, Clone, PartialEq)]
+struct Lattice {
+    size: usize,
+    table: Vec<[f32; 3]>,
+}
+
+impl Lattice {
+    /// Trilinearly interpolate the lattice at a point in the unit cube
+    fn sample(&self, p: [f32; 3]) -> [f32; 3] {
+        let n = self.size;
+        let mut idx = [0usize; 3];
+        let mut frac = [0f32; 3];
+        for c in 0..3 {
+            let x = p[c].clamp(0.0, 1.0) * (n - 1) as f32;
+            idx[c] = (x as usize).min(n - 2);
+            frac[c] = x - idx[c] as f32;
+        }
+
+        let at = |r: usize, g: usize, b: usize| {
+            self.table[((idx[2] + b) * n + idx[1] + g) * n + idx[0] + r]
+        };
+        let lerp = |a: [f32; 3], b: [f32; 3], t: f32| -> [f32; 3] {
+            [
+                a[0] + (b[0] - a[0]) * t,
+                a[1] + (b[1] - a[1]) * t,
+                a[2] + (b[2] - a[2]) * t,
+            ]
+        };
+        let along_r = |g, b| lerp(at(0, g, b), at(1, g, b), frac[0]);
+        let along_g = |b| lerp(along_r(0, b), along_r(1, b), frac[1]);
+        lerp(along_g(0), along_g(1), frac[2])
+    }
+}
+
+/// A 3D LUT parsed from a `.cube` file
+#[derive(Debug, Clone, PartialEq)]
+struct CubeLut {
+    domain_min: [f32; 3],
+    domain_max: [f32; 3],
+    lattice: Lattice,
+}
+
+impl CubeLut {
+    fn parse(contents: &str) -> Result<CubeLut, String> {
+        let mut size = None;
+        let mut domain_min = [0.0; 3];
+        let mut domain_max = [1.0; 3];
+        let mut table = Vec::new();
+
+        let floats = |line_no: usize, values: &[&str], n: usize| -> Result<Vec<f32>, String> {
+            let parsed: Option<Vec<f32>> = values.iter().map(|v| v.parse().ok()).collect();
+            match parsed {
+                Some(parsed) if parsed.len() == n => Ok(parsed),
+                _ => Err(format!("Expected {} numbers at line {}", n, line_no)),
+            }
+        };
+
+        for (idx, line) in contents.lines().enumerate() {
+            let line_no = idx + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            match tokens[0] {
+                "TITLE" => {}
+                "LUT_3D_SIZE" => {
+                    let n = match &tokens[1..] {
+                        [n] => n.parse::<usize>().ok(),
+                        _ => None,
+                    };
+                    match n {
+                        Some(n) if (2..=256).contains(&n) => size = Some(n),
+                        _ => return Err(format!("Invalid LUT_3D_SIZE at line {}", line_no)),
+                    }
+                }
+                "LUT_1D_SIZE" => return Err("1D LUTs are not supported".to_string()),
+                "DOMAIN_MIN" => {
+                    let v = floats(line_no, &tokens[1..], 3)?;
+                    domain_min = [v[0], v[1], v[2]];
+                }
+                "DOMAIN_MAX" => {
+                    let v = floats(line_no, &tokens[1..], 3)?;
+                    domain_max = [v[0], v[1], v[2]];
+                }
+                "LUT_3D_INPUT_RANGE" => {
+                    let v = floats(line_no, &tokens[1..], 2)?;
+                    domain_min = [v[0]; 3];
+                    domain_max = [v[1]; 3];
+                }
+                keyword if keyword.starts_with(|c: char| c.is_ascii_alphabetic()) => {
+                    // Other keywords (e.g., from grading applications) don't affect the table
+                }
+                _ => {
+                    let v = floats(line_no, &tokens, 3)?;
+                    table.push([v[0], v[1], v[2]]);
+                }
+            }
+        }
+
+        let size = size.ok_or("Missing LUT_3D_SIZE")?;
+        if table.len() != size * size * size {
+            return Err(format!(
+                "Expected {} entries for LUT_3D_SIZE {}, got {}",
+                size * size * size,
+                size,
+                table.len()
+            ));
+        }
+        if (0..3).any(|c| domain_max[c] <= domain_min[c]) {
+            return Err("Expected DOMAIN_MAX to be greater than DOMAIN_MIN".to_string());
+        }
+
+        Ok(CubeLut {
+            domain_min,
+            domain_max,
+            lattice: Lattice { size, table },
+        })
+    }
+
+    /// Look up an RGB color with components in [0, 1]
+    fn sample(&self, rgb: [f32; 3]) -> [f32; 3] {
+        let mut p = [0.0; 3];
+        for c in 0..3 {
+            p[c] = (rgb[c] - self.domain_min[c]) / (self.domain_max[c] - self.domain_min[c]);
+        }
+        self.lattice.sample(p)
+    }
+}
+
+/// How YUV relates to RGB: the matrix's red and blue luma coefficients, and whether values use the full 8-bit range
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+struct YuvEncoding {
+    kr: f32,
+    kb: f32,
+    full_range: bool,
+}
+
+const BT601: (f32, f32) = (0.299, 0.114);
+const BT709: (f32, f32) = (0.2126, 0.0722);
+
+/// Edge length of the lattice a LUT is resampled to for YUV frames
+const YUV_LATTICE_SIZE: usize = 33;
+
+impl YuvEncoding {
+    fn of(frame: &Frame) -> YuvEncoding {
+        let (colorspace, color_range) = unsafe {
+            (
+                (*frame.inner.inner).colorspace,
+                (*frame.inner.inner).color_range,
+            )
+        };
+        let (kr, kb) = match colorspace {
+            ffi::AVColorSpace_AVCOL_SPC_BT709 => BT709,
+            ffi::AVColorSpace_AVCOL_SPC_BT470BG | ffi::AVColorSpace_AVCOL_SPC_SMPTE170M => BT601,
+            // Untagged video is assumed to be HD if it's HD-sized
+            _ if frame.height >= 720 => BT709,
+            _ => BT601,
+        };
+        let full_range = color_range == ffi::AVColorRange_AVCOL_RANGE_JPEG
+            || [
+                ffi::AVPixelFormat_AV_PIX_FMT_YUVJ420P,
+                ffi::AVPixelFormat_AV_PIX_FMT_YUVJ422P,
+                ffi::AVPixelFormat_AV_PIX_FMT_YUVJ444P,
+            ]
+            .contains(&frame.format);
+        YuvEncoding { kr, kb, full_range }
+    }
+
+    /// Convert 8-bit YUV, scaled to [0, 1], to RGB in [0, 1]
+    fn yuv_to_rgb(&self, yuv: [f32; 3]) -> [f32; 3] {
+        let [y, u, v] = yuv.map(|c| c * 255.0);
+        let (y, pb, pr) = if self.full_range {
+            (y / 255.0, (u - 128.0) / 255.0, (v - 128.0) / 255.0)
+        } else {
+            ((y - 16.0) / 219.0, (u - 128.0) / 224.0, (v - 128.0) / 224.0)
+        };
+        let kg = 1.0 - self.kr - self.kb;
+        let r = y + 2.0 * (1.0 - self.kr) * pr;
+        let b = y + 2.0 * (1.0 - self.kb) * pb;
+        let g = (y - self.kr * r - self.kb * b) / kg;
+        [r, g, b].map(|c| c.clamp(0.0, 1.0))
+    }
+
+    /// Convert RGB in [0, 1] to 8-bit YUV, scaled to [0, 1]
+    fn rgb_to_yuv(&self, rgb: [f32; 3]) -> [f32; 3] {
+        let [r, g, b] = rgb;
+        let y = self.kr * r + (1.0 - self.kr - self.kb) * g + self.kb * b;
+        let pb = (b - y) / (2.0 * (1.0 - self.kb));
+        let pr = (r - y) / (2.0 * (1.0 - self.kr));
+        let yuv = if self.full_range {
+            [y * 255.0, pb * 255.0 + 128.0, pr * 255.0 + 128.0]
+        } else {
+            [y * 219.0 + 16.0, pb * 224.0 + 128.0, pr * 224.0 + 128.0]
+        };
+        yuv.map(|c| c.clamp(0.0, 255.0) / 255.0)
+    }
+
+    /// Resample a LUT into a lattice which maps YUV to YUV directly
+    fn lattice(&self, lut: &CubeLut) -> Lattice {
+        let n = YUV_LATTICE_SIZE;
+        let step = 1.0 / (n - 1) as f32;
+        let mut table = Vec::with_capacity(n * n * n);
+        for v in 0..n {
+            for u in 0..n {
+                for y in 0..n {
+                    let rgb = self.yuv_to_rgb([y as f32 * step, u as f32 * step, v as f32 * step]);
+                    table.push(self.rgb_to_yuv(lut.sample(rgb)));
+                }
+            }
+        }
+        Lattice { size: n, table }
+    }
+}
+
+fn mix(from: u8, to: f32, strength: f32) -> u8 {
+    let from = from as f32;
+    (from + (to * 255.0 - from) * strength)
+        .round()
+        .clamp(0.0, 255.0) as u8
+}
+
+fn alloc_frame(width: i32, height: i32, format: ffi::AVPixelFormat) -> *mut ffi::AVFrame {
+    let f = unsafe { ffi::av_frame_alloc() };
+    if f.is_null() {
+        panic!("ERROR could not allocate frame");
+    }
+    unsafe {
+        (*f).width = width;
+        (*f).height = height;
+        (*f).format = format;
+        if ffi::av_frame_get_buffer(f, 0) < 0 {
+            panic!("ERROR could not allocate frame data");
+        }
+    }
+    f
+}
+
+/// Apply a YUV-to-YUV lattice to a planar YUV frame
+///
+/// Each luma sample is looked up with the chroma it's drawn with, and each chroma sample with the mean luma it covers.
+fn apply_yuv(src: &Frame, lattice: &Lattice, strength: f32) -> *mut ffi::AVFrame {
+    let src_f = src.inner.inner;
+    let (width, height) = (src.width as usize, src.height as usize);
+    let desc = unsafe { ffi::av_pix_fmt_desc_get(src.format) };
+    let (log2_cw, log2_ch) = unsafe { ((*desc).log2_chroma_w, (*desc).log2_chroma_h) };
+    let chroma_width = (width + (1 << log2_cw) - 1) >> log2_cw;
+    let chroma_height = (height + (1 << log2_ch) - 1) >> log2_ch;
+
+    let f = alloc_frame(width as i32, height as i32, src.format);
+    unsafe {
+        ffi::av_frame_copy_props(f, src_f);
+    }
+
+    let row = |plane: usize, y: usize, len: usize| unsafe {
+        std::slice::from_raw_parts(
+            (*src_f).data[plane].add(y * (*src_f).linesize[plane] as usize),
+            len,
+        )
+    };
+    let row_mut = |plane: usize, y: usize, len: usize| unsafe {
+        std::slice::from_raw_parts_mut((*f).data[plane].add(y * (*f).linesize[plane] as usize), len)
+    };
+
+    for y in 0..height {
+        let (src_y, dst_y) = (row(0, y, width), row_mut(0, y, width));
+        let src_u = row(1, y >> log2_ch, chroma_width);
+        let src_v = row(2, y >> log2_ch, chroma_width);
+        for x in 0..width {
+            let (u, v) = (src_u[x >> log2_cw], src_v[x >> log2_cw]);
+            let out = lattice.sample([src_y[x], u, v].map(|c| c as f32 / 255.0));
+            dst_y[x] = mix(src_y[x], out[0], strength);
+        }
+    }
+
+    for cy in 0..chroma_height {
+        let rows = (cy << log2_ch)..((cy + 1) << log2_ch).min(height);
+        let (src_u, src_v) = (row(1, cy, chroma_width), row(2, cy, chroma_width));
+        let (dst_u, dst_v) = (row_mut(1, cy, chroma_width), row_mut(2, cy, chroma_width));
+        for cx in 0..chroma_width {
+            let cols = (cx << log2_cw)..((cx + 1) << log2_cw).min(width);
+            let mut sum = 0u32;
+            for y in rows.clone() {
+                sum += row(0, y, width)[cols.clone()]
+                    .iter()
+                    .map(|l| *l as u32)
+                    .sum::<u32>();
+            }
+            let luma = sum as f32 / (rows.len() * cols.len()) as f32;
+            let out = lattice.sample([
+                luma / 255.0,
+                src_u[cx] as f32 / 255.0,
+                src_v[cx] as f32 / 255.0,
+            ]);
+            dst_u[cx] = mix(src_u[cx], out[1], strength);
+            dst_v[cx] = mix(src_v[cx], out[2], strength);
+        }
+    }
+
+    f
+}
+
+/// 3D LUTs read through a [`Service`]
+///
+/// Each LUT is read the first time it's used and kept for the store's lifetime, along with the YUV lattices resampled
+/// from it, so contexts created over and over (like a server's) should share one store.
+pub struct LutStore {
+    luts: Service,
+    loaded: parking_lot::Mutex<BTreeMap<String, Arc<CubeLut>>>,
+    yuv_lattices: parking_lot::Mutex<Vec<(String, YuvEncoding, Arc<Lattice>)>>,
+}
+
+impl LutStore {
+    pub fn new(luts: Service) -> Self {
+        LutStore {
+            luts,
+            loaded: parking_lot::Mutex::new(BTreeMap::new()),
+            yuv_lattices: parking_lot::Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Read and parse a LUT from the LUT service, or return it if it was already read
+    fn cube(&self, path: &str) -> Result<Arc<CubeLut>, Error> {
+        if let Some(lut) = self.loaded.lock().get(path) {
+            return Ok(lut.clone());
+        }
+
+        let io_runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .enable_all()
+            .build()
+            .unwrap();
+        let op = self.luts.blocking_operator(io_runtime.handle())?;
+        let data = match op.read(path) {
+            Ok(buffer) => buffer.to_vec(),
+            Err(e) if e.kind() == opendal::ErrorKind::NotFound => {
+                return Err(Error::IOError(format!("LUT `{}` not found", path)));
+            }
+            Err(e) => {
+                return Err(Error::IOError(format!(
+                    "Failed to read LUT {}: {}",
+                    path, e
+                )));
+            }
+        };
+        let lut = String::from_utf8(data)
+            .map_err(|_| "Not valid UTF-8".to_string())
+            .and_then(|contents| CubeLut::parse(&contents))
+            .map_err(|e| Error::InvalidFilterArgValue(path.to_string(), e))?;
+
+        let lut = Arc::new(lut);
+        self.loaded.lock().insert(path.to_string(), lut.clone());
+        Ok(lut)
+    }
+
+    fn yuv_lattice(&self, path: &str, lut: &CubeLut, encoding: YuvEncoding) -> Arc<Lattice> {
+        let mut lattices = self.yuv_lattices.lock();
+        if let Some((_, _, lattice)) = lattices
+            .iter()
+            .find(|(p, e, _)| p == path && *e == encoding)
+        {
+            return lattice.clone();
+        }
+        let lattice = Arc::new(encoding.lattice(lut));
+        lattices.push((path.to_string(), encoding, lattice.clone()));
+        lattice
+    }
+}
+
+/// Apply a 3D LUT from a `.cube` file in a [`LutStore`] with trilinear interpolation
+pub struct Lut3d {
+    luts: Arc<LutStore>,
+}
+
+struct Lut3dArgs {
+    img: FrameArg,
+    lut: String,
+    strength: f64,
+}
+
+impl Lut3d {
+    pub fn new(luts: Arc<LutStore>) -> Self {
+        Lut3d { luts }
+    }
+
+    fn sig() -> Signature {
+        Signature::new(vec![
+            Param::positional("img", ParamType::Frame),
+            Param::positional("lut", ParamType::String)
+                .with_doc("Path of a .cube file in the filter's LUT service"),
+            Param::keyword_only("strength", ParamType::Float)
+                .with_default(Val::Float(1.0))
+                .with_doc("Blend between the original (0) and the graded frame (1)"),
+        ])
+        .with_doc("Apply a 3D LUT from a .cube file")
+    }
+
+    fn args(args: &[Val], kwargs: &BTreeMap<String, Val>) -> Result<Lut3dArgs, String> {
+        let parsed_args =
+            filter_utils::parse_arguments(&Self::sig(), args.to_vec(), kwargs.clone())?;
+
+        let img = filter_utils::get_frame(&parsed_args, "img")?;
+        let lut = filter_utils::get_string(&parsed_args, "lut")?;
+        let strength = filter_utils::get_float(&parsed_args, "strength")?;
+        if !(0.0..=1.0).contains(&strength) {
+            return Err("Expected 'strength' to be between 0 and 1".into());
+        }
+
+        Ok(Lut3dArgs { img, lut, strength })
+    }
+}
+
+impl Filter for Lut3d {
+    fn filter(&self, args: &[Val], kwargs: &BTreeMap<String, Val>) -> Result<Frame, Error> {
+        let opts = Self::args(args, kwargs).map_err(Error::AVError)?;
+        let lut = self.luts.cube(&opts.lut)?;
+        let strength = opts.strength as f32;
+
+        let img = opts.img.unwrap_frame();
+        let (width, height) = (img.width, img.height);
+
+        if img.format == ffi::AVPixelFormat_AV_PIX_FMT_RGB24 {
+            let mut mat = filter_utils::frame_to_mat_rgb24(&img, width, height);
+            for px in mat.data_bytes_mut().unwrap().chunks_exact_mut(3) {
+                let out = lut.sample([px[0], px[1], px[2]].map(|c| c as f32 / 255.0));
+                for (p, o) in px.iter_mut().zip(out) {
+                    *p = mix(*p, o, strength);
+                }
+            }
+
+            let f = match filter_utils::mat_to_frame_rgb24(mat, width, height) {
+                Ok(value) => value,
+                Err(value) => return value,
+            };
+            Ok(Frame::new(AVFrame { inner: f }))
+        } else {
+            debug_assert!(YUV_PIX_FMTS.contains(&img.format));
+            let lattice = self
+                .luts
+                .yuv_lattice(&opts.lut, &lut, YuvEncoding::of(&img));
+            let f = apply_yuv(&img, &lattice, strength);
+            Ok(Frame::new(AVFrame { inner: f }))
+        }
+    }
+
+    fn filter_type(
+        &self,
+        args: &[Val],
+        kwargs: &BTreeMap<String, Val>,
+    ) -> Result<FrameType, Error> {
+        let opts = Self::args(args, kwargs).map_err(Error::AVError)?;
+
+        let img = opts.img.unwrap_frame_type();
+        if img.format != ffi::AVPixelFormat_AV_PIX_FMT_RGB24 && !YUV_PIX_FMTS.contains(&img.format)
+        {
+            return Err(Error::AVError(
+                "Expected img to be an RGB24 or planar 8-bit YUV frame".into(),
+            ));
+        }
+
+        // Read the LUT now so a missing or invalid file is reported before rendering
+        self.luts.cube(&opts.lut)?;
+
+        Ok(img)
+    }
+
+    fn signature(&self) -> Option<Signature> {
+        Some(Self::sig())
+    }
+}
+
+/// The chroma (Cb, Cr) of an RGB color in [0, 1], with BT.709 coefficients
+fn chroma(rgb: [f32; 3]) -> [f32; 2] {
+    let y = 0.2126 * rgb[0] + 0.7152 * rgb[1] + 0.0722 * rgb[2];
+    [(rgb[2] - y) / 1.8556, (rgb[0] - y) / 1.5748]
+}
+
+/// Separates pixels from a key color by their distance from it in chroma
+///
+/// Pixels within `similarity` of the key are transparent, and opacity ramps up over the next `smoothness`. Pixels within
+/// `similarity + spill` of the key are desaturated, removing the key color reflected onto the subject.
+struct Keyer {
+    key: [f32; 2],
+    similarity: f32,
+    smoothness: f32,
+    spill: f32,
+}
+
+impl Keyer {
+    /// The alpha of a pixel, in [0, 1], and its color with the key's spill removed
+    fn key(&self, rgb: [u8; 3]) -> (f32, [f32; 3]) {
+        let rgb = rgb.map(|c| c as f32 / 255.0);
+        let c = chroma(rgb);
+        let dist = ((c[0] - self.key[0]).powi(2) + (c[1] - self.key[1]).powi(2)).sqrt();
+        let excess = dist - self.similarity;
+        let ramp = |width: f32| {
+            if width > 0.0 {
+                (excess / width).clamp(0.0, 1.0).powf(1.5)
+            } else if excess > 0.0 {
+                1.0
+            } else {
+                0.0
+            }
+        };
+
+        let luma = 0.2126 * rgb[0] + 0.7152 * rgb[1] + 0.0722 * rgb[2];
+        let keep = ramp(self.spill);
+        (ramp(self.smoothness), rgb.map(|c| luma + (c - luma) * keep))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum KeyOutput {
+    /// The keyed foreground over the background
+    Composite,
+    /// Just the foreground's alpha, as a GRAY8 frame
+    Mask,
+    /// The foreground with its spill removed and its alpha, as an RGBA frame
+    Rgba,
+}
+
+pub struct ChromaKey {}
+
+struct ChromaKeyArgs {
+    fg: FrameArg,
+    bg: Option<FrameArg>,
+    keyer: Keyer,
+    output: KeyOutput,
+}
+
+impl ChromaKey {
+    fn sig() -> Signature {
+        Signature::new(vec![
+            Param::positional("fg", ParamType::Frame)
+                .with_doc("Foreground shot in front of the key color"),
+            Param::positional("bg", ParamType::Frame)
+                .optional()
+                .with_doc("Background the same size as fg; required for the composite output"),
+            Param::keyword_only("key_color", ParamType::List)
+                .with_default(Val::List(vec![Val::Int(0), Val::Int(255), Val::Int(0)]))
+                .with_doc("[r, g, b] of the backdrop"),
+            Param::keyword_only("similarity", ParamType::Float)
+                .with_default(Val::Float(0.4))
+                .with_doc(
+                    "Chroma distance from the key color within which pixels are fully transparent",
+                ),
+            Param::keyword_only("smoothness", ParamType::Float)
+                .with_default(Val::Float(0.08))
+                .with_doc("Chroma distance over which edges fade from transparent to opaque"),
+            Param::keyword_only("spill", ParamType::Float)
+                .with_default(Val::Float(0.1))
+                .with_doc(
+                    "Chroma distance beyond similarity within which the key color is desaturated",
+                ),
+            Param::keyword_only("output", ParamType::String)
+                .with_default(Val::String("composite".to_string()))
+                .with_doc("composite, mask (GRAY8 alpha), or rgba (foreground with alpha)"),
+        ])
+        .with_doc("Key out a backdrop color, e.g., a green screen")
+    }
+
+    fn args(args: &[Val], kwargs: &BTreeMap<String, Val>) -> Result<ChromaKeyArgs, String> {
+        let parsed_args =
+            filter_utils::parse_arguments(&Self::sig(), args.to_vec(), kwargs.clone())?;
+
+        let fg = filter_utils::get_frame(&parsed_args, "fg")?;
+        let bg = if parsed_args.contains_key("bg") {
+            Some(filter_utils::get_frame(&parsed_args, "bg")?)
+        } else {
+            None
+        };
+
+        let key_color = match parsed_args.get("key_color") {
+            Some(Val::List(list)) if list.len() == 3 => {
+                let mut rgb = [0.0; 3];
+                for (c, val) in rgb.iter_mut().zip(list) {
+                    *c = match val {
+                        Val::Int(v) if (0..=255).contains(v) => *v as f32 / 255.0,
+                        _ => return Err("Expected 'key_color' to be [r, g, b] in [0, 255]".into()),
+                    };
+                }
+                rgb
+            }
+            _ => return Err("Expected 'key_color' to be [r, g, b] in [0, 255]".into()),
+        };
+
+        let similarity = filter_utils::get_float(&parsed_args, "similarity")?;
+        let smoothness = filter_utils::get_float(&parsed_args, "smoothness")?;
+        let spill = filter_utils::get_float(&parsed_args, "spill")?;
+        if similarity < 0.0 || smoothness < 0.0 || spill < 0.0 {
+            return Err(
+                "Expected 'similarity', 'smoothness', and 'spill' to be non-negative".into(),
+            );
+        }
+
+        let output = match filter_utils::get_string(&parsed_args, "output")?.as_str() {
+            "composite" => KeyOutput::Composite,
+            "mask" => KeyOutput::Mask,
+            "rgba" => KeyOutput::Rgba,
+            other => return Err(format!("Unknown output '{}'", other)),
+        };
+        if output == KeyOutput::Composite && bg.is_none() {
+            return Err("Expected 'bg' for the composite output".into());
+        }
+
+        Ok(ChromaKeyArgs {
+            fg,
+            bg,
+            keyer: Keyer {
+                key: chroma(key_color),
+                similarity: similarity as f32,
+                smoothness: smoothness as f32,
+                spill: spill as f32,
+            },
+            output,
+        })
+    }
+}
+
+impl Filter for ChromaKey {
+    fn filter(&self, args: &[Val], kwargs: &BTreeMap<String, Val>) -> Result<Frame, Error> {
+        let opts = Self::args(args, kwargs).map_err(Error::AVError)?;
+
+        let fg = opts.fg.unwrap_frame();
+        let (width, height) = (fg.width, fg.height);
+        debug_assert_eq!(fg.format, ffi::AVPixelFormat_AV_PIX_FMT_RGB24);
+        let fg_mat = filter_utils::frame_to_mat_rgb24(&fg, width, height);
+        let fg_data = fg_mat.data_bytes().unwrap();
+        let keyed = fg_data
+            .chunks_exact(3)
+            .map(|px| opts.keyer.key([px[0], px[1], px[2]]));
+
+        let f = match opts.output {
+            KeyOutput::Composite => {
+                let bg = opts.bg.as_ref().unwrap().unwrap_frame();
+                let mut mat = filter_utils::frame_to_mat_rgb24(&bg, width, height);
+                for (px, (alpha, color)) in
+                    mat.data_bytes_mut().unwrap().chunks_exact_mut(3).zip(keyed)
+                {
+                    for (p, c) in px.iter_mut().zip(color) {
+                        *p = mix(*p, c, alpha);
+                    }
+                }
+                filter_utils::mat_to_frame_rgb24(mat, width, height)
+            }
+            KeyOutput::Mask => {
+                let mut mat = unsafe {
+                    opencv::core::Mat::new_rows_cols(height, width, opencv::core::CV_8UC1)
+                }
+                .unwrap();
+                for (m, (alpha, _)) in mat.data_bytes_mut().unwrap().iter_mut().zip(keyed) {
+                    *m = (alpha * 255.0).round() as u8;
+                }
+                filter_utils::mat_to_frame_gray8(mat, width, height)
+            }
+            KeyOutput::Rgba => {
+                let f = alloc_frame(width, height, ffi::AVPixelFormat_AV_PIX_FMT_RGBA);
+                let mut keyed = keyed;
+                for y in 0..height as usize {
+                    let row = unsafe {
+                        std::slice::from_raw_parts_mut(
+                            (*f).data[0].add(y * (*f).linesize[0] as usize),
+                            width as usize * 4,
+                        )
+                    };
+                    for (px, (alpha, color)) in row.chunks_exact_mut(4).zip(keyed.by_ref()) {
+                        for (p, c) in px.iter_mut().zip(color) {
+                            *p = (c * 255.0).round().clamp(0.0, 255.0) as u8;
+                        }
+                        px[3] = (alpha * 255.0).round() as u8;
+                    }
+                }
+                Ok(f)
+            }
+        };
+
+        let f = match f {
+            Ok(value) => value,
+            Err(value) => return value,
+        };
+
+        Ok(Frame::new(AVFrame { inner: f }))
+    }
+
+    fn filter_type(
+        &self,
+        args: &[Val],
+        kwargs: &BTreeMap<String, Val>,
+    ) -> Result<FrameType, Error> {
+        let opts = Self::args(args, kwargs).map_err(Error::AVError)?;
+
+        let fg = opts.fg.unwrap_frame_type();
+        if fg.format != ffi::AVPixelFormat_AV_PIX_FMT_RGB24 {
+            return Err(Error::AVError("Expected fg to be an RGB24 frame".into()));
+        }
+        if let Some(bg) = &opts.bg {
+            let bg = bg.unwrap_frame_type();
+            if bg.format != ffi::AVPixelFormat_AV_PIX_FMT_RGB24 {
+                return Err(Error::AVError("Expected bg to be an RGB24 frame".into()));
+            }
+            if bg.width != fg.width || bg.height != fg.height {
+                return Err(Error::AVError(
+                    "Expected fg and bg to be the same size".into(),
+                ));
+            }
+        }
+
+        let format = match opts.output {
+            KeyOutput::Composite => ffi::AVPixelFormat_AV_PIX_FMT_RGB24,
+            KeyOutput::Mask => ffi::AVPixelFormat_AV_PIX_FMT_GRAY8,
+            KeyOutput::Rgba => ffi::AVPixelFormat_AV_PIX_FMT_RGBA,
+        };
+        Ok(FrameType::new(fg.width, fg.height, format))
+    }
+
+    fn signature(&self) -> Option<Signature> {
+        Some(Self::sig())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn identity_cube(size: usize) -> String {
+        let mut cube = format!("TITLE \"identity\"\n# comment\nLUT_3D_SIZE {}\n", size);
+        let step = 1.0 / (size - 1) as f32;
+        for b in 0..size {
+            for g in 0..size {
+                for r in 0..size {
+                    cube.push_str(&format!(
+                        "{} {} {}\n",
+                        r as f32 * step,
+                        g as f32 * step,
+                        b as f32 * step
+                    ));
+                }
+            }
+        }
+        cube
+    }
+
+    #[test]
+    fn test_tone_table() {
+        let identity = tone_table(0.0, 1.0, 1.0);
+        assert!(identity.iter().enumerate().all(|(i, v)| *v as usize == i));
+
+        let flat = tone_table(0.0, 0.0, 1.0);
+        assert!(flat.iter().all(|v| *v == 128));
+        assert_eq!(tone_table(0.5, 1.0, 1.0)[128], 255);
+        // Gamma keeps black and white, and brightens midtones
+        let bright = tone_table(0.0, 1.0, 2.0);
+        assert_eq!((bright[0], bright[255]), (0, 255));
+        assert!(bright[64] > 64);
+
+        let mut px = [200, 100, 50];
+        adjust_rgb24(&mut px, &identity, 0.0);
+        assert!(px[0] == px[1] && px[1] == px[2]);
+    }
+
+    #[test]
+    fn test_cube_lut() {
+        let lut = CubeLut::parse(&identity_cube(5)).unwrap();
+        let p = [0.1, 0.55, 0.93];
+        let out = lut.sample(p);
+        assert!((0..3).all(|c| (out[c] - p[c]).abs() < 1e-5));
+
+        // Red varies fastest: the second entry is the red axis
+        let mut swapped = "LUT_3D_SIZE 2\n".to_string();
+        for (r, g, b) in [
+            (0, 0, 0),
+            (0, 1, 0),
+            (1, 0, 0),
+            (1, 1, 0),
+            (0, 0, 1),
+            (0, 1, 1),
+            (1, 0, 1),
+            (1, 1, 1),
+        ] {
+            swapped.push_str(&format!("{} {} {}\n", r, g, b));
+        }
+        let swapped = CubeLut::parse(&swapped).unwrap();
+        assert_eq!(swapped.sample([1.0, 0.0, 0.0]), [0.0, 1.0, 0.0]);
+        assert_eq!(swapped.sample([0.5, 0.0, 0.0]), [0.0, 0.5, 0.0]);
+
+        let scaled = CubeLut::parse(&format!("DOMAIN_MAX 2 2 2\n{}", identity_cube(2))).unwrap();
+        assert!((scaled.sample([1.0, 1.0, 1.0])[0] - 0.5).abs() < 1e-5);
+
+        assert!(CubeLut::parse("LUT_3D_SIZE 2\n0 0 0\n").is_err());
+        assert!(CubeLut::parse("LUT_1D_SIZE 2\n0 0 0\n1 1 1\n").is_err());
+        assert!(CubeLut::parse("LUT_3D_SIZE 2\n0 0 zero\n").is_err());
+    }
+
+    #[test]
+    fn test_yuv_lattice() {
+        let lut = CubeLut::parse(&identity_cube(17)).unwrap();
+        for (kr, kb) in [BT601, BT709] {
+            for full_range in [false, true] {
+                let encoding = YuvEncoding { kr, kb, full_range };
+                let lattice = encoding.lattice(&lut);
+                // An identity LUT maps in-gamut YUV to itself
+                let gray = [0.5, 128.0 / 255.0, 128.0 / 255.0];
+                let out = lattice.sample(gray);
+                assert!((0..3).all(|c| (out[c] - gray[c]).abs() < 1.0 / 255.0));
+
+                let yuv = encoding.rgb_to_yuv([0.8, 0.3, 0.2]);
+                let rgb = encoding.yuv_to_rgb(yuv);
+                assert!((rgb[0] - 0.8).abs() < 1e-4 && (rgb[2] - 0.2).abs() < 1e-4);
+            }
+        }
+    }
+
+    #[test]
+    fn test_keyer() {
+        let keyer = Keyer {
+            key: chroma([0.0, 1.0, 0.0]),
+            similarity: 0.4,
+            smoothness: 0.08,
+            spill: 0.1,
+        };
+
+        assert_eq!(keyer.key([0, 255, 0]).0, 0.0);
+        assert_eq!(keyer.key([30, 200, 40]).0, 0.0);
+        // Colors far from the key are opaque and unchanged
+        let (alpha, color) = keyer.key([255, 0, 255]);
+        assert_eq!(alpha, 1.0);
+        assert!((color[0] - 1.0).abs() < 1e-6 && color[1].abs() < 1e-6);
+        let (alpha, _) = keyer.key([200, 150, 120]);
+        assert_eq!(alpha, 1.0);
+
+        // Near the edge of the similarity, pixels are partly transparent, and their green is desaturated
+        let edge = (0..=255u8)
+            .map(|r| keyer.key([r, 200, 60]))
+            .find(|(alpha, _)| *alpha > 0.0 && *alpha < 1.0);
+        let (_, color) = edge.unwrap();
+        assert!(color[1] < 200.0 / 255.0);
+
+        // Without smoothness, the key is a hard edge
+        let hard = Keyer {
+            smoothness: 0.0,
+            ..keyer
+        };
+        assert!((0..=255u8)
+            .map(|r| hard.key([r, 200, 60]).0)
+            .all(|alpha| alpha == 0.0 || alpha == 1.0));
+    }
+}
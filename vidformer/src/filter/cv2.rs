@@ -38,6 +38,21 @@ pub fn filters() -> BTreeMap<String, Box<dyn filter::Filter>> {
     );
     filters.insert("cv2.hconcat".to_string(), Box::new(Hconcat {}));
     filters.insert("cv2.vconcat".to_string(), Box::new(Vconcat {}));
+    filters.insert("cv2.GaussianBlur".to_string(), Box::new(GaussianBlur {}));
+    filters.insert("cv2.medianBlur".to_string(), Box::new(MedianBlur {}));
+    filters.insert("cv2.blur".to_string(), Box::new(Blur {}));
+    filters.insert(
+        "cv2.bilateralFilter".to_string(),
+        Box::new(BilateralFilter {}),
+    );
+    filters.insert("_blur_region".to_string(), Box::new(BlurRegion {}));
+    filters.insert("cv2.warpAffine".to_string(), Box::new(WarpAffine {}));
+    filters.insert(
+        "cv2.warpPerspective".to_string(),
+        Box::new(WarpPerspective {}),
+    );
+    filters.insert("cv2.resize".to_string(), Box::new(Resize {}));
+    filters.insert("_overlay".to_string(), Box::new(Overlay {}));
     filters
 }
 
@@ -54,34 +69,26 @@ struct RectangleArgs {
 }
 
 impl Rectangle {
+    fn sig() -> Signature {
+        Signature::new(vec![
+            Param::positional("img", ParamType::Frame),
+            Param::positional("pt1", ParamType::List),
+            Param::positional("pt2", ParamType::List),
+            Param::positional("color", ParamType::List),
+            Param::positional("thickness", ParamType::Int).with_default(Val::Int(1)),
+            Param::positional("lineType", ParamType::Int).with_default(Val::Int(8)),
+            Param::positional("shift", ParamType::Int).with_default(Val::Int(0)),
+        ])
+        .with_doc("Draw a rectangle (cv2.rectangle)")
+    }
+
     fn args(
         args: &[filter::Val],
         kwargs: &BTreeMap<std::string::String, filter::Val>,
     ) -> Result<RectangleArgs, String> {
-        let signature = filter_utils::FunctionSignature {
-            parameters: vec![
-                Parameter::Positional { name: "img" },
-                Parameter::Positional { name: "pt1" },
-                Parameter::Positional { name: "pt2" },
-                Parameter::Positional { name: "color" },
-                Parameter::PositionalOptional {
-                    name: "thickness",
-                    default_value: Val::Int(1),
-                },
-                Parameter::PositionalOptional {
-                    name: "lineType",
-                    default_value: Val::Int(8),
-                },
-                Parameter::PositionalOptional {
-                    name: "shift",
-                    default_value: Val::Int(0),
-                },
-            ],
-        };
-
         let kwargs = kwargs.clone();
         let args = args.to_vec();
-        let parsed_args = filter_utils::parse_arguments(&signature, args, kwargs)?;
+        let parsed_args = filter_utils::parse_arguments(&Self::sig(), args, kwargs)?;
 
         let img = match parsed_args.get("img") {
             Some(Val::Frame(frame)) => filter_utils::FrameArg::Frame(frame.clone()),
@@ -191,6 +198,10 @@ impl filter::Filter for Rectangle {
 
         Ok(opts.img.unwrap_frame_type())
     }
+
+    fn signature(&self) -> Option<Signature> {
+        Some(Self::sig())
+    }
 }
 
 pub struct PutText {}
@@ -208,36 +219,28 @@ struct PutTextArgs {
 }
 
 impl PutText {
+    fn sig() -> Signature {
+        Signature::new(vec![
+            Param::positional("img", ParamType::Frame),
+            Param::positional("text", ParamType::String),
+            Param::positional("org", ParamType::List),
+            Param::positional("fontFace", ParamType::Int),
+            Param::positional("fontScale", ParamType::Float),
+            Param::positional("color", ParamType::List),
+            Param::positional("thickness", ParamType::Int).with_default(Val::Int(1)),
+            Param::positional("lineType", ParamType::Int).with_default(Val::Int(8)),
+            Param::positional("bottomLeftOrigin", ParamType::Bool).with_default(Val::Bool(false)),
+        ])
+        .with_doc("Draw text (cv2.putText)")
+    }
+
     fn args(
         args: &[filter::Val],
         kwargs: &BTreeMap<std::string::String, filter::Val>,
     ) -> Result<PutTextArgs, String> {
-        let signature = filter_utils::FunctionSignature {
-            parameters: vec![
-                Parameter::Positional { name: "img" },
-                Parameter::Positional { name: "text" },
-                Parameter::Positional { name: "org" },
-                Parameter::Positional { name: "fontFace" },
-                Parameter::Positional { name: "fontScale" },
-                Parameter::Positional { name: "color" },
-                Parameter::PositionalOptional {
-                    name: "thickness",
-                    default_value: Val::Int(1),
-                },
-                Parameter::PositionalOptional {
-                    name: "lineType",
-                    default_value: Val::Int(8),
-                },
-                Parameter::PositionalOptional {
-                    name: "bottomLeftOrigin",
-                    default_value: Val::Bool(false),
-                },
-            ],
-        };
-
         let kwargs = kwargs.clone();
         let args: Vec<Val> = args.to_vec();
-        let parsed_args = filter_utils::parse_arguments(&signature, args, kwargs)?;
+        let parsed_args = filter_utils::parse_arguments(&Self::sig(), args, kwargs)?;
 
         let img = match parsed_args.get("img") {
             Some(Val::Frame(frame)) => filter_utils::FrameArg::Frame(frame.clone()),
@@ -364,6 +367,10 @@ impl filter::Filter for PutText {
 
         Ok(opts.img.unwrap_frame_type())
     }
+
+    fn signature(&self) -> Option<Signature> {
+        Some(Self::sig())
+    }
 }
 
 pub struct ArrowedLine {}
@@ -380,38 +387,27 @@ struct ArrowedLineArgs {
 }
 
 impl ArrowedLine {
+    fn sig() -> Signature {
+        Signature::new(vec![
+            Param::positional("img", ParamType::Frame),
+            Param::positional("pt1", ParamType::List),
+            Param::positional("pt2", ParamType::List),
+            Param::positional("color", ParamType::List),
+            Param::positional("thickness", ParamType::Int).with_default(Val::Int(1)),
+            Param::positional("lineType", ParamType::Int).with_default(Val::Int(8)),
+            Param::positional("shift", ParamType::Int).with_default(Val::Int(0)),
+            Param::positional("tipLength", ParamType::Float).with_default(Val::Float(0.1)),
+        ])
+        .with_doc("Draw an arrow (cv2.arrowedLine)")
+    }
+
     fn args(
         args: &[filter::Val],
         kwargs: &BTreeMap<std::string::String, filter::Val>,
     ) -> Result<ArrowedLineArgs, String> {
-        let signature = FunctionSignature {
-            parameters: vec![
-                Parameter::Positional { name: "img" },
-                Parameter::Positional { name: "pt1" },
-                Parameter::Positional { name: "pt2" },
-                Parameter::Positional { name: "color" },
-                Parameter::PositionalOptional {
-                    name: "thickness",
-                    default_value: Val::Int(1),
-                },
-                Parameter::PositionalOptional {
-                    name: "lineType",
-                    default_value: Val::Int(8),
-                },
-                Parameter::PositionalOptional {
-                    name: "shift",
-                    default_value: Val::Int(0),
-                },
-                Parameter::PositionalOptional {
-                    name: "tipLength",
-                    default_value: Val::Float(0.1),
-                },
-            ],
-        };
-
         let kwargs = kwargs.clone();
         let args = args.to_vec();
-        let parsed_args = parse_arguments(&signature, args, kwargs)?;
+        let parsed_args = parse_arguments(&Self::sig(), args, kwargs)?;
 
         let img = match parsed_args.get("img") {
             Some(Val::Frame(frame)) => FrameArg::Frame(frame.clone()),
@@ -526,6 +522,10 @@ impl filter::Filter for ArrowedLine {
 
         Ok(opts.img.unwrap_frame_type())
     }
+
+    fn signature(&self) -> Option<Signature> {
+        Some(Self::sig())
+    }
 }
 
 pub struct Line {}
@@ -541,34 +541,26 @@ struct LineArgs {
 }
 
 impl Line {
+    fn sig() -> Signature {
+        Signature::new(vec![
+            Param::positional("img", ParamType::Frame),
+            Param::positional("pt1", ParamType::List),
+            Param::positional("pt2", ParamType::List),
+            Param::positional("color", ParamType::List),
+            Param::positional("thickness", ParamType::Int).with_default(Val::Int(1)),
+            Param::positional("lineType", ParamType::Int).with_default(Val::Int(8)),
+            Param::positional("shift", ParamType::Int).with_default(Val::Int(0)),
+        ])
+        .with_doc("Draw a line segment (cv2.line)")
+    }
+
     fn args(
         args: &[filter::Val],
         kwargs: &BTreeMap<std::string::String, filter::Val>,
     ) -> Result<LineArgs, String> {
-        let signature = FunctionSignature {
-            parameters: vec![
-                Parameter::Positional { name: "img" },
-                Parameter::Positional { name: "pt1" },
-                Parameter::Positional { name: "pt2" },
-                Parameter::Positional { name: "color" },
-                Parameter::PositionalOptional {
-                    name: "thickness",
-                    default_value: Val::Int(1),
-                },
-                Parameter::PositionalOptional {
-                    name: "lineType",
-                    default_value: Val::Int(8),
-                },
-                Parameter::PositionalOptional {
-                    name: "shift",
-                    default_value: Val::Int(0),
-                },
-            ],
-        };
-
         let kwargs = kwargs.clone();
         let args = args.to_vec();
-        let parsed_args = parse_arguments(&signature, args, kwargs)?;
+        let parsed_args = parse_arguments(&Self::sig(), args, kwargs)?;
 
         let img = match parsed_args.get("img") {
             Some(Val::Frame(frame)) => FrameArg::Frame(frame.clone()),
@@ -675,6 +667,10 @@ impl filter::Filter for Line {
 
         Ok(opts.img.unwrap_frame_type())
     }
+
+    fn signature(&self) -> Option<Signature> {
+        Some(Self::sig())
+    }
 }
 
 pub struct Circle {}
@@ -690,34 +686,26 @@ struct CircleArgs {
 }
 
 impl Circle {
+    fn sig() -> Signature {
+        Signature::new(vec![
+            Param::positional("img", ParamType::Frame),
+            Param::positional("center", ParamType::List),
+            Param::positional("radius", ParamType::Int),
+            Param::positional("color", ParamType::List),
+            Param::positional("thickness", ParamType::Int).with_default(Val::Int(1)),
+            Param::positional("lineType", ParamType::Int).with_default(Val::Int(8)),
+            Param::positional("shift", ParamType::Int).with_default(Val::Int(0)),
+        ])
+        .with_doc("Draw a circle (cv2.circle)")
+    }
+
     fn args(
         args: &[filter::Val],
         kwargs: &BTreeMap<std::string::String, filter::Val>,
     ) -> Result<CircleArgs, String> {
-        let signature = filter_utils::FunctionSignature {
-            parameters: vec![
-                Parameter::Positional { name: "img" },
-                Parameter::Positional { name: "center" },
-                Parameter::Positional { name: "radius" },
-                Parameter::Positional { name: "color" },
-                Parameter::PositionalOptional {
-                    name: "thickness",
-                    default_value: Val::Int(1),
-                },
-                Parameter::PositionalOptional {
-                    name: "lineType",
-                    default_value: Val::Int(8),
-                },
-                Parameter::PositionalOptional {
-                    name: "shift",
-                    default_value: Val::Int(0),
-                },
-            ],
-        };
-
         let kwargs = kwargs.clone();
         let args = args.to_vec();
-        let parsed_args = filter_utils::parse_arguments(&signature, args, kwargs)?;
+        let parsed_args = filter_utils::parse_arguments(&Self::sig(), args, kwargs)?;
 
         let img = match parsed_args.get("img") {
             Some(Val::Frame(frame)) => filter_utils::FrameArg::Frame(frame.clone()),
@@ -828,6 +816,10 @@ impl filter::Filter for Circle {
 
         Ok(opts.img.unwrap_frame_type())
     }
+
+    fn signature(&self) -> Option<Signature> {
+        Some(Self::sig())
+    }
 }
 
 /*
@@ -860,37 +852,29 @@ struct EllipseArgs {
 }
 
 impl Ellipse {
+    fn sig() -> Signature {
+        Signature::new(vec![
+            Param::positional("img", ParamType::Frame),
+            Param::positional("center", ParamType::List),
+            Param::positional("axes", ParamType::List),
+            Param::positional("angle", ParamType::Float),
+            Param::positional("startAngle", ParamType::Float),
+            Param::positional("endAngle", ParamType::Float),
+            Param::positional("color", ParamType::List),
+            Param::positional("thickness", ParamType::Int).with_default(Val::Int(1)),
+            Param::positional("lineType", ParamType::Int).with_default(Val::Int(8)),
+            Param::positional("shift", ParamType::Int).with_default(Val::Int(0)),
+        ])
+        .with_doc("Draw an ellipse or elliptic arc (cv2.ellipse)")
+    }
+
     fn args(
         args: &[Val],
         kwargs: &BTreeMap<std::string::String, Val>,
     ) -> Result<EllipseArgs, String> {
-        let signature = filter_utils::FunctionSignature {
-            parameters: vec![
-                Parameter::Positional { name: "img" },
-                Parameter::Positional { name: "center" },
-                Parameter::Positional { name: "axes" },
-                Parameter::Positional { name: "angle" },
-                Parameter::Positional { name: "startAngle" },
-                Parameter::Positional { name: "endAngle" },
-                Parameter::Positional { name: "color" },
-                Parameter::PositionalOptional {
-                    name: "thickness",
-                    default_value: Val::Int(1),
-                },
-                Parameter::PositionalOptional {
-                    name: "lineType",
-                    default_value: Val::Int(8),
-                },
-                Parameter::PositionalOptional {
-                    name: "shift",
-                    default_value: Val::Int(0),
-                },
-            ],
-        };
-
         let kwargs = kwargs.clone();
         let args = args.to_vec();
-        let parsed_args = filter_utils::parse_arguments(&signature, args, kwargs)?;
+        let parsed_args = filter_utils::parse_arguments(&Self::sig(), args, kwargs)?;
 
         let img = match parsed_args.get("img") {
             Some(Val::Frame(frame)) => filter_utils::FrameArg::Frame(frame.clone()),
@@ -1022,6 +1006,10 @@ impl Filter for Ellipse {
 
         Ok(opts.img.unwrap_frame_type())
     }
+
+    fn signature(&self) -> Option<Signature> {
+        Some(Self::sig())
+    }
 }
 
 pub struct SetTo {}
@@ -1033,21 +1021,22 @@ struct SetToArgs {
 }
 
 impl SetTo {
+    fn sig() -> Signature {
+        Signature::new(vec![
+            Param::positional("img", ParamType::Frame),
+            Param::positional("color", ParamType::List),
+            Param::positional("mask", ParamType::Frame),
+        ])
+        .with_doc("Set the pixels selected by a mask to a color (cv2.Mat.setTo)")
+    }
+
     fn args(
         args: &[Val],
         kwargs: &BTreeMap<std::string::String, Val>,
     ) -> Result<SetToArgs, String> {
-        let signature = filter_utils::FunctionSignature {
-            parameters: vec![
-                Parameter::Positional { name: "img" },
-                Parameter::Positional { name: "color" },
-                Parameter::Positional { name: "mask" },
-            ],
-        };
-
         let kwargs = kwargs.clone();
         let args = args.to_vec();
-        let parsed_args = filter_utils::parse_arguments(&signature, args, kwargs)?;
+        let parsed_args = filter_utils::parse_arguments(&Self::sig(), args, kwargs)?;
 
         let img = match parsed_args.get("img") {
             Some(Val::Frame(frame)) => filter_utils::FrameArg::Frame(frame.clone()),
@@ -1143,6 +1132,10 @@ impl Filter for SetTo {
 
         Ok(opts.img.unwrap_frame_type())
     }
+
+    fn signature(&self) -> Option<Signature> {
+        Some(Self::sig())
+    }
 }
 
 pub struct AddWeighted {}
@@ -1156,23 +1149,24 @@ struct AddWeightedArgs {
 }
 
 impl AddWeighted {
+    fn sig() -> Signature {
+        Signature::new(vec![
+            Param::positional("src1", ParamType::Frame),
+            Param::positional("alpha", ParamType::Float),
+            Param::positional("src2", ParamType::Frame),
+            Param::positional("beta", ParamType::Float),
+            Param::positional("gamma", ParamType::Float),
+        ])
+        .with_doc("Blend two frames (cv2.addWeighted)")
+    }
+
     fn args(
         args: &[Val],
         kwargs: &BTreeMap<std::string::String, Val>,
     ) -> Result<AddWeightedArgs, String> {
-        let signature = filter_utils::FunctionSignature {
-            parameters: vec![
-                Parameter::Positional { name: "src1" },
-                Parameter::Positional { name: "alpha" },
-                Parameter::Positional { name: "src2" },
-                Parameter::Positional { name: "beta" },
-                Parameter::Positional { name: "gamma" },
-            ],
-        };
-
         let kwargs = kwargs.clone();
         let args = args.to_vec();
-        let parsed_args = filter_utils::parse_arguments(&signature, args, kwargs)?;
+        let parsed_args = filter_utils::parse_arguments(&Self::sig(), args, kwargs)?;
 
         let src1 = match parsed_args.get("src1") {
             Some(Val::Frame(frame)) => filter_utils::FrameArg::Frame(frame.clone()),
@@ -1307,6 +1301,10 @@ impl Filter for AddWeighted {
 
         Ok(opts.src1.unwrap_frame_type())
     }
+
+    fn signature(&self) -> Option<Signature> {
+        Some(Self::sig())
+    }
 }
 
 pub struct Polylines {}
@@ -1322,34 +1320,26 @@ struct PolylinesArgs {
 }
 
 impl Polylines {
+    fn sig() -> Signature {
+        Signature::new(vec![
+            Param::positional("img", ParamType::Frame),
+            Param::positional("pts", ParamType::List),
+            Param::positional("isClosed", ParamType::Bool),
+            Param::positional("color", ParamType::List),
+            Param::positional("thickness", ParamType::Int).with_default(Val::Int(1)),
+            Param::positional("lineType", ParamType::Int).with_default(Val::Int(8)),
+            Param::positional("shift", ParamType::Int).with_default(Val::Int(0)),
+        ])
+        .with_doc("Draw polygonal curves (cv2.polylines)")
+    }
+
     fn args(
         args: &[filter::Val],
         kwargs: &BTreeMap<std::string::String, filter::Val>,
     ) -> Result<PolylinesArgs, String> {
-        let signature = filter_utils::FunctionSignature {
-            parameters: vec![
-                Parameter::Positional { name: "img" },
-                Parameter::Positional { name: "pts" },
-                Parameter::Positional { name: "isClosed" },
-                Parameter::Positional { name: "color" },
-                Parameter::PositionalOptional {
-                    name: "thickness",
-                    default_value: Val::Int(1),
-                },
-                Parameter::PositionalOptional {
-                    name: "lineType",
-                    default_value: Val::Int(8),
-                },
-                Parameter::PositionalOptional {
-                    name: "shift",
-                    default_value: Val::Int(0),
-                },
-            ],
-        };
-
         let kwargs = kwargs.clone();
         let args = args.to_vec();
-        let parsed_args = filter_utils::parse_arguments(&signature, args, kwargs)?;
+        let parsed_args = filter_utils::parse_arguments(&Self::sig(), args, kwargs)?;
 
         let img = match parsed_args.get("img") {
             Some(Val::Frame(frame)) => filter_utils::FrameArg::Frame(frame.clone()),
@@ -1458,6 +1448,10 @@ impl filter::Filter for Polylines {
 
         Ok(opts.img.unwrap_frame_type())
     }
+
+    fn signature(&self) -> Option<Signature> {
+        Some(Self::sig())
+    }
 }
 
 // Helper functions for parsing and converting polygon data (shared by polylines, fillPoly, fillConvexPoly)
@@ -1545,33 +1539,26 @@ struct FillPolyArgs {
 }
 
 impl FillPoly {
+    fn sig() -> Signature {
+        Signature::new(vec![
+            Param::positional("img", ParamType::Frame),
+            Param::positional("pts", ParamType::List),
+            Param::positional("color", ParamType::List),
+            Param::positional("lineType", ParamType::Int).with_default(Val::Int(8)),
+            Param::positional("shift", ParamType::Int).with_default(Val::Int(0)),
+            Param::positional("offset", ParamType::List)
+                .with_default(Val::List(vec![Val::Int(0), Val::Int(0)])),
+        ])
+        .with_doc("Fill polygons (cv2.fillPoly)")
+    }
+
     fn args(
         args: &[filter::Val],
         kwargs: &BTreeMap<std::string::String, filter::Val>,
     ) -> Result<FillPolyArgs, String> {
-        let signature = filter_utils::FunctionSignature {
-            parameters: vec![
-                Parameter::Positional { name: "img" },
-                Parameter::Positional { name: "pts" },
-                Parameter::Positional { name: "color" },
-                Parameter::PositionalOptional {
-                    name: "lineType",
-                    default_value: Val::Int(8),
-                },
-                Parameter::PositionalOptional {
-                    name: "shift",
-                    default_value: Val::Int(0),
-                },
-                Parameter::PositionalOptional {
-                    name: "offset",
-                    default_value: Val::List(vec![Val::Int(0), Val::Int(0)]),
-                },
-            ],
-        };
-
         let kwargs = kwargs.clone();
         let args = args.to_vec();
-        let parsed_args = filter_utils::parse_arguments(&signature, args, kwargs)?;
+        let parsed_args = filter_utils::parse_arguments(&Self::sig(), args, kwargs)?;
 
         let img = match parsed_args.get("img") {
             Some(Val::Frame(frame)) => filter_utils::FrameArg::Frame(frame.clone()),
@@ -1680,6 +1667,10 @@ impl filter::Filter for FillPoly {
 
         Ok(opts.img.unwrap_frame_type())
     }
+
+    fn signature(&self) -> Option<Signature> {
+        Some(Self::sig())
+    }
 }
 
 pub struct FillConvexPoly {}
@@ -1693,29 +1684,24 @@ struct FillConvexPolyArgs {
 }
 
 impl FillConvexPoly {
+    fn sig() -> Signature {
+        Signature::new(vec![
+            Param::positional("img", ParamType::Frame),
+            Param::positional("points", ParamType::List),
+            Param::positional("color", ParamType::List),
+            Param::positional("lineType", ParamType::Int).with_default(Val::Int(8)),
+            Param::positional("shift", ParamType::Int).with_default(Val::Int(0)),
+        ])
+        .with_doc("Fill a convex polygon (cv2.fillConvexPoly)")
+    }
+
     fn args(
         args: &[filter::Val],
         kwargs: &BTreeMap<std::string::String, filter::Val>,
     ) -> Result<FillConvexPolyArgs, String> {
-        let signature = filter_utils::FunctionSignature {
-            parameters: vec![
-                Parameter::Positional { name: "img" },
-                Parameter::Positional { name: "points" },
-                Parameter::Positional { name: "color" },
-                Parameter::PositionalOptional {
-                    name: "lineType",
-                    default_value: Val::Int(8),
-                },
-                Parameter::PositionalOptional {
-                    name: "shift",
-                    default_value: Val::Int(0),
-                },
-            ],
-        };
-
         let kwargs = kwargs.clone();
         let args = args.to_vec();
-        let parsed_args = filter_utils::parse_arguments(&signature, args, kwargs)?;
+        let parsed_args = filter_utils::parse_arguments(&Self::sig(), args, kwargs)?;
 
         let img = match parsed_args.get("img") {
             Some(Val::Frame(frame)) => filter_utils::FrameArg::Frame(frame.clone()),
@@ -1797,6 +1783,10 @@ impl filter::Filter for FillConvexPoly {
 
         Ok(opts.img.unwrap_frame_type())
     }
+
+    fn signature(&self) -> Option<Signature> {
+        Some(Self::sig())
+    }
 }
 
 pub struct DrawContours {}
@@ -1814,42 +1804,29 @@ struct DrawContoursArgs {
 }
 
 impl DrawContours {
+    fn sig() -> Signature {
+        Signature::new(vec![
+            Param::positional("img", ParamType::Frame),
+            Param::positional("contours", ParamType::List),
+            Param::positional("contourIdx", ParamType::Int),
+            Param::positional("color", ParamType::List),
+            Param::positional("thickness", ParamType::Int).with_default(Val::Int(1)),
+            Param::positional("lineType", ParamType::Int).with_default(Val::Int(8)),
+            Param::positional("hierarchy", ParamType::List).with_default(Val::List(vec![])),
+            Param::positional("maxLevel", ParamType::Int).with_default(Val::Int(i32::MAX as i64)),
+            Param::positional("offset", ParamType::List)
+                .with_default(Val::List(vec![Val::Int(0), Val::Int(0)])),
+        ])
+        .with_doc("Draw contour outlines or filled contours (cv2.drawContours)")
+    }
+
     fn args(
         args: &[filter::Val],
         kwargs: &BTreeMap<std::string::String, filter::Val>,
     ) -> Result<DrawContoursArgs, String> {
-        let signature = filter_utils::FunctionSignature {
-            parameters: vec![
-                Parameter::Positional { name: "img" },
-                Parameter::Positional { name: "contours" },
-                Parameter::Positional { name: "contourIdx" },
-                Parameter::Positional { name: "color" },
-                Parameter::PositionalOptional {
-                    name: "thickness",
-                    default_value: Val::Int(1),
-                },
-                Parameter::PositionalOptional {
-                    name: "lineType",
-                    default_value: Val::Int(8),
-                },
-                Parameter::PositionalOptional {
-                    name: "hierarchy",
-                    default_value: Val::List(vec![]),
-                },
-                Parameter::PositionalOptional {
-                    name: "maxLevel",
-                    default_value: Val::Int(i32::MAX as i64),
-                },
-                Parameter::PositionalOptional {
-                    name: "offset",
-                    default_value: Val::List(vec![Val::Int(0), Val::Int(0)]),
-                },
-            ],
-        };
-
         let kwargs = kwargs.clone();
         let args = args.to_vec();
-        let parsed_args = filter_utils::parse_arguments(&signature, args, kwargs)?;
+        let parsed_args = filter_utils::parse_arguments(&Self::sig(), args, kwargs)?;
 
         let img = match parsed_args.get("img") {
             Some(Val::Frame(frame)) => filter_utils::FrameArg::Frame(frame.clone()),
@@ -2019,6 +1996,10 @@ impl filter::Filter for DrawContours {
 
         Ok(opts.img.unwrap_frame_type())
     }
+
+    fn signature(&self) -> Option<Signature> {
+        Some(Self::sig())
+    }
 }
 
 pub struct DrawMarker {}
@@ -2034,37 +2015,26 @@ struct DrawMarkerArgs {
 }
 
 impl DrawMarker {
+    fn sig() -> Signature {
+        Signature::new(vec![
+            Param::positional("img", ParamType::Frame),
+            Param::positional("position", ParamType::List),
+            Param::positional("color", ParamType::List),
+            Param::positional("markerType", ParamType::Int).with_default(Val::Int(0)), // MARKER_CROSS
+            Param::positional("markerSize", ParamType::Int).with_default(Val::Int(20)),
+            Param::positional("thickness", ParamType::Int).with_default(Val::Int(1)),
+            Param::positional("line_type", ParamType::Int).with_default(Val::Int(8)),
+        ])
+        .with_doc("Draw a marker (cv2.drawMarker)")
+    }
+
     fn args(
         args: &[filter::Val],
         kwargs: &BTreeMap<std::string::String, filter::Val>,
     ) -> Result<DrawMarkerArgs, String> {
-        let signature = filter_utils::FunctionSignature {
-            parameters: vec![
-                Parameter::Positional { name: "img" },
-                Parameter::Positional { name: "position" },
-                Parameter::Positional { name: "color" },
-                Parameter::PositionalOptional {
-                    name: "markerType",
-                    default_value: Val::Int(0), // MARKER_CROSS
-                },
-                Parameter::PositionalOptional {
-                    name: "markerSize",
-                    default_value: Val::Int(20),
-                },
-                Parameter::PositionalOptional {
-                    name: "thickness",
-                    default_value: Val::Int(1),
-                },
-                Parameter::PositionalOptional {
-                    name: "line_type",
-                    default_value: Val::Int(8),
-                },
-            ],
-        };
-
         let kwargs = kwargs.clone();
         let args = args.to_vec();
-        let parsed_args = filter_utils::parse_arguments(&signature, args, kwargs)?;
+        let parsed_args = filter_utils::parse_arguments(&Self::sig(), args, kwargs)?;
 
         let img = match parsed_args.get("img") {
             Some(Val::Frame(frame)) => filter_utils::FrameArg::Frame(frame.clone()),
@@ -2183,6 +2153,10 @@ impl filter::Filter for DrawMarker {
 
         Ok(opts.img.unwrap_frame_type())
     }
+
+    fn signature(&self) -> Option<Signature> {
+        Some(Self::sig())
+    }
 }
 
 // Helper to parse polygon list with a custom field name
@@ -2215,20 +2189,21 @@ struct FlipArgs {
 }
 
 impl Flip {
+    fn sig() -> Signature {
+        Signature::new(vec![
+            Param::positional("src", ParamType::Frame),
+            Param::positional("flipCode", ParamType::Int),
+        ])
+        .with_doc("Flip a frame (cv2.flip)")
+    }
+
     fn args(
         args: &[filter::Val],
         kwargs: &BTreeMap<std::string::String, filter::Val>,
     ) -> Result<FlipArgs, String> {
-        let signature = filter_utils::FunctionSignature {
-            parameters: vec![
-                Parameter::Positional { name: "src" },
-                Parameter::Positional { name: "flipCode" },
-            ],
-        };
-
         let kwargs = kwargs.clone();
         let args = args.to_vec();
-        let parsed_args = filter_utils::parse_arguments(&signature, args, kwargs)?;
+        let parsed_args = filter_utils::parse_arguments(&Self::sig(), args, kwargs)?;
 
         let src = match parsed_args.get("src") {
             Some(Val::Frame(frame)) => filter_utils::FrameArg::Frame(frame.clone()),
@@ -2291,6 +2266,10 @@ impl filter::Filter for Flip {
 
         Ok(opts.src.unwrap_frame_type())
     }
+
+    fn signature(&self) -> Option<Signature> {
+        Some(Self::sig())
+    }
 }
 
 pub struct Rotate {}
@@ -2301,20 +2280,21 @@ struct RotateArgs {
 }
 
 impl Rotate {
+    fn sig() -> Signature {
+        Signature::new(vec![
+            Param::positional("src", ParamType::Frame),
+            Param::positional("rotateCode", ParamType::Int),
+        ])
+        .with_doc("Rotate a frame by a multiple of 90 degrees (cv2.rotate)")
+    }
+
     fn args(
         args: &[filter::Val],
         kwargs: &BTreeMap<std::string::String, filter::Val>,
     ) -> Result<RotateArgs, String> {
-        let signature = filter_utils::FunctionSignature {
-            parameters: vec![
-                Parameter::Positional { name: "src" },
-                Parameter::Positional { name: "rotateCode" },
-            ],
-        };
-
         let kwargs = kwargs.clone();
         let args = args.to_vec();
-        let parsed_args = filter_utils::parse_arguments(&signature, args, kwargs)?;
+        let parsed_args = filter_utils::parse_arguments(&Self::sig(), args, kwargs)?;
 
         let src = match parsed_args.get("src") {
             Some(Val::Frame(frame)) => filter_utils::FrameArg::Frame(frame.clone()),
@@ -2397,6 +2377,10 @@ impl filter::Filter for Rotate {
             format: src_type.format,
         })
     }
+
+    fn signature(&self) -> Option<Signature> {
+        Some(Self::sig())
+    }
 }
 
 pub struct CopyMakeBorder {}
@@ -2412,33 +2396,31 @@ struct CopyMakeBorderArgs {
 }
 
 impl CopyMakeBorder {
+    fn sig() -> Signature {
+        Signature::new(vec![
+            Param::positional("src", ParamType::Frame),
+            Param::positional("top", ParamType::Int),
+            Param::positional("bottom", ParamType::Int),
+            Param::positional("left", ParamType::Int),
+            Param::positional("right", ParamType::Int),
+            Param::positional("borderType", ParamType::Int),
+            Param::positional("value", ParamType::List).with_default(Val::List(vec![
+                Val::Float(0.0),
+                Val::Float(0.0),
+                Val::Float(0.0),
+                Val::Float(255.0),
+            ])),
+        ])
+        .with_doc("Pad a frame with a border (cv2.copyMakeBorder)")
+    }
+
     fn args(
         args: &[filter::Val],
         kwargs: &BTreeMap<std::string::String, filter::Val>,
     ) -> Result<CopyMakeBorderArgs, String> {
-        let signature = filter_utils::FunctionSignature {
-            parameters: vec![
-                Parameter::Positional { name: "src" },
-                Parameter::Positional { name: "top" },
-                Parameter::Positional { name: "bottom" },
-                Parameter::Positional { name: "left" },
-                Parameter::Positional { name: "right" },
-                Parameter::Positional { name: "borderType" },
-                Parameter::PositionalOptional {
-                    name: "value",
-                    default_value: Val::List(vec![
-                        Val::Float(0.0),
-                        Val::Float(0.0),
-                        Val::Float(0.0),
-                        Val::Float(255.0),
-                    ]),
-                },
-            ],
-        };
-
         let kwargs = kwargs.clone();
         let args = args.to_vec();
-        let parsed_args = filter_utils::parse_arguments(&signature, args, kwargs)?;
+        let parsed_args = filter_utils::parse_arguments(&Self::sig(), args, kwargs)?;
 
         let src = match parsed_args.get("src") {
             Some(Val::Frame(frame)) => filter_utils::FrameArg::Frame(frame.clone()),
@@ -2555,6 +2537,10 @@ impl filter::Filter for CopyMakeBorder {
             format: src_type.format,
         })
     }
+
+    fn signature(&self) -> Option<Signature> {
+        Some(Self::sig())
+    }
 }
 
 pub struct Hconcat {}
@@ -2564,17 +2550,18 @@ struct HconcatArgs {
 }
 
 impl Hconcat {
+    fn sig() -> Signature {
+        Signature::new(vec![Param::positional("tup", ParamType::List)])
+            .with_doc("Concatenate frames horizontally (cv2.hconcat)")
+    }
+
     fn args(
         args: &[filter::Val],
         kwargs: &BTreeMap<std::string::String, filter::Val>,
     ) -> Result<HconcatArgs, String> {
-        let signature = filter_utils::FunctionSignature {
-            parameters: vec![Parameter::Positional { name: "tup" }],
-        };
-
         let kwargs = kwargs.clone();
         let args = args.to_vec();
-        let parsed_args = filter_utils::parse_arguments(&signature, args, kwargs)?;
+        let parsed_args = filter_utils::parse_arguments(&Self::sig(), args, kwargs)?;
 
         let sources = match parsed_args.get("tup") {
             Some(Val::List(list)) => {
@@ -2690,6 +2677,10 @@ impl filter::Filter for Hconcat {
             format: format.unwrap(),
         })
     }
+
+    fn signature(&self) -> Option<Signature> {
+        Some(Self::sig())
+    }
 }
 
 pub struct Vconcat {}
@@ -2699,17 +2690,18 @@ struct VconcatArgs {
 }
 
 impl Vconcat {
+    fn sig() -> Signature {
+        Signature::new(vec![Param::positional("tup", ParamType::List)])
+            .with_doc("Concatenate frames vertically (cv2.vconcat)")
+    }
+
     fn args(
         args: &[filter::Val],
         kwargs: &BTreeMap<std::string::String, filter::Val>,
     ) -> Result<VconcatArgs, String> {
-        let signature = filter_utils::FunctionSignature {
-            parameters: vec![Parameter::Positional { name: "tup" }],
-        };
-
         let kwargs = kwargs.clone();
         let args = args.to_vec();
-        let parsed_args = filter_utils::parse_arguments(&signature, args, kwargs)?;
+        let parsed_args = filter_utils::parse_arguments(&Self::sig(), args, kwargs)?;
 
         let sources = match parsed_args.get("tup") {
             Some(Val::List(list)) => {
@@ -2825,4 +2817,1398 @@ impl filter::Filter for Vconcat {
             format: format.unwrap(),
         })
     }
+
+    fn signature(&self) -> Option<Signature> {
+        Some(Self::sig())
+    }
+}
+
+/// Check a border type for filtering, which doesn't support wrapped or transparent borders
+fn check_filter_border_type(border_type: i32) -> Result<(), String> {
+    match border_type & !opencv::core::BORDER_ISOLATED {
+        opencv::core::BORDER_CONSTANT
+        | opencv::core::BORDER_REPLICATE
+        | opencv::core::BORDER_REFLECT
+        | opencv::core::BORDER_REFLECT_101 => Ok(()),
+        _ => Err(format!("Unsupported 'borderType' {}", border_type)),
+    }
+}
+
+/// Filter an RGB24 frame into one of the same size with OpenCV
+///
+/// `src` picks the frame out of the parsed arguments, and `apply` is given the arguments and the frame as a Mat.
+fn filter_rgb24<A>(
+    opts: Result<A, String>,
+    src: fn(&A) -> &filter_utils::FrameArg,
+    apply: impl FnOnce(&A, opencv::core::Mat) -> opencv::Result<opencv::core::Mat>,
+) -> Result<filter::Frame, dve::Error> {
+    let opts = opts.map_err(dve::Error::AVError)?;
+
+    let src = src(&opts).unwrap_frame();
+    let (width, height) = (src.width, src.height);
+    debug_assert_eq!(src.format, ffi::AVPixelFormat_AV_PIX_FMT_RGB24);
+
+    let src_mat = filter_utils::frame_to_mat_rgb24(&src, width, height);
+    let dst_mat = apply(&opts, src_mat).map_err(|e| dve::Error::AVError(e.to_string()))?;
+
+    let f = match filter_utils::mat_to_frame_rgb24(dst_mat, width, height) {
+        Ok(value) => value,
+        Err(value) => return value,
+    };
+
+    Ok(filter::Frame::new(AVFrame { inner: f }))
+}
+
+/// The type of a frame filtered with [`filter_rgb24`], which is the type of its RGB24 source
+fn filter_rgb24_type<A>(
+    opts: Result<A, String>,
+    src: fn(&A) -> &filter_utils::FrameArg,
+) -> Result<filter::FrameType, dve::Error> {
+    let opts = opts.map_err(dve::Error::AVError)?;
+
+    let frame_type = src(&opts).unwrap_frame_type();
+    if frame_type.format != ffi::AVPixelFormat_AV_PIX_FMT_RGB24 {
+        return Err(dve::Error::AVError("Expected RGB24 frame".into()));
+    }
+
+    Ok(frame_type)
+}
+
+pub struct GaussianBlur {}
+
+struct GaussianBlurArgs {
+    src: filter_utils::FrameArg,
+    ksize: (i32, i32),
+    sigma_x: f64,
+    sigma_y: f64,
+    border_type: i32,
+}
+
+impl GaussianBlur {
+    fn sig() -> Signature {
+        Signature::new(vec![
+            Param::positional("src", ParamType::Frame),
+            Param::positional("ksize", ParamType::List).with_doc(
+                "Kernel width and height; each odd, or zero to compute it from the sigma",
+            ),
+            Param::positional("sigmaX", ParamType::Float),
+            Param::positional("sigmaY", ParamType::Float)
+                .with_default(Val::Float(0.0))
+                .with_doc("Zero to use sigmaX"),
+            Param::positional("borderType", ParamType::Int)
+                .with_default(Val::Int(opencv::core::BORDER_DEFAULT as i64)),
+        ])
+        .with_doc("Blur with a Gaussian kernel (cv2.GaussianBlur)")
+    }
+
+    fn args(
+        args: &[filter::Val],
+        kwargs: &BTreeMap<std::string::String, filter::Val>,
+    ) -> Result<GaussianBlurArgs, String> {
+        let kwargs = kwargs.clone();
+        let args = args.to_vec();
+        let parsed_args = filter_utils::parse_arguments(&Self::sig(), args, kwargs)?;
+
+        let src = filter_utils::get_frame(&parsed_args, "src")?;
+        let ksize = filter_utils::get_point(&parsed_args, "ksize")?;
+        let sigma_x = filter_utils::get_float(&parsed_args, "sigmaX")?;
+        let sigma_y = filter_utils::get_float(&parsed_args, "sigmaY")?;
+        let border_type = filter_utils::get_int(&parsed_args, "borderType")?;
+
+        for (size, sigma) in [
+            (ksize.0, sigma_x),
+            (ksize.1, if sigma_y > 0.0 { sigma_y } else { sigma_x }),
+        ] {
+            if size < 0 || (size > 0 && size % 2 == 0) {
+                return Err("Expected 'ksize' to be odd and positive, or zero".into());
+            }
+            if size == 0 && sigma <= 0.0 {
+                return Err("Expected a positive sigma when 'ksize' is zero".into());
+            }
+        }
+        check_filter_border_type(border_type)?;
+
+        Ok(GaussianBlurArgs {
+            src,
+            ksize,
+            sigma_x,
+            sigma_y,
+            border_type,
+        })
+    }
+}
+
+impl filter::Filter for GaussianBlur {
+    fn filter(
+        &self,
+        args: &[filter::Val],
+        kwargs: &BTreeMap<std::string::String, filter::Val>,
+    ) -> std::result::Result<filter::Frame, dve::Error> {
+        filter_rgb24(
+            Self::args(args, kwargs),
+            |opts| &opts.src,
+            |opts, src_mat| {
+                let mut dst_mat = opencv::core::Mat::default();
+                imgproc::gaussian_blur(
+                    &src_mat,
+                    &mut dst_mat,
+                    opencv::core::Size::new(opts.ksize.0, opts.ksize.1),
+                    opts.sigma_x,
+                    opts.sigma_y,
+                    opts.border_type,
+                )?;
+                Ok(dst_mat)
+            },
+        )
+    }
+
+    fn filter_type(
+        &self,
+        args: &[filter::Val],
+        kwargs: &BTreeMap<std::string::String, filter::Val>,
+    ) -> std::result::Result<filter::FrameType, dve::Error> {
+        filter_rgb24_type(Self::args(args, kwargs), |opts| &opts.src)
+    }
+
+    fn signature(&self) -> Option<Signature> {
+        Some(Self::sig())
+    }
+}
+
+pub struct MedianBlur {}
+
+struct MedianBlurArgs {
+    src: filter_utils::FrameArg,
+    ksize: i32,
+}
+
+impl MedianBlur {
+    fn sig() -> Signature {
+        Signature::new(vec![
+            Param::positional("src", ParamType::Frame),
+            Param::positional("ksize", ParamType::Int).with_doc("Aperture size; odd and positive"),
+        ])
+        .with_doc("Blur with a median filter (cv2.medianBlur)")
+    }
+
+    fn args(
+        args: &[filter::Val],
+        kwargs: &BTreeMap<std::string::String, filter::Val>,
+    ) -> Result<MedianBlurArgs, String> {
+        let kwargs = kwargs.clone();
+        let args = args.to_vec();
+        let parsed_args = filter_utils::parse_arguments(&Self::sig(), args, kwargs)?;
+
+        let src = filter_utils::get_frame(&parsed_args, "src")?;
+        let ksize = filter_utils::get_int(&parsed_args, "ksize")?;
+        if ksize <= 0 || ksize % 2 == 0 {
+            return Err("Expected 'ksize' to be odd and positive".into());
+        }
+
+        Ok(MedianBlurArgs { src, ksize })
+    }
+}
+
+impl filter::Filter for MedianBlur {
+    fn filter(
+        &self,
+        args: &[filter::Val],
+        kwargs: &BTreeMap<std::string::String, filter::Val>,
+    ) -> std::result::Result<filter::Frame, dve::Error> {
+        filter_rgb24(
+            Self::args(args, kwargs),
+            |opts| &opts.src,
+            |opts, src_mat| {
+                let mut dst_mat = opencv::core::Mat::default();
+                imgproc::median_blur(&src_mat, &mut dst_mat, opts.ksize)?;
+                Ok(dst_mat)
+            },
+        )
+    }
+
+    fn filter_type(
+        &self,
+        args: &[filter::Val],
+        kwargs: &BTreeMap<std::string::String, filter::Val>,
+    ) -> std::result::Result<filter::FrameType, dve::Error> {
+        filter_rgb24_type(Self::args(args, kwargs), |opts| &opts.src)
+    }
+
+    fn signature(&self) -> Option<Signature> {
+        Some(Self::sig())
+    }
+}
+
+pub struct Blur {}
+
+struct BlurArgs {
+    src: filter_utils::FrameArg,
+    ksize: (i32, i32),
+    anchor: (i32, i32),
+    border_type: i32,
+}
+
+impl Blur {
+    fn sig() -> Signature {
+        Signature::new(vec![
+            Param::positional("src", ParamType::Frame),
+            Param::positional("ksize", ParamType::List).with_doc("Kernel width and height"),
+            Param::positional("anchor", ParamType::List)
+                .with_default(Val::List(vec![Val::Int(-1), Val::Int(-1)]))
+                .with_doc("Anchor point within the kernel; [-1, -1] for the center"),
+            Param::positional("borderType", ParamType::Int)
+                .with_default(Val::Int(opencv::core::BORDER_DEFAULT as i64)),
+        ])
+        .with_doc("Blur with a normalized box filter (cv2.blur)")
+    }
+
+    fn args(
+        args: &[filter::Val],
+        kwargs: &BTreeMap<std::string::String, filter::Val>,
+    ) -> Result<BlurArgs, String> {
+        let kwargs = kwargs.clone();
+        let args = args.to_vec();
+        let parsed_args = filter_utils::parse_arguments(&Self::sig(), args, kwargs)?;
+
+        let src = filter_utils::get_frame(&parsed_args, "src")?;
+        let ksize = filter_utils::get_point(&parsed_args, "ksize")?;
+        let anchor = filter_utils::get_point(&parsed_args, "anchor")?;
+        let border_type = filter_utils::get_int(&parsed_args, "borderType")?;
+
+        if ksize.0 <= 0 || ksize.1 <= 0 {
+            return Err("Expected 'ksize' to be positive".into());
+        }
+        let anchor_ok = |a: i32, size: i32| a == -1 || (0..size).contains(&a);
+        if !anchor_ok(anchor.0, ksize.0) || !anchor_ok(anchor.1, ksize.1) {
+            return Err("Expected 'anchor' to be within the kernel".into());
+        }
+        check_filter_border_type(border_type)?;
+
+        Ok(BlurArgs {
+            src,
+            ksize,
+            anchor,
+            border_type,
+        })
+    }
+}
+
+impl filter::Filter for Blur {
+    fn filter(
+        &self,
+        args: &[filter::Val],
+        kwargs: &BTreeMap<std::string::String, filter::Val>,
+    ) -> std::result::Result<filter::Frame, dve::Error> {
+        filter_rgb24(
+            Self::args(args, kwargs),
+            |opts| &opts.src,
+            |opts, src_mat| {
+                let mut dst_mat = opencv::core::Mat::default();
+                imgproc::blur(
+                    &src_mat,
+                    &mut dst_mat,
+                    opencv::core::Size::new(opts.ksize.0, opts.ksize.1),
+                    opencv::core::Point::new(opts.anchor.0, opts.anchor.1),
+                    opts.border_type,
+                )?;
+                Ok(dst_mat)
+            },
+        )
+    }
+
+    fn filter_type(
+        &self,
+        args: &[filter::Val],
+        kwargs: &BTreeMap<std::string::String, filter::Val>,
+    ) -> std::result::Result<filter::FrameType, dve::Error> {
+        filter_rgb24_type(Self::args(args, kwargs), |opts| &opts.src)
+    }
+
+    fn signature(&self) -> Option<Signature> {
+        Some(Self::sig())
+    }
+}
+
+pub struct BilateralFilter {}
+
+struct BilateralFilterArgs {
+    src: filter_utils::FrameArg,
+    d: i32,
+    sigma_color: f64,
+    sigma_space: f64,
+    border_type: i32,
+}
+
+impl BilateralFilter {
+    fn sig() -> Signature {
+        Signature::new(vec![
+            Param::positional("src", ParamType::Frame),
+            Param::positional("d", ParamType::Int).with_doc(
+                "Diameter of each pixel neighborhood; non-positive to compute it from sigmaSpace",
+            ),
+            Param::positional("sigmaColor", ParamType::Float),
+            Param::positional("sigmaSpace", ParamType::Float),
+            Param::positional("borderType", ParamType::Int)
+                .with_default(Val::Int(opencv::core::BORDER_DEFAULT as i64)),
+        ])
+        .with_doc("Smooth while keeping edges sharp (cv2.bilateralFilter)")
+    }
+
+    fn args(
+        args: &[filter::Val],
+        kwargs: &BTreeMap<std::string::String, filter::Val>,
+    ) -> Result<BilateralFilterArgs, String> {
+        let kwargs = kwargs.clone();
+        let args = args.to_vec();
+        let parsed_args = filter_utils::parse_arguments(&Self::sig(), args, kwargs)?;
+
+        let src = filter_utils::get_frame(&parsed_args, "src")?;
+        let d = filter_utils::get_int(&parsed_args, "d")?;
+        let sigma_color = filter_utils::get_float(&parsed_args, "sigmaColor")?;
+        let sigma_space = filter_utils::get_float(&parsed_args, "sigmaSpace")?;
+        let border_type = filter_utils::get_int(&parsed_args, "borderType")?;
+        check_filter_border_type(border_type)?;
+
+        Ok(BilateralFilterArgs {
+            src,
+            d,
+            sigma_color,
+            sigma_space,
+            border_type,
+        })
+    }
+}
+
+impl filter::Filter for BilateralFilter {
+    fn filter(
+        &self,
+        args: &[filter::Val],
+        kwargs: &BTreeMap<std::string::String, filter::Val>,
+    ) -> std::result::Result<filter::Frame, dve::Error> {
+        filter_rgb24(
+            Self::args(args, kwargs),
+            |opts| &opts.src,
+            |opts, src_mat| {
+                let mut dst_mat = opencv::core::Mat::default();
+                imgproc::bilateral_filter(
+                    &src_mat,
+                    &mut dst_mat,
+                    opts.d,
+                    opts.sigma_color,
+                    opts.sigma_space,
+                    opts.border_type,
+                )?;
+                Ok(dst_mat)
+            },
+        )
+    }
+
+    fn filter_type(
+        &self,
+        args: &[filter::Val],
+        kwargs: &BTreeMap<std::string::String, filter::Val>,
+    ) -> std::result::Result<filter::FrameType, dve::Error> {
+        filter_rgb24_type(Self::args(args, kwargs), |opts| &opts.src)
+    }
+
+    fn signature(&self) -> Option<Signature> {
+        Some(Self::sig())
+    }
+}
+
+/// Blur inside a rectangle or polygons, leaving the rest of the frame untouched
+///
+/// This isn't an OpenCV function; it's meant for anonymizing regions like faces and license plates.
+/// Only the region and a margin around it are blurred, so the cost scales with the region rather than the frame.
+pub struct BlurRegion {}
+
+#[derive(Clone, Copy)]
+enum BlurMethod {
+    Gaussian,
+    Median,
+    Box,
+}
+
+enum BlurShape {
+    /// x, y, width, height
+    Rect(i32, i32, i32, i32),
+    Polygons(Vec<Vec<(i32, i32)>>),
+}
+
+struct BlurRegionArgs {
+    src: filter_utils::FrameArg,
+    ksize: i32,
+    shape: BlurShape,
+    method: BlurMethod,
+}
+
+impl BlurRegion {
+    fn sig() -> Signature {
+        Signature::new(vec![
+            Param::positional("src", ParamType::Frame),
+            Param::positional("ksize", ParamType::Int)
+                .with_doc("Kernel size; odd and positive, larger blurs more"),
+            Param::keyword_only("rect", ParamType::List)
+                .optional()
+                .with_doc("Region to blur as [x, y, width, height]"),
+            Param::keyword_only("pts", ParamType::List)
+                .optional()
+                .with_doc("Region to blur as a list of polygons, each a list of [x, y] points"),
+            Param::keyword_only("method", ParamType::String)
+                .with_default(Val::String("gaussian".to_string()))
+                .with_doc("gaussian, median, or box"),
+        ])
+        .with_doc("Blur inside a rectangle or polygons, e.g., to anonymize faces")
+    }
+
+    fn args(
+        args: &[filter::Val],
+        kwargs: &BTreeMap<std::string::String, filter::Val>,
+    ) -> Result<BlurRegionArgs, String> {
+        let kwargs = kwargs.clone();
+        let args = args.to_vec();
+        let parsed_args = filter_utils::parse_arguments(&Self::sig(), args, kwargs)?;
+
+        let src = filter_utils::get_frame(&parsed_args, "src")?;
+        let ksize = filter_utils::get_int(&parsed_args, "ksize")?;
+        if ksize <= 0 || ksize % 2 == 0 {
+            return Err("Expected 'ksize' to be odd and positive".into());
+        }
+
+        let shape = match (parsed_args.get("rect"), parsed_args.contains_key("pts")) {
+            (Some(Val::List(rect)), false) => match rect.as_slice() {
+                [Val::Int(x), Val::Int(y), Val::Int(w), Val::Int(h)] if *w >= 0 && *h >= 0 => {
+                    BlurShape::Rect(*x as i32, *y as i32, *w as i32, *h as i32)
+                }
+                _ => {
+                    return Err(
+                        "Expected 'rect' to be [x, y, width, height] with a non-negative size"
+                            .into(),
+                    )
+                }
+            },
+            (None, true) => BlurShape::Polygons(parse_polygon_list(&parsed_args)?),
+            _ => return Err("Expected exactly one of 'rect' and 'pts'".into()),
+        };
+
+        let method = match filter_utils::get_string(&parsed_args, "method")?.as_str() {
+            "gaussian" => BlurMethod::Gaussian,
+            "median" => BlurMethod::Median,
+            "box" => BlurMethod::Box,
+            other => return Err(format!("Unknown blur method '{}'", other)),
+        };
+
+        Ok(BlurRegionArgs {
+            src,
+            ksize,
+            shape,
+            method,
+        })
+    }
+}
+
+impl filter::Filter for BlurRegion {
+    fn filter(
+        &self,
+        args: &[filter::Val],
+        kwargs: &BTreeMap<std::string::String, filter::Val>,
+    ) -> std::result::Result<filter::Frame, dve::Error> {
+        use opencv::core::{Mat, Point, Rect, Scalar, Size};
+        use opencv::prelude::MatTraitConst;
+
+        filter_rgb24(
+            Self::args(args, kwargs),
+            |opts| &opts.src,
+            |opts, mut mat| {
+                let (width, height) = (mat.cols(), mat.rows());
+
+                let bounds = match &opts.shape {
+                    BlurShape::Rect(x, y, w, h) => Some(Rect::new(*x, *y, *w, *h)),
+                    BlurShape::Polygons(polygons) => {
+                        let points = polygons.iter().flatten();
+                        let min_x = points.clone().map(|p| p.0).min();
+                        let min_y = points.clone().map(|p| p.1).min();
+                        let max_x = points.clone().map(|p| p.0).max();
+                        let max_y = points.map(|p| p.1).max();
+                        match (min_x, min_y, max_x, max_y) {
+                            (Some(min_x), Some(min_y), Some(max_x), Some(max_y)) => Some(
+                                Rect::new(min_x, min_y, max_x - min_x + 1, max_y - min_y + 1),
+                            ),
+                            _ => None,
+                        }
+                    }
+                };
+
+                // Blur the region plus a margin of half the kernel, so pixels at the region's edge are blurred
+                // with their real neighbors rather than a border
+                let margin = opts.ksize / 2;
+                let roi = bounds.map(|bounds| {
+                    let (x0, y0) = ((bounds.x - margin).max(0), (bounds.y - margin).max(0));
+                    let x1 = (bounds.x + bounds.width + margin).min(width);
+                    let y1 = (bounds.y + bounds.height + margin).min(height);
+                    Rect::new(x0, y0, x1 - x0, y1 - y0)
+                });
+
+                if let Some(roi) = roi.filter(|roi| roi.width > 0 && roi.height > 0) {
+                    let blurred = {
+                        let src_roi = Mat::roi(&mat, roi)?;
+                        let mut blurred = Mat::default();
+                        match opts.method {
+                            BlurMethod::Gaussian => imgproc::gaussian_blur(
+                                &*src_roi,
+                                &mut blurred,
+                                Size::new(opts.ksize, opts.ksize),
+                                0.0,
+                                0.0,
+                                opencv::core::BORDER_DEFAULT,
+                            ),
+                            BlurMethod::Median => {
+                                imgproc::median_blur(&*src_roi, &mut blurred, opts.ksize)
+                            }
+                            BlurMethod::Box => imgproc::blur(
+                                &*src_roi,
+                                &mut blurred,
+                                Size::new(opts.ksize, opts.ksize),
+                                Point::new(-1, -1),
+                                opencv::core::BORDER_DEFAULT,
+                            ),
+                        }?;
+                        blurred
+                    };
+
+                    // Draw the region into a mask covering the blurred area, then copy the blurred pixels through it
+                    let mut mask = Mat::new_rows_cols_with_default(
+                        roi.height,
+                        roi.width,
+                        opencv::core::CV_8UC1,
+                        Scalar::all(0.0),
+                    )?;
+                    match &opts.shape {
+                        BlurShape::Rect(x, y, w, h) => imgproc::rectangle(
+                            &mut mask,
+                            Rect::new(x - roi.x, y - roi.y, *w, *h),
+                            Scalar::all(255.0),
+                            imgproc::FILLED,
+                            imgproc::LINE_8,
+                            0,
+                        ),
+                        BlurShape::Polygons(polygons) => imgproc::fill_poly(
+                            &mut mask,
+                            &polygon_list_to_opencv(polygons),
+                            Scalar::all(255.0),
+                            imgproc::LINE_8,
+                            0,
+                            Point::new(-roi.x, -roi.y),
+                        ),
+                    }?;
+
+                    let mut dst_roi = Mat::roi_mut(&mut mat, roi)?;
+                    blurred.copy_to_masked(&mut *dst_roi, &mask)?;
+                }
+
+                Ok(mat)
+            },
+        )
+    }
+
+    fn filter_type(
+        &self,
+        args: &[filter::Val],
+        kwargs: &BTreeMap<std::string::String, filter::Val>,
+    ) -> std::result::Result<filter::FrameType, dve::Error> {
+        filter_rgb24_type(Self::args(args, kwargs), |opts| &opts.src)
+    }
+
+    fn signature(&self) -> Option<Signature> {
+        Some(Self::sig())
+    }
+}
+
+/// Parse a `rows` by `cols` matrix given as a list of rows of floats, in row-major order
+fn get_matrix(
+    parsed_args: &BTreeMap<&str, Val>,
+    key: &str,
+    rows: usize,
+    cols: usize,
+) -> Result<Vec<f64>, String> {
+    let err = || format!("Expected '{key}' to be a {rows}x{cols} list of lists of floats");
+    let list = match parsed_args.get(key) {
+        Some(Val::List(list)) if list.len() == rows => list,
+        _ => return Err(err()),
+    };
+    let mut values = Vec::with_capacity(rows * cols);
+    for row in list {
+        match row {
+            Val::List(row) if row.len() == cols => {
+                for value in row {
+                    match value {
+                        Val::Float(value) => values.push(*value),
+                        _ => return Err(err()),
+                    }
+                }
+            }
+            _ => return Err(err()),
+        }
+    }
+    Ok(values)
+}
+
+fn matrix_to_mat(values: &[f64], rows: usize, cols: usize) -> opencv::core::Mat {
+    debug_assert_eq!(values.len(), rows * cols);
+    let mut mat = opencv::core::Mat::new_rows_cols_with_default(
+        rows as i32,
+        cols as i32,
+        opencv::core::CV_64F,
+        opencv::core::Scalar::all(0.0),
+    )
+    .unwrap();
+    for (idx, value) in values.iter().enumerate() {
+        *mat.at_2d_mut::<f64>((idx / cols) as i32, (idx % cols) as i32)
+            .unwrap() = *value;
+    }
+    mat
+}
+
+/// Check the flags of a warp: an interpolation method, optionally combined with `WARP_INVERSE_MAP`
+fn check_warp_flags(flags: i32) -> Result<(), String> {
+    match flags & !imgproc::WARP_INVERSE_MAP {
+        imgproc::INTER_NEAREST
+        | imgproc::INTER_LINEAR
+        | imgproc::INTER_CUBIC
+        | imgproc::INTER_AREA
+        | imgproc::INTER_LANCZOS4 => Ok(()),
+        _ => Err(format!("Unsupported warp 'flags' {}", flags)),
+    }
+}
+
+fn check_warp_border_mode(border_mode: i32) -> Result<(), String> {
+    match border_mode {
+        opencv::core::BORDER_CONSTANT
+        | opencv::core::BORDER_REPLICATE
+        | opencv::core::BORDER_REFLECT
+        | opencv::core::BORDER_WRAP
+        | opencv::core::BORDER_REFLECT_101
+        | opencv::core::BORDER_TRANSPARENT => Ok(()),
+        _ => Err(format!("Unsupported 'borderMode' {}", border_mode)),
+    }
+}
+
+fn check_dsize(dsize: (i32, i32)) -> Result<(), String> {
+    if dsize.0 <= 0 || dsize.1 <= 0 {
+        return Err("Expected 'dsize' to be positive".into());
+    }
+    Ok(())
+}
+
+/// Apply a 2x3 affine or 3x3 perspective transform
+///
+/// Pixels of the output which come from outside the source are filled according to `border_mode`; with
+/// `BORDER_TRANSPARENT` they are left black.
+fn warp(
+    src: &filter::Frame,
+    matrix: &[f64],
+    dsize: (i32, i32),
+    flags: i32,
+    border_mode: i32,
+    border_value: [f64; 4],
+) -> Result<filter::Frame, dve::Error> {
+    debug_assert_eq!(src.format, ffi::AVPixelFormat_AV_PIX_FMT_RGB24);
+    let src_mat = filter_utils::frame_to_mat_rgb24(src, src.width, src.height);
+    let mut dst_mat = opencv::core::Mat::new_rows_cols_with_default(
+        dsize.1,
+        dsize.0,
+        opencv::core::CV_8UC3,
+        opencv::core::Scalar::all(0.0),
+    )
+    .unwrap();
+    let border_value = opencv::core::Scalar::new(
+        border_value[0],
+        border_value[1],
+        border_value[2],
+        border_value[3],
+    );
+    let size = opencv::core::Size::new(dsize.0, dsize.1);
+
+    if matrix.len() == 6 {
+        imgproc::warp_affine(
+            &src_mat,
+            &mut dst_mat,
+            &matrix_to_mat(matrix, 2, 3),
+            size,
+            flags,
+            border_mode,
+            border_value,
+        )
+        .unwrap();
+    } else {
+        imgproc::warp_perspective(
+            &src_mat,
+            &mut dst_mat,
+            &matrix_to_mat(matrix, 3, 3),
+            size,
+            flags,
+            border_mode,
+            border_value,
+        )
+        .unwrap();
+    }
+
+    let f = match filter_utils::mat_to_frame_rgb24(dst_mat, dsize.0, dsize.1) {
+        Ok(value) => value,
+        Err(value) => return value,
+    };
+
+    Ok(filter::Frame::new(AVFrame { inner: f }))
+}
+
+pub struct WarpAffine {}
+
+struct WarpArgs {
+    src: filter_utils::FrameArg,
+    matrix: Vec<f64>,
+    dsize: (i32, i32),
+    flags: i32,
+    border_mode: i32,
+    border_value: [f64; 4],
+}
+
+/// The parameters shared by `cv2.warpAffine` and `cv2.warpPerspective`
+fn warp_params(matrix_doc: &'static str) -> Vec<Param> {
+    vec![
+        Param::positional("src", ParamType::Frame),
+        Param::positional("M", ParamType::List).with_doc(matrix_doc),
+        Param::positional("dsize", ParamType::List).with_doc("Output width and height"),
+        Param::positional("flags", ParamType::Int)
+            .with_default(Val::Int(imgproc::INTER_LINEAR as i64))
+            .with_doc("Interpolation method, optionally combined with WARP_INVERSE_MAP"),
+        Param::positional("borderMode", ParamType::Int)
+            .with_default(Val::Int(opencv::core::BORDER_CONSTANT as i64)),
+        Param::positional("borderValue", ParamType::List).with_default(Val::List(vec![
+            Val::Float(0.0),
+            Val::Float(0.0),
+            Val::Float(0.0),
+            Val::Float(0.0),
+        ])),
+    ]
+}
+
+fn warp_args(
+    signature: &Signature,
+    matrix_rows: usize,
+    args: &[filter::Val],
+    kwargs: &BTreeMap<std::string::String, filter::Val>,
+) -> Result<WarpArgs, String> {
+    let kwargs = kwargs.clone();
+    let args = args.to_vec();
+    let parsed_args = filter_utils::parse_arguments(signature, args, kwargs)?;
+
+    let src = filter_utils::get_frame(&parsed_args, "src")?;
+    let matrix = get_matrix(&parsed_args, "M", matrix_rows, 3)?;
+    let dsize = filter_utils::get_point(&parsed_args, "dsize")?;
+    let flags = filter_utils::get_int(&parsed_args, "flags")?;
+    let border_mode = filter_utils::get_int(&parsed_args, "borderMode")?;
+    let border_value = filter_utils::get_color_with_key(&parsed_args, "borderValue")?;
+
+    check_dsize(dsize)?;
+    check_warp_flags(flags)?;
+    check_warp_border_mode(border_mode)?;
+
+    Ok(WarpArgs {
+        src,
+        matrix,
+        dsize,
+        flags,
+        border_mode,
+        border_value,
+    })
+}
+
+impl WarpAffine {
+    fn sig() -> Signature {
+        Signature::new(warp_params("2x3 transformation matrix"))
+            .with_doc("Apply an affine transformation (cv2.warpAffine)")
+    }
+
+    fn args(
+        args: &[filter::Val],
+        kwargs: &BTreeMap<std::string::String, filter::Val>,
+    ) -> Result<WarpArgs, String> {
+        warp_args(&Self::sig(), 2, args, kwargs)
+    }
+}
+
+impl filter::Filter for WarpAffine {
+    fn filter(
+        &self,
+        args: &[filter::Val],
+        kwargs: &BTreeMap<std::string::String, filter::Val>,
+    ) -> std::result::Result<filter::Frame, dve::Error> {
+        let opts: WarpArgs = match Self::args(args, kwargs) {
+            Ok(args) => args,
+            Err(err) => return Err(dve::Error::AVError(err)),
+        };
+
+        warp(
+            &opts.src.unwrap_frame(),
+            &opts.matrix,
+            opts.dsize,
+            opts.flags,
+            opts.border_mode,
+            opts.border_value,
+        )
+    }
+
+    fn filter_type(
+        &self,
+        args: &[filter::Val],
+        kwargs: &BTreeMap<std::string::String, filter::Val>,
+    ) -> std::result::Result<filter::FrameType, dve::Error> {
+        let opts: WarpArgs = match Self::args(args, kwargs) {
+            Ok(args) => args,
+            Err(err) => return Err(dve::Error::AVError(err)),
+        };
+
+        if opts.src.unwrap_frame_type().format != ffi::AVPixelFormat_AV_PIX_FMT_RGB24 {
+            return Err(dve::Error::AVError("Expected RGB24 frame".into()));
+        }
+
+        Ok(FrameType::new(
+            opts.dsize.0 as usize,
+            opts.dsize.1 as usize,
+            ffi::AVPixelFormat_AV_PIX_FMT_RGB24,
+        ))
+    }
+
+    fn signature(&self) -> Option<Signature> {
+        Some(Self::sig())
+    }
+}
+
+pub struct WarpPerspective {}
+
+impl WarpPerspective {
+    fn sig() -> Signature {
+        Signature::new(warp_params("3x3 transformation matrix"))
+            .with_doc("Apply a perspective transformation (cv2.warpPerspective)")
+    }
+
+    fn args(
+        args: &[filter::Val],
+        kwargs: &BTreeMap<std::string::String, filter::Val>,
+    ) -> Result<WarpArgs, String> {
+        warp_args(&Self::sig(), 3, args, kwargs)
+    }
+}
+
+impl filter::Filter for WarpPerspective {
+    fn filter(
+        &self,
+        args: &[filter::Val],
+        kwargs: &BTreeMap<std::string::String, filter::Val>,
+    ) -> std::result::Result<filter::Frame, dve::Error> {
+        let opts: WarpArgs = match Self::args(args, kwargs) {
+            Ok(args) => args,
+            Err(err) => return Err(dve::Error::AVError(err)),
+        };
+
+        warp(
+            &opts.src.unwrap_frame(),
+            &opts.matrix,
+            opts.dsize,
+            opts.flags,
+            opts.border_mode,
+            opts.border_value,
+        )
+    }
+
+    fn filter_type(
+        &self,
+        args: &[filter::Val],
+        kwargs: &BTreeMap<std::string::String, filter::Val>,
+    ) -> std::result::Result<filter::FrameType, dve::Error> {
+        let opts: WarpArgs = match Self::args(args, kwargs) {
+            Ok(args) => args,
+            Err(err) => return Err(dve::Error::AVError(err)),
+        };
+
+        if opts.src.unwrap_frame_type().format != ffi::AVPixelFormat_AV_PIX_FMT_RGB24 {
+            return Err(dve::Error::AVError("Expected RGB24 frame".into()));
+        }
+
+        Ok(FrameType::new(
+            opts.dsize.0 as usize,
+            opts.dsize.1 as usize,
+            ffi::AVPixelFormat_AV_PIX_FMT_RGB24,
+        ))
+    }
+
+    fn signature(&self) -> Option<Signature> {
+        Some(Self::sig())
+    }
+}
+
+/// Round like OpenCV's `cvRound`, with halves to even
+fn cv_round(value: f64) -> i32 {
+    let rounded = value.round();
+    if (value - value.trunc()).abs() == 0.5 {
+        (2.0 * (value / 2.0).round()) as i32
+    } else {
+        rounded as i32
+    }
+}
+
+pub struct Resize {}
+
+struct ResizeArgs {
+    src: filter_utils::FrameArg,
+    dsize: (i32, i32),
+    fx: f64,
+    fy: f64,
+    interpolation: i32,
+}
+
+impl ResizeArgs {
+    /// The output size, from `dsize` or else from the scale factors
+    fn output_size(&self, src_width: i32, src_height: i32) -> (i32, i32) {
+        if self.dsize != (0, 0) {
+            self.dsize
+        } else {
+            (
+                cv_round(src_width as f64 * self.fx),
+                cv_round(src_height as f64 * self.fy),
+            )
+        }
+    }
+}
+
+impl Resize {
+    fn sig() -> Signature {
+        Signature::new(vec![
+            Param::positional("src", ParamType::Frame),
+            Param::positional("dsize", ParamType::List)
+                .with_doc("Output width and height, or [0, 0] to scale by fx and fy"),
+            Param::positional("fx", ParamType::Float).with_default(Val::Float(0.0)),
+            Param::positional("fy", ParamType::Float).with_default(Val::Float(0.0)),
+            Param::positional("interpolation", ParamType::Int)
+                .with_default(Val::Int(imgproc::INTER_LINEAR as i64)),
+        ])
+        .with_doc("Resize a frame (cv2.resize)")
+    }
+
+    fn args(
+        args: &[filter::Val],
+        kwargs: &BTreeMap<std::string::String, filter::Val>,
+    ) -> Result<ResizeArgs, String> {
+        let kwargs = kwargs.clone();
+        let args = args.to_vec();
+        let parsed_args = filter_utils::parse_arguments(&Self::sig(), args, kwargs)?;
+
+        let src = filter_utils::get_frame(&parsed_args, "src")?;
+        let dsize = filter_utils::get_point(&parsed_args, "dsize")?;
+        let fx = filter_utils::get_float(&parsed_args, "fx")?;
+        let fy = filter_utils::get_float(&parsed_args, "fy")?;
+        let interpolation = filter_utils::get_int(&parsed_args, "interpolation")?;
+
+        if dsize != (0, 0) {
+            check_dsize(dsize)?;
+        } else if fx <= 0.0 || fy <= 0.0 {
+            return Err("Expected positive 'fx' and 'fy' when 'dsize' is [0, 0]".into());
+        }
+        match interpolation {
+            imgproc::INTER_NEAREST
+            | imgproc::INTER_LINEAR
+            | imgproc::INTER_CUBIC
+            | imgproc::INTER_AREA
+            | imgproc::INTER_LANCZOS4
+            | imgproc::INTER_LINEAR_EXACT
+            | imgproc::INTER_NEAREST_EXACT => {}
+            _ => return Err(format!("Unsupported 'interpolation' {}", interpolation)),
+        }
+
+        Ok(ResizeArgs {
+            src,
+            dsize,
+            fx,
+            fy,
+            interpolation,
+        })
+    }
+}
+
+impl filter::Filter for Resize {
+    fn filter(
+        &self,
+        args: &[filter::Val],
+        kwargs: &BTreeMap<std::string::String, filter::Val>,
+    ) -> std::result::Result<filter::Frame, dve::Error> {
+        let opts: ResizeArgs = match Self::args(args, kwargs) {
+            Ok(args) => args,
+            Err(err) => return Err(dve::Error::AVError(err)),
+        };
+
+        let src = opts.src.unwrap_frame();
+        let (width, height) = (src.width, src.height);
+        debug_assert_eq!(src.format, ffi::AVPixelFormat_AV_PIX_FMT_RGB24);
+        let (out_width, out_height) = opts.output_size(width, height);
+
+        let src_mat = filter_utils::frame_to_mat_rgb24(&src, width, height);
+        let mut dst_mat = opencv::core::Mat::default();
+
+        imgproc::resize(
+            &src_mat,
+            &mut dst_mat,
+            opencv::core::Size::new(out_width, out_height),
+            opts.fx,
+            opts.fy,
+            opts.interpolation,
+        )
+        .unwrap();
+
+        let f = match filter_utils::mat_to_frame_rgb24(dst_mat, out_width, out_height) {
+            Ok(value) => value,
+            Err(value) => return value,
+        };
+
+        Ok(filter::Frame::new(AVFrame { inner: f }))
+    }
+
+    fn filter_type(
+        &self,
+        args: &[filter::Val],
+        kwargs: &BTreeMap<std::string::String, filter::Val>,
+    ) -> std::result::Result<filter::FrameType, dve::Error> {
+        let opts: ResizeArgs = match Self::args(args, kwargs) {
+            Ok(args) => args,
+            Err(err) => return Err(dve::Error::AVError(err)),
+        };
+
+        let src = opts.src.unwrap_frame_type();
+        if src.format != ffi::AVPixelFormat_AV_PIX_FMT_RGB24 {
+            return Err(dve::Error::AVError("Expected RGB24 frame".into()));
+        }
+        let (out_width, out_height) = opts.output_size(src.width as i32, src.height as i32);
+        if out_width <= 0 || out_height <= 0 {
+            return Err(dve::Error::AVError(
+                "Resize would produce an empty frame".into(),
+            ));
+        }
+
+        Ok(FrameType::new(
+            out_width as usize,
+            out_height as usize,
+            ffi::AVPixelFormat_AV_PIX_FMT_RGB24,
+        ))
+    }
+
+    fn signature(&self) -> Option<Signature> {
+        Some(Self::sig())
+    }
+}
+
+pub struct Overlay {}
+
+/// How the overlay's colors combine with the base's, before alpha is applied
+#[derive(Clone, Copy)]
+enum BlendMode {
+    Normal,
+    Multiply,
+    Screen,
+    Overlay,
+    Add,
+    Darken,
+    Lighten,
+    Difference,
+}
+
+impl BlendMode {
+    fn parse(name: &str) -> Option<BlendMode> {
+        match name {
+            "normal" => Some(BlendMode::Normal),
+            "multiply" => Some(BlendMode::Multiply),
+            "screen" => Some(BlendMode::Screen),
+            "overlay" => Some(BlendMode::Overlay),
+            "add" => Some(BlendMode::Add),
+            "darken" => Some(BlendMode::Darken),
+            "lighten" => Some(BlendMode::Lighten),
+            "difference" => Some(BlendMode::Difference),
+            _ => None,
+        }
+    }
+
+    /// Blend a channel of the overlay (`top`) onto the base (`bottom`), both in [0, 1]
+    fn apply(self, bottom: f32, top: f32) -> f32 {
+        match self {
+            BlendMode::Normal => top,
+            BlendMode::Multiply => bottom * top,
+            BlendMode::Screen => 1.0 - (1.0 - bottom) * (1.0 - top),
+            BlendMode::Overlay => {
+                if bottom < 0.5 {
+                    2.0 * bottom * top
+                } else {
+                    1.0 - 2.0 * (1.0 - bottom) * (1.0 - top)
+                }
+            }
+            BlendMode::Add => (bottom + top).min(1.0),
+            BlendMode::Darken => bottom.min(top),
+            BlendMode::Lighten => bottom.max(top),
+            BlendMode::Difference => (bottom - top).abs(),
+        }
+    }
+}
+
+struct OverlayArgs {
+    base: filter_utils::FrameArg,
+    overlay: filter_utils::FrameArg,
+    x: i32,
+    y: i32,
+    mask: Option<filter_utils::FrameArg>,
+    width: Option<i32>,
+    height: Option<i32>,
+    opacity: f64,
+    blend: BlendMode,
+}
+
+impl OverlayArgs {
+    /// The size the overlay is drawn at, given its own size
+    fn output_size(&self, width: i32, height: i32) -> (i32, i32) {
+        let scaled = |size: i32, from: i32, to: i32| {
+            ((size as f64 * to as f64 / from as f64).round() as i32).max(1)
+        };
+        match (self.width, self.height) {
+            (Some(w), Some(h)) => (w, h),
+            (Some(w), None) => (w, scaled(height, width, w)),
+            (None, Some(h)) => (scaled(width, height, h), h),
+            (None, None) => (width, height),
+        }
+    }
+}
+
+impl Overlay {
+    fn sig() -> Signature {
+        Signature::new(vec![
+            Param::positional("base", ParamType::Frame).with_doc("Frame to draw onto"),
+            Param::positional("overlay", ParamType::Frame)
+                .with_doc("Frame to draw; RGB24, or RGBA to use its alpha channel"),
+            Param::positional("x", ParamType::Int)
+                .with_default(Val::Int(0))
+                .with_doc("Column of the overlay's left edge in base; may be negative"),
+            Param::positional("y", ParamType::Int)
+                .with_default(Val::Int(0))
+                .with_doc("Row of the overlay's top edge in base; may be negative"),
+            Param::keyword_only("mask", ParamType::Frame)
+                .optional()
+                .with_doc("Grayscale frame the size of overlay, multiplied into its alpha"),
+            Param::keyword_only("width", ParamType::Int)
+                .optional()
+                .with_doc("Width to scale the overlay to; keeps the aspect ratio if height isn't given"),
+            Param::keyword_only("height", ParamType::Int)
+                .optional()
+                .with_doc("Height to scale the overlay to; keeps the aspect ratio if width isn't given"),
+            Param::keyword_only("opacity", ParamType::Float)
+                .with_default(Val::Float(1.0))
+                .with_doc("Global opacity in [0, 1], multiplied into the overlay's alpha"),
+            Param::keyword_only("blend", ParamType::String)
+                .with_default(Val::String("normal".to_string()))
+                .with_doc(
+                    "normal, multiply, screen, overlay, add, darken, lighten, or difference",
+                ),
+        ])
+        .with_doc("Composite a frame onto another with transparency, e.g., a logo or a picture-in-picture")
+    }
+
+    fn args(
+        args: &[filter::Val],
+        kwargs: &BTreeMap<std::string::String, filter::Val>,
+    ) -> Result<OverlayArgs, String> {
+        let kwargs = kwargs.clone();
+        let args = args.to_vec();
+        let parsed_args = filter_utils::parse_arguments(&Self::sig(), args, kwargs)?;
+
+        let base = filter_utils::get_frame(&parsed_args, "base")?;
+        let overlay = filter_utils::get_frame(&parsed_args, "overlay")?;
+        let x = filter_utils::get_int(&parsed_args, "x")?;
+        let y = filter_utils::get_int(&parsed_args, "y")?;
+
+        let mask = if parsed_args.contains_key("mask") {
+            Some(filter_utils::get_frame(&parsed_args, "mask")?)
+        } else {
+            None
+        };
+
+        let mut size = [None, None];
+        for (key, size) in ["width", "height"].iter().zip(size.iter_mut()) {
+            if parsed_args.contains_key(key) {
+                let value = filter_utils::get_int(&parsed_args, key)?;
+                if value <= 0 {
+                    return Err(format!("Expected '{}' to be positive", key));
+                }
+                *size = Some(value);
+            }
+        }
+
+        let opacity = filter_utils::get_float(&parsed_args, "opacity")?;
+        if !(0.0..=1.0).contains(&opacity) {
+            return Err("Expected 'opacity' to be between 0 and 1".into());
+        }
+
+        let blend = filter_utils::get_string(&parsed_args, "blend")?;
+        let blend =
+            BlendMode::parse(&blend).ok_or_else(|| format!("Unknown blend mode '{}'", blend))?;
+
+        Ok(OverlayArgs {
+            base,
+            overlay,
+            x,
+            y,
+            mask,
+            width: size[0],
+            height: size[1],
+            opacity,
+            blend,
+        })
+    }
+}
+
+impl filter::Filter for Overlay {
+    fn filter(
+        &self,
+        args: &[filter::Val],
+        kwargs: &BTreeMap<std::string::String, filter::Val>,
+    ) -> std::result::Result<filter::Frame, dve::Error> {
+        use opencv::core::{Mat, Size};
+        use opencv::prelude::{MatTraitConst, MatTraitConstManual, MatTraitManual};
+
+        let opts: OverlayArgs = match Self::args(args, kwargs) {
+            Ok(args) => args,
+            Err(err) => return Err(dve::Error::AVError(err)),
+        };
+
+        let base = opts.base.unwrap_frame();
+        let (width, height) = (base.width, base.height);
+        debug_assert_eq!(base.format, ffi::AVPixelFormat_AV_PIX_FMT_RGB24);
+        let mut base_mat = filter_utils::frame_to_mat_rgb24(&base, width, height);
+
+        let overlay = opts.overlay.unwrap_frame();
+        let mut overlay_mat = if overlay.format == ffi::AVPixelFormat_AV_PIX_FMT_RGBA {
+            filter_utils::frame_to_mat_rgba(&overlay, overlay.width, overlay.height)
+        } else {
+            debug_assert_eq!(overlay.format, ffi::AVPixelFormat_AV_PIX_FMT_RGB24);
+            filter_utils::frame_to_mat_rgb24(&overlay, overlay.width, overlay.height)
+        };
+        let mut mask_mat = opts.mask.as_ref().map(|mask| {
+            let mask = mask.unwrap_frame();
+            filter_utils::frame_to_mat_gray8(&mask, mask.width, mask.height)
+        });
+
+        let (overlay_width, overlay_height) = opts.output_size(overlay.width, overlay.height);
+        if (overlay_width, overlay_height) != (overlay.width, overlay.height) {
+            let resize = |mat: &Mat| {
+                let mut resized = Mat::default();
+                imgproc::resize(
+                    mat,
+                    &mut resized,
+                    Size::new(overlay_width, overlay_height),
+                    0.0,
+                    0.0,
+                    imgproc::INTER_LINEAR,
+                )
+                .unwrap();
+                resized
+            };
+            overlay_mat = resize(&overlay_mat);
+            mask_mat = mask_mat.as_ref().map(resize);
+        }
+
+        // Only the part of the overlay inside the base is drawn
+        let x0 = opts.x.max(0);
+        let y0 = opts.y.max(0);
+        let x1 = opts.x.saturating_add(overlay_width).min(width);
+        let y1 = opts.y.saturating_add(overlay_height).min(height);
+
+        if x0 < x1 && y0 < y1 {
+            let channels = overlay_mat.channels() as usize;
+            let overlay_data = overlay_mat.data_bytes().unwrap();
+            let mask_data = mask_mat.as_ref().map(|mask| mask.data_bytes().unwrap());
+            let base_data = base_mat.data_bytes_mut().unwrap();
+
+            for y in y0..y1 {
+                for x in x0..x1 {
+                    let o = ((y - opts.y) * overlay_width + (x - opts.x)) as usize;
+                    let mut alpha = opts.opacity as f32;
+                    if channels == 4 {
+                        alpha *= overlay_data[o * 4 + 3] as f32 / 255.0;
+                    }
+                    if let Some(mask_data) = mask_data {
+                        alpha *= mask_data[o] as f32 / 255.0;
+                    }
+                    if alpha <= 0.0 {
+                        continue;
+                    }
+
+                    let b = (y * width + x) as usize * 3;
+                    for c in 0..3 {
+                        let bottom = base_data[b + c] as f32 / 255.0;
+                        let top = overlay_data[o * channels + c] as f32 / 255.0;
+                        let blended = opts.blend.apply(bottom, top);
+                        let out = bottom + (blended - bottom) * alpha;
+                        base_data[b + c] = (out * 255.0).round().clamp(0.0, 255.0) as u8;
+                    }
+                }
+            }
+        }
+
+        let f = match filter_utils::mat_to_frame_rgb24(base_mat, width, height) {
+            Ok(value) => value,
+            Err(value) => return value,
+        };
+
+        Ok(filter::Frame::new(AVFrame { inner: f }))
+    }
+
+    fn filter_type(
+        &self,
+        args: &[filter::Val],
+        kwargs: &BTreeMap<std::string::String, filter::Val>,
+    ) -> std::result::Result<filter::FrameType, dve::Error> {
+        let opts: OverlayArgs = match Self::args(args, kwargs) {
+            Ok(args) => args,
+            Err(err) => return Err(dve::Error::AVError(err)),
+        };
+
+        let base = opts.base.unwrap_frame_type();
+        if base.format != ffi::AVPixelFormat_AV_PIX_FMT_RGB24 {
+            return Err(dve::Error::AVError(
+                "Expected base to be an RGB24 frame".into(),
+            ));
+        }
+
+        let overlay = opts.overlay.unwrap_frame_type();
+        if overlay.format != ffi::AVPixelFormat_AV_PIX_FMT_RGB24
+            && overlay.format != ffi::AVPixelFormat_AV_PIX_FMT_RGBA
+        {
+            return Err(dve::Error::AVError(
+                "Expected overlay to be an RGB24 or RGBA frame".into(),
+            ));
+        }
+
+        if let Some(mask) = &opts.mask {
+            let mask = mask.unwrap_frame_type();
+            if mask.format != ffi::AVPixelFormat_AV_PIX_FMT_GRAY8 {
+                return Err(dve::Error::AVError(
+                    "Expected mask to be a grayscale frame".into(),
+                ));
+            }
+            if mask.width != overlay.width || mask.height != overlay.height {
+                return Err(dve::Error::AVError(
+                    "Expected mask to be the same size as overlay".into(),
+                ));
+            }
+        }
+
+        Ok(base)
+    }
+
+    fn signature(&self) -> Option<Signature> {
+        Some(Self::sig())
+    }
 }
@@ -5,105 +5,25 @@ use std::collections::BTreeMap;
 
 use super::Val;
 
-#[derive(Clone, Debug)]
-pub(crate) enum Parameter {
-    Positional {
-        name: &'static str,
-    },
-    PositionalOptional {
-        name: &'static str,
-        default_value: Val,
-    },
-    // VarArgs {
-    //     name: &'static str,
-    // },
-    // KeywordOnly {
-    //     name: &'static str,
-    // },
-    // KeywordOnlyOptional {
-    //     name: &'static str,
-    //     default_value: Val,
-    // },
-    // KwArgs {
-    //     name: &'static str,
-    // },
-}
-
-pub(crate) struct FunctionSignature {
-    pub(crate) parameters: Vec<Parameter>,
-}
-
+/// Bind a call's arguments to the parameters of a signature, filling in defaults
 pub(crate) fn parse_arguments(
-    signature: &FunctionSignature,
+    signature: &Signature,
     args: Vec<Val>,
     mut kwargs: std::collections::BTreeMap<String, Val>,
 ) -> Result<std::collections::BTreeMap<&'static str, Val>, String> {
     let mut parsed_args: BTreeMap<&'static str, Val> = std::collections::BTreeMap::new();
     let mut arg_iter = args.into_iter();
-    // let mut varargs = Vec::new();
-    // let mut keyword_only: bool = false;
-
-    for param in &signature.parameters {
-        match param {
-            Parameter::Positional { name } => {
-                // assert!(
-                //     !keyword_only,
-                //     "Positional argument after keyword-only argument"
-                // );
-                if let Some(val) = arg_iter.next() {
-                    parsed_args.insert(name, val);
-                } else if let Some(val) = kwargs.remove(*name) {
-                    parsed_args.insert(name, val);
-                } else {
-                    return Err(format!("Missing required positional argument '{}'", name));
-                }
-            }
-            Parameter::PositionalOptional {
-                name,
-                default_value,
-            } => {
-                // assert!(
-                //     !keyword_only,
-                //     "PositionalOptional argument after keyword-only argument"
-                // );
-                if let Some(val) = arg_iter.next() {
-                    parsed_args.insert(name, val);
-                } else if let Some(val) = kwargs.remove(*name) {
-                    parsed_args.insert(name, val);
-                } else {
-                    parsed_args.insert(name, default_value.clone());
-                }
-            } // Parameter::VarArgs { name } => {
-              //     assert!(
-              //         !keyword_only,
-              //         "VarArgs argument after keyword-only argument"
-              //     );
-              //     for val in arg_iter.by_ref() {
-              //         varargs.push(val);
-              //     }
-              //     parsed_args.insert(name, Val::List(varargs.clone()));
-              //     keyword_only = true; // Everything after *args is keyword-only
-              // }
-              // Parameter::KeywordOnly { name } => {
-              //     if let Some(val) = kwargs.remove(*name) {
-              //         parsed_args.insert(name, val);
-              //     } else {
-              //         return Err(format!("Missing required keyword-only argument '{}'", name));
-              //     }
-              // }
-              // Parameter::KeywordOnlyOptional {
-              //     name,
-              //     default_value,
-              // } => {
-              //     if let Some(val) = kwargs.remove(*name) {
-              //         parsed_args.insert(name, val);
-              //     } else {
-              //         parsed_args.insert(name, default_value.clone());
-              //     }
-              // }
-              // Parameter::KwArgs { name: _ } => {
-              //     todo!()
-              // }
+
+    for param in &signature.params {
+        let val = match param.kind {
+            ParamKind::PositionalOnly => arg_iter.next(),
+            ParamKind::Positional => arg_iter.next().or_else(|| kwargs.remove(param.name)),
+            ParamKind::KeywordOnly => kwargs.remove(param.name),
+        };
+        if let Some(val) = val.or_else(|| param.default.clone()) {
+            parsed_args.insert(param.name, val);
+        } else if param.required {
+            return Err(format!("Missing required argument '{}'", param.name));
         }
     }
 
@@ -172,6 +92,44 @@ pub(crate) fn get_point(
     Ok(pt)
 }
 
+pub(crate) fn get_frame(
+    parsed_args: &BTreeMap<&'static str, Val>,
+    key: &str,
+) -> Result<FrameArg, String> {
+    match parsed_args.get(key) {
+        Some(Val::Frame(frame)) => Ok(FrameArg::Frame(frame.clone())),
+        Some(Val::FrameType(frame_type)) => Ok(FrameArg::FrameType(frame_type.clone())),
+        _ => Err(format!("Expected '{key}' to be a Frame")),
+    }
+}
+
+pub(crate) fn get_int(parsed_args: &BTreeMap<&'static str, Val>, key: &str) -> Result<i32, String> {
+    match parsed_args.get(key) {
+        Some(Val::Int(value)) => Ok(*value as i32),
+        _ => Err(format!("Expected '{key}' to be an integer")),
+    }
+}
+
+pub(crate) fn get_float(
+    parsed_args: &BTreeMap<&'static str, Val>,
+    key: &str,
+) -> Result<f64, String> {
+    match parsed_args.get(key) {
+        Some(Val::Float(value)) => Ok(*value),
+        _ => Err(format!("Expected '{key}' to be a float")),
+    }
+}
+
+pub(crate) fn get_string(
+    parsed_args: &BTreeMap<&'static str, Val>,
+    key: &str,
+) -> Result<String, String> {
+    match parsed_args.get(key) {
+        Some(Val::String(value)) => Ok(value.clone()),
+        _ => Err(format!("Expected '{key}' to be a string")),
+    }
+}
+
 pub(crate) enum FrameArg {
     Frame(Frame),
     FrameType(FrameType),
@@ -242,6 +200,44 @@ pub(crate) fn mat_to_frame_rgb24(
     Ok(f)
 }
 
+pub(crate) fn mat_to_frame_gray8(
+    mat: opencv::prelude::Mat,
+    width: i32,
+    height: i32,
+) -> Result<*mut ffi::AVFrame, Result<Frame, crate::dve::Error>> {
+    let f = unsafe { ffi::av_frame_alloc() };
+    if f.is_null() {
+        return Err(Err(crate::dve::Error::AVError(
+            "Failed to allocate frame".into(),
+        )));
+    }
+
+    debug_assert_eq!(mat.elem_size().unwrap(), 1);
+    debug_assert_eq!(mat.channels(), 1);
+    debug_assert_eq!(mat.size().unwrap().height, height);
+    debug_assert_eq!(mat.size().unwrap().width, width);
+    unsafe {
+        (*f).width = width;
+        (*f).height = height;
+        (*f).format = ffi::AVPixelFormat_AV_PIX_FMT_GRAY8;
+
+        if ffi::av_frame_get_buffer(f, 0) < 0 {
+            panic!("ERROR could not allocate frame data");
+        }
+    }
+
+    unsafe {
+        let mut src = mat.data();
+        let mut dst = (*f).data[0];
+        for _ in 0..height {
+            std::ptr::copy_nonoverlapping(src, dst, width as usize);
+            src = src.add(width as usize);
+            dst = dst.add((*f).linesize[0] as usize);
+        }
+    }
+    Ok(f)
+}
+
 pub(crate) fn frame_to_mat_rgb24(img: &Frame, width: i32, height: i32) -> opencv::prelude::Mat {
     debug_assert!(img.format == ffi::AVPixelFormat_AV_PIX_FMT_RGB24);
     debug_assert_eq!(img.height, height);
@@ -334,6 +330,52 @@ pub(crate) fn frame_to_mat_gray8(img: &Frame, width: i32, height: i32) -> opencv
     mat
 }
 
+pub(crate) fn frame_to_mat_rgba(img: &Frame, width: i32, height: i32) -> opencv::prelude::Mat {
+    debug_assert!(img.format == ffi::AVPixelFormat_AV_PIX_FMT_RGBA);
+    debug_assert_eq!(img.height, height);
+    debug_assert_eq!(img.width, width);
+    debug_assert_eq!(
+        unsafe { (*(img.inner.inner)).format },
+        ffi::AVPixelFormat_AV_PIX_FMT_RGBA
+    );
+    debug_assert_eq!(unsafe { (*(img.inner.inner)).width }, width);
+    debug_assert_eq!(unsafe { (*(img.inner.inner)).height }, height);
+
+    let img: ffi::AVFrame = unsafe { *img.inner.inner };
+
+    let mut mat =
+        unsafe { opencv::core::Mat::new_rows_cols(height, width, opencv::core::CV_8UC4) }.unwrap();
+
+    debug_assert!(mat.elem_size().unwrap() == 4);
+    debug_assert!(mat.channels() == 4);
+    debug_assert!(mat.size().unwrap().height == height);
+    debug_assert!(mat.size().unwrap().width == width);
+    debug_assert!(mat.is_continuous());
+
+    if img.linesize[0] == width * 4 {
+        // no padding, just copy the data
+        unsafe {
+            let src = img.data[0];
+            let dst = mat.data_mut();
+            std::ptr::copy_nonoverlapping(src, dst, width as usize * height as usize * 4);
+        }
+    } else {
+        // there is padding, copy line by line
+        debug_assert!(img.linesize[0] > width * 4);
+        unsafe {
+            let mut src = img.data[0];
+            let mut dst = mat.data_mut();
+            for _ in 0..height {
+                std::ptr::copy_nonoverlapping(src, dst, width as usize * 4);
+                src = src.add(img.linesize[0] as usize);
+                dst = dst.add(width as usize * 4);
+            }
+        }
+    }
+
+    mat
+}
+
 #[cfg(test)]
 mod tests {
     use opencv::core::Scalar;
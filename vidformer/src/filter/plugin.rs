@@ -0,0 +1,599 @@
+//! Filters loaded from shared libraries
+//!
+//! A plugin is a shared library exporting `vidformer_filter_plugin`, which returns a [`VfPlugin`] describing its filters.
+//! Everything crossing the boundary is a `#[repr(C)]` type from this module, mirrored in `include/vidformer_filter_plugin.h`,
+//! so plugins can be written in any language with a C FFI and built without vidformer's sources.
+//!
+//! The layout is versioned by [`ABI_VERSION`], which a plugin reports as the first field of its [`VfPlugin`].
+//! Plugins built for another version are refused when loaded.
+//!
+//! Calls follow the same rules as [`Filter`]:
+//! - Arguments arrive as an array of [`VfArg`], positional arguments first (with a null name) and then keyword arguments.
+//! - `filter_type` is called with frame types at type-checking time, and with frames at render time to size the output;
+//!   it must only read `frame_type` from frame arguments.
+//! - `filter` writes into an output frame the host has already allocated with the type `filter_type` returned.
+//! - Both return 0 on success, or write a nul-terminated message of at most `err_cap` bytes to `err` and return nonzero.
+//! - Filters are called from many threads at once.
+//! - Filters have no [`Filter::signature`], so `filter_type` is the only check on their arguments.
+
+use super::{Filter, Frame, FrameType, Val};
+use crate::dve::{AVFrame, Error};
+use rusty_ffmpeg::ffi;
+use std::collections::BTreeMap;
+use std::ffi::{c_char, c_void, CStr, CString};
+use std::sync::Arc;
+
+/// The plugin ABI version this build of vidformer speaks
+pub const ABI_VERSION: u32 = 1;
+
+/// The symbol a plugin exports, as `const VfPlugin *vidformer_filter_plugin(void)`
+pub const PLUGIN_SYMBOL: &str = "vidformer_filter_plugin";
+
+pub const VF_VAL_BOOL: u32 = 0;
+pub const VF_VAL_INT: u32 = 1;
+pub const VF_VAL_FLOAT: u32 = 2;
+pub const VF_VAL_STRING: u32 = 3;
+pub const VF_VAL_BYTES: u32 = 4;
+pub const VF_VAL_LIST: u32 = 5;
+pub const VF_VAL_FRAME: u32 = 6;
+pub const VF_VAL_FRAME_TYPE: u32 = 7;
+
+/// Size and FFmpeg `AVPixelFormat` of a frame
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct VfFrameType {
+    pub width: i32,
+    pub height: i32,
+    pub format: i32,
+}
+
+/// A frame's planes, laid out as in an `AVFrame`
+///
+/// Planes of input frames are read-only. Planes of a frame type are null.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct VfFrame {
+    pub frame_type: VfFrameType,
+    pub data: [*mut u8; 8],
+    pub linesize: [i32; 8],
+}
+
+/// A [`Val`], tagged by one of the `VF_VAL_*` constants
+///
+/// - `BOOL` and `INT` use `int_val`, and `FLOAT` uses `float_val`
+/// - `STRING` (UTF-8, not nul-terminated) and `BYTES` point `data` at `len` bytes
+/// - `LIST` points `data` at `len` [`VfVal`]s
+/// - `FRAME` and `FRAME_TYPE` use `frame`
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct VfVal {
+    pub tag: u32,
+    pub int_val: i64,
+    pub float_val: f64,
+    pub data: *const c_void,
+    pub len: usize,
+    pub frame: VfFrame,
+}
+
+/// An argument, with a null name if positional
+#[repr(C)]
+pub struct VfArg {
+    pub name: *const c_char,
+    pub val: VfVal,
+}
+
+pub type VfFilterTypeFn = unsafe extern "C" fn(
+    state: *mut c_void,
+    args: *const VfArg,
+    n_args: usize,
+    out: *mut VfFrameType,
+    err: *mut c_char,
+    err_cap: usize,
+) -> i32;
+
+pub type VfFilterFn = unsafe extern "C" fn(
+    state: *mut c_void,
+    args: *const VfArg,
+    n_args: usize,
+    out: *mut VfFrame,
+    err: *mut c_char,
+    err_cap: usize,
+) -> i32;
+
+/// One filter of a plugin
+#[repr(C)]
+pub struct VfFilterDef {
+    /// Name the filter is registered under, nul-terminated
+    pub name: *const c_char,
+    /// Passed back to the filter's functions
+    pub state: *mut c_void,
+    pub filter_type: VfFilterTypeFn,
+    pub filter: VfFilterFn,
+}
+
+/// What a plugin exports, which must stay valid for as long as the library is loaded
+#[repr(C)]
+pub struct VfPlugin {
+    pub abi_version: u32,
+    pub filters: *const VfFilterDef,
+    pub filter_count: usize,
+}
+
+const ERR_CAP: usize = 1024;
+
+struct LoadedPlugin {
+    path: String,
+    plugin: *const VfPlugin,
+    // Dropped last, so the plugin's functions and data outlive every use of them
+    _library: libloading::Library,
+}
+
+// The plugin contract requires its filters to be callable from any thread
+unsafe impl Send for LoadedPlugin {}
+unsafe impl Sync for LoadedPlugin {}
+
+impl LoadedPlugin {
+    fn defs(&self) -> &[VfFilterDef] {
+        let plugin = unsafe { &*self.plugin };
+        if plugin.filter_count == 0 {
+            return &[];
+        }
+        unsafe { std::slice::from_raw_parts(plugin.filters, plugin.filter_count) }
+    }
+}
+
+/// A loaded plugin shared library
+#[derive(Clone)]
+pub struct FilterPlugin {
+    inner: Arc<LoadedPlugin>,
+}
+
+impl FilterPlugin {
+    /// Load a plugin from a shared library
+    ///
+    /// # Safety
+    ///
+    /// Loading a library runs its initialization code, and its filters are trusted to follow the plugin ABI.
+    pub unsafe fn load(path: &str) -> Result<FilterPlugin, Error> {
+        let library = libloading::Library::new(path).map_err(|e| {
+            Error::IOError(format!("Failed to load filter plugin `{}`: {}", path, e))
+        })?;
+        let plugin = {
+            let entry: libloading::Symbol<unsafe extern "C" fn() -> *const VfPlugin> =
+                library.get(PLUGIN_SYMBOL.as_bytes()).map_err(|e| {
+                    Error::IOError(format!(
+                        "Filter plugin `{}` does not export `{}`: {}",
+                        path, PLUGIN_SYMBOL, e
+                    ))
+                })?;
+            entry()
+        };
+        if plugin.is_null() {
+            return Err(Error::ConfigError(format!(
+                "Filter plugin `{}` returned no filters",
+                path
+            )));
+        }
+        if (*plugin).abi_version != ABI_VERSION {
+            return Err(Error::ConfigError(format!(
+                "Filter plugin `{}` uses ABI version {}, but vidformer uses {}",
+                path,
+                (*plugin).abi_version,
+                ABI_VERSION
+            )));
+        }
+
+        Ok(FilterPlugin {
+            inner: Arc::new(LoadedPlugin {
+                path: path.to_string(),
+                plugin,
+                _library: library,
+            }),
+        })
+    }
+
+    pub fn path(&self) -> &str {
+        &self.inner.path
+    }
+
+    /// The plugin's filters, by name
+    pub fn filters(&self) -> BTreeMap<String, Box<dyn Filter>> {
+        let mut filters: BTreeMap<String, Box<dyn Filter>> = BTreeMap::new();
+        for (idx, def) in self.inner.defs().iter().enumerate() {
+            let name = unsafe { CStr::from_ptr(def.name) }
+                .to_string_lossy()
+                .into_owned();
+            filters.insert(
+                name.clone(),
+                Box::new(PluginFilter {
+                    plugin: self.inner.clone(),
+                    idx,
+                    name,
+                }),
+            );
+        }
+        filters
+    }
+}
+
+struct PluginFilter {
+    plugin: Arc<LoadedPlugin>,
+    idx: usize,
+    name: String,
+}
+
+impl PluginFilter {
+    fn def(&self) -> &VfFilterDef {
+        &self.plugin.defs()[self.idx]
+    }
+
+    fn call_filter_type(&self, args: &MarshalledArgs) -> Result<VfFrameType, Error> {
+        let def = self.def();
+        let mut out = VfFrameType {
+            width: 0,
+            height: 0,
+            format: ffi::AVPixelFormat_AV_PIX_FMT_NONE,
+        };
+        let mut err = [0 as c_char; ERR_CAP];
+        let ret = unsafe {
+            (def.filter_type)(
+                def.state,
+                args.args.as_ptr(),
+                args.args.len(),
+                &mut out,
+                err.as_mut_ptr(),
+                ERR_CAP,
+            )
+        };
+        if ret != 0 {
+            return Err(Error::InvalidFilterArgValue(
+                self.name.clone(),
+                error_message(&err),
+            ));
+        }
+        if out.width <= 0 || out.height <= 0 {
+            return Err(Error::FilterInternalError(format!(
+                "Filter `{}` returned an invalid frame size {}x{}",
+                self.name, out.width, out.height
+            )));
+        }
+        Ok(out)
+    }
+}
+
+impl Filter for PluginFilter {
+    fn filter(&self, args: &[Val], kwargs: &BTreeMap<String, Val>) -> Result<Frame, Error> {
+        let args = MarshalledArgs::new(args, kwargs);
+        let out_type = self.call_filter_type(&args)?;
+
+        let f = unsafe { ffi::av_frame_alloc() };
+        if f.is_null() {
+            return Err(Error::FilterInternalError(
+                "Failed to allocate frame".to_string(),
+            ));
+        }
+        // Owned from here on, so it's freed on error
+        let oframe = AVFrame { inner: f };
+        unsafe {
+            (*f).width = out_type.width;
+            (*f).height = out_type.height;
+            (*f).format = out_type.format;
+            if ffi::av_frame_get_buffer(f, 0) < 0 {
+                return Err(Error::FilterInternalError(format!(
+                    "Failed to allocate a {}x{} frame of format {} for filter `{}`",
+                    out_type.width, out_type.height, out_type.format, self.name
+                )));
+            }
+        }
+
+        let def = self.def();
+        let mut out = vf_frame(f);
+        let mut err = [0 as c_char; ERR_CAP];
+        let ret = unsafe {
+            (def.filter)(
+                def.state,
+                args.args.as_ptr(),
+                args.args.len(),
+                &mut out,
+                err.as_mut_ptr(),
+                ERR_CAP,
+            )
+        };
+        if ret != 0 {
+            return Err(Error::FilterInternalError(format!(
+                "Filter `{}` failed: {}",
+                self.name,
+                error_message(&err)
+            )));
+        }
+
+        Ok(Frame::new(oframe))
+    }
+
+    fn filter_type(
+        &self,
+        args: &[Val],
+        kwargs: &BTreeMap<String, Val>,
+    ) -> Result<FrameType, Error> {
+        let args = MarshalledArgs::new(args, kwargs);
+        let out = self.call_filter_type(&args)?;
+        Ok(FrameType::new(
+            out.width as usize,
+            out.height as usize,
+            out.format,
+        ))
+    }
+}
+
+fn error_message(err: &[c_char; ERR_CAP]) -> String {
+    // Don't trust the plugin to have terminated the message
+    let bytes: Vec<u8> = err
+        .iter()
+        .take_while(|c| **c != 0)
+        .map(|c| *c as u8)
+        .collect();
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+fn vf_frame(f: *const ffi::AVFrame) -> VfFrame {
+    unsafe {
+        VfFrame {
+            frame_type: VfFrameType {
+                width: (*f).width,
+                height: (*f).height,
+                format: (*f).format,
+            },
+            data: (*f).data,
+            linesize: (*f).linesize,
+        }
+    }
+}
+
+/// Arguments converted for a plugin call, along with the storage their pointers refer to
+struct MarshalledArgs<'a> {
+    args: Vec<VfArg>,
+    _names: Vec<CString>,
+    _lists: Vec<Vec<VfVal>>,
+    _vals: std::marker::PhantomData<&'a Val>,
+}
+
+impl<'a> MarshalledArgs<'a> {
+    fn new(args: &'a [Val], kwargs: &'a BTreeMap<String, Val>) -> Self {
+        let mut names = Vec::with_capacity(kwargs.len());
+        let mut lists = Vec::new();
+        let mut vf_args = Vec::with_capacity(args.len() + kwargs.len());
+        for val in args {
+            vf_args.push(VfArg {
+                name: std::ptr::null(),
+                val: marshal(val, &mut lists),
+            });
+        }
+        for (name, val) in kwargs {
+            // Kwarg names come from specs, which can't contain nul bytes in a name anyway
+            let name = CString::new(name.as_str()).unwrap_or_default();
+            vf_args.push(VfArg {
+                name: name.as_ptr(),
+                val: marshal(val, &mut lists),
+            });
+            names.push(name);
+        }
+        MarshalledArgs {
+            args: vf_args,
+            _names: names,
+            _lists: lists,
+            _vals: std::marker::PhantomData,
+        }
+    }
+}
+
+/// Convert a value, keeping any list arrays alive in `lists`
+///
+/// Moving a `Vec` into `lists` doesn't move its elements, so pointers to them stay valid.
+fn marshal(val: &Val, lists: &mut Vec<Vec<VfVal>>) -> VfVal {
+    let mut out = VfVal {
+        tag: 0,
+        int_val: 0,
+        float_val: 0.0,
+        data: std::ptr::null(),
+        len: 0,
+        frame: VfFrame {
+            frame_type: VfFrameType {
+                width: 0,
+                height: 0,
+                format: ffi::AVPixelFormat_AV_PIX_FMT_NONE,
+            },
+            data: [std::ptr::null_mut(); 8],
+            linesize: [0; 8],
+        },
+    };
+    match val {
+        Val::Bool(b) => {
+            out.tag = VF_VAL_BOOL;
+            out.int_val = *b as i64;
+        }
+        Val::Int(i) => {
+            out.tag = VF_VAL_INT;
+            out.int_val = *i;
+        }
+        Val::Float(f) => {
+            out.tag = VF_VAL_FLOAT;
+            out.float_val = *f;
+        }
+        Val::String(s) => {
+            out.tag = VF_VAL_STRING;
+            out.data = s.as_ptr() as *const c_void;
+            out.len = s.len();
+        }
+        Val::Bytes(b) => {
+            out.tag = VF_VAL_BYTES;
+            out.data = b.as_ptr() as *const c_void;
+            out.len = b.len();
+        }
+        Val::List(list) => {
+            let items: Vec<VfVal> = list.iter().map(|item| marshal(item, lists)).collect();
+            out.tag = VF_VAL_LIST;
+            out.data = items.as_ptr() as *const c_void;
+            out.len = items.len();
+            lists.push(items);
+        }
+        Val::Frame(frame) => {
+            out.tag = VF_VAL_FRAME;
+            out.frame = vf_frame(frame.inner.inner);
+        }
+        Val::FrameType(frame_type) => {
+            out.tag = VF_VAL_FRAME_TYPE;
+            out.frame.frame_type = VfFrameType {
+                width: frame_type.width as i32,
+                height: frame_type.height as i32,
+                format: frame_type.format,
+            };
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_marshal_args() {
+        let args = vec![
+            Val::Int(7),
+            Val::List(vec![Val::String("ab".to_string()), Val::Bool(true)]),
+        ];
+        let kwargs = BTreeMap::from([(
+            "size".to_string(),
+            Val::FrameType(FrameType::new(
+                64,
+                32,
+                ffi::AVPixelFormat_AV_PIX_FMT_YUV420P,
+            )),
+        )]);
+        let marshalled = MarshalledArgs::new(&args, &kwargs);
+        let vf_args = &marshalled.args;
+        assert_eq!(vf_args.len(), 3);
+
+        assert!(vf_args[0].name.is_null());
+        assert_eq!(vf_args[0].val.tag, VF_VAL_INT);
+        assert_eq!(vf_args[0].val.int_val, 7);
+
+        assert_eq!(vf_args[1].val.tag, VF_VAL_LIST);
+        let items = unsafe { std::slice::from_raw_parts(vf_args[1].val.data as *const VfVal, 2) };
+        assert_eq!(items[0].tag, VF_VAL_STRING);
+        let s = unsafe { std::slice::from_raw_parts(items[0].data as *const u8, items[0].len) };
+        assert_eq!(s, b"ab");
+        assert_eq!(items[1].tag, VF_VAL_BOOL);
+        assert_eq!(items[1].int_val, 1);
+
+        assert_eq!(
+            unsafe { CStr::from_ptr(vf_args[2].name) }.to_str().unwrap(),
+            "size"
+        );
+        assert_eq!(vf_args[2].val.tag, VF_VAL_FRAME_TYPE);
+        assert_eq!(vf_args[2].val.frame.frame_type.width, 64);
+        assert_eq!(vf_args[2].val.frame.frame_type.height, 32);
+    }
+
+    fn gray_frame(width: i32, height: i32, value: u8) -> Frame {
+        let f = unsafe { ffi::av_frame_alloc() };
+        assert!(!f.is_null());
+        unsafe {
+            (*f).width = width;
+            (*f).height = height;
+            (*f).format = ffi::AVPixelFormat_AV_PIX_FMT_GRAY8;
+            assert!(ffi::av_frame_get_buffer(f, 0) >= 0);
+            std::ptr::write_bytes(
+                (*f).data[0],
+                value,
+                (*f).linesize[0] as usize * height as usize,
+            );
+        }
+        Frame::new(AVFrame { inner: f })
+    }
+
+    fn pixels(frame: &Frame) -> Vec<u8> {
+        let f = frame.inner.inner;
+        let mut out = Vec::new();
+        for y in 0..frame.height as isize {
+            let row = unsafe {
+                std::slice::from_raw_parts(
+                    (*f).data[0].offset(y * (*f).linesize[0] as isize),
+                    frame.width as usize,
+                )
+            };
+            out.extend_from_slice(row);
+        }
+        out
+    }
+
+    #[test]
+    fn test_plugin_filter() {
+        let plugin = unsafe { FilterPlugin::load(env!("VIDFORMER_TEST_PLUGIN")) }.unwrap();
+        let filters = plugin.filters();
+        assert_eq!(
+            filters.keys().map(String::as_str).collect::<Vec<_>>(),
+            ["TestInvert"]
+        );
+        let invert = &filters["TestInvert"];
+        let no_kwargs = BTreeMap::new();
+
+        let gray = FrameType::new(6, 4, ffi::AVPixelFormat_AV_PIX_FMT_GRAY8);
+        let out_type = invert
+            .filter_type(&[Val::FrameType(gray.clone())], &no_kwargs)
+            .unwrap();
+        assert_eq!(out_type, gray);
+
+        let frame = gray_frame(6, 4, 10);
+        let out = invert.filter(&[Val::Frame(frame)], &no_kwargs).unwrap();
+        assert_eq!((out.width, out.height), (6, 4));
+        assert_eq!(out.format, ffi::AVPixelFormat_AV_PIX_FMT_GRAY8);
+        assert_eq!(pixels(&out), [245; 24]);
+    }
+
+    #[test]
+    fn test_plugin_errors() {
+        let plugin = unsafe { FilterPlugin::load(env!("VIDFORMER_TEST_PLUGIN")) }.unwrap();
+        let filters = plugin.filters();
+        let invert = &filters["TestInvert"];
+
+        // Type errors carry the plugin's message
+        match invert.filter_type(&[Val::Int(1)], &BTreeMap::new()) {
+            Err(Error::InvalidFilterArgValue(name, msg)) => {
+                assert_eq!(name, "TestInvert");
+                assert_eq!(msg, "TestInvert takes a single frame");
+            }
+            other => panic!("Expected InvalidFilterArgValue, got {:?}", other),
+        }
+
+        // So do failures while rendering
+        let kwargs = BTreeMap::from([("fail".to_string(), Val::Bool(true))]);
+        match invert.filter(&[Val::Frame(gray_frame(6, 4, 10))], &kwargs) {
+            Err(Error::FilterInternalError(msg)) => {
+                assert_eq!(msg, "Filter `TestInvert` failed: failing as asked");
+            }
+            other => panic!("Expected FilterInternalError, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_plugin_abi_version() {
+        let path = env!("VIDFORMER_TEST_PLUGIN_BAD_ABI");
+        match unsafe { FilterPlugin::load(path) } {
+            Err(Error::ConfigError(msg)) => assert_eq!(
+                msg,
+                format!(
+                    "Filter plugin `{}` uses ABI version 99, but vidformer uses {}",
+                    path, ABI_VERSION
+                )
+            ),
+            Err(e) => panic!("Expected ConfigError, got {:?}", e),
+            Ok(_) => panic!("Loaded a plugin with another ABI version"),
+        }
+
+        assert!(matches!(
+            unsafe { FilterPlugin::load("/nonexistent/plugin.so") },
+            Err(Error::IOError(_))
+        ));
+    }
+}
@@ -0,0 +1,378 @@
+//! Declared filter arguments
+//!
+//! A [`Signature`] lists the arguments a filter accepts, much like a Python function signature.
+//! Filters which declare one through [`Filter::signature`](super::Filter::signature) have their calls checked against it when a
+//! spec is type checked, so a misspelled keyword or a missing argument is reported before anything is rendered.
+//! Signatures serialize to JSON so clients can discover filters and their arguments.
+
+use super::{Filter, Val};
+use crate::dve::Error;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// The kind of value a parameter accepts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ParamType {
+    /// A frame, or the type of one while type checking
+    Frame,
+    Bool,
+    Int,
+    Float,
+    String,
+    Bytes,
+    List,
+    /// Any value; the filter checks it itself
+    Any,
+}
+
+impl ParamType {
+    fn accepts(&self, val: &Val) -> bool {
+        matches!(
+            (self, val),
+            (ParamType::Any, _)
+                | (ParamType::Frame, Val::Frame(_) | Val::FrameType(_))
+                | (ParamType::Bool, Val::Bool(_))
+                | (ParamType::Int, Val::Int(_))
+                | (ParamType::Float, Val::Float(_))
+                | (ParamType::String, Val::String(_))
+                | (ParamType::Bytes, Val::Bytes(_))
+                | (ParamType::List, Val::List(_))
+        )
+    }
+}
+
+impl std::fmt::Display for ParamType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ParamType::Frame => "frame",
+            ParamType::Bool => "bool",
+            ParamType::Int => "int",
+            ParamType::Float => "float",
+            ParamType::String => "string",
+            ParamType::Bytes => "bytes",
+            ParamType::List => "list",
+            ParamType::Any => "any",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// How an argument may be passed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ParamKind {
+    /// Passed only by position
+    PositionalOnly,
+    /// Passed by position or by name
+    Positional,
+    /// Passed only by name
+    KeywordOnly,
+}
+
+/// A parameter of a filter
+#[derive(Debug, Clone, Serialize)]
+pub struct Param {
+    pub name: &'static str,
+    pub kind: ParamKind,
+    #[serde(rename = "type")]
+    pub ty: ParamType,
+    pub required: bool,
+    /// The value used when the argument is not given
+    #[serde(serialize_with = "serialize_default")]
+    pub default: Option<Val>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub doc: Option<&'static str>,
+}
+
+impl Param {
+    /// A required parameter which may be passed by position or by name
+    pub fn positional(name: &'static str, ty: ParamType) -> Self {
+        Param {
+            name,
+            kind: ParamKind::Positional,
+            ty,
+            required: true,
+            default: None,
+            doc: None,
+        }
+    }
+
+    /// A required parameter which may only be passed by position
+    pub fn positional_only(name: &'static str, ty: ParamType) -> Self {
+        Param {
+            kind: ParamKind::PositionalOnly,
+            ..Param::positional(name, ty)
+        }
+    }
+
+    /// A required parameter which may only be passed by name
+    pub fn keyword_only(name: &'static str, ty: ParamType) -> Self {
+        Param {
+            kind: ParamKind::KeywordOnly,
+            ..Param::positional(name, ty)
+        }
+    }
+
+    /// Make the parameter optional, defaulting to `default`
+    pub fn with_default(mut self, default: Val) -> Self {
+        self.required = false;
+        self.default = Some(default);
+        self
+    }
+
+    /// Make the parameter optional, without a default; the filter handles the argument being absent
+    pub fn optional(mut self) -> Self {
+        self.required = false;
+        self
+    }
+
+    pub fn with_doc(mut self, doc: &'static str) -> Self {
+        self.doc = Some(doc);
+        self
+    }
+}
+
+/// The parameters of a filter, in positional order
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Signature {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub doc: Option<&'static str>,
+    pub params: Vec<Param>,
+}
+
+impl Signature {
+    pub fn new(params: Vec<Param>) -> Self {
+        Signature { doc: None, params }
+    }
+
+    pub fn with_doc(mut self, doc: &'static str) -> Self {
+        self.doc = Some(doc);
+        self
+    }
+
+    /// Check a call to the filter named `filter` against the signature
+    ///
+    /// This checks that each required argument is given exactly once, that no unknown arguments are given, and that each
+    /// argument has the declared type. Values are not checked beyond their type.
+    pub fn check(
+        &self,
+        filter: &str,
+        args: &[Val],
+        kwargs: &BTreeMap<String, Val>,
+    ) -> Result<(), Error> {
+        let positional = self
+            .params
+            .iter()
+            .filter(|param| param.kind != ParamKind::KeywordOnly)
+            .count();
+        if args.len() > positional {
+            return Err(Error::InvalidFilterArgValue(
+                filter.to_string(),
+                format!(
+                    "takes at most {} positional arguments, got {}",
+                    positional,
+                    args.len()
+                ),
+            ));
+        }
+        if let Some(name) = kwargs.keys().find(|name| {
+            !self
+                .params
+                .iter()
+                .any(|param| param.name == *name && param.kind != ParamKind::PositionalOnly)
+        }) {
+            return Err(Error::InvalidFilterArgValue(
+                filter.to_string(),
+                format!("unexpected keyword argument `{}`", name),
+            ));
+        }
+
+        let mut args = args.iter();
+        for param in &self.params {
+            let by_position = match param.kind {
+                ParamKind::PositionalOnly | ParamKind::Positional => args.next(),
+                ParamKind::KeywordOnly => None,
+            };
+            let by_name = match param.kind {
+                ParamKind::PositionalOnly => None,
+                ParamKind::Positional | ParamKind::KeywordOnly => kwargs.get(param.name),
+            };
+            let val = match (by_position, by_name) {
+                (Some(_), Some(_)) => {
+                    return Err(Error::InvalidFilterArgValue(
+                        filter.to_string(),
+                        format!("got multiple values for argument `{}`", param.name),
+                    ))
+                }
+                (Some(val), None) | (None, Some(val)) => val,
+                (None, None) if param.required => {
+                    return Err(Error::InvalidFilterArgValue(
+                        filter.to_string(),
+                        format!("missing required argument `{}`", param.name),
+                    ))
+                }
+                (None, None) => continue,
+            };
+            if !param.ty.accepts(val) {
+                return Err(Error::InvalidFilterArgType(
+                    format!("{}({})", filter, param.name),
+                    param.ty.to_string(),
+                    val_type_name(val).to_string(),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The signatures of a set of filters, by filter name, skipping filters which don't declare one
+///
+/// The result serializes to a JSON object, e.g., with `serde_json::to_string`.
+pub fn signatures(filters: &BTreeMap<String, Box<dyn Filter>>) -> BTreeMap<String, Signature> {
+    filters
+        .iter()
+        .filter_map(|(name, filter)| Some((name.clone(), filter.signature()?)))
+        .collect()
+}
+
+fn val_type_name(val: &Val) -> &'static str {
+    match val {
+        Val::Frame(_) | Val::FrameType(_) => "frame",
+        Val::Bool(_) => "bool",
+        Val::Int(_) => "int",
+        Val::String(_) => "string",
+        Val::Bytes(_) => "bytes",
+        Val::Float(_) => "float",
+        Val::List(_) => "list",
+    }
+}
+
+fn val_to_json(val: &Val) -> serde_json::Value {
+    match val {
+        Val::Frame(_) | Val::FrameType(_) => serde_json::Value::Null,
+        Val::Bool(b) => serde_json::Value::from(*b),
+        Val::Int(i) => serde_json::Value::from(*i),
+        Val::String(s) => serde_json::Value::from(s.as_str()),
+        Val::Bytes(b) => serde_json::Value::from(b.as_slice()),
+        Val::Float(f) => serde_json::Value::from(*f),
+        Val::List(list) => serde_json::Value::Array(list.iter().map(val_to_json).collect()),
+    }
+}
+
+fn serialize_default<S: serde::Serializer>(
+    default: &Option<Val>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match default {
+        Some(val) => val_to_json(val).serialize(serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::FrameType;
+
+    fn signature() -> Signature {
+        Signature::new(vec![
+            Param::positional("img", ParamType::Frame),
+            Param::positional("thickness", ParamType::Int).with_default(Val::Int(1)),
+            Param::keyword_only("label", ParamType::String).with_default(Val::String("".into())),
+            Param::keyword_only("scale", ParamType::Float).optional(),
+        ])
+    }
+
+    #[test]
+    fn test_check() {
+        let sig = signature();
+        let kwargs = |pairs: &[(&str, Val)]| -> BTreeMap<String, Val> {
+            pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.clone()))
+                .collect()
+        };
+
+        let img = Val::FrameType(FrameType::new(
+            64,
+            64,
+            rusty_ffmpeg::ffi::AVPixelFormat_AV_PIX_FMT_RGB24,
+        ));
+
+        assert!(sig
+            .check("f", std::slice::from_ref(&img), &kwargs(&[]))
+            .is_ok());
+        assert!(sig
+            .check("f", &[], &kwargs(&[("img", img.clone())]))
+            .is_ok());
+        assert!(sig
+            .check(
+                "f",
+                &[img.clone(), Val::Int(2)],
+                &kwargs(&[("label", Val::String("a".into()))])
+            )
+            .is_ok());
+
+        // missing, duplicated, unknown, too many, and keyword-only passed by position
+        assert!(sig.check("f", &[], &kwargs(&[])).is_err());
+        assert!(sig
+            .check(
+                "f",
+                std::slice::from_ref(&img),
+                &kwargs(&[("img", img.clone())])
+            )
+            .is_err());
+        assert!(sig
+            .check(
+                "f",
+                std::slice::from_ref(&img),
+                &kwargs(&[("thick", Val::Int(2))])
+            )
+            .is_err());
+        assert!(sig
+            .check(
+                "f",
+                &[img.clone(), Val::Int(2), Val::String("a".into())],
+                &kwargs(&[])
+            )
+            .is_err());
+        assert!(sig
+            .check("f", &[img.clone(), Val::Float(2.0)], &kwargs(&[]))
+            .is_err());
+
+        let positional_only = Signature::new(vec![Param::positional_only("img", ParamType::Frame)]);
+        assert!(positional_only
+            .check("f", std::slice::from_ref(&img), &kwargs(&[]))
+            .is_ok());
+        assert!(positional_only
+            .check("f", &[], &kwargs(&[("img", img.clone())]))
+            .is_err());
+
+        match sig.check("f", &[Val::Int(0)], &kwargs(&[])) {
+            Err(Error::InvalidFilterArgType(param, expected, got)) => {
+                assert_eq!(param, "f(img)");
+                assert_eq!(expected, "frame");
+                assert_eq!(got, "int");
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_signature_json() {
+        let json = serde_json::to_value(signature().with_doc("Does things")).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "doc": "Does things",
+                "params": [
+                    {"name": "img", "kind": "positional", "type": "frame", "required": true, "default": null},
+                    {"name": "thickness", "kind": "positional", "type": "int", "required": false, "default": 1},
+                    {"name": "label", "kind": "keyword_only", "type": "string", "required": false, "default": ""},
+                    {"name": "scale", "kind": "keyword_only", "type": "float", "required": false, "default": null},
+                ],
+            })
+        );
+    }
+}
@@ -0,0 +1,221 @@
+//! Burned-in subtitles
+//!
+//! The [`Subtitle`] filter draws the cues of a subtitle track which are active at a given time. Tracks are registered on
+//! the [`Context`](crate::Context) with [`Context::with_subtitles`](crate::Context::with_subtitles), which also adds the
+//! filter. Cues are drawn with the [`Text`] filter, so any script the font covers renders correctly.
+
+use super::filter_utils::{self, FrameArg};
+use super::text::{FontStore, Text};
+use super::{Filter, Frame, FrameType, Param, ParamType, Signature, Val};
+use crate::dve::Error;
+use crate::subtitle::SubtitleTrack;
+use num_rational::Rational64;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// Arguments passed through to the [`Text`] filter unchanged
+const TEXT_ARGS: [&str; 12] = [
+    "font",
+    "font_index",
+    "align",
+    "valign",
+    "line_spacing",
+    "color",
+    "outline",
+    "outline_color",
+    "shadow",
+    "shadow_color",
+    "background",
+    "padding",
+];
+
+struct SubtitleArgs {
+    img: FrameArg,
+    track: Arc<SubtitleTrack>,
+    t: Rational64,
+    /// Arguments for the Text filter, except `img` and `text`
+    text_kwargs: BTreeMap<String, Val>,
+}
+
+/// Draw the cues of a subtitle track active at a time
+pub struct Subtitle {
+    tracks: BTreeMap<String, Arc<SubtitleTrack>>,
+    text: Text,
+}
+
+impl Subtitle {
+    pub fn new(tracks: BTreeMap<String, Arc<SubtitleTrack>>, fonts: Arc<FontStore>) -> Self {
+        Subtitle {
+            tracks,
+            text: Text::new(fonts),
+        }
+    }
+
+    fn sig() -> Signature {
+        Signature::new(vec![
+            Param::positional("img", ParamType::Frame),
+            Param::positional("track", ParamType::String)
+                .with_doc("Name of a subtitle track registered on the context"),
+            Param::positional("t", ParamType::Any)
+                .with_doc("Time in seconds, as a number or a [numerator, denominator] pair"),
+            Param::keyword_only("font", ParamType::String)
+                .with_doc("Path of a TrueType or OpenType font in the filter's font service"),
+            Param::keyword_only("font_index", ParamType::Int)
+                .with_default(Val::Int(0))
+                .with_doc("Index of the font within a font collection"),
+            Param::keyword_only("size", ParamType::Float)
+                .optional()
+                .with_doc("Font size in pixels; defaults to 1/18 of the frame height"),
+            Param::keyword_only("margin", ParamType::Int)
+                .optional()
+                .with_doc(
+                "Space in pixels around the subtitle area; defaults to 1/20 of the frame height",
+            ),
+            Param::keyword_only("align", ParamType::String)
+                .with_default(Val::String("center".to_string()))
+                .with_doc("left, center, or right"),
+            Param::keyword_only("valign", ParamType::String)
+                .with_default(Val::String("bottom".to_string()))
+                .with_doc("top, middle, or bottom of the frame"),
+            Param::keyword_only("line_spacing", ParamType::Float)
+                .with_default(Val::Float(1.0))
+                .with_doc("Multiple of the font's line height between lines"),
+            Param::keyword_only("color", ParamType::List)
+                .with_default(Val::List(vec![Val::Int(255); 4]))
+                .with_doc("[r, g, b] or [r, g, b, a]"),
+            Param::keyword_only("outline", ParamType::Int)
+                .with_default(Val::Int(2))
+                .with_doc("Outline width in pixels"),
+            Param::keyword_only("outline_color", ParamType::List)
+                .with_default(Val::List(vec![
+                    Val::Int(0),
+                    Val::Int(0),
+                    Val::Int(0),
+                    Val::Int(255),
+                ]))
+                .with_doc("[r, g, b] or [r, g, b, a]"),
+            Param::keyword_only("shadow", ParamType::List)
+                .optional()
+                .with_doc("[dx, dy] offset of a drop shadow"),
+            Param::keyword_only("shadow_color", ParamType::List)
+                .with_default(Val::List(vec![
+                    Val::Int(0),
+                    Val::Int(0),
+                    Val::Int(0),
+                    Val::Int(128),
+                ]))
+                .with_doc("[r, g, b] or [r, g, b, a]"),
+            Param::keyword_only("background", ParamType::List)
+                .optional()
+                .with_doc("[r, g, b] or [r, g, b, a] of a box behind the text"),
+            Param::keyword_only("padding", ParamType::Int)
+                .with_default(Val::Int(4))
+                .with_doc("Space in pixels between the text and its background box"),
+        ])
+        .with_doc("Draw the cues of a subtitle track active at a time")
+    }
+
+    fn args(&self, args: &[Val], kwargs: &BTreeMap<String, Val>) -> Result<SubtitleArgs, String> {
+        let parsed_args =
+            filter_utils::parse_arguments(&Self::sig(), args.to_vec(), kwargs.clone())?;
+
+        let img = filter_utils::get_frame(&parsed_args, "img")?;
+        let (width, height) = match &img {
+            FrameArg::Frame(frame) => (frame.width, frame.height),
+            FrameArg::FrameType(frame_type) => (frame_type.width as i32, frame_type.height as i32),
+        };
+
+        let track_name = filter_utils::get_string(&parsed_args, "track")?;
+        let track = match self.tracks.get(&track_name) {
+            Some(track) => track.clone(),
+            None => return Err(format!("Subtitle track '{}' not found", track_name)),
+        };
+        let t = get_time(&parsed_args, "t")?;
+
+        let mut text_kwargs: BTreeMap<String, Val> = TEXT_ARGS
+            .iter()
+            .filter_map(|key| Some((key.to_string(), parsed_args.get(key)?.clone())))
+            .collect();
+
+        let size = if parsed_args.contains_key("size") {
+            filter_utils::get_float(&parsed_args, "size")?
+        } else {
+            (height as f64 / 18.0).max(1.0)
+        };
+        let margin = if parsed_args.contains_key("margin") {
+            filter_utils::get_int(&parsed_args, "margin")?
+        } else {
+            height / 20
+        };
+        if margin < 0 || 2 * margin >= width.min(height) {
+            return Err("Expected 'margin' to leave room for the subtitles".into());
+        }
+        text_kwargs.insert("size".to_string(), Val::Float(size));
+        text_kwargs.insert(
+            "rect".to_string(),
+            Val::List(vec![
+                Val::Int(margin as i64),
+                Val::Int(margin as i64),
+                Val::Int((width - 2 * margin) as i64),
+                Val::Int((height - 2 * margin) as i64),
+            ]),
+        );
+
+        Ok(SubtitleArgs {
+            img,
+            track,
+            t,
+            text_kwargs,
+        })
+    }
+}
+
+fn get_time(parsed_args: &BTreeMap<&'static str, Val>, key: &str) -> Result<Rational64, String> {
+    let err =
+        || format!("Expected '{key}' to be a number or a [numerator, denominator] pair of seconds");
+    match parsed_args.get(key) {
+        Some(Val::Int(t)) => Ok(Rational64::from_integer(*t)),
+        Some(Val::Float(t)) => Rational64::approximate_float(*t).ok_or_else(err),
+        Some(Val::List(list)) => match list.as_slice() {
+            [Val::Int(n), Val::Int(d)] if *d > 0 => Ok(Rational64::new(*n, *d)),
+            _ => Err(err()),
+        },
+        _ => Err(err()),
+    }
+}
+
+impl Filter for Subtitle {
+    fn filter(&self, args: &[Val], kwargs: &BTreeMap<String, Val>) -> Result<Frame, Error> {
+        let opts = self.args(args, kwargs).map_err(Error::AVError)?;
+        let img = opts.img.unwrap_frame();
+
+        let text = opts.track.text_at(opts.t);
+        if text.trim().is_empty() {
+            return Ok(img);
+        }
+
+        let mut text_kwargs = opts.text_kwargs;
+        text_kwargs.insert("text".to_string(), Val::String(text));
+        self.text.filter(&[Val::Frame(img)], &text_kwargs)
+    }
+
+    fn filter_type(
+        &self,
+        args: &[Val],
+        kwargs: &BTreeMap<String, Val>,
+    ) -> Result<FrameType, Error> {
+        let opts = self.args(args, kwargs).map_err(Error::AVError)?;
+
+        // Type the call as if a cue were showing, so styling and the font are checked for every frame
+        let mut text_kwargs = opts.text_kwargs;
+        text_kwargs.insert("text".to_string(), Val::String(String::new()));
+        self.text.filter_type(
+            &[Val::FrameType(opts.img.unwrap_frame_type())],
+            &text_kwargs,
+        )
+    }
+
+    fn signature(&self) -> Option<Signature> {
+        Some(Self::sig())
+    }
+}
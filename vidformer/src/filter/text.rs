@@ -0,0 +1,1073 @@
+//! Text drawn with TrueType and OpenType fonts
+//!
+//! `cv2.putText` only has OpenCV's Hershey vector fonts, which cover ASCII. The [`Text`] filter draws text with font
+//! files loaded through a [`Service`], so any script the font covers renders correctly: text is shaped with rustybuzz
+//! (joining Arabic, combining marks, ligatures), reordered for bidirectional text, and wrapped at Unicode line break
+//! opportunities.
+
+use super::filter_utils::{self, FrameArg};
+use super::{Filter, Frame, FrameType, Param, ParamType, Signature, Val};
+use crate::dve::{AVFrame, Error};
+use crate::service::Service;
+use opencv::prelude::MatTraitManual;
+use rusty_ffmpeg::ffi;
+use rustybuzz::ttf_parser;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// Text filters, loading fonts through `fonts`
+///
+/// Font paths given to the filters are relative to the store's service, e.g., to its `root` for a filesystem service.
+pub fn filters(fonts: Arc<FontStore>) -> BTreeMap<String, Box<dyn Filter>> {
+    let mut filters: BTreeMap<String, Box<dyn Filter>> = BTreeMap::new();
+    filters.insert("Text".to_string(), Box::new(Text::new(fonts)));
+    filters
+}
+
+/// Horizontal alignment of lines within the text block
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+/// A glyph positioned relative to the top left of the text block, in pixels
+struct PlacedGlyph {
+    id: ttf_parser::GlyphId,
+    x: f32,
+    y: f32,
+}
+
+/// Shaped and positioned text, in pixels relative to the top left of the text block
+struct TextLayout {
+    glyphs: Vec<PlacedGlyph>,
+    /// Left edge and width of each line
+    lines: Vec<(f32, f32)>,
+    height: f32,
+    scale: f32,
+}
+
+/// Shape text, guessing its direction unless `rtl` is given
+fn shape(face: &rustybuzz::Face, text: &str, rtl: Option<bool>) -> rustybuzz::GlyphBuffer {
+    let mut buffer = rustybuzz::UnicodeBuffer::new();
+    buffer.push_str(text);
+    if let Some(rtl) = rtl {
+        buffer.set_direction(if rtl {
+            rustybuzz::Direction::RightToLeft
+        } else {
+            rustybuzz::Direction::LeftToRight
+        });
+    }
+    buffer.guess_segment_properties();
+    rustybuzz::shape(face, &[], buffer)
+}
+
+/// Shape a run of text with a single direction, returning its glyphs and their x positions from the run's start
+fn shape_run(
+    face: &rustybuzz::Face,
+    text: &str,
+    rtl: Option<bool>,
+    scale: f32,
+) -> (Vec<(ttf_parser::GlyphId, f32, f32)>, f32) {
+    let output = shape(face, text, rtl);
+
+    let mut glyphs = Vec::with_capacity(output.len());
+    let mut pen = 0.0;
+    for (info, pos) in output.glyph_infos().iter().zip(output.glyph_positions()) {
+        glyphs.push((
+            ttf_parser::GlyphId(info.glyph_id as u16),
+            pen + pos.x_offset as f32 * scale,
+            -pos.y_offset as f32 * scale,
+        ));
+        pen += pos.x_advance as f32 * scale;
+    }
+    (glyphs, pen)
+}
+
+/// Split a paragraph into lines no wider than `max_width`, breaking at Unicode line break opportunities
+///
+/// A word wider than `max_width` gets a line of its own rather than being split.
+fn wrap(
+    face: &rustybuzz::Face,
+    paragraph: &str,
+    max_width: Option<f32>,
+    scale: f32,
+) -> Vec<std::ops::Range<usize>> {
+    // Shape the paragraph once, and measure a line by the advances of the glyphs in its clusters
+    let mut advances = vec![0.0; paragraph.len() + 1];
+    if max_width.is_some() {
+        let output = shape(face, paragraph, None);
+        for (info, pos) in output.glyph_infos().iter().zip(output.glyph_positions()) {
+            advances[info.cluster as usize + 1] += pos.x_advance as f32 * scale;
+        }
+        let mut total = 0.0;
+        for advance in advances.iter_mut() {
+            total += *advance;
+            *advance = total;
+        }
+    }
+    let width = |range: std::ops::Range<usize>| {
+        let range = trim_end(paragraph, range);
+        advances[range.end] - advances[range.start]
+    };
+
+    let mut lines = Vec::new();
+    let mut start = 0;
+    let mut end = None;
+    for (idx, _) in unicode_linebreak::linebreaks(paragraph) {
+        if let Some(line_end) = end {
+            if max_width.is_some_and(|max_width| width(start..idx) > max_width) {
+                lines.push(start..line_end);
+                start = line_end;
+            }
+        }
+        end = Some(idx);
+    }
+    if start < paragraph.len() || lines.is_empty() {
+        lines.push(start..paragraph.len());
+    }
+    lines
+}
+
+/// Trim trailing whitespace from a line's range
+fn trim_end(text: &str, range: std::ops::Range<usize>) -> std::ops::Range<usize> {
+    range.start..range.start + text[range].trim_end().len()
+}
+
+/// Shape, wrap, and position text
+///
+/// Lines break at newlines and, given `max_width`, wherever a line would be wider than it. Bidirectional text is
+/// reordered per line, so right-to-left scripts like Arabic display correctly.
+fn layout(
+    face: &rustybuzz::Face,
+    text: &str,
+    size: f32,
+    max_width: Option<f32>,
+    align: Align,
+    line_spacing: f32,
+) -> TextLayout {
+    let scale = size / face.units_per_em() as f32;
+    let ascender = face.ascender() as f32 * scale;
+    let line_height = (face.ascender() as f32 - face.descender() as f32 + face.line_gap() as f32)
+        * scale
+        * line_spacing;
+
+    let mut lines = Vec::new();
+    for paragraph in text.split('\n') {
+        let bidi = unicode_bidi::BidiInfo::new(paragraph, None);
+        for range in wrap(face, paragraph, max_width, scale) {
+            let range = trim_end(paragraph, range);
+            let mut glyphs = Vec::new();
+            let mut pen = 0.0;
+            if let Some(para) = bidi.paragraphs.first() {
+                if !range.is_empty() {
+                    let (levels, runs) = bidi.visual_runs(para, range);
+                    for run in runs {
+                        let rtl = levels[run.start].is_rtl();
+                        let (run_glyphs, advance) =
+                            shape_run(face, &paragraph[run], Some(rtl), scale);
+                        glyphs.extend(run_glyphs.into_iter().map(|(id, x, y)| (id, pen + x, y)));
+                        pen += advance;
+                    }
+                }
+            }
+            lines.push((glyphs, pen));
+        }
+    }
+
+    let width = match max_width {
+        Some(max_width) => max_width,
+        None => lines.iter().map(|(_, w)| *w).fold(0.0, f32::max),
+    };
+
+    let mut layout = TextLayout {
+        glyphs: Vec::new(),
+        lines: Vec::new(),
+        height: line_height * lines.len() as f32,
+        scale,
+    };
+    for (i, (glyphs, line_width)) in lines.into_iter().enumerate() {
+        let x = match align {
+            Align::Left => 0.0,
+            Align::Center => (width - line_width) / 2.0,
+            Align::Right => width - line_width,
+        };
+        let baseline = ascender + line_height * i as f32;
+        layout
+            .glyphs
+            .extend(glyphs.into_iter().map(|(id, gx, gy)| PlacedGlyph {
+                id,
+                x: x + gx,
+                y: baseline + gy,
+            }));
+        layout.lines.push((x, line_width));
+    }
+    layout
+}
+
+impl TextLayout {
+    /// Coverage of a box around the lines of the text block, with its top left at (`x`, `y`), grown by `padding`
+    fn background(&self, x: f32, y: f32, padding: i32, clip: &ClipRect) -> Option<Coverage> {
+        let lines = self.lines.iter().filter(|(_, w)| *w > 0.0);
+        let left = lines.clone().map(|(lx, _)| *lx).reduce(f32::min)?;
+        let right = lines.map(|(lx, w)| lx + w).reduce(f32::max)?;
+        let padding = padding as f32;
+        let (x0, y0, x1, y1) = clip.intersect(
+            (x + left).floor() - padding,
+            y.floor() - padding,
+            (x + right).ceil() + padding,
+            (y + self.height).ceil() + padding,
+        )?;
+        let (width, height) = ((x1 - x0) as usize, (y1 - y0) as usize);
+        Some(Coverage {
+            x: x0,
+            y: y0,
+            width,
+            height,
+            data: vec![1.0; width * height],
+        })
+    }
+}
+
+/// The part of the frame, grown by a margin, which drawing can reach; coverage is only computed inside it
+///
+/// Text can be laid out far larger than the frame, so coverage buffers are sized to this rather than to the text.
+#[derive(Clone, Copy)]
+struct ClipRect {
+    x0: f32,
+    y0: f32,
+    x1: f32,
+    y1: f32,
+}
+
+impl ClipRect {
+    fn frame(width: i32, height: i32, margin: i32) -> Self {
+        let margin = margin as f32;
+        ClipRect {
+            x0: -margin,
+            y0: -margin,
+            x1: width as f32 + margin,
+            y1: height as f32 + margin,
+        }
+    }
+
+    /// The area whose coverage lands in this one when drawn offset by (`dx`, `dy`)
+    fn unshift(&self, dx: i32, dy: i32) -> Self {
+        let (dx, dy) = (dx as f32, dy as f32);
+        ClipRect {
+            x0: self.x0 - dx,
+            y0: self.y0 - dy,
+            x1: self.x1 - dx,
+            y1: self.y1 - dy,
+        }
+    }
+
+    /// Integer bounds of the intersection with a box, or None if it's empty
+    fn intersect(&self, x0: f32, y0: f32, x1: f32, y1: f32) -> Option<(i32, i32, i32, i32)> {
+        let (x0, y0) = (x0.max(self.x0).floor(), y0.max(self.y0).floor());
+        let (x1, y1) = (x1.min(self.x1).ceil(), y1.min(self.y1).ceil());
+        if x0 >= x1 || y0 >= y1 {
+            return None;
+        }
+        Some((x0 as i32, y0 as i32, x1 as i32, y1 as i32))
+    }
+}
+
+/// Per-pixel coverage in [0, 1] of a `width` by `height` area whose top left is at (`x`, `y`)
+struct Coverage {
+    x: i32,
+    y: i32,
+    width: usize,
+    height: usize,
+    data: Vec<f32>,
+}
+
+/// Draws a glyph's outline into a rasterizer one column wider than the canvas
+///
+/// Outlines can extend past the canvas. The rasterizer clips them vertically, but not horizontally, so lines are
+/// clamped to the canvas's sides, which keeps the fill between them exact.
+struct GlyphOutline<'a> {
+    rasterizer: &'a mut ab_glyph_rasterizer::Rasterizer,
+    scale: f32,
+    /// Position of the glyph origin in the rasterizer
+    x: f32,
+    y: f32,
+    /// Width of the canvas
+    width: f32,
+    start: ab_glyph_rasterizer::Point,
+    last: ab_glyph_rasterizer::Point,
+}
+
+/// Line segments needed to flatten a curve to within a fraction of a pixel, given its control points' second difference
+fn curve_segments(ddx: f32, ddy: f32) -> usize {
+    1 + (3.0 * (ddx * ddx + ddy * ddy)).sqrt().sqrt() as usize
+}
+
+impl GlyphOutline<'_> {
+    fn point(&self, x: f32, y: f32) -> ab_glyph_rasterizer::Point {
+        ab_glyph_rasterizer::point(self.x + x * self.scale, self.y - y * self.scale)
+    }
+
+    fn line(&mut self, p0: ab_glyph_rasterizer::Point, p1: ab_glyph_rasterizer::Point) {
+        // Split where the line crosses a side, since clamping a line's ends doesn't clamp the line between them
+        let mut ts = [0.0, 1.0, 1.0, 1.0];
+        let mut len = 1;
+        for side in [0.0, self.width] {
+            let t = (side - p0.x) / (p1.x - p0.x);
+            if t > 0.0 && t < 1.0 {
+                ts[len] = t;
+                len += 1;
+            }
+        }
+        ts[1..len].sort_by(f32::total_cmp);
+        ts[len] = 1.0;
+
+        let at = |t: f32| {
+            ab_glyph_rasterizer::point(
+                (p0.x + (p1.x - p0.x) * t).clamp(0.0, self.width),
+                p0.y + (p1.y - p0.y) * t,
+            )
+        };
+        for pair in ts[..=len].windows(2) {
+            self.rasterizer.draw_line(at(pair[0]), at(pair[1]));
+        }
+        self.last = p1;
+    }
+}
+
+impl ttf_parser::OutlineBuilder for GlyphOutline<'_> {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.start = self.point(x, y);
+        self.last = self.start;
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        let p = self.point(x, y);
+        self.line(self.last, p);
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        let (p0, p1, p2) = (self.last, self.point(x1, y1), self.point(x, y));
+        let n = curve_segments(p0.x - 2.0 * p1.x + p2.x, p0.y - 2.0 * p1.y + p2.y);
+        for i in 1..=n {
+            let t = i as f32 / n as f32;
+            let (a, b, c) = ((1.0 - t) * (1.0 - t), 2.0 * (1.0 - t) * t, t * t);
+            let p = ab_glyph_rasterizer::point(
+                a * p0.x + b * p1.x + c * p2.x,
+                a * p0.y + b * p1.y + c * p2.y,
+            );
+            self.line(self.last, p);
+        }
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        let (p0, p1, p2, p3) = (
+            self.last,
+            self.point(x1, y1),
+            self.point(x2, y2),
+            self.point(x, y),
+        );
+        let dd0 = (p0.x - 2.0 * p1.x + p2.x, p0.y - 2.0 * p1.y + p2.y);
+        let dd1 = (p1.x - 2.0 * p2.x + p3.x, p1.y - 2.0 * p2.y + p3.y);
+        let dd = if dd0.0.hypot(dd0.1) > dd1.0.hypot(dd1.1) {
+            dd0
+        } else {
+            dd1
+        };
+        // A cubic's second derivative is three times a quadratic's for the same second difference
+        let n = curve_segments(3.0 * dd.0, 3.0 * dd.1);
+        for i in 1..=n {
+            let t = i as f32 / n as f32;
+            let mt = 1.0 - t;
+            let (a, b, c, d) = (mt * mt * mt, 3.0 * mt * mt * t, 3.0 * mt * t * t, t * t * t);
+            let p = ab_glyph_rasterizer::point(
+                a * p0.x + b * p1.x + c * p2.x + d * p3.x,
+                a * p0.y + b * p1.y + c * p2.y + d * p3.y,
+            );
+            self.line(self.last, p);
+        }
+    }
+
+    fn close(&mut self) {
+        if self.last != self.start {
+            self.line(self.last, self.start);
+        }
+        self.last = self.start;
+    }
+}
+
+/// Rasterize the part of laid out text inside `clip`, with the text block's top left at (`x`, `y`)
+fn rasterize(
+    face: &rustybuzz::Face,
+    layout: &TextLayout,
+    x: f32,
+    y: f32,
+    clip: &ClipRect,
+) -> Coverage {
+    // Size the canvas to the glyphs' bounding boxes, which may extend past the line boxes
+    let glyph_bounds = |glyph: &PlacedGlyph| {
+        let bbox = face.glyph_bounding_box(glyph.id)?;
+        Some((
+            x + glyph.x + bbox.x_min as f32 * layout.scale - 1.0,
+            y + glyph.y - bbox.y_max as f32 * layout.scale - 1.0,
+            x + glyph.x + bbox.x_max as f32 * layout.scale + 1.0,
+            y + glyph.y - bbox.y_min as f32 * layout.scale + 1.0,
+        ))
+    };
+    let bounds = layout.glyphs.iter().filter_map(glyph_bounds).reduce(
+        |(ax0, ay0, ax1, ay1), (bx0, by0, bx1, by1)| {
+            (ax0.min(bx0), ay0.min(by0), ax1.max(bx1), ay1.max(by1))
+        },
+    );
+    let (x0, y0, x1, y1) = match bounds.and_then(|(x0, y0, x1, y1)| clip.intersect(x0, y0, x1, y1))
+    {
+        Some(bounds) => bounds,
+        None => {
+            return Coverage {
+                x: 0,
+                y: 0,
+                width: 0,
+                height: 0,
+                data: Vec::new(),
+            }
+        }
+    };
+    let (width, height) = ((x1 - x0) as usize, (y1 - y0) as usize);
+    let canvas = ClipRect {
+        x0: x0 as f32,
+        y0: y0 as f32,
+        x1: x1 as f32,
+        y1: y1 as f32,
+    };
+
+    let mut rasterizer = ab_glyph_rasterizer::Rasterizer::new(width + 1, height);
+    for glyph in &layout.glyphs {
+        // Glyphs off the canvas add nothing
+        match glyph_bounds(glyph) {
+            Some((gx0, gy0, gx1, gy1)) if canvas.intersect(gx0, gy0, gx1, gy1).is_some() => {}
+            _ => continue,
+        }
+        let mut outline = GlyphOutline {
+            rasterizer: &mut rasterizer,
+            scale: layout.scale,
+            x: x + glyph.x - x0 as f32,
+            y: y + glyph.y - y0 as f32,
+            width: width as f32,
+            start: ab_glyph_rasterizer::point(0.0, 0.0),
+            last: ab_glyph_rasterizer::point(0.0, 0.0),
+        };
+        face.outline_glyph(glyph.id, &mut outline);
+    }
+
+    // Skip the rasterizer's extra column, where lines clamped to the right side land
+    let mut data = vec![0.0; width * height];
+    rasterizer.for_each_pixel(|i, alpha| {
+        let (cx, cy) = (i % (width + 1), i / (width + 1));
+        if cx < width {
+            data[cy * width + cx] = alpha.min(1.0);
+        }
+    });
+    Coverage {
+        x: x0,
+        y: y0,
+        width,
+        height,
+        data,
+    }
+}
+
+impl Coverage {
+    /// Grow the covered area by `radius` pixels in every direction, e.g., for an outline
+    fn dilate(&self, radius: i32) -> Coverage {
+        let r = radius.max(0);
+        let (width, height) = (self.width + 2 * r as usize, self.height + 2 * r as usize);
+        let offsets: Vec<(i32, i32)> = (-r..=r)
+            .flat_map(|dy| (-r..=r).map(move |dx| (dx, dy)))
+            .filter(|(dx, dy)| dx * dx + dy * dy <= r * r)
+            .collect();
+
+        let mut data = vec![0.0f32; width * height];
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let value = self.data[y * self.width + x];
+                if value <= 0.0 {
+                    continue;
+                }
+                for (dx, dy) in &offsets {
+                    let i = (y as i32 + r + dy) as usize * width + (x as i32 + r + dx) as usize;
+                    data[i] = data[i].max(value);
+                }
+            }
+        }
+        Coverage {
+            x: self.x - r,
+            y: self.y - r,
+            width,
+            height,
+            data,
+        }
+    }
+
+    /// Blend `color` (RGB with alpha, all in [0, 1]) into an RGB24 image where the coverage is, offset by (`dx`, `dy`)
+    fn draw(
+        &self,
+        image: &mut [u8],
+        image_width: i32,
+        image_height: i32,
+        color: [f32; 4],
+        dx: i32,
+        dy: i32,
+    ) {
+        for cy in 0..self.height as i32 {
+            let y = self.y + dy + cy;
+            if y < 0 || y >= image_height {
+                continue;
+            }
+            for cx in 0..self.width as i32 {
+                let x = self.x + dx + cx;
+                if x < 0 || x >= image_width {
+                    continue;
+                }
+                let alpha = self.data[(cy as usize) * self.width + cx as usize] * color[3];
+                if alpha <= 0.0 {
+                    continue;
+                }
+                let i = (y * image_width + x) as usize * 3;
+                for c in 0..3 {
+                    let bottom = image[i + c] as f32;
+                    let out = bottom + (color[c] * 255.0 - bottom) * alpha;
+                    image[i + c] = out.round().clamp(0.0, 255.0) as u8;
+                }
+            }
+        }
+    }
+}
+
+/// Vertical alignment of the text block within its rectangle
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum VAlign {
+    Top,
+    Middle,
+    Bottom,
+}
+
+/// Where the text block goes
+enum Placement {
+    /// Top left of the text block
+    Org(i32, i32),
+    /// x, y, width, height; the text wraps to the width and is aligned within the rectangle
+    Rect(i32, i32, i32, i32),
+}
+
+struct TextArgs {
+    img: FrameArg,
+    text: String,
+    font: String,
+    font_index: u32,
+    size: f32,
+    placement: Placement,
+    align: Align,
+    valign: VAlign,
+    line_spacing: f32,
+    color: [f32; 4],
+    outline: i32,
+    outline_color: [f32; 4],
+    shadow: Option<(i32, i32)>,
+    shadow_color: [f32; 4],
+    background: Option<[f32; 4]>,
+    padding: i32,
+}
+
+/// Largest font size in pixels, past which a single glyph is larger than any frame
+const MAX_SIZE: f64 = 8192.0;
+
+/// Largest outline width in pixels, since outlining takes time in the square of the width
+const MAX_OUTLINE: i32 = 64;
+
+/// Parse a color given as `[r, g, b]` or `[r, g, b, a]` in [0, 255], returning it in [0, 1]
+fn get_rgba(parsed_args: &BTreeMap<&'static str, Val>, key: &str) -> Result<[f32; 4], String> {
+    let err = || format!("Expected '{key}' to be a list of three or four numbers in [0, 255]");
+    let list = match parsed_args.get(key) {
+        Some(Val::List(list)) if list.len() == 3 || list.len() == 4 => list,
+        _ => return Err(err()),
+    };
+    let mut color = [1.0; 4];
+    for (c, val) in color.iter_mut().zip(list) {
+        let value = match val {
+            Val::Int(value) => *value as f64,
+            Val::Float(value) => *value,
+            _ => return Err(err()),
+        };
+        if !(0.0..=255.0).contains(&value) {
+            return Err(err());
+        }
+        *c = (value / 255.0) as f32;
+    }
+    Ok(color)
+}
+
+fn get_int_list<const N: usize>(
+    parsed_args: &BTreeMap<&'static str, Val>,
+    key: &str,
+    what: &str,
+) -> Result<[i32; N], String> {
+    let err = || format!("Expected '{key}' to be {what}");
+    let list = match parsed_args.get(key) {
+        Some(Val::List(list)) if list.len() == N => list,
+        _ => return Err(err()),
+    };
+    let mut out = [0; N];
+    for (o, val) in out.iter_mut().zip(list) {
+        match val {
+            Val::Int(value) => *o = *value as i32,
+            _ => return Err(err()),
+        }
+    }
+    Ok(out)
+}
+
+/// A font's data, behind its own lock so calls needing the same font wait for a single read
+type FontSlot = Arc<parking_lot::Mutex<Option<Arc<Vec<u8>>>>>;
+
+/// Fonts read through a [`Service`]
+///
+/// Each font is read the first time it's used and kept for the store's lifetime. Filters share a store through an
+/// `Arc`, so a long-running process reads each font once.
+pub struct FontStore {
+    fonts: Service,
+    loaded: parking_lot::Mutex<BTreeMap<String, FontSlot>>,
+    /// Created on the first font read, for the font service's IO
+    io_runtime: std::sync::OnceLock<tokio::runtime::Runtime>,
+}
+
+impl FontStore {
+    pub fn new(fonts: Service) -> Self {
+        FontStore {
+            fonts,
+            loaded: parking_lot::Mutex::new(BTreeMap::new()),
+            io_runtime: std::sync::OnceLock::new(),
+        }
+    }
+
+    /// Read a font file from the font service, or return it if it was already read
+    fn font_data(&self, path: &str) -> Result<Arc<Vec<u8>>, Error> {
+        let slot = self
+            .loaded
+            .lock()
+            .entry(path.to_string())
+            .or_default()
+            .clone();
+        let mut slot = slot.lock();
+        if let Some(data) = &*slot {
+            return Ok(data.clone());
+        }
+
+        let io_runtime = self.io_runtime.get_or_init(|| {
+            tokio::runtime::Builder::new_multi_thread()
+                .worker_threads(1)
+                .thread_name("text-font-io")
+                .enable_all()
+                .build()
+                .unwrap()
+        });
+        let op = self.fonts.blocking_operator(io_runtime.handle())?;
+        let data = match op.read(path) {
+            Ok(buffer) => Arc::new(buffer.to_vec()),
+            Err(e) if e.kind() == opendal::ErrorKind::NotFound => {
+                return Err(Error::IOError(format!("Font `{}` not found", path)));
+            }
+            Err(e) => {
+                return Err(Error::IOError(format!(
+                    "Failed to read font {}: {}",
+                    path, e
+                )));
+            }
+        };
+
+        *slot = Some(data.clone());
+        Ok(data)
+    }
+}
+
+impl Drop for FontStore {
+    fn drop(&mut self) {
+        // Don't block if dropped from async code
+        if let Some(io_runtime) = self.io_runtime.take() {
+            io_runtime.shutdown_background();
+        }
+    }
+}
+
+/// Draw text with a TrueType or OpenType font from a [`FontStore`]
+pub struct Text {
+    fonts: Arc<FontStore>,
+}
+
+impl Text {
+    pub fn new(fonts: Arc<FontStore>) -> Self {
+        Text { fonts }
+    }
+
+    fn sig() -> Signature {
+        Signature::new(vec![
+            Param::positional("img", ParamType::Frame),
+            Param::positional("text", ParamType::String)
+                .with_doc("Text to draw; newlines start new lines"),
+            Param::keyword_only("font", ParamType::String)
+                .with_doc("Path of a TrueType or OpenType font in the filter's font service"),
+            Param::keyword_only("font_index", ParamType::Int)
+                .with_default(Val::Int(0))
+                .with_doc("Index of the font within a font collection"),
+            Param::keyword_only("size", ParamType::Float)
+                .with_default(Val::Float(32.0))
+                .with_doc("Font size in pixels, up to 8192"),
+            Param::keyword_only("org", ParamType::List)
+                .optional()
+                .with_doc("[x, y] of the top left of the text"),
+            Param::keyword_only("rect", ParamType::List)
+                .optional()
+                .with_doc("[x, y, width, height] to wrap and align the text within"),
+            Param::keyword_only("align", ParamType::String)
+                .with_default(Val::String("left".to_string()))
+                .with_doc("left, center, or right"),
+            Param::keyword_only("valign", ParamType::String)
+                .with_default(Val::String("top".to_string()))
+                .with_doc("top, middle, or bottom of rect"),
+            Param::keyword_only("line_spacing", ParamType::Float)
+                .with_default(Val::Float(1.0))
+                .with_doc("Multiple of the font's line height between lines"),
+            Param::keyword_only("color", ParamType::List)
+                .with_default(Val::List(vec![Val::Int(255); 4]))
+                .with_doc("[r, g, b] or [r, g, b, a]"),
+            Param::keyword_only("outline", ParamType::Int)
+                .with_default(Val::Int(0))
+                .with_doc("Outline width in pixels, up to 64"),
+            Param::keyword_only("outline_color", ParamType::List)
+                .with_default(Val::List(vec![
+                    Val::Int(0),
+                    Val::Int(0),
+                    Val::Int(0),
+                    Val::Int(255),
+                ]))
+                .with_doc("[r, g, b] or [r, g, b, a]"),
+            Param::keyword_only("shadow", ParamType::List)
+                .optional()
+                .with_doc("[dx, dy] offset of a drop shadow"),
+            Param::keyword_only("shadow_color", ParamType::List)
+                .with_default(Val::List(vec![
+                    Val::Int(0),
+                    Val::Int(0),
+                    Val::Int(0),
+                    Val::Int(128),
+                ]))
+                .with_doc("[r, g, b] or [r, g, b, a]"),
+            Param::keyword_only("background", ParamType::List)
+                .optional()
+                .with_doc(
+                    "[r, g, b] or [r, g, b, a] of a box behind the text, e.g., for subtitles",
+                ),
+            Param::keyword_only("padding", ParamType::Int)
+                .with_default(Val::Int(4))
+                .with_doc("Space in pixels between the text and its background box"),
+        ])
+        .with_doc("Draw text with a TrueType or OpenType font")
+    }
+
+    fn args(args: &[Val], kwargs: &BTreeMap<String, Val>) -> Result<TextArgs, String> {
+        let parsed_args =
+            filter_utils::parse_arguments(&Self::sig(), args.to_vec(), kwargs.clone())?;
+
+        let img = filter_utils::get_frame(&parsed_args, "img")?;
+        let text = filter_utils::get_string(&parsed_args, "text")?;
+        let font = filter_utils::get_string(&parsed_args, "font")?;
+        let font_index = filter_utils::get_int(&parsed_args, "font_index")?;
+        if font_index < 0 {
+            return Err("Expected 'font_index' to be non-negative".into());
+        }
+        let size = filter_utils::get_float(&parsed_args, "size")?;
+        if !(size > 0.0 && size <= MAX_SIZE) {
+            return Err(format!(
+                "Expected 'size' to be positive and at most {}",
+                MAX_SIZE
+            ));
+        }
+
+        let placement = match (
+            parsed_args.contains_key("org"),
+            parsed_args.contains_key("rect"),
+        ) {
+            (true, false) => {
+                let [x, y] = get_int_list(&parsed_args, "org", "[x, y]")?;
+                Placement::Org(x, y)
+            }
+            (false, true) => {
+                let [x, y, w, h] = get_int_list(&parsed_args, "rect", "[x, y, width, height]")?;
+                if w <= 0 || h <= 0 {
+                    return Err("Expected 'rect' to have a positive size".into());
+                }
+                Placement::Rect(x, y, w, h)
+            }
+            _ => return Err("Expected exactly one of 'org' and 'rect'".into()),
+        };
+
+        let align = match filter_utils::get_string(&parsed_args, "align")?.as_str() {
+            "left" => Align::Left,
+            "center" => Align::Center,
+            "right" => Align::Right,
+            other => return Err(format!("Unknown alignment '{}'", other)),
+        };
+        let valign = match filter_utils::get_string(&parsed_args, "valign")?.as_str() {
+            "top" => VAlign::Top,
+            "middle" => VAlign::Middle,
+            "bottom" => VAlign::Bottom,
+            other => return Err(format!("Unknown vertical alignment '{}'", other)),
+        };
+
+        let line_spacing = filter_utils::get_float(&parsed_args, "line_spacing")?;
+        if line_spacing <= 0.0 {
+            return Err("Expected 'line_spacing' to be positive".into());
+        }
+
+        let outline = filter_utils::get_int(&parsed_args, "outline")?;
+        let padding = filter_utils::get_int(&parsed_args, "padding")?;
+        if outline < 0 || padding < 0 {
+            return Err("Expected 'outline' and 'padding' to be non-negative".into());
+        }
+        if outline > MAX_OUTLINE {
+            return Err(format!("Expected 'outline' to be at most {}", MAX_OUTLINE));
+        }
+
+        let shadow = if parsed_args.contains_key("shadow") {
+            let [dx, dy] = get_int_list(&parsed_args, "shadow", "[dx, dy]")?;
+            Some((dx, dy))
+        } else {
+            None
+        };
+        let background = if parsed_args.contains_key("background") {
+            Some(get_rgba(&parsed_args, "background")?)
+        } else {
+            None
+        };
+
+        Ok(TextArgs {
+            img,
+            text,
+            font,
+            font_index: font_index as u32,
+            size: size as f32,
+            placement,
+            align,
+            valign,
+            line_spacing: line_spacing as f32,
+            color: get_rgba(&parsed_args, "color")?,
+            outline,
+            outline_color: get_rgba(&parsed_args, "outline_color")?,
+            shadow,
+            shadow_color: get_rgba(&parsed_args, "shadow_color")?,
+            background,
+            padding,
+        })
+    }
+}
+
+fn parse_face<'a>(data: &'a [u8], opts: &TextArgs) -> Result<rustybuzz::Face<'a>, Error> {
+    rustybuzz::Face::from_slice(data, opts.font_index).ok_or_else(|| {
+        Error::InvalidFilterArgValue(
+            opts.font.clone(),
+            "Not a TrueType or OpenType font".to_string(),
+        )
+    })
+}
+
+impl Filter for Text {
+    fn filter(&self, args: &[Val], kwargs: &BTreeMap<String, Val>) -> Result<Frame, Error> {
+        let opts = Self::args(args, kwargs).map_err(Error::AVError)?;
+        let data = self.fonts.font_data(&opts.font)?;
+        let face = parse_face(&data, &opts)?;
+
+        let img = opts.img.unwrap_frame();
+        let (width, height) = (img.width, img.height);
+        debug_assert_eq!(img.format, ffi::AVPixelFormat_AV_PIX_FMT_RGB24);
+        let mut mat = filter_utils::frame_to_mat_rgb24(&img, width, height);
+
+        let max_width = match opts.placement {
+            Placement::Org(..) => None,
+            Placement::Rect(_, _, w, _) => Some(w as f32),
+        };
+        let text = layout(
+            &face,
+            &opts.text,
+            opts.size,
+            max_width,
+            opts.align,
+            opts.line_spacing,
+        );
+        let (x, y) = match opts.placement {
+            Placement::Org(x, y) => (x as f32, y as f32),
+            Placement::Rect(x, y, _, h) => {
+                let slack = h as f32 - text.height;
+                let dy = match opts.valign {
+                    VAlign::Top => 0.0,
+                    VAlign::Middle => slack / 2.0,
+                    VAlign::Bottom => slack,
+                };
+                (x as f32, y as f32 + dy)
+            }
+        };
+
+        // Glyphs just off the frame still reach it once outlined
+        let clip = ClipRect::frame(width, height, opts.outline);
+        let glyphs = rasterize(&face, &text, x, y, &clip);
+        let outlined = if opts.outline > 0 {
+            Some(glyphs.dilate(opts.outline))
+        } else {
+            None
+        };
+
+        let pixels = mat.data_bytes_mut().unwrap();
+        if let Some(background) = opts.background {
+            let frame = ClipRect::frame(width, height, 0);
+            if let Some(box_coverage) = text.background(x, y, opts.padding, &frame) {
+                box_coverage.draw(pixels, width, height, background, 0, 0);
+            }
+        }
+        if let Some((dx, dy)) = opts.shadow {
+            // The shadow shows a different part of the text than the glyphs do
+            let shadow = rasterize(&face, &text, x, y, &clip.unshift(dx, dy));
+            let shadow = if opts.outline > 0 {
+                shadow.dilate(opts.outline)
+            } else {
+                shadow
+            };
+            shadow.draw(pixels, width, height, opts.shadow_color, dx, dy);
+        }
+        if let Some(outlined) = &outlined {
+            outlined.draw(pixels, width, height, opts.outline_color, 0, 0);
+        }
+        glyphs.draw(pixels, width, height, opts.color, 0, 0);
+
+        let f = match filter_utils::mat_to_frame_rgb24(mat, width, height) {
+            Ok(value) => value,
+            Err(value) => return value,
+        };
+
+        Ok(Frame::new(AVFrame { inner: f }))
+    }
+
+    fn filter_type(
+        &self,
+        args: &[Val],
+        kwargs: &BTreeMap<String, Val>,
+    ) -> Result<FrameType, Error> {
+        let opts = Self::args(args, kwargs).map_err(Error::AVError)?;
+
+        let img = opts.img.unwrap_frame_type();
+        if img.format != ffi::AVPixelFormat_AV_PIX_FMT_RGB24 {
+            return Err(Error::AVError("Expected img to be an RGB24 frame".into()));
+        }
+
+        // Read the font now so a missing or invalid font is reported before rendering
+        let data = self.fonts.font_data(&opts.font)?;
+        parse_face(&data, &opts)?;
+
+        Ok(img)
+    }
+
+    fn signature(&self) -> Option<Signature> {
+        Some(Self::sig())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_coverage_dilate_and_draw() {
+        let dot = Coverage {
+            x: 5,
+            y: 5,
+            width: 1,
+            height: 1,
+            data: vec![1.0],
+        };
+        let grown = dot.dilate(2);
+        assert_eq!((grown.x, grown.y, grown.width, grown.height), (3, 3, 5, 5));
+        // A disc: the center row is covered, the corners aren't
+        assert!(grown.data[2 * 5..3 * 5].iter().all(|v| *v == 1.0));
+        assert_eq!(grown.data[0], 0.0);
+
+        // Drawing clips to the image, and blends by the color's alpha
+        let mut image = vec![0u8; 6 * 6 * 3];
+        grown.draw(&mut image, 6, 6, [1.0, 0.0, 0.0, 0.5], 0, 0);
+        assert_eq!(&image[(5 * 6 + 5) * 3..], &[128, 0, 0]);
+        assert_eq!(&image[..3], &[0, 0, 0]);
+    }
+
+    #[test]
+    fn test_wrap() {
+        let data = std::fs::read(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fonts/DejaVuSans.ttf"
+        ))
+        .unwrap();
+        let face = rustybuzz::Face::from_slice(&data, 0).unwrap();
+        let scale = 32.0 / face.units_per_em() as f32;
+        let paragraph = "The quick brown fox jumps over the lazy dog, then naps in the sun.";
+
+        let lines = wrap(&face, paragraph, Some(300.0), scale);
+        assert!(lines.len() > 1);
+        assert_eq!(lines.first().unwrap().start, 0);
+        assert_eq!(lines.last().unwrap().end, paragraph.len());
+        for (line, next) in lines.iter().zip(lines.iter().skip(1)) {
+            assert_eq!(line.end, next.start);
+            // Measuring from the paragraph's shaping agrees with shaping each line alone
+            let line = trim_end(paragraph, line.clone());
+            assert!(shape_run(&face, &paragraph[line], None, scale).1 <= 300.0);
+        }
+
+        assert_eq!(
+            wrap(&face, paragraph, None, scale),
+            vec![0..paragraph.len()]
+        );
+    }
+
+    #[test]
+    fn test_rasterize_clipped() {
+        let data = std::fs::read(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fonts/DejaVuSans.ttf"
+        ))
+        .unwrap();
+        let face = rustybuzz::Face::from_slice(&data, 0).unwrap();
+
+        // Text straddling the frame's corner is only rasterized where it's on the frame, and matches the unclipped text there
+        let text = layout(&face, "O&W", 120.0, None, Align::Left, 1.0);
+        let everywhere = ClipRect::frame(1000, 1000, 1000);
+        let full = rasterize(&face, &text, -60.0, -50.0, &everywhere);
+        let frame = ClipRect::frame(100, 80, 0);
+        let clipped = rasterize(&face, &text, -60.0, -50.0, &frame);
+        assert_eq!((clipped.x, clipped.y), (0, 0));
+        assert!(clipped.width <= 100 && clipped.height <= 80);
+        let mut covered = 0;
+        for y in 0..clipped.height {
+            for x in 0..clipped.width {
+                let value = clipped.data[y * clipped.width + x];
+                let fx = (x as i32 - full.x) as usize;
+                let fy = (y as i32 - full.y) as usize;
+                assert!((value - full.data[fy * full.width + fx]).abs() < 1e-3);
+                covered += (value > 0.5) as usize;
+            }
+        }
+        assert!(covered > 0);
+
+        // Huge text only allocates the frame's area
+        let text = layout(&face, "W", MAX_SIZE as f32, None, Align::Left, 1.0);
+        let clipped = rasterize(&face, &text, -4000.0, -4000.0, &frame);
+        assert!(clipped.width <= 100 && clipped.height <= 80);
+        let background = text.background(-4000.0, -4000.0, 4, &frame).unwrap();
+        assert_eq!((background.width, background.height), (100, 80));
+        assert!(rasterize(&face, &text, 5000.0, 0.0, &frame).data.is_empty());
+    }
+}
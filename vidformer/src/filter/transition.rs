@@ -0,0 +1,409 @@
+//! Transitions between two frames
+//!
+//! The [`Transition`] filter renders one frame of a transition from a frame `a` to a frame `b` at a progress in
+//! [0, 1], so a timeline only needs one filter expression per frame of the transition.
+
+use super::filter_utils::{self, FrameArg};
+use super::{Filter, Frame, FrameType, Param, ParamType, Signature, Val};
+use crate::dve::{AVFrame, Error};
+use opencv::prelude::{MatTraitConstManual, MatTraitManual};
+use rusty_ffmpeg::ffi;
+use std::collections::BTreeMap;
+
+/// Transition filters
+pub fn filters() -> BTreeMap<String, Box<dyn Filter>> {
+    let mut filters: BTreeMap<String, Box<dyn Filter>> = BTreeMap::new();
+    filters.insert("Transition".to_string(), Box::new(Transition {}));
+    filters
+}
+
+/// The direction an edge or frame moves in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Direction {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+impl Direction {
+    fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "left" => Ok(Direction::Left),
+            "right" => Ok(Direction::Right),
+            "up" => Ok(Direction::Up),
+            "down" => Ok(Direction::Down),
+            _ => Err(format!("Unknown direction '{}'", s)),
+        }
+    }
+
+    /// The unit vector of the direction, with y pointing down
+    fn vector(self) -> (i32, i32) {
+        match self {
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0),
+            Direction::Up => (0, -1),
+            Direction::Down => (0, 1),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Kind {
+    /// Blend from `a` to `b`
+    Crossfade,
+    /// A straight edge moving in a direction, revealing `b` behind it
+    Wipe(Direction),
+    /// `b` moving in over `a`
+    Slide(Direction),
+    /// `b` moving in and pushing `a` out
+    Push(Direction),
+    /// A circle growing from a center, revealing `b` inside it
+    Iris(f32, f32),
+    /// Fade `a` out to a color, then fade `b` in from it
+    Dip([u8; 3]),
+}
+
+/// How far through a wipe or iris a pixel is revealed, in [0, 1], for a `width` x `height` frame
+fn reveal_at(kind: Kind, x: i32, y: i32, width: i32, height: i32) -> f32 {
+    let (x, y) = (x as f32 + 0.5, y as f32 + 0.5);
+    let (w, h) = (width as f32, height as f32);
+    match kind {
+        Kind::Wipe(Direction::Left) => 1.0 - x / w,
+        Kind::Wipe(Direction::Right) => x / w,
+        Kind::Wipe(Direction::Up) => 1.0 - y / h,
+        Kind::Wipe(Direction::Down) => y / h,
+        Kind::Iris(cx, cy) => {
+            // The circle reaches the farthest corner at the end
+            let radius = [(0.0, 0.0), (w, 0.0), (0.0, h), (w, h)]
+                .iter()
+                .map(|(px, py)| (px - cx).hypot(py - cy))
+                .fold(0.0, f32::max);
+            ((x - cx).hypot(y - cy) / radius).min(1.0)
+        }
+        _ => unreachable!(),
+    }
+}
+
+/// The weight of `b` at a pixel revealed at `reveal`, with edges feathered over `softness`
+///
+/// The edge travels from before the first pixel at progress 0 to past the last at progress 1, so the ends of a
+/// transition are exactly `a` and `b`.
+fn coverage(reveal: f32, progress: f32, softness: f32) -> f32 {
+    if softness > 0.0 {
+        ((progress * (1.0 + softness) - reveal) / softness).clamp(0.0, 1.0)
+    } else if reveal < progress {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+fn blend(a: u8, b: u8, t: f32) -> u8 {
+    (a as f32 + (b as f32 - a as f32) * t)
+        .round()
+        .clamp(0.0, 255.0) as u8
+}
+
+/// Render a transition between two packed RGB24 images `width` pixels wide
+fn render(
+    kind: Kind,
+    progress: f32,
+    softness: f32,
+    a: &[u8],
+    b: &[u8],
+    out: &mut [u8],
+    width: i32,
+) {
+    let height = (a.len() / (width as usize * 3)) as i32;
+    match kind {
+        Kind::Crossfade => {
+            for ((o, a), b) in out.iter_mut().zip(a).zip(b) {
+                *o = blend(*a, *b, progress);
+            }
+        }
+        Kind::Dip(color) => {
+            let (src, t) = if progress < 0.5 {
+                (a, progress * 2.0)
+            } else {
+                (b, 2.0 - progress * 2.0)
+            };
+            for (o, px) in out.chunks_exact_mut(3).zip(src.chunks_exact(3)) {
+                for c in 0..3 {
+                    o[c] = blend(px[c], color[c], t);
+                }
+            }
+        }
+        Kind::Wipe(_) | Kind::Iris(..) => {
+            for y in 0..height {
+                for x in 0..width {
+                    let idx = (y * width + x) as usize * 3;
+                    let t = coverage(reveal_at(kind, x, y, width, height), progress, softness);
+                    for c in idx..idx + 3 {
+                        out[c] = blend(a[c], b[c], t);
+                    }
+                }
+            }
+        }
+        Kind::Slide(direction) | Kind::Push(direction) => {
+            // `b` starts a frame away, opposite the direction it moves in, and `a` moves with it when pushed
+            let (vx, vy) = direction.vector();
+            let remaining = 1.0 - progress;
+            let b_offset = (
+                -((vx * width) as f32 * remaining).round() as i32,
+                -((vy * height) as f32 * remaining).round() as i32,
+            );
+            let a_offset = match kind {
+                Kind::Push(_) => (b_offset.0 + vx * width, b_offset.1 + vy * height),
+                _ => (0, 0),
+            };
+
+            let sample = |img: &[u8], (dx, dy): (i32, i32), x: i32, y: i32| {
+                let (sx, sy) = (x - dx, y - dy);
+                if sx >= 0 && sx < width && sy >= 0 && sy < height {
+                    let idx = (sy * width + sx) as usize * 3;
+                    Some([img[idx], img[idx + 1], img[idx + 2]])
+                } else {
+                    None
+                }
+            };
+            for y in 0..height {
+                for x in 0..width {
+                    let idx = (y * width + x) as usize * 3;
+                    let px = sample(b, b_offset, x, y)
+                        .or_else(|| sample(a, a_offset, x, y))
+                        .unwrap_or([0, 0, 0]);
+                    out[idx..idx + 3].copy_from_slice(&px);
+                }
+            }
+        }
+    }
+}
+
+pub struct Transition {}
+
+struct TransitionArgs {
+    a: FrameArg,
+    b: FrameArg,
+    progress: f32,
+    kind: Kind,
+    softness: f32,
+}
+
+impl Transition {
+    fn sig() -> Signature {
+        Signature::new(vec![
+            Param::positional("a", ParamType::Frame).with_doc("Frame transitioned from"),
+            Param::positional("b", ParamType::Frame).with_doc("Frame transitioned to, the same size as a"),
+            Param::positional("progress", ParamType::Float)
+                .with_doc("From 0 (just a) to 1 (just b)"),
+            Param::positional("kind", ParamType::String)
+                .with_default(Val::String("crossfade".to_string()))
+                .with_doc("crossfade, wipe, slide, push, iris, or dip"),
+            Param::keyword_only("direction", ParamType::String)
+                .with_default(Val::String("left".to_string()))
+                .with_doc("left, right, up, or down; the direction a wipe's edge or a sliding frame moves in"),
+            Param::keyword_only("softness", ParamType::Float)
+                .with_default(Val::Float(0.0))
+                .with_doc("Width of a wipe's or iris's feathered edge, as a fraction of the transition"),
+            Param::keyword_only("center", ParamType::List)
+                .optional()
+                .with_doc("[x, y] an iris grows from; defaults to the center of the frame"),
+            Param::keyword_only("color", ParamType::List)
+                .with_default(Val::List(vec![Val::Int(0), Val::Int(0), Val::Int(0)]))
+                .with_doc("[r, g, b] a dip fades through"),
+        ])
+        .with_doc("Render a frame of a transition between two frames")
+    }
+
+    fn args(args: &[Val], kwargs: &BTreeMap<String, Val>) -> Result<TransitionArgs, String> {
+        let parsed_args =
+            filter_utils::parse_arguments(&Self::sig(), args.to_vec(), kwargs.clone())?;
+
+        let a = filter_utils::get_frame(&parsed_args, "a")?;
+        let b = filter_utils::get_frame(&parsed_args, "b")?;
+        let (width, height) = match &a {
+            FrameArg::Frame(frame) => (frame.width, frame.height),
+            FrameArg::FrameType(frame_type) => (frame_type.width as i32, frame_type.height as i32),
+        };
+
+        let progress = filter_utils::get_float(&parsed_args, "progress")?;
+        if !(0.0..=1.0).contains(&progress) {
+            return Err("Expected 'progress' to be in [0, 1]".into());
+        }
+        let softness = filter_utils::get_float(&parsed_args, "softness")?;
+        if softness < 0.0 {
+            return Err("Expected 'softness' to be non-negative".into());
+        }
+
+        let direction = Direction::parse(&filter_utils::get_string(&parsed_args, "direction")?)?;
+        let kind = match filter_utils::get_string(&parsed_args, "kind")?.as_str() {
+            "crossfade" => Kind::Crossfade,
+            "wipe" => Kind::Wipe(direction),
+            "slide" => Kind::Slide(direction),
+            "push" => Kind::Push(direction),
+            "iris" => {
+                let (cx, cy) = if parsed_args.contains_key("center") {
+                    let (x, y) = filter_utils::get_point(&parsed_args, "center")?;
+                    (x as f32, y as f32)
+                } else {
+                    (width as f32 / 2.0, height as f32 / 2.0)
+                };
+                Kind::Iris(cx, cy)
+            }
+            "dip" => {
+                let color = match parsed_args.get("color") {
+                    Some(Val::List(list)) if list.len() == 3 => {
+                        let mut rgb = [0u8; 3];
+                        for (c, val) in rgb.iter_mut().zip(list) {
+                            *c = match val {
+                                Val::Int(v) if (0..=255).contains(v) => *v as u8,
+                                _ => {
+                                    return Err(
+                                        "Expected 'color' to be [r, g, b] in [0, 255]".into()
+                                    )
+                                }
+                            };
+                        }
+                        rgb
+                    }
+                    _ => return Err("Expected 'color' to be [r, g, b] in [0, 255]".into()),
+                };
+                Kind::Dip(color)
+            }
+            other => return Err(format!("Unknown transition kind '{}'", other)),
+        };
+
+        Ok(TransitionArgs {
+            a,
+            b,
+            progress: progress as f32,
+            kind,
+            softness: softness as f32,
+        })
+    }
+}
+
+impl Filter for Transition {
+    fn filter(&self, args: &[Val], kwargs: &BTreeMap<String, Val>) -> Result<Frame, Error> {
+        let opts = Self::args(args, kwargs).map_err(Error::AVError)?;
+
+        let a = opts.a.unwrap_frame();
+        let b = opts.b.unwrap_frame();
+        let (width, height) = (a.width, a.height);
+        debug_assert_eq!(a.format, ffi::AVPixelFormat_AV_PIX_FMT_RGB24);
+        debug_assert_eq!(b.format, ffi::AVPixelFormat_AV_PIX_FMT_RGB24);
+
+        let a_mat = filter_utils::frame_to_mat_rgb24(&a, width, height);
+        let b_mat = filter_utils::frame_to_mat_rgb24(&b, width, height);
+        let mut out_mat = filter_utils::frame_to_mat_rgb24(&a, width, height);
+        render(
+            opts.kind,
+            opts.progress,
+            opts.softness,
+            a_mat.data_bytes().unwrap(),
+            b_mat.data_bytes().unwrap(),
+            out_mat.data_bytes_mut().unwrap(),
+            width,
+        );
+
+        let f = match filter_utils::mat_to_frame_rgb24(out_mat, width, height) {
+            Ok(value) => value,
+            Err(value) => return value,
+        };
+
+        Ok(Frame::new(AVFrame { inner: f }))
+    }
+
+    fn filter_type(
+        &self,
+        args: &[Val],
+        kwargs: &BTreeMap<String, Val>,
+    ) -> Result<FrameType, Error> {
+        let opts = Self::args(args, kwargs).map_err(Error::AVError)?;
+
+        let a = opts.a.unwrap_frame_type();
+        let b = opts.b.unwrap_frame_type();
+        if a.format != ffi::AVPixelFormat_AV_PIX_FMT_RGB24
+            || b.format != ffi::AVPixelFormat_AV_PIX_FMT_RGB24
+        {
+            return Err(Error::AVError("Expected a and b to be RGB24 frames".into()));
+        }
+        if a.width != b.width || a.height != b.height {
+            return Err(Error::AVError(
+                "Expected a and b to be the same size".into(),
+            ));
+        }
+
+        Ok(a)
+    }
+
+    fn signature(&self) -> Option<Signature> {
+        Some(Self::sig())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(kind: Kind, progress: f32, softness: f32) -> Vec<u8> {
+        // 4x2 frames, a with its pixels numbered and b all white
+        let a: Vec<u8> = (0..8).flat_map(|i| [i * 10; 3]).collect();
+        let b = vec![255; 24];
+        let mut out = vec![0; 24];
+        render(kind, progress, softness, &a, &b, &mut out, 4);
+        // One channel per pixel is enough, as the test frames are gray
+        out.iter().step_by(3).copied().collect()
+    }
+
+    #[test]
+    fn test_endpoints() {
+        let a: Vec<u8> = (0..8).map(|i| i * 10).collect();
+        for kind in [
+            Kind::Crossfade,
+            Kind::Wipe(Direction::Left),
+            Kind::Wipe(Direction::Down),
+            Kind::Slide(Direction::Right),
+            Kind::Push(Direction::Up),
+            Kind::Iris(2.0, 1.0),
+            Kind::Dip([0, 0, 0]),
+        ] {
+            for softness in [0.0, 0.25] {
+                assert_eq!(run(kind, 0.0, softness), a, "{:?}", kind);
+                assert_eq!(run(kind, 1.0, softness), vec![255; 8], "{:?}", kind);
+            }
+        }
+    }
+
+    #[test]
+    fn test_midpoints() {
+        assert_eq!(run(Kind::Crossfade, 0.5, 0.0)[0], 128);
+        assert_eq!(run(Kind::Dip([255, 255, 255]), 0.5, 0.0), vec![255; 8]);
+        assert_eq!(run(Kind::Dip([0, 0, 0]), 0.5, 0.0), vec![0; 8]);
+        assert_eq!(
+            run(Kind::Wipe(Direction::Left), 0.5, 0.0),
+            vec![0, 10, 255, 255, 40, 50, 255, 255]
+        );
+        assert_eq!(
+            run(Kind::Wipe(Direction::Down), 0.5, 0.0),
+            vec![255, 255, 255, 255, 40, 50, 60, 70]
+        );
+        // b enters from the left, covering a
+        assert_eq!(
+            run(Kind::Slide(Direction::Right), 0.25, 0.0),
+            vec![255, 10, 20, 30, 255, 50, 60, 70]
+        );
+        // a moves right along with b
+        assert_eq!(
+            run(Kind::Push(Direction::Right), 0.25, 0.0),
+            vec![255, 0, 10, 20, 255, 40, 50, 60]
+        );
+        let iris = run(Kind::Iris(2.0, 1.0), 0.5, 0.0);
+        assert_eq!(iris, vec![0, 255, 255, 30, 40, 255, 255, 70]);
+
+        // A soft edge blends the pixels it covers
+        let soft = run(Kind::Wipe(Direction::Left), 0.5, 0.5);
+        assert!(soft[1] > 10 && soft[1] < 255 && soft[3] == 255);
+    }
+}
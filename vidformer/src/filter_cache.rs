@@ -0,0 +1,414 @@
+//! Memoization of filter outputs which are shared between output frames
+//!
+//! Before a run, [`FilterCache::plan`] finds the filter sub-expressions which are rendered more than once (e.g., a watermark drawn onto every frame).
+//! Filterers then cache the outputs of those sub-expressions, keyed by their structural hash, and reuse them instead of re-rendering.
+//! Each shared node counts the output frames which use it and haven't been rendered yet; its entry is dropped once that reaches zero, in whatever order
+//! the filterers finish. The cache never holds more than `Config::filter_cache_bytes`.
+//!
+//! This assumes filters are deterministic, which they already need to be for a spec to be well-defined.
+//!
+//! Shared nodes below the top level are pinned while their expected output sizes fit in the budget, most used first. A pinned node's bytes are
+//! reserved up front, so its entry is always stored and stays until its last user is rendered. Only its first user renders it: the others wait
+//! for that output frame (see [`FilterCache::waits_on`]) and don't have the source frames below it decoded (see [`FilterCache::add_source_deps`]).
+//! Other shared nodes share what's left of the budget and aren't guaranteed a hit, so their users still have every source frame decoded.
+
+use crate::dve::AVFrame;
+use crate::sir::{FrameExpr, FrameSource, ProcessSpan};
+use log::*;
+use parking_lot::Mutex;
+use rayon::prelude::*;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+
+pub(crate) struct FilterCache {
+    /// Filter node hashes of each output frame, only for frames which use a shared node
+    frame_hashes: Vec<Option<Vec<(u128, usize)>>>,
+    /// The shared nodes, and how many output frames use each
+    users: HashMap<u128, usize>,
+    /// Shared nodes with a reserved slot, and the output frame which renders each
+    pinned: HashMap<u128, usize>,
+    /// Budget left for the shared nodes which aren't pinned
+    max_bytes: usize,
+    state: Mutex<FilterCacheState>,
+}
+
+struct FilterCacheState {
+    /// Cached outputs, and the bytes each takes from the unpinned budget
+    entries: HashMap<u128, (Arc<AVFrame>, usize)>,
+    /// Output frames using each shared node which haven't been rendered yet
+    remaining: HashMap<u128, usize>,
+    bytes: usize,
+}
+
+impl FilterCache {
+    /// Plans the cache for a span, using `node_bytes` to estimate the output size of a shared node when pinning it
+    pub(crate) fn plan(
+        process_span: &ProcessSpan,
+        max_bytes: usize,
+        node_bytes: impl Fn(&FrameExpr) -> Option<usize>,
+    ) -> Self {
+        let mut cache = FilterCache {
+            frame_hashes: vec![None; process_span.frames.len()],
+            users: HashMap::new(),
+            pinned: HashMap::new(),
+            max_bytes,
+            state: Mutex::new(FilterCacheState {
+                entries: HashMap::new(),
+                remaining: HashMap::new(),
+                bytes: 0,
+            }),
+        };
+        if max_bytes == 0 {
+            return cache;
+        }
+
+        let frame_hashes: Vec<Vec<(u128, usize)>> = process_span
+            .frames
+            .par_iter()
+            .map(|frame| frame.filter_hashes())
+            .collect();
+
+        // How many times each node is rendered, how many output frames render it, and the first and last of those
+        let mut uses: HashMap<u128, (usize, usize, usize, usize)> = HashMap::new();
+        for (gen, hashes) in frame_hashes.iter().enumerate() {
+            for (hash, _) in hashes {
+                let entry = uses.entry(*hash).or_insert((0, 0, gen, usize::MAX));
+                entry.0 += 1;
+                if entry.3 != gen {
+                    entry.1 += 1;
+                    entry.3 = gen;
+                }
+            }
+        }
+
+        // A node which is only ever rendered as part of a shared parent doesn't need its own entry
+        let mut subsumed = std::collections::HashSet::new();
+        for hashes in &frame_hashes {
+            for (idx, (hash, descendants)) in hashes.iter().enumerate() {
+                let count = uses[hash].0;
+                if count < 2 {
+                    continue;
+                }
+                let mut child = idx + 1;
+                while child <= idx + descendants {
+                    let (child_hash, child_descendants) = hashes[child];
+                    if uses[&child_hash].0 == count {
+                        subsumed.insert(child_hash);
+                    }
+                    child += child_descendants + 1;
+                }
+            }
+        }
+
+        for (hash, (count, frames, _, _)) in &uses {
+            if *count >= 2 && !subsumed.contains(hash) {
+                cache.users.insert(*hash, *frames);
+            }
+        }
+        cache.state.get_mut().remaining = cache.users.clone();
+
+        // Top-level nodes aren't pinned, since data filters aren't cached when analyzing
+        let roots: std::collections::HashSet<u128> = frame_hashes
+            .iter()
+            .filter_map(|hashes| hashes.first().map(|(hash, _)| *hash))
+            .collect();
+        let mut candidates: Vec<(usize, u128)> = cache
+            .users
+            .iter()
+            .filter(|(hash, _)| !roots.contains(hash))
+            .map(|(hash, frames)| (*frames, *hash))
+            .collect();
+        candidates.sort_unstable_by(|a, b| b.cmp(a));
+        if !candidates.is_empty() {
+            let mut nodes: HashMap<u128, &FrameExpr> = HashMap::new();
+            for (frame, hashes) in process_span.frames.iter().zip(&frame_hashes) {
+                if candidates.len() == nodes.len() {
+                    break;
+                }
+                for (node, (hash, _)) in frame.filter_nodes().into_iter().zip(hashes) {
+                    if cache.users.contains_key(hash) && !roots.contains(hash) {
+                        nodes.entry(*hash).or_insert(node);
+                    }
+                }
+            }
+            for (_, hash) in candidates {
+                match node_bytes(nodes[&hash]) {
+                    Some(bytes) if bytes <= cache.max_bytes => {
+                        cache.max_bytes -= bytes;
+                        cache.pinned.insert(hash, uses[&hash].2);
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        for (gen, hashes) in frame_hashes.into_iter().enumerate() {
+            if hashes
+                .iter()
+                .any(|(hash, _)| cache.users.contains_key(hash))
+            {
+                cache.frame_hashes[gen] = Some(hashes);
+            }
+        }
+
+        if !cache.users.is_empty() {
+            info!(
+                "Found {} shared filter expressions to cache, {} of them pinned",
+                cache.users.len(),
+                cache.pinned.len()
+            );
+        }
+
+        cache
+    }
+
+    /// Cache access for rendering one output frame, if it uses any shared nodes
+    pub(crate) fn frame_memo(&self, gen: usize) -> Option<FrameMemo<'_>> {
+        self.frame_hashes[gen].as_ref().map(|hashes| FrameMemo {
+            cache: self,
+            hashes,
+            next: 0,
+            local: HashMap::new(),
+            hits: 0,
+        })
+    }
+
+    /// Whether a filter node's output is shared between output frames, and so cached
+    pub(crate) fn is_shared(&self, key: u128) -> bool {
+        self.users.contains_key(&key)
+    }
+
+    /// Whether an output frame takes a node from the cache because another output frame renders it
+    fn is_pinned_elsewhere(&self, gen: usize, key: u128) -> bool {
+        self.pinned.get(&key).is_some_and(|first| *first != gen)
+    }
+
+    /// Adds the source frames an output frame needs to a set
+    ///
+    /// This leaves out the sources below the pinned nodes the frame doesn't render itself.
+    pub(crate) fn add_source_deps<'a>(
+        &self,
+        gen: usize,
+        frame: &'a FrameExpr,
+        deps: &mut BTreeSet<&'a FrameSource>,
+    ) {
+        match &self.frame_hashes[gen] {
+            Some(hashes) if !self.pinned.is_empty() => frame
+                .add_source_deps_skipping(|idx| self.is_pinned_elsewhere(gen, hashes[idx].0), deps),
+            _ => frame.add_source_deps(deps),
+        }
+    }
+
+    /// The output frames which have to be rendered before an output frame, because they render pinned nodes it uses
+    pub(crate) fn waits_on(&self, gen: usize) -> Vec<usize> {
+        let mut out: Vec<usize> = match &self.frame_hashes[gen] {
+            Some(hashes) => hashes
+                .iter()
+                .filter(|(hash, _)| self.is_pinned_elsewhere(gen, *hash))
+                .map(|(hash, _)| self.pinned[hash])
+                .collect(),
+            None => Vec::new(),
+        };
+        out.sort_unstable();
+        out.dedup();
+        out
+    }
+
+    fn get(&self, key: u128) -> Option<Arc<AVFrame>> {
+        let state = self.state.lock();
+        state.entries.get(&key).map(|(frame, _)| frame.clone())
+    }
+
+    fn insert(&self, key: u128, frame: &Arc<AVFrame>) {
+        let mut state = self.state.lock();
+        if state.remaining[&key] <= 1 || state.entries.contains_key(&key) {
+            // Either no other frame needs it, or another frame already cached it
+            return;
+        }
+        if self.pinned.contains_key(&key) {
+            // Paid for by its reservation
+            state.entries.insert(key, (frame.clone(), 0));
+            return;
+        }
+        let bytes = frame.size_bytes();
+        if state.bytes + bytes > self.max_bytes {
+            debug!("Filter cache full, not caching {:032x}", key);
+            return;
+        }
+        state.bytes += bytes;
+        state.entries.insert(key, (frame.clone(), bytes));
+    }
+}
+
+impl FilterCacheState {
+    /// Mark one output frame using a node as rendered, dropping the node's entry after the last
+    fn release(&mut self, key: u128) {
+        let remaining = self.remaining.get_mut(&key).unwrap();
+        *remaining -= 1;
+        if *remaining == 0 {
+            if let Some((_, bytes)) = self.entries.remove(&key) {
+                self.bytes -= bytes;
+            }
+        }
+    }
+}
+
+/// Walks the filter nodes of one output frame in render order, alongside the renderer
+///
+/// Dropping it marks the frame as rendered for every shared node it uses, including any skipped over by a cache hit.
+pub(crate) struct FrameMemo<'a> {
+    cache: &'a FilterCache,
+    hashes: &'a [(u128, usize)],
+    next: usize,
+    /// Outputs already rendered for this frame, for nodes used more than once within it
+    local: HashMap<u128, Arc<AVFrame>>,
+    pub(crate) hits: usize,
+}
+
+impl FrameMemo<'_> {
+    /// Move to the next filter node, returning its cache key if its output is shared
+    pub(crate) fn enter(&mut self) -> Option<u128> {
+        let (hash, _) = self.hashes[self.next];
+        self.next += 1;
+        if self.cache.users.contains_key(&hash) {
+            Some(hash)
+        } else {
+            None
+        }
+    }
+
+    /// Look up the output of the node just entered, skipping over its sub-expressions on a hit
+    pub(crate) fn lookup(&mut self, key: u128) -> Option<Arc<AVFrame>> {
+        let (_, descendants) = self.hashes[self.next - 1];
+        let frame = match self.local.get(&key) {
+            Some(frame) => frame.clone(),
+            None => {
+                let frame = self.cache.get(key)?;
+                self.local.insert(key, frame.clone());
+                frame
+            }
+        };
+        self.next += descendants;
+        self.hits += 1;
+        Some(frame)
+    }
+
+    pub(crate) fn store(&mut self, key: u128, frame: &Arc<AVFrame>) {
+        self.local.insert(key, frame.clone());
+        self.cache.insert(key, frame);
+    }
+}
+
+impl Drop for FrameMemo<'_> {
+    fn drop(&mut self) {
+        let mut keys: Vec<u128> = self
+            .hashes
+            .iter()
+            .map(|(hash, _)| *hash)
+            .filter(|hash| self.cache.users.contains_key(hash))
+            .collect();
+        keys.sort_unstable();
+        keys.dedup();
+
+        let mut state = self.cache.state.lock();
+        for key in keys {
+            state.release(key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_rational::Rational64;
+
+    #[test]
+    fn test_plan() {
+        let frames: Vec<&str> = vec![
+            "cv2.putText(_inline_mat(b\"abc\"), \"logo\")",
+            "_slice_write_mat(src[0], cv2.putText(_inline_mat(b\"abc\"), \"logo\"))",
+            "_slice_write_mat(src[1/24], cv2.putText(_inline_mat(b\"abc\"), \"logo\"))",
+            "Scale(src[2/24])",
+        ];
+        let process_span = ProcessSpan {
+            ts: (0..frames.len() as i64)
+                .map(|i| Rational64::new(i, 24))
+                .collect(),
+            frames: frames.iter().map(|f| f.parse().unwrap()).collect(),
+            output_ts_offset: None,
+        };
+
+        let cache = FilterCache::plan(&process_span, 1024, |_| Some(1024));
+        // Only the watermark is cached, not the `_inline_mat` below it
+        assert_eq!(cache.users.len(), 1);
+        // It's the whole of frame 0, so it isn't pinned
+        assert!(cache.pinned.is_empty());
+        assert_eq!(cache.users.values().next(), Some(&3));
+        assert!(cache.frame_hashes[..3].iter().all(|h| h.is_some()));
+        assert!(cache.frame_hashes[3].is_none());
+
+        let mut memo = cache.frame_memo(1).unwrap();
+        assert_eq!(memo.enter(), None);
+        let key = memo.enter().unwrap();
+
+        // Frames are released as they finish, in any order
+        drop(memo);
+        drop(cache.frame_memo(2).unwrap());
+        assert_eq!(cache.state.lock().remaining[&key], 1);
+        drop(cache.frame_memo(0).unwrap());
+        assert_eq!(cache.state.lock().remaining[&key], 0);
+
+        let cache = FilterCache::plan(&process_span, 0, |_| Some(1024));
+        assert!(cache.users.is_empty());
+        assert!(cache.frame_memo(1).is_none());
+    }
+
+    #[test]
+    fn test_pinned() {
+        let frames: Vec<&str> = vec![
+            "cv2.putText(cv2.putText(Scale(src[0]), \"b\"), \"a\")",
+            "cv2.putText(Scale(src[0]), \"x\")",
+            "cv2.putText(cv2.putText(Scale(src[0]), \"b\"), \"c\")",
+            "Scale(src[1/24])",
+        ];
+        let process_span = ProcessSpan {
+            ts: (0..frames.len() as i64)
+                .map(|i| Rational64::new(i, 24))
+                .collect(),
+            frames: frames.iter().map(|f| f.parse().unwrap()).collect(),
+            output_ts_offset: None,
+        };
+        let deps = |cache: &FilterCache, gen: usize| {
+            let mut deps = BTreeSet::new();
+            cache.add_source_deps(gen, &process_span.frames[gen], &mut deps);
+            deps.len()
+        };
+
+        // Both the scale and the "b" title fit, and frame 0 renders both for the others
+        let cache = FilterCache::plan(&process_span, 2048, |_| Some(1024));
+        assert_eq!(cache.pinned.len(), 2);
+        assert!(cache.pinned.values().all(|first| *first == 0));
+        assert_eq!(cache.max_bytes, 0);
+        assert_eq!(deps(&cache, 0), 1);
+        assert_eq!(deps(&cache, 1), 0);
+        assert_eq!(deps(&cache, 2), 0);
+        assert_eq!(deps(&cache, 3), 1);
+        assert!(cache.waits_on(0).is_empty());
+        assert_eq!(cache.waits_on(2), vec![0]);
+        assert!(cache.waits_on(3).is_empty());
+
+        // Only the most used node fits, and the rest of the budget is left to the title
+        let cache = FilterCache::plan(&process_span, 1500, |_| Some(1024));
+        assert_eq!(cache.pinned.len(), 1);
+        assert_eq!(cache.max_bytes, 1500 - 1024);
+        assert_eq!(deps(&cache, 2), 0);
+        assert_eq!(cache.waits_on(2), vec![0]);
+
+        // Nodes which don't fit fall back to being cached when there's room
+        let cache = FilterCache::plan(&process_span, 1000, |_| Some(1024));
+        assert!(cache.pinned.is_empty());
+        assert_eq!(cache.users.len(), 2);
+        assert_eq!(deps(&cache, 2), 1);
+        assert!(cache.waits_on(2).is_empty());
+    }
+}
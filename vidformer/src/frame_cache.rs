@@ -0,0 +1,155 @@
+//! Decoded source frames kept across runs
+//!
+//! Specs rendered piece by piece, such as HLS segments, often need the same source GOPs in several runs.
+//! A [`FrameCache`] set on a [`Context`](crate::Context) lets those runs reuse frames other runs already decoded:
+//! decoders add every frame they decode, and the decode pool takes frames from the cache before opening a decoder for them.
+//!
+//! Frames are keyed by the source's fuid and the frame's timestamp, so only sources with a fuid are cached.
+
+use crate::filter::Frame;
+use num_rational::Rational64;
+use parking_lot::Mutex;
+use std::collections::{BTreeMap, HashMap};
+
+/// A store of decoded source frames which can be shared between runs
+pub trait FrameCache: Send + Sync {
+    fn get(&self, fuid: &str, pts: &Rational64) -> Option<Frame>;
+    fn insert(&self, fuid: &str, pts: &Rational64, frame: Frame);
+}
+
+/// A [`FrameCache`] which holds up to a number of bytes of frames, dropping the least recently used first
+pub struct LruFrameCache {
+    max_bytes: usize,
+    state: Mutex<LruState>,
+}
+
+struct LruEntry {
+    frame: Frame,
+    bytes: usize,
+    last_used: u64,
+}
+
+struct LruState {
+    entries: HashMap<String, HashMap<Rational64, LruEntry>>,
+    by_last_used: BTreeMap<u64, (String, Rational64)>,
+    clock: u64,
+    bytes: usize,
+}
+
+impl LruFrameCache {
+    pub fn new(max_bytes: usize) -> Self {
+        LruFrameCache {
+            max_bytes,
+            state: Mutex::new(LruState {
+                entries: HashMap::new(),
+                by_last_used: BTreeMap::new(),
+                clock: 0,
+                bytes: 0,
+            }),
+        }
+    }
+
+    /// The bytes of frames currently held
+    pub fn bytes(&self) -> usize {
+        self.state.lock().bytes
+    }
+}
+
+impl LruState {
+    fn touch(&mut self, fuid: &str, pts: &Rational64) -> Option<&LruEntry> {
+        self.clock += 1;
+        let clock = self.clock;
+        let entry = self.entries.get_mut(fuid)?.get_mut(pts)?;
+        let key = self.by_last_used.remove(&entry.last_used).unwrap();
+        entry.last_used = clock;
+        self.by_last_used.insert(clock, key);
+        Some(entry)
+    }
+
+    fn evict_oldest(&mut self) {
+        let Some((_, (fuid, pts))) = self.by_last_used.pop_first() else {
+            return;
+        };
+        let frames = self.entries.get_mut(&fuid).unwrap();
+        let entry = frames.remove(&pts).unwrap();
+        if frames.is_empty() {
+            self.entries.remove(&fuid);
+        }
+        self.bytes -= entry.bytes;
+    }
+}
+
+impl FrameCache for LruFrameCache {
+    fn get(&self, fuid: &str, pts: &Rational64) -> Option<Frame> {
+        let mut state = self.state.lock();
+        state.touch(fuid, pts).map(|entry| entry.frame.clone())
+    }
+
+    fn insert(&self, fuid: &str, pts: &Rational64, frame: Frame) {
+        let bytes = frame.clone().into_avframe().size_bytes();
+        if bytes > self.max_bytes {
+            return;
+        }
+
+        let mut state = self.state.lock();
+        if state.touch(fuid, pts).is_some() {
+            return;
+        }
+        while state.bytes + bytes > self.max_bytes {
+            state.evict_oldest();
+        }
+
+        state.clock += 1;
+        let clock = state.clock;
+        state.by_last_used.insert(clock, (fuid.to_string(), *pts));
+        state.entries.entry(fuid.to_string()).or_default().insert(
+            *pts,
+            LruEntry {
+                frame,
+                bytes,
+                last_used: clock,
+            },
+        );
+        state.bytes += bytes;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::{Filter, Val};
+
+    fn frame() -> Frame {
+        let kwargs = BTreeMap::from([
+            ("width".to_string(), Val::Int(64)),
+            ("height".to_string(), Val::Int(64)),
+        ]);
+        crate::filter::builtin::PlaceholderFrame {}
+            .filter(&[], &kwargs)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_lru_frame_cache() {
+        let frame_bytes = frame().into_avframe().size_bytes();
+        let cache = LruFrameCache::new(2 * frame_bytes + frame_bytes / 2);
+        let t = |i| Rational64::new(i, 24);
+
+        cache.insert("a", &t(0), frame());
+        cache.insert("a", &t(1), frame());
+        cache.insert("a", &t(1), frame());
+        assert_eq!(cache.bytes(), 2 * frame_bytes);
+
+        // Using 0 makes 1 the least recently used
+        assert!(cache.get("a", &t(0)).is_some());
+        cache.insert("b", &t(0), frame());
+        assert_eq!(cache.bytes(), 2 * frame_bytes);
+        assert!(cache.get("a", &t(0)).is_some());
+        assert!(cache.get("a", &t(1)).is_none());
+        assert!(cache.get("b", &t(0)).is_some());
+
+        let tiny = LruFrameCache::new(frame_bytes - 1);
+        tiny.insert("a", &t(0), frame());
+        assert!(tiny.get("a", &t(0)).is_none());
+    }
+}
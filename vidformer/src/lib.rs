@@ -6,20 +6,31 @@
 //! * [📘 Documentation](https://ixlab.github.io/vidformer/vidformer/)
 //! * [🧑‍💻 Source Code](https://github.com/ixlab/vidformer/tree/main/vidformer/)
 
+pub mod decoder_registry;
 pub mod filter;
+pub mod frame_cache;
 pub mod io;
 pub mod service;
 pub mod sir;
 pub mod source;
 pub mod spec;
+pub mod subtitle;
 
+mod analysis;
+mod auto_config;
 pub(crate) mod av;
 mod dve;
+mod explain;
+mod filter_cache;
+mod optimizer;
 mod pool;
 mod util;
 
+pub use analysis::{analyze, Analysis};
+pub use auto_config::Resources;
 pub use dve::{
-    create_spec_hls, run, validate, Config, Context, EncoderConfig, Error, Range, RangeTsFormat,
-    Stats,
+    create_spec_hls, optimized_spec, run, validate, Config, Context, EncoderConfig, Error,
+    FilterStats, Range, RangeTsFormat, StageStats, Stats, FILTER_LATENCY_BUCKETS,
 };
+pub use explain::{explain, Plan, SourcePlan};
 pub use util::{codecs, init, CodecDescriptor};
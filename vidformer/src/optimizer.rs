@@ -0,0 +1,397 @@
+//! Rewrites output frame expressions before they are executed
+//!
+//! The optimizer runs on a type-checked [`ProcessSpan`], before the decode pool is planned, so rewrites which remove source references also remove decodes.
+//! Every rewrite must give the same output type as the expression it replaces; this is checked with [`type_frame`] and a rewrite which fails the check is skipped.
+//! Rewrites must also give the same pixels, so the optimizer is always safe to run.
+//!
+//! Rules only apply to the builtin and cv2 filters; a call to another filter registered under the same name is left alone.
+
+use crate::dve::{type_frame, Config, Context, Error};
+use crate::filter::{builtin, cv2, Filter, FrameType};
+use crate::sir::{DataExpr, Expr, FilterExpr, FrameExpr, FrameSource, IndexConst, ProcessSpan};
+use log::*;
+use rayon::prelude::*;
+use std::collections::BTreeMap;
+
+pub(crate) fn optimize(
+    process_span: &mut ProcessSpan,
+    context: &Context,
+    config: &Config,
+) -> Result<(), Error> {
+    let rewrites = process_span
+        .ts
+        .par_iter()
+        .zip(process_span.frames.par_iter_mut())
+        .map(|(t, frame)| {
+            let mut optimizer = Optimizer {
+                context,
+                config,
+                rewrites: BTreeMap::new(),
+            };
+            let optimized = optimizer.frame(frame)?;
+            if optimized != *frame {
+                debug!("Optimized frame at t={}: {} => {}", t, frame, optimized);
+                *frame = optimized;
+            }
+            Ok(optimizer.rewrites)
+        })
+        .try_reduce(BTreeMap::new, |mut a, b| {
+            for (rule, count) in b {
+                *a.entry(rule).or_default() += count;
+            }
+            Ok(a)
+        })?;
+
+    if !rewrites.is_empty() {
+        info!("Optimizer rewrites: {:?}", rewrites);
+    }
+
+    Ok(())
+}
+
+/// A rewritten frame, along with the name of the rule which rewrote it
+type Rewrite = (&'static str, FrameExpr);
+
+struct Optimizer<'a> {
+    context: &'a Context,
+    config: &'a Config,
+    rewrites: BTreeMap<&'static str, usize>,
+}
+
+impl Optimizer<'_> {
+    fn frame(&mut self, frame: &FrameExpr) -> Result<FrameExpr, Error> {
+        match frame {
+            FrameExpr::Source(s) => {
+                // Canonicalize all source references to timestamps, so equivalent references compare equal
+                let (_, t) = self.context.resolve_frame_source(s)?;
+                if s.index() == &IndexConst::T(t) {
+                    return Ok(frame.clone());
+                }
+                *self.rewrites.entry("canonicalize_source").or_default() += 1;
+                Ok(FrameExpr::Source(FrameSource::new(
+                    s.video().to_string(),
+                    IndexConst::T(t),
+                )))
+            }
+            FrameExpr::Filter(f) => {
+                let mut node = FilterExpr {
+                    name: f.name.clone(),
+                    args: f
+                        .args
+                        .iter()
+                        .map(|arg| self.expr(arg))
+                        .collect::<Result<_, _>>()?,
+                    kwargs: f
+                        .kwargs
+                        .iter()
+                        .map(|(k, v)| Ok((k.clone(), self.expr(v)?)))
+                        .collect::<Result<_, Error>>()?,
+                };
+
+                // Children are already optimized, so keep rewriting this node until no rule applies
+                loop {
+                    let (rule, rewritten) = match self.rewrite(&node)? {
+                        Some(rewrite) => rewrite,
+                        None => return Ok(FrameExpr::Filter(node)),
+                    };
+                    let original = FrameExpr::Filter(node);
+                    if !self.same_type(&original, &rewritten) {
+                        warn!(
+                            "Skipping `{}` rewrite which changes the frame type: {} => {}",
+                            rule, original, rewritten
+                        );
+                        return Ok(original);
+                    }
+                    *self.rewrites.entry(rule).or_default() += 1;
+                    match rewritten {
+                        FrameExpr::Filter(f) => node = f,
+                        source => return Ok(source),
+                    }
+                }
+            }
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) -> Result<Expr, Error> {
+        match expr {
+            Expr::Frame(frame) => Ok(Expr::Frame(self.frame(frame)?)),
+            Expr::Data(DataExpr::List(list)) => Ok(Expr::Data(DataExpr::List(
+                list.iter()
+                    .map(|item| self.expr(item))
+                    .collect::<Result<_, _>>()?,
+            ))),
+            Expr::Data(data) => Ok(Expr::Data(data.clone())),
+        }
+    }
+
+    fn frame_type(&self, frame: &FrameExpr) -> Result<FrameType, Error> {
+        type_frame(self.context, self.config, frame)
+    }
+
+    fn same_type(&self, a: &FrameExpr, b: &FrameExpr) -> bool {
+        match (self.frame_type(a), self.frame_type(b)) {
+            (Ok(a), Ok(b)) => a == b,
+            _ => false,
+        }
+    }
+
+    /// Whether the context's filter named `name` is `F`
+    fn is<F: Filter>(&self, name: &str) -> bool {
+        self.context.filters.get(name).is_some_and(|filter| {
+            let filter: &dyn std::any::Any = filter.as_ref();
+            filter.is::<F>()
+        })
+    }
+
+    fn rewrite(&self, f: &FilterExpr) -> Result<Option<Rewrite>, Error> {
+        let name = f.name.as_str();
+        match name {
+            "Scale" if self.is::<builtin::Scale>(name) => self.rewrite_scale(f),
+            "_slice_mat" if self.is::<builtin::SliceMat>(name) => self.rewrite_slice(f),
+            "_slice_write_mat" if self.is::<builtin::SliceWriteMat>(name) => {
+                self.rewrite_slice_write(f)
+            }
+            "cv2.rectangle" if self.is::<cv2::Rectangle>(name) => self.rewrite_rectangle(f),
+            "cv2.addWeighted" if self.is::<cv2::AddWeighted>(name) => self.rewrite_add_weighted(f),
+            _ => Ok(None),
+        }
+    }
+
+    /// Drops `Scale` kwargs which match the input and folds no-op scales
+    ///
+    /// `Scale(Scale(x))` is left alone, since scaling twice doesn't give the same pixels as scaling once.
+    fn rewrite_scale(&self, f: &FilterExpr) -> Result<Option<Rewrite>, Error> {
+        let (input, size, pix_fmt) = match scale_parts(f) {
+            Some(parts) => parts,
+            None => return Ok(None),
+        };
+        let input_type = self.frame_type(input)?;
+
+        let mut kwargs = f.kwargs.clone();
+        if size == Some((input_type.width, input_type.height)) {
+            kwargs.remove("width");
+            kwargs.remove("height");
+        }
+        if let Some(pix_fmt) = pix_fmt {
+            if crate::util::pixel_fmt_str_to_av_pix_fmt(pix_fmt) == Ok(input_type.format) {
+                kwargs.remove("pix_fmt");
+            }
+        }
+
+        if kwargs.is_empty() {
+            return Ok(Some(("fold_scale", input.clone())));
+        }
+
+        if kwargs != f.kwargs {
+            return Ok(Some((
+                "canonicalize_scale",
+                FrameExpr::Filter(FilterExpr {
+                    name: f.name.clone(),
+                    args: f.args.clone(),
+                    kwargs,
+                }),
+            )));
+        }
+
+        Ok(None)
+    }
+
+    /// Folds `_slice_mat` of the whole frame
+    fn rewrite_slice(&self, f: &FilterExpr) -> Result<Option<Rewrite>, Error> {
+        let input = match (f.args.len(), frame_arg(f, 0)) {
+            (5, Some(input)) => input,
+            _ => return Ok(None),
+        };
+        let input_type = self.frame_type(input)?;
+        if covers_frame(f, 1, &input_type) {
+            return Ok(Some(("fold_slice", input.clone())));
+        }
+        Ok(None)
+    }
+
+    /// A `_slice_write_mat` over the whole frame is just the written frame
+    fn rewrite_slice_write(&self, f: &FilterExpr) -> Result<Option<Rewrite>, Error> {
+        let (input, written) = match (f.args.len(), frame_arg(f, 0), frame_arg(f, 1)) {
+            (6, Some(input), Some(written)) => (input, written),
+            _ => return Ok(None),
+        };
+        let input_type = self.frame_type(input)?;
+        if covers_frame(f, 2, &input_type) {
+            return Ok(Some(("drop_overwritten", written.clone())));
+        }
+        Ok(None)
+    }
+
+    /// A filled, aliased `cv2.rectangle` over the whole frame doesn't need the frame it draws on
+    fn rewrite_rectangle(&self, f: &FilterExpr) -> Result<Option<Rewrite>, Error> {
+        let (input, pt1, pt2) = match (frame_arg(f, 0), point_arg(f, 1), point_arg(f, 2)) {
+            (Some(input), Some(pt1), Some(pt2)) => (input, pt1, pt2),
+            _ => return Ok(None),
+        };
+        let thickness = int_arg(f, 4, "thickness").unwrap_or(1);
+        let shift = int_arg(f, 6, "shift").unwrap_or(0);
+        // Anti-aliased edges are blended with the frame underneath
+        let line_type = int_arg(f, 5, "lineType").unwrap_or(LINE_8);
+        if thickness >= 0 || shift != 0 || line_type == LINE_AA || is_black(input) {
+            return Ok(None);
+        }
+
+        let input_type = self.frame_type(input)?;
+        let covers = pt1.0.min(pt2.0) <= 0
+            && pt1.1.min(pt2.1) <= 0
+            && pt1.0.max(pt2.0) >= input_type.width as i64 - 1
+            && pt1.1.max(pt2.1) >= input_type.height as i64 - 1;
+        if !covers {
+            return Ok(None);
+        }
+
+        Ok(self.black(&input_type).map(|black| {
+            let mut args = f.args.clone();
+            args[0] = Expr::Frame(black);
+            (
+                "drop_overwritten",
+                FrameExpr::Filter(FilterExpr {
+                    name: f.name.clone(),
+                    args,
+                    kwargs: f.kwargs.clone(),
+                }),
+            )
+        }))
+    }
+
+    /// An input to `cv2.addWeighted` with a weight of zero doesn't contribute to the output
+    fn rewrite_add_weighted(&self, f: &FilterExpr) -> Result<Option<Rewrite>, Error> {
+        if f.args.len() != 5 {
+            return Ok(None);
+        }
+
+        let mut args = f.args.clone();
+        for (frame_idx, weight_idx) in [(0, 1), (2, 3)] {
+            let input = match frame_arg(f, frame_idx) {
+                Some(input) if !is_black(input) => input,
+                _ => continue,
+            };
+            if float_arg(f, weight_idx) != Some(0.0) {
+                continue;
+            }
+            if let Some(black) = self.black(&self.frame_type(input)?) {
+                args[frame_idx] = Expr::Frame(black);
+            }
+        }
+
+        if args == f.args {
+            return Ok(None);
+        }
+        Ok(Some((
+            "drop_overwritten",
+            FrameExpr::Filter(FilterExpr {
+                name: f.name.clone(),
+                args,
+                kwargs: f.kwargs.clone(),
+            }),
+        )))
+    }
+
+    /// A `_black` frame of the given type, which stands in for frames whose pixels are never used
+    fn black(&self, frame_type: &FrameType) -> Option<FrameExpr> {
+        if !self.is::<builtin::Black>("_black") {
+            return None;
+        }
+        let pix_fmt = crate::util::av_pix_fmt_to_pixel_fmt_str(frame_type.format)?;
+        let mut kwargs = BTreeMap::new();
+        kwargs.insert(
+            "width".to_string(),
+            Expr::Data(DataExpr::Int(frame_type.width as i64)),
+        );
+        kwargs.insert(
+            "height".to_string(),
+            Expr::Data(DataExpr::Int(frame_type.height as i64)),
+        );
+        kwargs.insert("pix_fmt".to_string(), Expr::Data(DataExpr::String(pix_fmt)));
+        Some(FrameExpr::Filter(FilterExpr {
+            name: "_black".to_string(),
+            args: vec![],
+            kwargs,
+        }))
+    }
+}
+
+/// OpenCV's default `lineType`
+const LINE_8: i64 = 8;
+/// OpenCV's anti-aliased `lineType`
+const LINE_AA: i64 = 16;
+
+type ScaleParts<'a> = (&'a FrameExpr, Option<(usize, usize)>, Option<&'a str>);
+
+/// Splits a well-formed `Scale` into its input, size, and pixel format
+fn scale_parts(f: &FilterExpr) -> Option<ScaleParts<'_>> {
+    if f.name != "Scale" || f.args.len() != 1 {
+        return None;
+    }
+    let input = frame_arg(f, 0)?;
+    let mut width = None;
+    let mut height = None;
+    let mut pix_fmt = None;
+    for (k, v) in &f.kwargs {
+        match (k.as_str(), v) {
+            ("width", Expr::Data(DataExpr::Int(w))) if *w > 0 => width = Some(*w as usize),
+            ("height", Expr::Data(DataExpr::Int(h))) if *h > 0 => height = Some(*h as usize),
+            ("pix_fmt", Expr::Data(DataExpr::String(s))) => pix_fmt = Some(s.as_str()),
+            _ => return None,
+        }
+    }
+    let size = match (width, height) {
+        (Some(w), Some(h)) => Some((w, h)),
+        (None, None) => None,
+        _ => return None,
+    };
+    Some((input, size, pix_fmt))
+}
+
+fn frame_arg(f: &FilterExpr, idx: usize) -> Option<&FrameExpr> {
+    match f.args.get(idx) {
+        Some(Expr::Frame(frame)) => Some(frame),
+        _ => None,
+    }
+}
+
+fn int_arg(f: &FilterExpr, idx: usize, name: &str) -> Option<i64> {
+    match f.args.get(idx).or_else(|| f.kwargs.get(name)) {
+        Some(Expr::Data(DataExpr::Int(i))) => Some(*i),
+        _ => None,
+    }
+}
+
+fn float_arg(f: &FilterExpr, idx: usize) -> Option<f64> {
+    match f.args.get(idx) {
+        Some(Expr::Data(DataExpr::Float(x))) => Some(*x),
+        Some(Expr::Data(DataExpr::Int(i))) => Some(*i as f64),
+        _ => None,
+    }
+}
+
+fn point_arg(f: &FilterExpr, idx: usize) -> Option<(i64, i64)> {
+    match f.args.get(idx) {
+        Some(Expr::Data(DataExpr::List(list))) => match list.as_slice() {
+            [Expr::Data(DataExpr::Int(x)), Expr::Data(DataExpr::Int(y))] => Some((*x, *y)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Whether the `miny, maxy, minx, maxx` args starting at `idx` are the whole frame
+fn covers_frame(f: &FilterExpr, idx: usize, frame_type: &FrameType) -> bool {
+    let bound = |i| match f.args.get(idx + i) {
+        Some(Expr::Data(DataExpr::Int(v))) => Some(*v),
+        _ => None,
+    };
+    bound(0) == Some(0)
+        && bound(1) == Some(frame_type.height as i64)
+        && bound(2) == Some(0)
+        && bound(3) == Some(frame_type.width as i64)
+}
+
+fn is_black(frame: &FrameExpr) -> bool {
+    matches!(frame, FrameExpr::Filter(f) if f.name == "_black")
+}
@@ -1,5 +1,6 @@
 use crate::dve::IFrameRef;
 use crate::dve::{AVFrame, Config, Context, SourceRef};
+use num_rational::Rational64;
 use std::fmt::Debug;
 use std::{
     collections::{BTreeMap, BTreeSet},
@@ -8,17 +9,30 @@ use std::{
 
 const F_NOT_USED: usize = usize::MAX;
 
+/// Expected size of a decoded frame from a source, assuming 4 bytes per pixel if its pixel format is unknown
+pub(crate) fn expected_frame_bytes(source: &crate::source::SourceVideoStreamMeta) -> usize {
+    let (width, height) = source.resolution;
+    crate::util::image_size_bytes(&source.pix_fmt, width, height).unwrap_or(width * height * 4)
+}
+
 pub(crate) struct Pool {
     done_gens_recent: BTreeSet<usize>,
     done_gens_past: usize, // If a generation is less than this it is done
     next_gen: usize,
     members: BTreeMap<IFrameRef, Arc<AVFrame>>,
+    member_bytes: usize,
     pub(crate) decoders: BTreeMap<String, crate::dve::DecoderState>,
     pub(crate) finished_unjoined_decoders: BTreeSet<String>,
     pub(crate) terminate_decoders: bool,
+    /// Frames taken from the context's frame cache
+    pub(crate) frame_cache_hits: usize,
 
     iframes_per_oframe: Vec<BTreeSet<IFrameRef>>,
     iframe_refs_in_out_idx: BTreeMap<IFrameRef, BTreeSet<usize>>,
+    /// Earlier gens each gen has to wait for, since it takes their pinned filter outputs from the filter cache
+    waits_on: Vec<Vec<usize>>,
+    /// Expected size of a decoded frame from each source, used when a byte budget is set
+    frame_bytes: BTreeMap<SourceRef, usize>,
     dve_context: Arc<Context>,
     dve_config: Arc<Config>,
 }
@@ -27,6 +41,7 @@ impl Pool {
     pub(crate) fn new(
         iframes_per_oframe: Vec<BTreeSet<IFrameRef>>,
         iframe_refs_in_out_idx: BTreeMap<IFrameRef, BTreeSet<usize>>,
+        waits_on: Vec<Vec<usize>>,
         dve_context: Arc<Context>,
         dve_config: Arc<Config>,
     ) -> Result<Self, crate::Error> {
@@ -36,19 +51,43 @@ impl Pool {
             ));
         }
 
+        let mut frame_bytes = BTreeMap::new();
+        if dve_config.decode_pool_bytes.is_some() {
+            for (sourceref, source) in &dve_context.sources {
+                frame_bytes.insert(sourceref.clone(), expected_frame_bytes(source));
+            }
+        }
+
         let mut out = Pool {
             done_gens_recent: BTreeSet::new(),
             done_gens_past: 0,
             next_gen: 0,
             members: BTreeMap::new(),
+            member_bytes: 0,
             decoders: BTreeMap::new(),
             finished_unjoined_decoders: BTreeSet::new(),
             terminate_decoders: false,
+            frame_cache_hits: 0,
             iframes_per_oframe,
             iframe_refs_in_out_idx,
+            waits_on,
+            frame_bytes,
             dve_context,
             dve_config,
         };
+
+        if let Some(max_bytes) = out.dve_config.decode_pool_bytes {
+            for (gen, deps) in out.iframes_per_oframe.iter().enumerate() {
+                let bytes = out.estimated_bytes(deps.iter());
+                if bytes > max_bytes {
+                    return Err(crate::Error::ConfigError(format!(
+                        "Output frame {} needs about {} bytes of source frames, more than decode_pool_bytes ({})",
+                        gen, bytes, max_bytes
+                    )));
+                }
+            }
+        }
+
         while out.plan_gen() {}
         Ok(out)
     }
@@ -93,9 +132,10 @@ impl Pool {
         }
     }
 
-    pub(crate) fn new_decoder_gop(&self) -> Option<(SourceRef, usize)> {
+    /// The GOP to open a new decoder on, along with the soonest needed frame in it
+    pub(crate) fn new_decoder_gop(&self) -> Option<(SourceRef, usize, Rational64)> {
         debug_assert!(self.decoders.len() <= self.dve_config.decoders);
-        if self.decoders.len() == self.dve_config.decoders {
+        if self.at_decoder_limit() {
             return None;
         }
 
@@ -116,10 +156,55 @@ impl Pool {
 
         soonest_needed_basis_frame.map(|frame| {
             let gop_id = self.frame_gop(frame);
-            (frame.sourceref.clone(), gop_id)
+            (frame.sourceref.clone(), gop_id, frame.pts)
         })
     }
 
+    /// Whether another decoder can't be started, either by count or because its frames might not fit in the byte budget
+    fn at_decoder_limit(&self) -> bool {
+        if self.decoders.len() == self.dve_config.decoders {
+            return true;
+        }
+        match self.dve_config.decode_pool_bytes {
+            // Each decoder may be holding a frame which isn't in the pool yet
+            Some(max_bytes) if !self.decoders.is_empty() => {
+                let largest_frame = self.frame_bytes.values().max().copied().unwrap_or(0);
+                self.member_bytes + (self.decoders.len() + 1) * largest_frame > max_bytes
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether a frame of `bytes` can be added without evicting anything
+    fn has_room(&self, bytes: usize) -> bool {
+        self.members.len() < self.dve_config.decode_pool_size
+            && match self.dve_config.decode_pool_bytes {
+                Some(max_bytes) => self.member_bytes + bytes <= max_bytes,
+                None => true,
+            }
+    }
+
+    /// Bytes of the given frames, using actual sizes for members and expected sizes for the rest
+    fn estimated_bytes<'b>(&self, frames: impl Iterator<Item = &'b IFrameRef>) -> usize {
+        frames
+            .map(|frame| match self.members.get(frame) {
+                Some(avframe) => avframe.size_bytes(),
+                None => self.frame_bytes[&frame.sourceref],
+            })
+            .sum()
+    }
+
+    fn insert_member(&mut self, frame: IFrameRef, avframe: Arc<AVFrame>) {
+        self.member_bytes += avframe.size_bytes();
+        self.members.insert(frame, avframe);
+    }
+
+    fn remove_member(&mut self, frame: &IFrameRef) {
+        if let Some(avframe) = self.members.remove(frame) {
+            self.member_bytes -= avframe.size_bytes();
+        }
+    }
+
     fn eviction_set(
         &self,
         size: usize,
@@ -203,17 +288,24 @@ impl Pool {
         }
 
         let need_set: BTreeSet<IFrameRef> = self.need_set().into_iter().cloned().collect();
-
-        if need_set.contains(&frame) || self.members.len() < self.dve_config.decode_pool_size {
-            // If the pool is full evict a cache frame
-            if self.members.len() == self.dve_config.decode_pool_size {
+        let bytes = avframe.size_bytes();
+
+        if need_set.contains(&frame) || self.has_room(bytes) {
+            // If the pool is full evict cache frames
+            let mut evictable = self
+                .members
+                .keys()
+                .filter(|k| !need_set.contains(*k))
+                .count();
+            while !self.has_room(bytes) && evictable > 0 {
                 let evict_set = self.eviction_set(1, &need_set);
                 debug_assert_eq!(evict_set.len(), 1);
                 for frame_ts in evict_set {
-                    self.members.remove(&frame_ts);
+                    self.remove_member(&frame_ts);
                 }
+                evictable -= 1;
             }
-            self.members.insert(frame, avframe);
+            self.insert_member(frame, avframe);
         } else {
             // See if we can evict a frame to make room
             let f_next_need = self.next_needed_gen(&frame);
@@ -236,10 +328,17 @@ impl Pool {
                     }
                 }
 
-                if f_next_need < least_needed_pool_frame_next_needed {
-                    log::info!("Evicting frame {:?} (needed in gen {}) for sooner-needed frame {:?} (needed in gen {})", least_needed_pool_frame, least_needed_pool_frame_next_needed, frame, f_next_need);
-                    self.members.remove(&least_needed_pool_frame.unwrap());
-                    self.members.insert(frame, avframe);
+                if let Some(least_needed_pool_frame) = least_needed_pool_frame {
+                    let freed_bytes = self.members[&least_needed_pool_frame].size_bytes();
+                    let fits = match self.dve_config.decode_pool_bytes {
+                        Some(max_bytes) => self.member_bytes - freed_bytes + bytes <= max_bytes,
+                        None => true,
+                    };
+                    if f_next_need < least_needed_pool_frame_next_needed && fits {
+                        log::info!("Evicting frame {:?} (needed in gen {}) for sooner-needed frame {:?} (needed in gen {})", least_needed_pool_frame, least_needed_pool_frame_next_needed, frame, f_next_need);
+                        self.remove_member(&least_needed_pool_frame);
+                        self.insert_member(frame, avframe);
+                    }
                 }
             }
         }
@@ -248,7 +347,7 @@ impl Pool {
     }
 
     pub(crate) fn should_decoder_abandon(&self, decoder_id: &str) -> bool {
-        if self.decoders.len() < self.dve_config.decoders || !self.should_stall(decoder_id) {
+        if !self.at_decoder_limit() || !self.should_stall(decoder_id) {
             return false;
         }
 
@@ -315,6 +414,11 @@ impl Pool {
             // not enough space in the pool even with evictions
             return false;
         }
+        if let Some(max_bytes) = self.dve_config.decode_pool_bytes {
+            if self.estimated_bytes(next_need_set.iter()) > max_bytes {
+                return false;
+            }
+        }
 
         // Enough space, but check if we need to evict some frames first
         let members_not_in_need_set = self
@@ -329,13 +433,37 @@ impl Pool {
             debug_assert!(evict_set.len() == needed_evictions);
             for frame_ts in evict_set {
                 debug_assert!(!next_need_set.contains(&frame_ts));
-                self.members.remove(&frame_ts);
+                self.remove_member(&frame_ts);
             }
         }
+        self.load_cached_frames(self.next_gen);
         self.next_gen += 1;
         true
     }
 
+    /// Take any frames of a newly planned gen which are in the frame cache, so they don't need decoding
+    fn load_cached_frames(&mut self, gen: usize) {
+        let frame_cache = match &self.dve_context.frame_cache {
+            Some(frame_cache) => frame_cache.clone(),
+            None => return,
+        };
+        let deps: Vec<IFrameRef> = self.iframes_per_oframe[gen]
+            .iter()
+            .filter(|frame| !self.members.contains_key(*frame))
+            .cloned()
+            .collect();
+        for frame in deps {
+            let fuid = match &self.dve_context.sources[&frame.sourceref].fuid {
+                Some(fuid) => fuid,
+                None => continue,
+            };
+            if let Some(cached) = frame_cache.get(fuid, &frame.pts) {
+                self.insert_member(frame, cached.into_avframe());
+                self.frame_cache_hits += 1;
+            }
+        }
+    }
+
     pub(crate) fn active_gens(&self) -> BTreeSet<usize> {
         let mut out = BTreeSet::new();
         for g in self.done_gens_past..self.next_gen {
@@ -371,6 +499,13 @@ impl Pool {
         self.iframes_per_oframe[gen]
             .iter()
             .all(|dep_frame| self.members.contains_key(dep_frame))
+            && self.waits_on[gen]
+                .iter()
+                .all(|other| self.is_gen_done(*other))
+    }
+
+    fn is_gen_done(&self, gen: usize) -> bool {
+        gen < self.done_gens_past || self.done_gens_recent.contains(&gen)
     }
 
     pub(crate) fn get_ready_gen_frames(&self, gen: usize) -> BTreeMap<IFrameRef, Arc<AVFrame>> {
@@ -381,6 +516,21 @@ impl Pool {
             .collect()
     }
 
+    /// The number of decoded frames currently held
+    pub(crate) fn member_count(&self) -> usize {
+        self.members.len()
+    }
+
+    /// The bytes of decoded frames currently held
+    pub(crate) fn member_bytes(&self) -> usize {
+        self.member_bytes
+    }
+
+    /// Every source frame used by some output frame
+    pub(crate) fn iframe_refs(&self) -> impl Iterator<Item = &IFrameRef> {
+        self.iframe_refs_in_out_idx.keys()
+    }
+
     pub(crate) fn need_set(&self) -> BTreeSet<&IFrameRef> {
         let mut out = BTreeSet::new();
         for gen in self.done_gens_past..self.next_gen {
@@ -3,21 +3,205 @@
 //! The SIR is a high-level representation of constructing a video frame from source frames and data.
 //! This is the primary interface between specs and the rest of the system.
 
+pub mod builder;
+
 use crate::dve::Range;
 use num_rational::Rational64;
+use rayon::prelude::*;
 use std::collections::BTreeMap;
 use std::collections::BTreeSet;
 use std::fmt;
 use std::fmt::Display;
 use std::fmt::Formatter;
+use std::hash::Hash;
+use std::hash::Hasher;
 
-#[derive(Ord, PartialOrd, PartialEq, Eq, Debug, serde::Deserialize, serde::Serialize, Clone)]
+#[derive(
+    Ord, PartialOrd, PartialEq, Eq, Hash, Debug, serde::Deserialize, serde::Serialize, Clone,
+)]
 pub enum IndexConst {
     ILoc(usize),
     T(Rational64),
+    /// A SMPTE timecode, resolved against the source's start timecode
+    Timecode(Timecode),
+}
+
+/// A SMPTE timecode (`HH:MM:SS:FF`, or `HH:MM:SS;FF` for drop-frame)
+///
+/// Timecodes are serialized in their string form.
+#[derive(
+    Ord, PartialOrd, PartialEq, Eq, Hash, Debug, serde::Deserialize, serde::Serialize, Clone,
+)]
+#[serde(try_from = "String", into = "String")]
+pub struct Timecode {
+    pub hours: u32,
+    pub minutes: u32,
+    pub seconds: u32,
+    pub frames: u32,
+    pub drop_frame: bool,
+}
+
+impl Timecode {
+    /// Returns the frame number of this timecode, counting from `00:00:00:00`
+    ///
+    /// `rate` is the actual frame rate of the video (e.g., 30000/1001).
+    /// Drop-frame timecodes are only valid at 29.97 and 59.94 fps (and their multiples).
+    pub fn frame_number(&self, rate: &Rational64) -> Result<i64, String> {
+        let fps = Self::nominal_fps(rate)?;
+        if self.minutes >= 60 || self.seconds >= 60 {
+            return Err(format!("Invalid timecode `{}`", self));
+        }
+        if self.frames as i64 >= fps {
+            return Err(format!(
+                "Invalid timecode `{}`: frame count exceeds {} fps",
+                self, fps
+            ));
+        }
+
+        let total_minutes = 60 * self.hours as i64 + self.minutes as i64;
+        let frame_number = (60 * total_minutes + self.seconds as i64) * fps + self.frames as i64;
+        if !self.drop_frame {
+            return Ok(frame_number);
+        }
+
+        // Drop-frame skips the first frame numbers of every minute, except every tenth minute
+        let dropped = Self::dropped_per_minute(rate, fps)?;
+        if self.seconds == 0 && !self.minutes.is_multiple_of(10) && (self.frames as i64) < dropped {
+            return Err(format!(
+                "Invalid timecode `{}`: frame number is dropped in drop-frame timecode",
+                self
+            ));
+        }
+        Ok(frame_number - dropped * (total_minutes - total_minutes / 10))
+    }
+
+    /// Creates a timecode from a frame number, counting from `00:00:00:00`
+    pub fn from_frame_number(
+        frame_number: i64,
+        rate: &Rational64,
+        drop_frame: bool,
+    ) -> Result<Self, String> {
+        if frame_number < 0 {
+            return Err(format!("Negative frame number {}", frame_number));
+        }
+        let fps = Self::nominal_fps(rate)?;
+
+        let mut frame_number = frame_number;
+        if drop_frame {
+            let dropped = Self::dropped_per_minute(rate, fps)?;
+            let frames_per_10_minutes = 600 * fps - 9 * dropped;
+            let frames_per_minute = 60 * fps - dropped;
+
+            let tens = frame_number / frames_per_10_minutes;
+            let rem = frame_number % frames_per_10_minutes;
+            frame_number += 9 * dropped * tens;
+            if rem >= dropped {
+                frame_number += dropped * ((rem - dropped) / frames_per_minute);
+            }
+        }
+
+        Ok(Timecode {
+            hours: (frame_number / (3600 * fps)) as u32,
+            minutes: (frame_number / (60 * fps) % 60) as u32,
+            seconds: (frame_number / fps % 60) as u32,
+            frames: (frame_number % fps) as u32,
+            drop_frame,
+        })
+    }
+
+    /// The number of frames in 24 hours of timecode
+    pub fn frames_per_day(rate: &Rational64, drop_frame: bool) -> Result<i64, String> {
+        let fps = Self::nominal_fps(rate)?;
+        if drop_frame {
+            let dropped = Self::dropped_per_minute(rate, fps)?;
+            Ok(24 * 6 * (600 * fps - 9 * dropped))
+        } else {
+            Ok(24 * 3600 * fps)
+        }
+    }
+
+    /// The integer frame rate timecodes count in (e.g., 30 for 29.97 fps)
+    fn nominal_fps(rate: &Rational64) -> Result<i64, String> {
+        let fps = rate.round().to_integer();
+        if *rate.denom() <= 0 || fps <= 0 {
+            return Err(format!("Invalid timecode frame rate {}", rate));
+        }
+        Ok(fps)
+    }
+
+    fn dropped_per_minute(rate: &Rational64, fps: i64) -> Result<i64, String> {
+        if fps % 30 != 0 || *rate == Rational64::from_integer(fps) {
+            return Err(format!(
+                "Drop-frame timecode is not defined for {} fps",
+                rate
+            ));
+        }
+        Ok(fps / 15)
+    }
+}
+
+impl std::str::FromStr for Timecode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || format!("Invalid timecode `{}`", s);
+        // `;` (or `.` / `,` on some systems) before the frame field marks drop-frame
+        let (hms, frames, drop_frame) = match s.rfind([':', ';', '.', ',']) {
+            Some(idx) => (&s[..idx], &s[idx + 1..], &s[idx..idx + 1] != ":"),
+            None => return Err(err()),
+        };
+        let hms: Vec<&str> = hms.split(':').collect();
+        if hms.len() != 3 {
+            return Err(err());
+        }
+        let field = |f: &str| {
+            if f.is_empty() || !f.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(err());
+            }
+            f.parse::<u32>().map_err(|_| err())
+        };
+
+        Ok(Timecode {
+            hours: field(hms[0])?,
+            minutes: field(hms[1])?,
+            seconds: field(hms[2])?,
+            frames: field(frames)?,
+            drop_frame,
+        })
+    }
+}
+
+impl TryFrom<String> for Timecode {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<Timecode> for String {
+    fn from(tc: Timecode) -> Self {
+        tc.to_string()
+    }
+}
+
+impl Display for Timecode {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:02}:{:02}:{:02}{}{:02}",
+            self.hours,
+            self.minutes,
+            self.seconds,
+            if self.drop_frame { ';' } else { ':' },
+            self.frames
+        )
+    }
 }
 
-#[derive(Ord, PartialOrd, PartialEq, Eq, Debug, serde::Deserialize, serde::Serialize, Clone)]
+#[derive(
+    Ord, PartialOrd, PartialEq, Eq, Hash, Debug, serde::Deserialize, serde::Serialize, Clone,
+)]
 pub struct FrameSource {
     pub(crate) video: String,
     pub(crate) index: IndexConst,
@@ -53,8 +237,8 @@ pub enum Expr {
 impl Display for Expr {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         match self {
-            Expr::Frame(frame) => write!(f, "{}", frame),
-            Expr::Data(data) => write!(f, "{}", data),
+            Expr::Frame(frame) => Display::fmt(frame, f),
+            Expr::Data(data) => Display::fmt(data, f),
         }
     }
 }
@@ -98,16 +282,15 @@ impl Display for DataExpr {
         match self {
             DataExpr::Bool(b) => write!(f, "{}", b),
             DataExpr::Int(i) => write!(f, "{}", i),
-            DataExpr::String(s) => write!(f, "\"{}\"", s),
+            DataExpr::String(s) => write_str_literal(f, s),
+            DataExpr::Bytes(b) if f.alternate() => write_bytes_literal(f, b),
             DataExpr::Bytes(b) => write!(f, "<{} bytes>", b.len()),
-            DataExpr::Float(n) => write!(f, "{}", n),
+            // Debug formatting always includes a `.` or exponent, which keeps floats distinct from ints
+            DataExpr::Float(n) => write!(f, "{:?}", n),
             DataExpr::List(list) => {
                 write!(f, "[")?;
                 for (idx, item) in list.iter().enumerate() {
-                    match item {
-                        Expr::Frame(frame) => write!(f, "{}", frame)?,
-                        Expr::Data(data) => write!(f, "{}", data)?,
-                    }
+                    Display::fmt(item, f)?;
                     if idx < list.len() - 1 {
                         write!(f, ", ")?;
                     }
@@ -128,15 +311,21 @@ impl Display for FrameExpr {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         match self {
             FrameExpr::Source(src) => {
-                write!(f, "{}{}", src.video, src.index)
+                write_name(f, &src.video, false)?;
+                write!(f, "{}", src.index)
             }
             FrameExpr::Filter(filter) => {
-                write!(f, "{}(", filter.name)?;
+                write_name(f, &filter.name, true)?;
+                write!(f, "(")?;
                 for arg in &filter.args {
-                    write!(f, "{}, ", arg)?;
+                    Display::fmt(arg, f)?;
+                    write!(f, ", ")?;
                 }
                 for (k, v) in &filter.kwargs {
-                    write!(f, "{}={}, ", k, v)?;
+                    write_name(f, k, false)?;
+                    write!(f, "=")?;
+                    Display::fmt(v, f)?;
+                    write!(f, ", ")?;
                 }
                 write!(f, ")")
             }
@@ -149,8 +338,548 @@ impl Display for IndexConst {
         match self {
             IndexConst::ILoc(i) => write!(f, ".iloc[{}]", i),
             IndexConst::T(t) => write!(f, "[{}]", t),
+            IndexConst::Timecode(tc) => write!(f, ".tc[{}]", tc),
+        }
+    }
+}
+
+// Text form
+//
+// The alternate `Display` output (`{:#}`) of `FrameExpr`, `Expr`, `DataExpr`, and `IndexConst` is a stable text format which parses back with `FromStr`.
+// Plain `Display` is the same, except bytes are summarized as `<N bytes>` so logs stay readable:
+//
+// frame  := source | filter
+// source := (IDENT | STRING) index
+// index  := '.iloc[' INT ']' | '[' INT ['/' INT] ']' | '.tc[' TIMECODE ']'
+// filter := (IDENT ('.' IDENT)* | STRING) '(' [arg (',' arg)* [',']] ')'
+// arg    := [(IDENT | STRING) '='] expr
+// expr   := frame | data
+// data   := 'true' | 'false' | INT | FLOAT | STRING | BYTES | '[' [expr (',' expr)* [',']] ']'
+//
+// Names which aren't plain identifiers are written as strings.
+// Strings support the `\"`, `\\`, `\n`, `\r`, `\t`, and `\u{XXXX}` escapes.
+// Bytes are written as `b"..."`, with `\xNN` escapes for non-printable bytes.
+// Floats always contain a `.` or exponent (or are `NaN`, `inf`, or `-inf`).
+
+fn is_ident(s: &str) -> bool {
+    let mut chars = s.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn write_name(f: &mut Formatter, name: &str, dotted: bool) -> fmt::Result {
+    let bare = if dotted {
+        name.split('.').all(is_ident)
+    } else {
+        is_ident(name)
+    };
+    if bare {
+        write!(f, "{}", name)
+    } else {
+        write_str_literal(f, name)
+    }
+}
+
+fn write_str_literal(f: &mut Formatter, s: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if c.is_control() => write!(f, "\\u{{{:x}}}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+fn write_bytes_literal(f: &mut Formatter, b: &[u8]) -> fmt::Result {
+    write!(f, "b\"")?;
+    for byte in b {
+        match byte {
+            b'"' => write!(f, "\\\"")?,
+            b'\\' => write!(f, "\\\\")?,
+            0x20..=0x7e => write!(f, "{}", *byte as char)?,
+            _ => write!(f, "\\x{:02x}", byte)?,
         }
     }
+    write!(f, "\"")
+}
+
+/// An error parsing the text form of a SIR expression
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[error("{message} at line {line}, column {column}")]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn new(s: &str) -> Self {
+        Parser {
+            chars: s.chars().collect(),
+            pos: 0,
+        }
+    }
+
+    fn error_at(&self, pos: usize, message: String) -> ParseError {
+        let mut line = 1;
+        let mut column = 1;
+        for c in &self.chars[..pos] {
+            if *c == '\n' {
+                line += 1;
+                column = 1;
+            } else {
+                column += 1;
+            }
+        }
+        ParseError {
+            line,
+            column,
+            message,
+        }
+    }
+
+    fn error(&self, message: String) -> ParseError {
+        self.error_at(self.pos, message)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek();
+        if c.is_some() {
+            self.pos += 1;
+        }
+        c
+    }
+
+    fn skip_ws(&mut self) {
+        while matches!(self.peek(), Some(c) if c.is_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), ParseError> {
+        self.skip_ws();
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.pos += 1;
+                Ok(())
+            }
+            Some(c) => Err(self.error(format!("Expected `{}`, found `{}`", expected, c))),
+            None => Err(self.error(format!("Expected `{}`, found end of input", expected))),
+        }
+    }
+
+    fn finish(&mut self) -> Result<(), ParseError> {
+        self.skip_ws();
+        match self.peek() {
+            None => Ok(()),
+            Some(c) => Err(self.error(format!("Unexpected `{}` after expression", c))),
+        }
+    }
+
+    fn ident(&mut self) -> Result<String, ParseError> {
+        let start = self.pos;
+        match self.peek() {
+            Some(c) if c.is_ascii_alphabetic() || c == '_' => {}
+            Some(c) => return Err(self.error(format!("Expected an identifier, found `{}`", c))),
+            None => return Err(self.error("Expected an identifier".to_string())),
+        }
+        while matches!(self.peek(), Some(c) if c.is_ascii_alphanumeric() || c == '_') {
+            self.pos += 1;
+        }
+        Ok(self.chars[start..self.pos].iter().collect())
+    }
+
+    fn dotted_ident(&mut self) -> Result<String, ParseError> {
+        let mut name = self.ident()?;
+        while self.peek() == Some('.')
+            && matches!(self.peek_at(1), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        {
+            self.pos += 1;
+            name.push('.');
+            name.push_str(&self.ident()?);
+        }
+        Ok(name)
+    }
+
+    fn escape(&mut self, in_bytes: bool) -> Result<u32, ParseError> {
+        // The `\\` has already been consumed
+        let start = self.pos - 1;
+        match self.bump() {
+            Some('"') => Ok('"' as u32),
+            Some('\\') => Ok('\\' as u32),
+            Some('n') => Ok('\n' as u32),
+            Some('r') => Ok('\r' as u32),
+            Some('t') => Ok('\t' as u32),
+            Some('x') if in_bytes => {
+                let hex: String = (0..2).filter_map(|_| self.bump()).collect();
+                u32::from_str_radix(&hex, 16)
+                    .ok()
+                    .filter(|_| hex.len() == 2)
+                    .ok_or_else(|| self.error_at(start, format!("Invalid escape `\\x{}`", hex)))
+            }
+            Some('u') if !in_bytes => {
+                self.expect('{')?;
+                let hex_start = self.pos;
+                while matches!(self.peek(), Some(c) if c.is_ascii_hexdigit()) {
+                    self.pos += 1;
+                }
+                let hex: String = self.chars[hex_start..self.pos].iter().collect();
+                self.expect('}')?;
+                u32::from_str_radix(&hex, 16)
+                    .ok()
+                    .filter(|c| char::from_u32(*c).is_some())
+                    .ok_or_else(|| self.error_at(start, format!("Invalid escape `\\u{{{}}}`", hex)))
+            }
+            Some(c) => Err(self.error_at(start, format!("Invalid escape `\\{}`", c))),
+            None => Err(self.error("Unterminated string".to_string())),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, ParseError> {
+        self.expect('"')?;
+        let mut out = String::new();
+        loop {
+            match self.bump() {
+                Some('"') => return Ok(out),
+                Some('\\') => out.push(char::from_u32(self.escape(false)?).unwrap()),
+                Some(c) => out.push(c),
+                None => return Err(self.error("Unterminated string".to_string())),
+            }
+        }
+    }
+
+    fn bytes(&mut self) -> Result<Vec<u8>, ParseError> {
+        self.expect('b')?;
+        self.expect('"')?;
+        let mut out = Vec::new();
+        loop {
+            let start = self.pos;
+            match self.bump() {
+                Some('"') => return Ok(out),
+                Some('\\') => out.push(self.escape(true)? as u8),
+                Some(c) if (' '..='~').contains(&c) => out.push(c as u8),
+                Some(c) => {
+                    return Err(self.error_at(start, format!("Invalid character `{}` in bytes", c)))
+                }
+                None => return Err(self.error("Unterminated bytes".to_string())),
+            }
+        }
+    }
+
+    fn integer(&mut self) -> Result<i64, ParseError> {
+        self.skip_ws();
+        let start = self.pos;
+        if self.peek() == Some('-') {
+            self.pos += 1;
+        }
+        while matches!(self.peek(), Some(c) if c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        let text: String = self.chars[start..self.pos].iter().collect();
+        text.parse()
+            .map_err(|_| self.error_at(start, format!("Invalid integer `{}`", text)))
+    }
+
+    fn number(&mut self) -> Result<DataExpr, ParseError> {
+        let start = self.pos;
+        if self.peek() == Some('-') {
+            self.pos += 1;
+            if self.peek() == Some('i') {
+                let ident = self.ident()?;
+                if ident == "inf" {
+                    return Ok(DataExpr::Float(f64::NEG_INFINITY));
+                }
+                return Err(self.error_at(start, format!("Invalid number `-{}`", ident)));
+            }
+        }
+        let mut is_float = false;
+        while let Some(c) = self.peek() {
+            if c.is_ascii_digit() {
+                self.pos += 1;
+            } else if c == '.' || c == 'e' || c == 'E' {
+                is_float = true;
+                self.pos += 1;
+                if (c == 'e' || c == 'E') && matches!(self.peek(), Some('-') | Some('+')) {
+                    self.pos += 1;
+                }
+            } else {
+                break;
+            }
+        }
+        let text: String = self.chars[start..self.pos].iter().collect();
+        let invalid = || self.error_at(start, format!("Invalid number `{}`", text));
+        if is_float {
+            text.parse().map(DataExpr::Float).map_err(|_| invalid())
+        } else {
+            text.parse().map(DataExpr::Int).map_err(|_| invalid())
+        }
+    }
+
+    fn index_body(&mut self, kind: &str) -> Result<IndexConst, ParseError> {
+        self.expect('[')?;
+        let index = match kind {
+            "iloc" => {
+                let start = self.pos;
+                let i = self.integer()?;
+                IndexConst::ILoc(
+                    usize::try_from(i)
+                        .map_err(|_| self.error_at(start, format!("Invalid iloc index `{}`", i)))?,
+                )
+            }
+            "tc" => {
+                self.skip_ws();
+                let start = self.pos;
+                while matches!(self.peek(), Some(c) if c != ']') {
+                    self.pos += 1;
+                }
+                let text: String = self.chars[start..self.pos].iter().collect();
+                IndexConst::Timecode(
+                    text.trim()
+                        .parse()
+                        .map_err(|e: String| self.error_at(start, e))?,
+                )
+            }
+            _ => {
+                let start = self.pos;
+                let numer = self.integer()?;
+                self.skip_ws();
+                let denom = if self.peek() == Some('/') {
+                    self.pos += 1;
+                    self.integer()?
+                } else {
+                    1
+                };
+                if denom == 0 {
+                    return Err(self.error_at(start, "Zero denominator in timestamp".to_string()));
+                }
+                IndexConst::T(Rational64::new(numer, denom))
+            }
+        };
+        self.expect(']')?;
+        Ok(index)
+    }
+
+    fn index(&mut self) -> Result<IndexConst, ParseError> {
+        self.skip_ws();
+        if self.peek() == Some('.') {
+            self.pos += 1;
+            let start = self.pos;
+            let kind = self.ident()?;
+            if kind != "iloc" && kind != "tc" {
+                return Err(self.error_at(start, format!("Unknown index `.{}`", kind)));
+            }
+            self.index_body(&kind)
+        } else {
+            self.index_body("t")
+        }
+    }
+
+    fn filter_args(&mut self, name: String) -> Result<FrameExpr, ParseError> {
+        self.expect('(')?;
+        let mut args = Vec::new();
+        let mut kwargs = BTreeMap::new();
+        loop {
+            self.skip_ws();
+            if self.peek() == Some(')') {
+                self.pos += 1;
+                break;
+            }
+
+            let arg_start = self.pos;
+            let key = match self.peek() {
+                Some('"') => Some(self.string()?),
+                Some(c) if c.is_ascii_alphabetic() || c == '_' => Some(self.ident()?),
+                _ => None,
+            };
+            self.skip_ws();
+            match key {
+                Some(key) if self.peek() == Some('=') => {
+                    self.pos += 1;
+                    let value = self.expr()?;
+                    if kwargs.insert(key.clone(), value).is_some() {
+                        return Err(self
+                            .error_at(arg_start, format!("Duplicate keyword argument `{}`", key)));
+                    }
+                }
+                _ => {
+                    self.pos = arg_start;
+                    let value = self.expr()?;
+                    if !kwargs.is_empty() {
+                        return Err(self.error_at(
+                            arg_start,
+                            "Positional argument after keyword argument".to_string(),
+                        ));
+                    }
+                    args.push(value);
+                }
+            }
+
+            self.skip_ws();
+            match self.bump() {
+                Some(',') => {}
+                Some(')') => break,
+                Some(c) => {
+                    self.pos -= 1;
+                    return Err(self.error(format!("Expected `,` or `)`, found `{}`", c)));
+                }
+                None => return Err(self.error("Unterminated argument list".to_string())),
+            }
+        }
+        Ok(FrameExpr::Filter(FilterExpr { name, args, kwargs }))
+    }
+
+    fn list(&mut self) -> Result<DataExpr, ParseError> {
+        self.expect('[')?;
+        let mut items = Vec::new();
+        loop {
+            self.skip_ws();
+            if self.peek() == Some(']') {
+                self.pos += 1;
+                break;
+            }
+            items.push(self.expr()?);
+            self.skip_ws();
+            match self.bump() {
+                Some(',') => {}
+                Some(']') => break,
+                Some(c) => {
+                    self.pos -= 1;
+                    return Err(self.error(format!("Expected `,` or `]`, found `{}`", c)));
+                }
+                None => return Err(self.error("Unterminated list".to_string())),
+            }
+        }
+        Ok(DataExpr::List(items))
+    }
+
+    fn expr(&mut self) -> Result<Expr, ParseError> {
+        self.skip_ws();
+        let start = self.pos;
+        match self.peek() {
+            Some('[') => Ok(Expr::Data(self.list()?)),
+            Some(c) if c == '-' || c.is_ascii_digit() => Ok(Expr::Data(self.number()?)),
+            Some('b') if self.peek_at(1) == Some('"') => {
+                Ok(Expr::Data(DataExpr::Bytes(self.bytes()?)))
+            }
+            Some('"') => {
+                let name = self.string()?;
+                self.skip_ws();
+                match self.peek() {
+                    Some('(') => Ok(Expr::Frame(self.filter_args(name)?)),
+                    Some('[') | Some('.') => Ok(Expr::Frame(FrameExpr::Source(FrameSource::new(
+                        name,
+                        self.index()?,
+                    )))),
+                    _ => Ok(Expr::Data(DataExpr::String(name))),
+                }
+            }
+            Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+                let name = self.dotted_ident()?;
+                self.skip_ws();
+                match self.peek() {
+                    Some('(') => Ok(Expr::Frame(self.filter_args(name)?)),
+                    Some('[') => {
+                        let (video, kind) = match name.rsplit_once('.') {
+                            Some((video, kind)) if kind == "iloc" || kind == "tc" => (video, kind),
+                            _ => (name.as_str(), "t"),
+                        };
+                        let index = self.index_body(kind)?;
+                        Ok(Expr::Frame(FrameExpr::Source(FrameSource::new(
+                            video.to_string(),
+                            index,
+                        ))))
+                    }
+                    _ => match name.as_str() {
+                        "true" => Ok(Expr::Data(DataExpr::Bool(true))),
+                        "false" => Ok(Expr::Data(DataExpr::Bool(false))),
+                        "NaN" => Ok(Expr::Data(DataExpr::Float(f64::NAN))),
+                        "inf" => Ok(Expr::Data(DataExpr::Float(f64::INFINITY))),
+                        _ => Err(self.error_at(
+                            start,
+                            format!("Expected a filter call or source index after `{}`", name),
+                        )),
+                    },
+                }
+            }
+            Some(c) => Err(self.error(format!("Expected an expression, found `{}`", c))),
+            None => Err(self.error("Expected an expression, found end of input".to_string())),
+        }
+    }
+}
+
+impl std::str::FromStr for Expr {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser::new(s);
+        let expr = parser.expr()?;
+        parser.finish()?;
+        Ok(expr)
+    }
+}
+
+impl std::str::FromStr for FrameExpr {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser::new(s);
+        parser.skip_ws();
+        let start = parser.pos;
+        match parser.expr()? {
+            Expr::Frame(frame) => {
+                parser.finish()?;
+                Ok(frame)
+            }
+            Expr::Data(_) => Err(parser.error_at(start, "Expected a frame expression".to_string())),
+        }
+    }
+}
+
+impl std::str::FromStr for DataExpr {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser::new(s);
+        parser.skip_ws();
+        let start = parser.pos;
+        match parser.expr()? {
+            Expr::Data(data) => {
+                parser.finish()?;
+                Ok(data)
+            }
+            Expr::Frame(_) => Err(parser.error_at(start, "Expected a data expression".to_string())),
+        }
+    }
+}
+
+impl std::str::FromStr for IndexConst {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser::new(s);
+        let index = parser.index()?;
+        parser.finish()?;
+        Ok(index)
+    }
 }
 
 impl FrameExpr {
@@ -170,6 +899,138 @@ impl FrameExpr {
             }
         }
     }
+
+    /// Structural hashes of the filter nodes in this expression, in render order
+    ///
+    /// Nodes are visited depth-first, with positional args before kwargs and list items in order.
+    /// Each entry is `(hash, descendants)`, where the `descendants` filter nodes below a node directly follow it.
+    /// Equal expressions have equal hashes. Different expressions can collide, which would make the filter cache return
+    /// the wrong output; expressions aren't compared on a hit, so that risk is accepted.
+    pub(crate) fn filter_hashes(&self) -> Vec<(u128, usize)> {
+        let mut out = Vec::new();
+        hash_frame(self, &mut out);
+        out
+    }
+
+    /// The filter nodes in this expression, in the same order as [`FrameExpr::filter_hashes`]
+    pub(crate) fn filter_nodes(&self) -> Vec<&FrameExpr> {
+        let mut out = Vec::new();
+        visit_frame(self, &mut out, &mut |_| false, &mut |_| {});
+        out
+    }
+
+    /// Like [`FrameExpr::add_source_deps`], but leaving out the sources below the filter nodes for which `skip` returns true
+    ///
+    /// `skip` is called with each filter node's index in [`FrameExpr::filter_hashes`] order.
+    pub(crate) fn add_source_deps_skipping<'a>(
+        &'a self,
+        mut skip: impl FnMut(usize) -> bool,
+        deps: &mut BTreeSet<&'a FrameSource>,
+    ) {
+        visit_frame(self, &mut Vec::new(), &mut skip, &mut |src| {
+            deps.insert(src);
+        });
+    }
+}
+
+/// Walks an expression in [`FrameExpr::filter_hashes`] order, collecting filter nodes and reporting the sources which aren't skipped over
+fn visit_frame<'a>(
+    frame: &'a FrameExpr,
+    nodes: &mut Vec<&'a FrameExpr>,
+    skip: &mut dyn FnMut(usize) -> bool,
+    source: &mut dyn FnMut(&'a FrameSource),
+) {
+    match frame {
+        FrameExpr::Source(src) => source(src),
+        FrameExpr::Filter(filter) => {
+            let idx = nodes.len();
+            nodes.push(frame);
+            if skip(idx) {
+                // Still count the nodes below, so later indices line up
+                for arg in filter.args.iter().chain(filter.kwargs.values()) {
+                    visit_expr(arg, nodes, &mut |_| false, &mut |_| {});
+                }
+            } else {
+                for arg in filter.args.iter().chain(filter.kwargs.values()) {
+                    visit_expr(arg, nodes, skip, source);
+                }
+            }
+        }
+    }
+}
+
+fn visit_expr<'a>(
+    expr: &'a Expr,
+    nodes: &mut Vec<&'a FrameExpr>,
+    skip: &mut dyn FnMut(usize) -> bool,
+    source: &mut dyn FnMut(&'a FrameSource),
+) {
+    match expr {
+        Expr::Frame(frame) => visit_frame(frame, nodes, skip, source),
+        Expr::Data(DataExpr::List(list)) => {
+            for item in list {
+                visit_expr(item, nodes, skip, source);
+            }
+        }
+        Expr::Data(_) => {}
+    }
+}
+
+fn hash128(write: impl Fn(&mut std::collections::hash_map::DefaultHasher)) -> u128 {
+    let mut lo = std::collections::hash_map::DefaultHasher::new();
+    let mut hi = std::collections::hash_map::DefaultHasher::new();
+    hi.write_u8(0xff);
+    write(&mut lo);
+    write(&mut hi);
+    ((hi.finish() as u128) << 64) | lo.finish() as u128
+}
+
+fn hash_frame(frame: &FrameExpr, out: &mut Vec<(u128, usize)>) -> u128 {
+    match frame {
+        FrameExpr::Source(src) => hash128(|h| {
+            h.write_u8(0);
+            src.hash(h);
+        }),
+        FrameExpr::Filter(filter) => {
+            let idx = out.len();
+            out.push((0, 0));
+            let args: Vec<u128> = filter.args.iter().map(|arg| hash_expr(arg, out)).collect();
+            let kwargs: Vec<(&String, u128)> = filter
+                .kwargs
+                .iter()
+                .map(|(k, v)| (k, hash_expr(v, out)))
+                .collect();
+            let hash = hash128(|h| {
+                h.write_u8(1);
+                filter.name.hash(h);
+                args.hash(h);
+                kwargs.hash(h);
+            });
+            out[idx] = (hash, out.len() - idx - 1);
+            hash
+        }
+    }
+}
+
+fn hash_expr(expr: &Expr, out: &mut Vec<(u128, usize)>) -> u128 {
+    match expr {
+        Expr::Frame(frame) => hash_frame(frame, out),
+        Expr::Data(DataExpr::List(list)) => {
+            let items: Vec<u128> = list.iter().map(|item| hash_expr(item, out)).collect();
+            hash128(|h| {
+                h.write_u8(2);
+                items.hash(h);
+            })
+        }
+        Expr::Data(data) => hash128(|h| match data {
+            DataExpr::Bool(b) => (3u8, b).hash(h),
+            DataExpr::Int(i) => (4u8, i).hash(h),
+            DataExpr::String(s) => (5u8, s).hash(h),
+            DataExpr::Bytes(b) => (6u8, b).hash(h),
+            DataExpr::Float(f) => (7u8, f.to_bits()).hash(h),
+            DataExpr::List(_) => unreachable!(),
+        }),
+    }
 }
 
 pub(crate) struct ProcessSpan {
@@ -217,11 +1078,7 @@ impl ProcessSpan {
             None => ts,
         };
 
-        // TODO: parallelize?
-        let mut frames = Vec::with_capacity(ts.len());
-        for t in &ts {
-            frames.push(spec.render(&spec_ctx, t));
-        }
+        let frames: Vec<FrameExpr> = ts.par_iter().map(|t| spec.render(&spec_ctx, t)).collect();
 
         // TODO: Data-dependent optimizations somewhere here?
 
@@ -232,3 +1089,193 @@ impl ProcessSpan {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timecode_parse() {
+        let tc: Timecode = "01:02:03:04".parse().unwrap();
+        assert_eq!((tc.hours, tc.minutes, tc.seconds, tc.frames), (1, 2, 3, 4));
+        assert!(!tc.drop_frame);
+        assert_eq!(tc.to_string(), "01:02:03:04");
+
+        let tc: Timecode = "01:02:03;04".parse().unwrap();
+        assert!(tc.drop_frame);
+        assert_eq!(tc.to_string(), "01:02:03;04");
+
+        assert!("01:02:03".parse::<Timecode>().is_err());
+        assert!("01:02:03:-4".parse::<Timecode>().is_err());
+        assert!("01:02:0a:04".parse::<Timecode>().is_err());
+    }
+
+    #[test]
+    fn test_timecode_non_drop_frame() {
+        let rate = Rational64::new(25, 1);
+        let tc: Timecode = "01:00:00:00".parse().unwrap();
+        assert_eq!(tc.frame_number(&rate).unwrap(), 90000);
+        let tc: Timecode = "00:00:01:24".parse().unwrap();
+        assert_eq!(tc.frame_number(&rate).unwrap(), 49);
+        let tc: Timecode = "00:00:01:25".parse().unwrap();
+        assert!(tc.frame_number(&rate).is_err());
+
+        // Non-drop timecode at 29.97 counts 30 frames per timecode second
+        let rate = Rational64::new(30000, 1001);
+        let tc: Timecode = "00:01:00:00".parse().unwrap();
+        assert_eq!(tc.frame_number(&rate).unwrap(), 1800);
+    }
+
+    #[test]
+    fn test_timecode_drop_frame() {
+        let rate = Rational64::new(30000, 1001);
+        let frame_number = |s: &str| s.parse::<Timecode>().unwrap().frame_number(&rate);
+
+        assert_eq!(frame_number("00:00:59;29").unwrap(), 1799);
+        assert_eq!(frame_number("00:01:00;02").unwrap(), 1800);
+        assert!(frame_number("00:01:00;00").is_err());
+        assert!(frame_number("00:01:00;01").is_err());
+        assert_eq!(frame_number("00:10:00;00").unwrap(), 17982);
+        assert_eq!(frame_number("01:00:00;00").unwrap(), 107892);
+
+        let rate = Rational64::new(60000, 1001);
+        let tc: Timecode = "00:01:00;04".parse().unwrap();
+        assert_eq!(tc.frame_number(&rate).unwrap(), 3600);
+        let tc: Timecode = "00:01:00;03".parse().unwrap();
+        assert!(tc.frame_number(&rate).is_err());
+
+        // Drop-frame is not defined at integer frame rates
+        let tc: Timecode = "00:00:00;00".parse().unwrap();
+        assert!(tc.frame_number(&Rational64::new(30, 1)).is_err());
+    }
+
+    #[test]
+    fn test_timecode_frame_number_round_trip() {
+        for (rate, drop_frame) in [
+            (Rational64::new(24, 1), false),
+            (Rational64::new(30000, 1001), false),
+            (Rational64::new(30000, 1001), true),
+            (Rational64::new(60000, 1001), true),
+        ] {
+            for frame_number in (0..200_000).step_by(7) {
+                let tc = Timecode::from_frame_number(frame_number, &rate, drop_frame).unwrap();
+                assert_eq!(tc.frame_number(&rate).unwrap(), frame_number, "{}", tc);
+            }
+        }
+    }
+
+    #[test]
+    fn test_timecode_serde() {
+        let index = IndexConst::Timecode("00:01:00;02".parse().unwrap());
+        let json = serde_json::to_string(&index).unwrap();
+        assert_eq!(json, r#"{"Timecode":"00:01:00;02"}"#);
+        assert_eq!(serde_json::from_str::<IndexConst>(&json).unwrap(), index);
+        assert!(serde_json::from_str::<IndexConst>(r#"{"Timecode":"bad"}"#).is_err());
+    }
+
+    #[test]
+    fn test_text_round_trip() {
+        let mut kwargs = BTreeMap::new();
+        kwargs.insert("width".to_string(), Expr::Data(DataExpr::Int(640)));
+        kwargs.insert("not an ident".to_string(), Expr::Data(DataExpr::Float(1.0)));
+        let frame = FrameExpr::Filter(FilterExpr {
+            name: "cv2.putText".to_string(),
+            args: vec![
+                Expr::Frame(FrameExpr::Source(FrameSource::new(
+                    "tos".to_string(),
+                    IndexConst::ILoc(3),
+                ))),
+                Expr::Data(DataExpr::String("say \"hi\"\n\\ \u{1}".to_string())),
+                Expr::Data(DataExpr::List(vec![
+                    Expr::Data(DataExpr::Int(-10)),
+                    Expr::Data(DataExpr::Float(-2.5e-7)),
+                    Expr::Data(DataExpr::Float(f64::INFINITY)),
+                    Expr::Data(DataExpr::Bool(false)),
+                    Expr::Frame(FrameExpr::Source(FrameSource::new(
+                        "weird.name".to_string(),
+                        IndexConst::T(Rational64::new(-1, 24)),
+                    ))),
+                    Expr::Frame(FrameExpr::Source(FrameSource::new(
+                        "iloc".to_string(),
+                        IndexConst::Timecode("00:01:00;02".parse().unwrap()),
+                    ))),
+                ])),
+                Expr::Data(DataExpr::Bytes(vec![0, 1, b'a', b'"', 255])),
+                Expr::Data(DataExpr::List(vec![])),
+                Expr::Frame(FrameExpr::Filter(FilterExpr {
+                    name: "weird filter".to_string(),
+                    args: vec![],
+                    kwargs: BTreeMap::new(),
+                })),
+            ],
+            kwargs,
+        });
+
+        let text = format!("{:#}", frame);
+        assert_eq!(text.parse::<FrameExpr>().unwrap(), frame, "{}", text);
+        assert!(frame.to_string().contains("<5 bytes>"));
+
+        let text = "Scale(tos.iloc[3], width=640, )";
+        assert_eq!(text.parse::<FrameExpr>().unwrap().to_string(), text);
+        assert_eq!(
+            "[1.0, 1, \"1\", b\"1\"]".parse::<DataExpr>().unwrap(),
+            DataExpr::List(vec![
+                Expr::Data(DataExpr::Float(1.0)),
+                Expr::Data(DataExpr::Int(1)),
+                Expr::Data(DataExpr::String("1".to_string())),
+                Expr::Data(DataExpr::Bytes(b"1".to_vec())),
+            ])
+        );
+        for index in [".iloc[7]", "[3/2]", "[4]", ".tc[01:00:00:00]"] {
+            assert_eq!(index.parse::<IndexConst>().unwrap().to_string(), index);
+        }
+    }
+
+    #[test]
+    fn test_text_parse_errors() {
+        let err = "Scale(\n  tos.iloc[3],\n  width=640 height=360)"
+            .parse::<FrameExpr>()
+            .unwrap_err();
+        assert_eq!((err.line, err.column), (3, 13));
+        assert_eq!(
+            err.to_string(),
+            "Expected `,` or `)`, found `h` at line 3, column 13"
+        );
+
+        let err = "f(\"abc\\q\")".parse::<FrameExpr>().unwrap_err();
+        assert_eq!((err.line, err.column), (1, 7));
+
+        assert!("f(x=1, 2)".parse::<FrameExpr>().is_err());
+        assert!("f(x=1, x=2)".parse::<FrameExpr>().is_err());
+        assert!("f(1".parse::<FrameExpr>().is_err());
+        assert!("tos".parse::<FrameExpr>().is_err());
+        assert!("tos.iloc[-1]".parse::<FrameExpr>().is_err());
+        assert!("tos[1/0]".parse::<FrameExpr>().is_err());
+        assert!("tos.tc[bad]".parse::<FrameExpr>().is_err());
+        assert!("1".parse::<FrameExpr>().is_err());
+        assert!("tos.iloc[1] x".parse::<FrameExpr>().is_err());
+    }
+
+    #[test]
+    fn test_filter_hashes() {
+        let a: FrameExpr =
+            "cv2.hconcat([Scale(tos[0], pix_fmt=\"rgb24\"), Scale(tos[0], pix_fmt=\"rgb24\")])"
+                .parse()
+                .unwrap();
+        let hashes = a.filter_hashes();
+        assert_eq!(hashes.len(), 3);
+        assert_eq!(hashes[0].1, 2);
+        assert_eq!(hashes[1], hashes[2]);
+        assert_eq!(hashes[1].1, 0);
+
+        let b: FrameExpr = "Scale(tos[0], pix_fmt=\"rgb24\")".parse().unwrap();
+        assert_eq!(b.filter_hashes(), vec![hashes[1]]);
+        let c: FrameExpr = "Scale(tos[1], pix_fmt=\"rgb24\")".parse().unwrap();
+        assert_ne!(c.filter_hashes(), b.filter_hashes());
+        let d: FrameExpr = "Scale(tos[0], pix_fmt=\"gray\")".parse().unwrap();
+        assert_ne!(d.filter_hashes(), b.filter_hashes());
+        let e: FrameExpr = "f(1)".parse().unwrap();
+        let f: FrameExpr = "f(1.0)".parse().unwrap();
+        assert_ne!(e.filter_hashes(), f.filter_hashes());
+    }
+}
@@ -0,0 +1,590 @@
+//! A builder DSL for constructing [`FrameExpr`] trees
+//!
+//! Writing SIR by hand means nesting [`FilterExpr`]s and wrapping every value in [`Expr`] and [`DataExpr`].
+//! The builder wraps this up into chained method calls:
+//!
+//! ```
+//! use vidformer::sir::builder::*;
+//!
+//! let frame = src("tos")
+//!     .iloc(10)
+//!     .scale(640, 360)
+//!     .rectangle((10, 10), (100, 100), [255.0, 0.0, 0.0, 0.0], 2)
+//!     .build();
+//! assert_eq!(
+//!     frame.to_string(),
+//!     "cv2.rectangle(Scale(tos.iloc[10], height=360, width=640, ), [10, 10], [100, 100], [255.0, 0.0, 0.0, 0.0], 2, )"
+//! );
+//! ```
+//!
+//! Methods on [`FrameBuilder`] pass the frame as the first argument of a filter.
+//! Filters which don't take an input frame are free functions (e.g., [`black`]).
+//! Optional filter arguments can be set with [`FrameBuilder::kwarg`], and any other filter can be called with [`filter`].
+
+use super::{DataExpr, Expr, FilterExpr, FrameExpr, FrameSource, IndexConst, Timecode};
+use num_rational::Rational64;
+use std::collections::BTreeMap;
+
+/// A value which can be used as a filter argument
+pub trait IntoExpr {
+    fn into_expr(self) -> Expr;
+}
+
+impl IntoExpr for Expr {
+    fn into_expr(self) -> Expr {
+        self
+    }
+}
+
+impl IntoExpr for FrameExpr {
+    fn into_expr(self) -> Expr {
+        Expr::Frame(self)
+    }
+}
+
+impl IntoExpr for DataExpr {
+    fn into_expr(self) -> Expr {
+        Expr::Data(self)
+    }
+}
+
+impl IntoExpr for FrameBuilder {
+    fn into_expr(self) -> Expr {
+        Expr::Frame(self.expr)
+    }
+}
+
+impl IntoExpr for bool {
+    fn into_expr(self) -> Expr {
+        Expr::Data(DataExpr::Bool(self))
+    }
+}
+
+macro_rules! impl_into_expr_int {
+    ($($t:ty),*) => {
+        $(
+            impl IntoExpr for $t {
+                fn into_expr(self) -> Expr {
+                    Expr::Data(DataExpr::Int(self as i64))
+                }
+            }
+        )*
+    };
+}
+
+impl_into_expr_int!(i32, i64, u32, usize);
+
+impl IntoExpr for f64 {
+    fn into_expr(self) -> Expr {
+        Expr::Data(DataExpr::Float(self))
+    }
+}
+
+impl IntoExpr for &str {
+    fn into_expr(self) -> Expr {
+        Expr::Data(DataExpr::String(self.to_string()))
+    }
+}
+
+impl IntoExpr for String {
+    fn into_expr(self) -> Expr {
+        Expr::Data(DataExpr::String(self))
+    }
+}
+
+impl IntoExpr for &[u8] {
+    fn into_expr(self) -> Expr {
+        Expr::Data(DataExpr::Bytes(self.to_vec()))
+    }
+}
+
+/// A point, as used by the cv2 filters
+impl IntoExpr for (i32, i32) {
+    fn into_expr(self) -> Expr {
+        list(vec![self.0, self.1])
+    }
+}
+
+/// A color, as used by the cv2 filters
+impl IntoExpr for [f64; 4] {
+    fn into_expr(self) -> Expr {
+        list(self.to_vec())
+    }
+}
+
+impl<T: IntoExpr> IntoExpr for Vec<T> {
+    fn into_expr(self) -> Expr {
+        list(self)
+    }
+}
+
+/// Create a list argument
+pub fn list<T: IntoExpr>(items: Vec<T>) -> Expr {
+    Expr::Data(DataExpr::List(
+        items.into_iter().map(IntoExpr::into_expr).collect(),
+    ))
+}
+
+/// A reference to a source video, which can be indexed into a frame
+pub struct SourceBuilder {
+    video: String,
+}
+
+/// Reference a source video by name
+pub fn src(video: &str) -> SourceBuilder {
+    SourceBuilder {
+        video: video.to_string(),
+    }
+}
+
+impl SourceBuilder {
+    /// The frame at position `i` of the source
+    pub fn iloc(self, i: usize) -> FrameBuilder {
+        self.index(IndexConst::ILoc(i))
+    }
+
+    /// The frame at timestamp `t` of the source
+    pub fn t(self, t: Rational64) -> FrameBuilder {
+        self.index(IndexConst::T(t))
+    }
+
+    /// The frame at SMPTE timecode `tc` of the source
+    pub fn tc(self, tc: Timecode) -> FrameBuilder {
+        self.index(IndexConst::Timecode(tc))
+    }
+
+    fn index(self, index: IndexConst) -> FrameBuilder {
+        FrameBuilder {
+            expr: FrameExpr::Source(FrameSource::new(self.video, index)),
+        }
+    }
+}
+
+/// A generic filter call
+pub struct FilterBuilder {
+    name: String,
+    args: Vec<Expr>,
+    kwargs: BTreeMap<String, Expr>,
+}
+
+/// Call a filter by name
+pub fn filter(name: &str) -> FilterBuilder {
+    FilterBuilder {
+        name: name.to_string(),
+        args: Vec::new(),
+        kwargs: BTreeMap::new(),
+    }
+}
+
+impl FilterBuilder {
+    /// Add a positional argument
+    pub fn arg(mut self, value: impl IntoExpr) -> Self {
+        self.args.push(value.into_expr());
+        self
+    }
+
+    /// Add a keyword argument
+    pub fn kwarg(mut self, key: &str, value: impl IntoExpr) -> Self {
+        self.kwargs.insert(key.to_string(), value.into_expr());
+        self
+    }
+
+    pub fn build(self) -> FrameBuilder {
+        FrameBuilder {
+            expr: FrameExpr::Filter(FilterExpr {
+                name: self.name,
+                args: self.args,
+                kwargs: self.kwargs,
+            }),
+        }
+    }
+}
+
+/// A frame expression under construction
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameBuilder {
+    expr: FrameExpr,
+}
+
+impl From<FrameExpr> for FrameBuilder {
+    fn from(expr: FrameExpr) -> Self {
+        FrameBuilder { expr }
+    }
+}
+
+impl From<FrameBuilder> for FrameExpr {
+    fn from(builder: FrameBuilder) -> Self {
+        builder.expr
+    }
+}
+
+impl FrameBuilder {
+    pub fn build(self) -> FrameExpr {
+        self.expr
+    }
+
+    /// Set a keyword argument on the outermost filter
+    ///
+    /// This is how optional filter arguments are set, e.g., `.rectangle(..).kwarg("thickness", 2)`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the expression is a source frame rather than a filter.
+    pub fn kwarg(mut self, key: &str, value: impl IntoExpr) -> Self {
+        match &mut self.expr {
+            FrameExpr::Filter(f) => {
+                f.kwargs.insert(key.to_string(), value.into_expr());
+            }
+            FrameExpr::Source(s) => {
+                panic!("Can not set kwarg `{}` on source frame {}", key, s.video)
+            }
+        }
+        self
+    }
+
+    /// Call a filter with this frame as the first argument
+    pub fn apply(self, name: &str) -> FilterBuilder {
+        filter(name).arg(self)
+    }
+
+    // Built-in filters
+
+    /// Scale the frame (`Scale`)
+    pub fn scale(self, width: usize, height: usize) -> Self {
+        self.apply("Scale")
+            .kwarg("width", width)
+            .kwarg("height", height)
+            .build()
+    }
+
+    /// Convert the pixel format of the frame (`Scale`)
+    pub fn pix_fmt(self, pix_fmt: &str) -> Self {
+        self.apply("Scale").kwarg("pix_fmt", pix_fmt).build()
+    }
+
+    /// Crop the frame to `[miny:maxy, minx:maxx]` (`_slice_mat`)
+    pub fn slice(self, miny: i64, maxy: i64, minx: i64, maxx: i64) -> Self {
+        self.apply("_slice_mat")
+            .arg(miny)
+            .arg(maxy)
+            .arg(minx)
+            .arg(maxx)
+            .build()
+    }
+
+    /// Write `other` into the region `[miny:maxy, minx:maxx]` of the frame (`_slice_write_mat`)
+    pub fn slice_write(
+        self,
+        other: impl Into<FrameBuilder>,
+        miny: i64,
+        maxy: i64,
+        minx: i64,
+        maxx: i64,
+    ) -> Self {
+        self.apply("_slice_write_mat")
+            .arg(other.into())
+            .arg(miny)
+            .arg(maxy)
+            .arg(minx)
+            .arg(maxx)
+            .build()
+    }
+
+    // cv2 filters
+
+    /// `cv2.rectangle`
+    pub fn rectangle(
+        self,
+        pt1: (i32, i32),
+        pt2: (i32, i32),
+        color: [f64; 4],
+        thickness: i32,
+    ) -> Self {
+        self.apply("cv2.rectangle")
+            .arg(pt1)
+            .arg(pt2)
+            .arg(color)
+            .arg(thickness)
+            .build()
+    }
+
+    /// `cv2.putText`
+    pub fn put_text(
+        self,
+        text: &str,
+        org: (i32, i32),
+        font_face: i32,
+        font_scale: f64,
+        color: [f64; 4],
+    ) -> Self {
+        self.apply("cv2.putText")
+            .arg(text)
+            .arg(org)
+            .arg(font_face)
+            .arg(font_scale)
+            .arg(color)
+            .build()
+    }
+
+    /// `cv2.arrowedLine`
+    pub fn arrowed_line(
+        self,
+        pt1: (i32, i32),
+        pt2: (i32, i32),
+        color: [f64; 4],
+        thickness: i32,
+    ) -> Self {
+        self.apply("cv2.arrowedLine")
+            .arg(pt1)
+            .arg(pt2)
+            .arg(color)
+            .arg(thickness)
+            .build()
+    }
+
+    /// `cv2.line`
+    pub fn line(self, pt1: (i32, i32), pt2: (i32, i32), color: [f64; 4], thickness: i32) -> Self {
+        self.apply("cv2.line")
+            .arg(pt1)
+            .arg(pt2)
+            .arg(color)
+            .arg(thickness)
+            .build()
+    }
+
+    /// `cv2.circle`
+    pub fn circle(self, center: (i32, i32), radius: i32, color: [f64; 4], thickness: i32) -> Self {
+        self.apply("cv2.circle")
+            .arg(center)
+            .arg(radius)
+            .arg(color)
+            .arg(thickness)
+            .build()
+    }
+
+    /// `cv2.ellipse`
+    #[allow(clippy::too_many_arguments)]
+    pub fn ellipse(
+        self,
+        center: (i32, i32),
+        axes: (i32, i32),
+        angle: f64,
+        start_angle: f64,
+        end_angle: f64,
+        color: [f64; 4],
+        thickness: i32,
+    ) -> Self {
+        self.apply("cv2.ellipse")
+            .arg(center)
+            .arg(axes)
+            .arg(angle)
+            .arg(start_angle)
+            .arg(end_angle)
+            .arg(color)
+            .arg(thickness)
+            .build()
+    }
+
+    /// `cv2.setTo`, set pixels to `color` where `mask` (a gray frame) is non-zero
+    pub fn set_to(self, color: [f64; 4], mask: impl Into<FrameBuilder>) -> Self {
+        self.apply("cv2.setTo").arg(color).arg(mask.into()).build()
+    }
+
+    /// `cv2.addWeighted`, computes `self * alpha + other * beta + gamma`
+    pub fn add_weighted(
+        self,
+        alpha: f64,
+        other: impl Into<FrameBuilder>,
+        beta: f64,
+        gamma: f64,
+    ) -> Self {
+        self.apply("cv2.addWeighted")
+            .arg(alpha)
+            .arg(other.into())
+            .arg(beta)
+            .arg(gamma)
+            .build()
+    }
+
+    /// `cv2.polylines`
+    pub fn polylines(
+        self,
+        pts: Vec<Vec<(i32, i32)>>,
+        is_closed: bool,
+        color: [f64; 4],
+        thickness: i32,
+    ) -> Self {
+        self.apply("cv2.polylines")
+            .arg(pts)
+            .arg(is_closed)
+            .arg(color)
+            .arg(thickness)
+            .build()
+    }
+
+    /// `cv2.fillPoly`
+    pub fn fill_poly(self, pts: Vec<Vec<(i32, i32)>>, color: [f64; 4]) -> Self {
+        self.apply("cv2.fillPoly").arg(pts).arg(color).build()
+    }
+
+    /// `cv2.fillConvexPoly`
+    pub fn fill_convex_poly(self, points: Vec<(i32, i32)>, color: [f64; 4]) -> Self {
+        self.apply("cv2.fillConvexPoly")
+            .arg(points)
+            .arg(color)
+            .build()
+    }
+
+    /// `cv2.drawContours`
+    pub fn draw_contours(
+        self,
+        contours: Vec<Vec<(i32, i32)>>,
+        contour_idx: i32,
+        color: [f64; 4],
+        thickness: i32,
+    ) -> Self {
+        self.apply("cv2.drawContours")
+            .arg(contours)
+            .arg(contour_idx)
+            .arg(color)
+            .arg(thickness)
+            .build()
+    }
+
+    /// `cv2.drawMarker`
+    pub fn draw_marker(self, position: (i32, i32), color: [f64; 4]) -> Self {
+        self.apply("cv2.drawMarker")
+            .arg(position)
+            .arg(color)
+            .build()
+    }
+
+    /// `cv2.flip`
+    pub fn flip(self, flip_code: i32) -> Self {
+        self.apply("cv2.flip").arg(flip_code).build()
+    }
+
+    /// `cv2.rotate`
+    pub fn rotate(self, rotate_code: i32) -> Self {
+        self.apply("cv2.rotate").arg(rotate_code).build()
+    }
+
+    /// `cv2.copyMakeBorder`
+    pub fn copy_make_border(
+        self,
+        top: i32,
+        bottom: i32,
+        left: i32,
+        right: i32,
+        border_type: i32,
+    ) -> Self {
+        self.apply("cv2.copyMakeBorder")
+            .arg(top)
+            .arg(bottom)
+            .arg(left)
+            .arg(right)
+            .arg(border_type)
+            .build()
+    }
+}
+
+/// A black frame (`_black`)
+pub fn black(width: usize, height: usize, pix_fmt: &str) -> FrameBuilder {
+    filter("_black")
+        .kwarg("width", width)
+        .kwarg("height", height)
+        .kwarg("pix_fmt", pix_fmt)
+        .build()
+}
+
+/// A solid color frame (`_solid`), `color` is RGB
+pub fn solid(width: usize, height: usize, pix_fmt: &str, color: [i64; 3]) -> FrameBuilder {
+    filter("_solid")
+        .kwarg("width", width)
+        .kwarg("height", height)
+        .kwarg("pix_fmt", pix_fmt)
+        .kwarg("color", list(color.to_vec()))
+        .build()
+}
+
+/// A frame from raw `rgb24` pixel data (`_inline_mat`)
+pub fn inline_mat(data: &[u8], width: usize, height: usize) -> FrameBuilder {
+    filter("_inline_mat")
+        .arg(data)
+        .kwarg("width", width)
+        .kwarg("height", height)
+        .kwarg("pix_fmt", "rgb24")
+        .build()
+}
+
+/// A test pattern frame (`PlaceholderFrame`)
+pub fn placeholder(width: usize, height: usize) -> FrameBuilder {
+    filter("PlaceholderFrame")
+        .kwarg("width", width)
+        .kwarg("height", height)
+        .build()
+}
+
+/// `cv2.hconcat`
+pub fn hconcat(frames: Vec<FrameBuilder>) -> FrameBuilder {
+    filter("cv2.hconcat").arg(frames).build()
+}
+
+/// `cv2.vconcat`
+pub fn vconcat(frames: Vec<FrameBuilder>) -> FrameBuilder {
+    filter("cv2.vconcat").arg(frames).build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builder_matches_hand_built() {
+        let built = src("tos").iloc(3).scale(640, 360).build();
+
+        let mut kwargs = BTreeMap::new();
+        kwargs.insert("width".to_string(), Expr::Data(DataExpr::Int(640)));
+        kwargs.insert("height".to_string(), Expr::Data(DataExpr::Int(360)));
+        let hand_built = FrameExpr::Filter(FilterExpr {
+            name: "Scale".to_string(),
+            args: vec![Expr::Frame(FrameExpr::Source(FrameSource::new(
+                "tos".to_string(),
+                IndexConst::ILoc(3),
+            )))],
+            kwargs,
+        });
+
+        assert_eq!(built, hand_built);
+    }
+
+    #[test]
+    fn test_builder_kwarg() {
+        let built = black(100, 100, "rgb24")
+            .rectangle((0, 0), (10, 10), [255.0, 0.0, 0.0, 0.0], 1)
+            .kwarg("lineType", 16)
+            .build();
+
+        match built {
+            FrameExpr::Filter(f) => {
+                assert_eq!(f.name, "cv2.rectangle");
+                assert_eq!(f.args.len(), 5);
+                assert_eq!(f.kwargs["lineType"], Expr::Data(DataExpr::Int(16)));
+                assert_eq!(
+                    f.args[1],
+                    Expr::Data(DataExpr::List(vec![
+                        Expr::Data(DataExpr::Int(0)),
+                        Expr::Data(DataExpr::Int(0))
+                    ]))
+                );
+            }
+            _ => panic!("Expected a filter"),
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_builder_kwarg_on_source() {
+        src("tos").iloc(0).kwarg("thickness", 1);
+    }
+}
@@ -4,6 +4,8 @@ use rusty_ffmpeg::ffi;
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeSet;
 use std::ffi::CStr;
+use std::ffi::CString;
+use std::ptr;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SourceFileMeta {
@@ -23,6 +25,17 @@ pub struct SourceVideoStreamMeta {
     pub ts: Vec<Rational64>,
     pub keys: Vec<Rational64>,
     pub fuid: Option<String>, // unique identifier for the file (for caching)
+    #[serde(default)]
+    pub timecode: Option<SourceTimecode>,
+}
+
+/// The start timecode of a source stream
+///
+/// `start` is the timecode of the first frame and `rate` is the actual frame rate timecodes are counted at.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SourceTimecode {
+    pub start: crate::sir::Timecode,
+    pub rate: Rational64,
 }
 
 pub fn create_profile_file(streams: &[SourceVideoStreamMeta]) -> SourceFileMeta {
@@ -103,6 +116,8 @@ impl SourceVideoStreamMeta {
                 .to_string()
         };
 
+        let timecode = read_start_timecode(&demuxer);
+
         let packet = unsafe { ffi::av_packet_alloc().as_mut() }
             .expect("failed to allocated memory for AVPacket");
 
@@ -197,6 +212,7 @@ impl SourceVideoStreamMeta {
             keys: key_array,
             file_path: vid_path.to_string(),
             fuid: io_cache.map(|(_, fuid)| fuid.to_string()),
+            timecode,
         })
     }
 
@@ -291,3 +307,63 @@ impl SourceVideoStreamMeta {
         &self.ts[start_i..end_i]
     }
 }
+
+/// Read the start timecode of a stream
+///
+/// The `timecode` tag is checked on the stream first, then on the container.
+/// Streams without a tag start at `00:00:00:00`.
+/// Returns `None` if the stream has no usable frame rate or timecode.
+fn read_start_timecode(demuxer: &crate::av::demuxer::Demuxer) -> Option<SourceTimecode> {
+    let rate = unsafe {
+        let r_frame_rate = (*demuxer.stream).r_frame_rate;
+        if r_frame_rate.num > 0 && r_frame_rate.den > 0 {
+            r_frame_rate
+        } else {
+            (*demuxer.stream).avg_frame_rate
+        }
+    };
+    if rate.num <= 0 || rate.den <= 0 {
+        return None;
+    }
+    let rate = crate::util::avrat_to_rat(&rate);
+
+    let key = CString::new("timecode").unwrap();
+    let tag = unsafe {
+        let mut entry = ffi::av_dict_get((*demuxer.stream).metadata, key.as_ptr(), ptr::null(), 0);
+        if entry.is_null() {
+            entry = ffi::av_dict_get(
+                (*demuxer.format_context).metadata,
+                key.as_ptr(),
+                ptr::null(),
+                0,
+            );
+        }
+        if entry.is_null() {
+            None
+        } else {
+            Some(CStr::from_ptr((*entry).value).to_string_lossy().to_string())
+        }
+    };
+
+    let start = match tag {
+        Some(tag) => {
+            // Make sure the tag is valid at this frame rate
+            match tag
+                .parse::<crate::sir::Timecode>()
+                .and_then(|tc| tc.frame_number(&rate).map(|_| tc))
+            {
+                Ok(start) => start,
+                Err(err) => {
+                    warn!("Ignoring timecode tag `{}`: {}", tag, err);
+                    return None;
+                }
+            }
+        }
+        None => match crate::sir::Timecode::from_frame_number(0, &rate, false) {
+            Ok(start) => start,
+            Err(_) => return None,
+        },
+    };
+
+    Some(SourceTimecode { start, rate })
+}
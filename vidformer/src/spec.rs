@@ -7,7 +7,10 @@
 //! Specs are stateless and immutable. They are intended to be smaller than just storing the entire output video as an array.
 //! Additionally, this generic spec interface allows the general case of a video-editing DSL, as done in V2V, while allowing for whatever language the use case needs.
 
-use crate::sir::FrameExpr;
+pub mod combinators;
+
+use crate::dve::Error;
+use crate::sir::{FilterExpr, FrameExpr};
 use num_rational::Rational64;
 
 /// A trait for providing information to a spec during runtime
@@ -35,6 +38,40 @@ pub trait Spec: Sync + Send {
     fn render(&self, context: &dyn SpecContext, t: &Rational64) -> FrameExpr;
 }
 
+/// A trait for a spec which computes data from frames instead of rendering a video
+///
+/// Each timestamp renders to a call of a [`DataFilter`](crate::filter::DataFilter), whose values [`analyze`](crate::analyze) collects.
+pub trait DataSpec: Sync + Send {
+    /// Returns the timestamps to compute values at, following the same rules as [`Spec::timestamps`]
+    fn timestamps(&self, context: &dyn SpecContext) -> Vec<Rational64>;
+
+    /// Returns the data filter call to compute at a given time.
+    fn render(&self, context: &dyn SpecContext, t: &Rational64) -> FilterExpr;
+}
+
+/// Checks that a list of timestamps follows the [`Spec::timestamps`] contract
+pub fn check_timestamps(timestamps: &[Rational64]) -> Result<(), Error> {
+    match timestamps.first() {
+        None => return Err(Error::InvalidSpec("Spec has no timestamps".to_string())),
+        Some(t) if *t != Rational64::new(0, 1) => {
+            return Err(Error::InvalidSpec(format!(
+                "Spec timestamps must begin with 0, got {}",
+                t
+            )))
+        }
+        _ => {}
+    }
+
+    if let Some(w) = timestamps.windows(2).find(|w| w[0] >= w[1]) {
+        return Err(Error::InvalidSpec(format!(
+            "Spec timestamps must be sorted and unique, got {} before {}",
+            w[0], w[1]
+        )));
+    }
+
+    Ok(())
+}
+
 pub(crate) fn get_framerate(spec: &dyn Spec) -> usize {
     // TODO: Not all framerates are widely supported in HLS. We should probably add a check for that.
 
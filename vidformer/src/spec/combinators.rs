@@ -0,0 +1,434 @@
+//! Specs which are built from other specs or source videos
+//!
+//! Combinators query the timestamps of the specs they wrap once, on construction, and check them with [`check_timestamps`].
+//! Each combinator is itself a [`Spec`], so they can be nested:
+//!
+//! ```ignore
+//! let ctx = context.spec_ctx();
+//! let a = SourceSpec::new(&source_a)?;
+//! let b = SourceSpec::new(&source_b)?;
+//! let intro = Slice::new(Box::new(a), Rational64::new(0, 1), Rational64::new(5, 1), &ctx)?;
+//! let spec = Resample::new(Box::new(Concat::new(vec![Box::new(intro), Box::new(b)], &ctx)?), 30, &ctx)?;
+//! ```
+
+use super::{check_timestamps, Spec, SpecContext};
+use crate::dve::Error;
+use crate::sir::{FrameExpr, FrameSource, IndexConst};
+use crate::source::SourceVideoStreamMeta;
+use num_rational::Rational64;
+
+/// A wrapped spec along with its (checked) timestamps
+struct Inner {
+    spec: Box<dyn Spec>,
+    ts: Vec<Rational64>,
+}
+
+impl Inner {
+    fn new(spec: Box<dyn Spec>, context: &dyn SpecContext) -> Result<Self, Error> {
+        let ts = spec.timestamps(context);
+        check_timestamps(&ts)?;
+        Ok(Inner { spec, ts })
+    }
+
+    /// The time from the first frame until the end of the last frame
+    ///
+    /// The last frame is assumed to last as long as the frame before it.
+    fn duration(&self) -> Rational64 {
+        let last = self.ts[self.ts.len() - 1];
+        if self.ts.len() < 2 {
+            // Same default as `get_framerate` for single-frame specs
+            return last + Rational64::new(1, 30);
+        }
+        last + (last - self.ts[self.ts.len() - 2])
+    }
+}
+
+/// Plays a source video, with its timestamps re-based to start at 0
+pub struct SourceSpec {
+    video: String,
+    base: Rational64,
+    ts: Vec<Rational64>,
+}
+
+impl SourceSpec {
+    pub fn new(source: &SourceVideoStreamMeta) -> Result<Self, Error> {
+        let base = match source.ts.first() {
+            Some(t) => *t,
+            None => {
+                return Err(Error::InvalidSpec(format!(
+                    "Source `{}` has no frames",
+                    source.name
+                )))
+            }
+        };
+        let ts: Vec<Rational64> = source.ts.iter().map(|t| t - base).collect();
+        check_timestamps(&ts)?;
+        Ok(SourceSpec {
+            video: source.name.clone(),
+            base,
+            ts,
+        })
+    }
+}
+
+impl Spec for SourceSpec {
+    fn timestamps(&self, _context: &dyn SpecContext) -> Vec<Rational64> {
+        self.ts.clone()
+    }
+
+    fn render(&self, _context: &dyn SpecContext, t: &Rational64) -> FrameExpr {
+        FrameExpr::Source(FrameSource::new(
+            self.video.clone(),
+            IndexConst::T(t + self.base),
+        ))
+    }
+}
+
+/// Plays specs one after another
+///
+/// Each spec starts where the previous one ends, with its last frame lasting as long as the frame before it.
+pub struct Concat {
+    parts: Vec<(Rational64, Inner)>,
+    ts: Vec<Rational64>,
+}
+
+impl Concat {
+    pub fn new(specs: Vec<Box<dyn Spec>>, context: &dyn SpecContext) -> Result<Self, Error> {
+        if specs.is_empty() {
+            return Err(Error::InvalidSpec("Can not concat zero specs".to_string()));
+        }
+
+        let mut parts = Vec::with_capacity(specs.len());
+        let mut ts = Vec::new();
+        let mut offset = Rational64::new(0, 1);
+        for spec in specs {
+            let inner = Inner::new(spec, context)?;
+            ts.extend(inner.ts.iter().map(|t| t + offset));
+            let next_offset = offset + inner.duration();
+            parts.push((offset, inner));
+            offset = next_offset;
+        }
+
+        Ok(Concat { parts, ts })
+    }
+}
+
+impl Spec for Concat {
+    fn timestamps(&self, _context: &dyn SpecContext) -> Vec<Rational64> {
+        self.ts.clone()
+    }
+
+    fn render(&self, context: &dyn SpecContext, t: &Rational64) -> FrameExpr {
+        let idx = self.parts.partition_point(|(offset, _)| offset <= t) - 1;
+        let (offset, inner) = &self.parts[idx];
+        inner.spec.render(context, &(t - offset))
+    }
+}
+
+/// The frames of a spec in `[start, end)`, re-based to start at 0
+pub struct Slice {
+    inner: Inner,
+    base: Rational64,
+    ts: Vec<Rational64>,
+}
+
+impl Slice {
+    pub fn new(
+        spec: Box<dyn Spec>,
+        start: Rational64,
+        end: Rational64,
+        context: &dyn SpecContext,
+    ) -> Result<Self, Error> {
+        let inner = Inner::new(spec, context)?;
+        let kept: Vec<Rational64> = inner
+            .ts
+            .iter()
+            .filter(|t| **t >= start && **t < end)
+            .cloned()
+            .collect();
+        let base = match kept.first() {
+            Some(t) => *t,
+            None => {
+                return Err(Error::InvalidSpec(format!(
+                    "Slice [{}, {}) contains no frames",
+                    start, end
+                )))
+            }
+        };
+        let ts = kept.iter().map(|t| t - base).collect();
+        Ok(Slice { inner, base, ts })
+    }
+}
+
+impl Spec for Slice {
+    fn timestamps(&self, _context: &dyn SpecContext) -> Vec<Rational64> {
+        self.ts.clone()
+    }
+
+    fn render(&self, context: &dyn SpecContext, t: &Rational64) -> FrameExpr {
+        self.inner.spec.render(context, &(t + self.base))
+    }
+}
+
+/// Plays a spec `count` times in a row
+pub struct Loop {
+    inner: Inner,
+    count: usize,
+    period: Rational64,
+}
+
+impl Loop {
+    pub fn new(
+        spec: Box<dyn Spec>,
+        count: usize,
+        context: &dyn SpecContext,
+    ) -> Result<Self, Error> {
+        if count == 0 {
+            return Err(Error::InvalidSpec(
+                "Loop count must be at least 1".to_string(),
+            ));
+        }
+        let inner = Inner::new(spec, context)?;
+        let period = inner.duration();
+        Ok(Loop {
+            inner,
+            count,
+            period,
+        })
+    }
+}
+
+impl Spec for Loop {
+    fn timestamps(&self, _context: &dyn SpecContext) -> Vec<Rational64> {
+        (0..self.count)
+            .flat_map(|i| {
+                let offset = self.period * Rational64::from_integer(i as i64);
+                self.inner.ts.iter().map(move |t| t + offset)
+            })
+            .collect()
+    }
+
+    fn render(&self, context: &dyn SpecContext, t: &Rational64) -> FrameExpr {
+        let i = (t / self.period).floor();
+        self.inner.spec.render(context, &(t - self.period * i))
+    }
+}
+
+/// Plays a spec backwards
+///
+/// The gaps between frames are kept, so a variable frame rate spec stays variable.
+pub struct Reverse {
+    inner: Inner,
+}
+
+impl Reverse {
+    pub fn new(spec: Box<dyn Spec>, context: &dyn SpecContext) -> Result<Self, Error> {
+        Ok(Reverse {
+            inner: Inner::new(spec, context)?,
+        })
+    }
+
+    fn last(&self) -> Rational64 {
+        self.inner.ts[self.inner.ts.len() - 1]
+    }
+}
+
+impl Spec for Reverse {
+    fn timestamps(&self, _context: &dyn SpecContext) -> Vec<Rational64> {
+        let last = self.last();
+        self.inner.ts.iter().rev().map(|t| last - t).collect()
+    }
+
+    fn render(&self, context: &dyn SpecContext, t: &Rational64) -> FrameExpr {
+        self.inner.spec.render(context, &(self.last() - t))
+    }
+}
+
+/// Plays a spec `factor` times faster
+///
+/// Every frame is kept, so the frame rate changes too; wrap in [`Resample`] to get back to a fixed frame rate.
+pub struct Speed {
+    inner: Inner,
+    factor: Rational64,
+}
+
+impl Speed {
+    pub fn new(
+        spec: Box<dyn Spec>,
+        factor: Rational64,
+        context: &dyn SpecContext,
+    ) -> Result<Self, Error> {
+        if factor <= Rational64::new(0, 1) {
+            return Err(Error::InvalidSpec(format!(
+                "Speed factor must be positive, got {}",
+                factor
+            )));
+        }
+        Ok(Speed {
+            inner: Inner::new(spec, context)?,
+            factor,
+        })
+    }
+}
+
+impl Spec for Speed {
+    fn timestamps(&self, _context: &dyn SpecContext) -> Vec<Rational64> {
+        self.inner.ts.iter().map(|t| t / self.factor).collect()
+    }
+
+    fn render(&self, context: &dyn SpecContext, t: &Rational64) -> FrameExpr {
+        self.inner.spec.render(context, &(t * self.factor))
+    }
+}
+
+/// Resamples a spec to a constant frame rate
+///
+/// Each output frame shows the latest frame of the wrapped spec at or before its time, so frames are duplicated or dropped as needed.
+pub struct Resample {
+    inner: Inner,
+    fps: usize,
+    frames: usize,
+}
+
+impl Resample {
+    pub fn new(spec: Box<dyn Spec>, fps: usize, context: &dyn SpecContext) -> Result<Self, Error> {
+        if fps == 0 {
+            return Err(Error::InvalidSpec(
+                "Frame rate must be positive".to_string(),
+            ));
+        }
+        let inner = Inner::new(spec, context)?;
+        let frames = (inner.duration() * Rational64::from_integer(fps as i64))
+            .ceil()
+            .to_integer() as usize;
+        Ok(Resample { inner, fps, frames })
+    }
+}
+
+impl Spec for Resample {
+    fn timestamps(&self, _context: &dyn SpecContext) -> Vec<Rational64> {
+        (0..self.frames)
+            .map(|i| Rational64::new(i as i64, self.fps as i64))
+            .collect()
+    }
+
+    fn render(&self, context: &dyn SpecContext, t: &Rational64) -> FrameExpr {
+        let idx = self.inner.ts.partition_point(|t2| t2 <= t) - 1;
+        self.inner.spec.render(context, &self.inner.ts[idx])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dve::EmptySpecCtx;
+
+    /// A spec whose frame at `ts[i]` is `test.iloc[i]`
+    struct IlocSpec {
+        ts: Vec<Rational64>,
+    }
+
+    impl IlocSpec {
+        fn boxed(n: i64, fps: i64) -> Box<dyn Spec> {
+            Box::new(IlocSpec {
+                ts: (0..n).map(|i| Rational64::new(i, fps)).collect(),
+            })
+        }
+    }
+
+    impl Spec for IlocSpec {
+        fn timestamps(&self, _context: &dyn SpecContext) -> Vec<Rational64> {
+            self.ts.clone()
+        }
+
+        fn render(&self, _context: &dyn SpecContext, t: &Rational64) -> FrameExpr {
+            let idx = self.ts.binary_search(t).unwrap();
+            FrameExpr::Source(FrameSource::new("test".to_string(), IndexConst::ILoc(idx)))
+        }
+    }
+
+    fn ilocs(spec: &dyn Spec) -> Vec<usize> {
+        let ctx = EmptySpecCtx;
+        let ts = spec.timestamps(&ctx);
+        check_timestamps(&ts).unwrap();
+        ts.iter()
+            .map(|t| match spec.render(&ctx, t) {
+                FrameExpr::Source(s) => match s.index() {
+                    IndexConst::ILoc(i) => *i,
+                    _ => panic!(),
+                },
+                _ => panic!(),
+            })
+            .collect()
+    }
+
+    fn r(n: i64, d: i64) -> Rational64 {
+        Rational64::new(n, d)
+    }
+
+    #[test]
+    fn test_check_timestamps() {
+        assert!(check_timestamps(&[r(0, 1), r(1, 24)]).is_ok());
+        assert!(check_timestamps(&[]).is_err());
+        assert!(check_timestamps(&[r(1, 24)]).is_err());
+        assert!(check_timestamps(&[r(0, 1), r(1, 24), r(1, 24)]).is_err());
+        assert!(check_timestamps(&[r(0, 1), r(2, 24), r(1, 24)]).is_err());
+        assert!(Slice::new(
+            Box::new(IlocSpec { ts: vec![r(1, 24)] }),
+            r(0, 1),
+            r(1, 1),
+            &EmptySpecCtx
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_concat() {
+        let ctx = EmptySpecCtx;
+        let spec = Concat::new(vec![IlocSpec::boxed(2, 24), IlocSpec::boxed(3, 24)], &ctx).unwrap();
+        assert_eq!(
+            spec.timestamps(&ctx),
+            (0..5).map(|i| r(i, 24)).collect::<Vec<_>>()
+        );
+        assert_eq!(ilocs(&spec), vec![0, 1, 0, 1, 2]);
+    }
+
+    #[test]
+    fn test_slice() {
+        let ctx = EmptySpecCtx;
+        let spec = Slice::new(IlocSpec::boxed(10, 24), r(3, 24), r(6, 24), &ctx).unwrap();
+        assert_eq!(spec.timestamps(&ctx), vec![r(0, 1), r(1, 24), r(2, 24)]);
+        assert_eq!(ilocs(&spec), vec![3, 4, 5]);
+        assert!(Slice::new(IlocSpec::boxed(10, 24), r(1, 1), r(2, 1), &ctx).is_err());
+    }
+
+    #[test]
+    fn test_loop() {
+        let ctx = EmptySpecCtx;
+        let spec = Loop::new(IlocSpec::boxed(3, 24), 3, &ctx).unwrap();
+        assert_eq!(spec.timestamps(&ctx).len(), 9);
+        assert_eq!(ilocs(&spec), vec![0, 1, 2, 0, 1, 2, 0, 1, 2]);
+    }
+
+    #[test]
+    fn test_reverse() {
+        let ctx = EmptySpecCtx;
+        let spec = Reverse::new(IlocSpec::boxed(4, 24), &ctx).unwrap();
+        assert_eq!(ilocs(&spec), vec![3, 2, 1, 0]);
+    }
+
+    #[test]
+    fn test_speed_and_resample() {
+        let ctx = EmptySpecCtx;
+        let spec = Speed::new(IlocSpec::boxed(8, 24), r(2, 1), &ctx).unwrap();
+        assert_eq!(spec.timestamps(&ctx)[1], r(1, 48));
+        assert_eq!(ilocs(&spec), (0..8).collect::<Vec<_>>());
+
+        let spec = Resample::new(Box::new(spec), 24, &ctx).unwrap();
+        assert_eq!(ilocs(&spec), vec![0, 2, 4, 6]);
+
+        let spec = Resample::new(IlocSpec::boxed(2, 12), 24, &ctx).unwrap();
+        assert_eq!(ilocs(&spec), vec![0, 0, 1, 1]);
+        assert!(Speed::new(IlocSpec::boxed(2, 12), r(0, 1), &ctx).is_err());
+    }
+}
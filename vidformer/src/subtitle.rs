@@ -0,0 +1,313 @@
+//! Subtitle tracks parsed from SRT and WebVTT files
+//!
+//! Tracks are registered on a [`Context`](crate::Context) with [`Context::with_subtitles`](crate::Context::with_subtitles),
+//! which makes them available to the `Subtitle` filter by name.
+
+use crate::dve::Error;
+use crate::service::Service;
+use num_rational::Rational64;
+
+/// A subtitle cue, shown from `start` (inclusive) to `end` (exclusive), in seconds
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cue {
+    pub start: Rational64,
+    pub end: Rational64,
+    /// The cue's text, with markup removed; lines are separated by `\n`
+    pub text: String,
+}
+
+/// A named track of subtitle cues, sorted by start time
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubtitleTrack {
+    pub name: String,
+    pub cues: Vec<Cue>,
+}
+
+/// A subtitle file format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubtitleFormat {
+    Srt,
+    WebVtt,
+}
+
+impl SubtitleFormat {
+    /// The format of a file, from its extension
+    pub fn from_path(path: &str) -> Option<SubtitleFormat> {
+        let ext = path.rsplit_once('.')?.1.to_ascii_lowercase();
+        match ext.as_str() {
+            "srt" => Some(SubtitleFormat::Srt),
+            "vtt" => Some(SubtitleFormat::WebVtt),
+            _ => None,
+        }
+    }
+}
+
+impl SubtitleTrack {
+    /// Parse the contents of an SRT or WebVTT file
+    pub fn parse(name: &str, format: SubtitleFormat, contents: &str) -> Result<Self, Error> {
+        let invalid = |e: String| Error::InvalidSubtitles(name.to_string(), e);
+        let contents = contents.strip_prefix('\u{feff}').unwrap_or(contents);
+        let contents = contents.replace("\r\n", "\n").replace('\r', "\n");
+
+        let mut blocks = blocks(&contents);
+        if format == SubtitleFormat::WebVtt {
+            match blocks.first() {
+                Some((_, lines)) if is_vtt_header(lines[0]) => {
+                    blocks.remove(0);
+                }
+                _ => return Err(invalid("Missing WEBVTT header".to_string())),
+            }
+        }
+
+        let mut cues = Vec::new();
+        for (line_no, lines) in blocks {
+            if format == SubtitleFormat::WebVtt
+                && ["NOTE", "STYLE", "REGION"]
+                    .iter()
+                    .any(|kw| lines[0] == *kw || lines[0].starts_with(&format!("{} ", kw)))
+            {
+                continue;
+            }
+
+            // The timing line is optionally preceded by a cue number (SRT) or identifier (WebVTT)
+            let timing_idx = match lines.iter().take(2).position(|line| line.contains("-->")) {
+                Some(idx) => idx,
+                None => {
+                    return Err(invalid(format!(
+                        "Expected a cue timing at line {}",
+                        line_no
+                    )))
+                }
+            };
+            let (start, end) = parse_timing(lines[timing_idx])
+                .map_err(|e| invalid(format!("{} at line {}", e, line_no + timing_idx)))?;
+            if end < start {
+                return Err(invalid(format!(
+                    "Cue ends before it starts at line {}",
+                    line_no + timing_idx
+                )));
+            }
+
+            let text = lines[timing_idx + 1..]
+                .iter()
+                .map(|line| strip_markup(line))
+                .collect::<Vec<_>>()
+                .join("\n");
+            cues.push(Cue { start, end, text });
+        }
+        cues.sort_by_key(|cue| cue.start);
+
+        Ok(SubtitleTrack {
+            name: name.to_string(),
+            cues,
+        })
+    }
+
+    /// Read and parse a subtitle file through a [`Service`], with the format taken from the file extension
+    pub fn load(name: &str, path: &str, service: &Service) -> Result<Self, Error> {
+        let format = SubtitleFormat::from_path(path).ok_or_else(|| {
+            Error::InvalidSubtitles(
+                name.to_string(),
+                format!(
+                    "Unknown subtitle format for `{}`, expected .srt or .vtt",
+                    path
+                ),
+            )
+        })?;
+
+        let io_runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .enable_all()
+            .build()
+            .unwrap();
+        let op = service.blocking_operator(io_runtime.handle())?;
+        let data = match op.read(path) {
+            Ok(buffer) => buffer.to_vec(),
+            Err(e) if e.kind() == opendal::ErrorKind::NotFound => {
+                return Err(Error::IOError(format!("File `{}` not found", path)));
+            }
+            Err(e) => {
+                return Err(Error::IOError(format!("Failed to read {}: {}", path, e)));
+            }
+        };
+        let contents = String::from_utf8(data).map_err(|_| {
+            Error::InvalidSubtitles(name.to_string(), "Not valid UTF-8".to_string())
+        })?;
+
+        Self::parse(name, format, &contents)
+    }
+
+    /// The cues shown at time `t`, in seconds, in start order
+    pub fn active(&self, t: Rational64) -> impl Iterator<Item = &Cue> {
+        // Cues are sorted by start, so only those starting at or before `t` can be active
+        let started = self.cues.partition_point(|cue| cue.start <= t);
+        self.cues[..started].iter().filter(move |cue| t < cue.end)
+    }
+
+    /// The text shown at time `t`, in seconds, with overlapping cues on separate lines
+    pub fn text_at(&self, t: Rational64) -> String {
+        self.active(t)
+            .map(|cue| cue.text.as_str())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Split a file into blocks of non-blank lines, with the 1-based line number each starts at
+fn blocks(contents: &str) -> Vec<(usize, Vec<&str>)> {
+    let mut blocks = Vec::new();
+    let mut current: Option<(usize, Vec<&str>)> = None;
+    for (idx, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            blocks.extend(current.take());
+        } else {
+            current
+                .get_or_insert_with(|| (idx + 1, Vec::new()))
+                .1
+                .push(line);
+        }
+    }
+    blocks.extend(current);
+    blocks
+}
+
+fn is_vtt_header(line: &str) -> bool {
+    line == "WEBVTT" || line.starts_with("WEBVTT ") || line.starts_with("WEBVTT\t")
+}
+
+/// Parse `start --> end [settings]`
+fn parse_timing(line: &str) -> Result<(Rational64, Rational64), String> {
+    let (start, rest) = line.split_once("-->").unwrap();
+    // WebVTT cue settings (e.g., `line:0`) follow the end time; they aren't supported and are ignored
+    let end = rest.split_whitespace().next().unwrap_or("");
+    Ok((parse_timestamp(start.trim())?, parse_timestamp(end)?))
+}
+
+/// Parse `[hh:]mm:ss[.,]mmm` into seconds
+fn parse_timestamp(ts: &str) -> Result<Rational64, String> {
+    let invalid = || format!("Invalid timestamp `{}`", ts);
+    let parts: Vec<&str> = ts.split(':').collect();
+    let (hours, minutes, seconds) = match parts.as_slice() {
+        [m, s] => ("0", *m, *s),
+        [h, m, s] => (*h, *m, *s),
+        _ => return Err(invalid()),
+    };
+    let (whole, frac) = seconds.split_once([',', '.']).ok_or_else(invalid)?;
+
+    let number = |s: &str| -> Result<i64, String> {
+        if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(invalid());
+        }
+        s.parse().map_err(|_| invalid())
+    };
+    let (hours, minutes, whole) = (number(hours)?, number(minutes)?, number(whole)?);
+    if minutes >= 60 || whole >= 60 || frac.is_empty() || frac.len() > 3 {
+        return Err(invalid());
+    }
+    let millis = number(frac)? * 10i64.pow(3 - frac.len() as u32);
+
+    Ok(Rational64::new(
+        ((hours * 60 + minutes) * 60 + whole) * 1000 + millis,
+        1000,
+    ))
+}
+
+/// Remove tags (`<i>`, `<c.yellow>`, `<00:00:01.000>`), SRT override blocks (`{\an8}`), and decode character references
+fn strip_markup(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '<' => {
+                for c in chars.by_ref() {
+                    if c == '>' {
+                        break;
+                    }
+                }
+            }
+            '{' if chars.peek() == Some(&'\\') => {
+                for c in chars.by_ref() {
+                    if c == '}' {
+                        break;
+                    }
+                }
+            }
+            c => out.push(c),
+        }
+    }
+
+    if out.contains('&') {
+        out = out
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&nbsp;", "\u{a0}")
+            .replace("&lrm;", "\u{200e}")
+            .replace("&rlm;", "\u{200f}")
+            .replace("&amp;", "&");
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(n: i64, d: i64) -> Rational64 {
+        Rational64::new(n, d)
+    }
+
+    #[test]
+    fn test_parse_srt() {
+        let srt = "\u{feff}1\r\n00:00:01,000 --> 00:00:02,500\r\nHello <i>there</i>\r\n{\\an8}General Kenobi\r\n\r\n2\r\n00:00:02,000 --> 00:00:04,000\r\nSecond &amp; overlapping\r\n";
+        let track = SubtitleTrack::parse("en", SubtitleFormat::Srt, srt).unwrap();
+        assert_eq!(track.cues.len(), 2);
+        assert_eq!(track.cues[0].start, secs(1, 1));
+        assert_eq!(track.cues[0].end, secs(5, 2));
+        assert_eq!(track.cues[0].text, "Hello there\nGeneral Kenobi");
+        assert_eq!(track.cues[1].text, "Second & overlapping");
+
+        assert_eq!(track.text_at(secs(1, 2)), "");
+        assert_eq!(track.text_at(secs(1, 1)), "Hello there\nGeneral Kenobi");
+        assert_eq!(
+            track.text_at(secs(9, 4)),
+            "Hello there\nGeneral Kenobi\nSecond & overlapping"
+        );
+        // Cues end exclusively
+        assert_eq!(track.text_at(secs(4, 1)), "");
+    }
+
+    #[test]
+    fn test_parse_vtt() {
+        let vtt = "WEBVTT - a title\n\nNOTE a comment\nspanning lines\n\nSTYLE\n::cue { color: red }\n\nintro\n00:01.000 --> 00:02.000 line:0 align:start\n<v Alice>Hi</v>\n\n01:00:00.5 --> 01:00:01.000\nLate\n";
+        let track = SubtitleTrack::parse("en", SubtitleFormat::WebVtt, vtt).unwrap();
+        assert_eq!(track.cues.len(), 2);
+        assert_eq!(track.cues[0].start, secs(1, 1));
+        assert_eq!(track.cues[0].text, "Hi");
+        assert_eq!(track.cues[1].start, secs(3600 * 1000 + 500, 1000));
+
+        assert!(
+            SubtitleTrack::parse("en", SubtitleFormat::WebVtt, "00:01.000 --> 00:02.000\nHi")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for bad in [
+            "1\n00:00:01,000 -> 00:00:02,000\nHi",
+            "1\n00:00:01 --> 00:00:02,000\nHi",
+            "1\n00:00:61,000 --> 00:01:02,000\nHi",
+            "1\n00:00:03,000 --> 00:00:02,000\nHi",
+        ] {
+            assert!(matches!(
+                SubtitleTrack::parse("en", SubtitleFormat::Srt, bad),
+                Err(Error::InvalidSubtitles(..))
+            ));
+        }
+        assert_eq!(
+            SubtitleFormat::from_path("a/b.SRT"),
+            Some(SubtitleFormat::Srt)
+        );
+        assert_eq!(SubtitleFormat::from_path("a.ass"), None);
+    }
+}
@@ -98,6 +98,27 @@ pub(crate) fn pixel_fmt_str_to_av_pix_fmt(s: &str) -> Result<ffi::AVPixelFormat,
     }
 }
 
+/// The size of an image's data, without padding, or None if the pixel format is unknown
+pub(crate) fn image_size_bytes(pix_fmt: &str, width: usize, height: usize) -> Option<usize> {
+    let fmt = pixel_fmt_str_to_av_pix_fmt(pix_fmt).ok()?;
+    let size = unsafe { ffi::av_image_get_buffer_size(fmt, width as i32, height as i32, 1) };
+    usize::try_from(size).ok()
+}
+
+pub(crate) fn av_pix_fmt_to_pixel_fmt_str(fmt: ffi::AVPixelFormat) -> Option<String> {
+    let name = unsafe { ffi::av_get_pix_fmt_name(fmt) };
+    if name.is_null() {
+        None
+    } else {
+        Some(
+            unsafe { CStr::from_ptr(name) }
+                .to_str()
+                .unwrap()
+                .to_string(),
+        )
+    }
+}
+
 pub(crate) fn av_strerror(err: i32) -> String {
     let mut buf = [0u8; 1024];
     unsafe {
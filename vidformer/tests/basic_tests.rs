@@ -93,19 +93,7 @@ fn test_placeholder() {
     let sources = vec![];
     let context: vidformer::Context = vidformer::Context::new(sources, filters, None);
 
-    let dve_config = vidformer::Config {
-        decode_pool_size: 10,
-        decoder_view: usize::MAX,
-        decoders: u16::MAX as usize,
-        filterers: 4,
-
-        output_width: 1920,
-        output_height: 1080,
-        output_pix_fmt: "yuv420p".to_string(),
-
-        encoder: None,
-        format: None,
-    };
+    let dve_config = test_config();
 
     let spec: Box<dyn spec::Spec> = Box::new(MySpec {});
 
@@ -121,6 +109,206 @@ fn test_placeholder() {
     assert!(std::path::Path::new(output_path).exists());
 }
 
+#[test]
+fn test_filter_cache() {
+    struct MySpec {}
+    impl spec::Spec for MySpec {
+        fn timestamps(&self, _context: &dyn spec::SpecContext) -> Vec<num_rational::Rational64> {
+            (0..24 * 3).map(|i| Rational64::new(i, 24)).collect()
+        }
+
+        fn render(
+            &self,
+            _context: &dyn spec::SpecContext,
+            _t: &num_rational::Rational64,
+        ) -> sir::FrameExpr {
+            filter!(PlaceholderFrame; ; width=int!(1920), height=int!(1080))
+        }
+    }
+
+    let context = std::sync::Arc::new(vidformer::Context::new(
+        vec![],
+        filter::builtin::filters(),
+        None,
+    ));
+    let dve_config = std::sync::Arc::new(vidformer::Config {
+        filterers: 1,
+        filter_cache_bytes: 256 * 1024 * 1024,
+        ..test_config()
+    });
+
+    let spec: std::sync::Arc<Box<dyn spec::Spec>> = std::sync::Arc::new(Box::new(MySpec {}));
+    let output_path = test_output_path!(test_filter_cache);
+    let stats = run(&spec, output_path, &context, &dve_config, &None).unwrap();
+    assert_eq!(stats.frames_written, 24 * 3);
+    // Every frame is the same placeholder, so each is either rendered or a filter cache hit
+    let placeholder_stats = &stats.filters["PlaceholderFrame"];
+    assert!(stats.filter_cache_hits > 0);
+    assert_eq!(placeholder_stats.calls + stats.filter_cache_hits, 24 * 3);
+    assert_eq!(
+        placeholder_stats.latency_histogram.iter().sum::<usize>(),
+        placeholder_stats.calls
+    );
+    assert!(stats.bytes_read.is_empty());
+}
+
+#[test]
+fn test_filter_batch() {
+    use vidformer::filter::Filter;
+
+    struct BatchedPlaceholder {
+        batch_sizes: std::sync::Arc<std::sync::Mutex<Vec<usize>>>,
+    }
+    impl Filter for BatchedPlaceholder {
+        fn filter(
+            &self,
+            args: &[filter::Val],
+            kwargs: &BTreeMap<String, filter::Val>,
+        ) -> Result<filter::Frame, Error> {
+            filter::builtin::PlaceholderFrame {}.filter(args, kwargs)
+        }
+
+        fn filter_batch(
+            &self,
+            calls: &[(Vec<filter::Val>, BTreeMap<String, filter::Val>)],
+        ) -> Result<Vec<filter::Frame>, Error> {
+            self.batch_sizes.lock().unwrap().push(calls.len());
+            calls
+                .iter()
+                .map(|(args, kwargs)| self.filter(args, kwargs))
+                .collect()
+        }
+
+        fn filter_type(
+            &self,
+            args: &[filter::Val],
+            kwargs: &BTreeMap<String, filter::Val>,
+        ) -> Result<filter::FrameType, Error> {
+            filter::builtin::PlaceholderFrame {}.filter_type(args, kwargs)
+        }
+    }
+
+    struct MySpec {}
+    impl spec::Spec for MySpec {
+        fn timestamps(&self, _context: &dyn spec::SpecContext) -> Vec<num_rational::Rational64> {
+            (0..24 * 3).map(|i| Rational64::new(i, 24)).collect()
+        }
+
+        fn render(
+            &self,
+            _context: &dyn spec::SpecContext,
+            _t: &num_rational::Rational64,
+        ) -> sir::FrameExpr {
+            filter!(BatchedPlaceholder; ; width=int!(1920), height=int!(1080))
+        }
+    }
+
+    let batch_sizes = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let mut filters: BTreeMap<String, Box<dyn filter::Filter>> = BTreeMap::new();
+    filters.insert(
+        "BatchedPlaceholder".to_string(),
+        Box::new(BatchedPlaceholder {
+            batch_sizes: batch_sizes.clone(),
+        }),
+    );
+    let context = std::sync::Arc::new(vidformer::Context::new(vec![], filters, None));
+
+    let dve_config = std::sync::Arc::new(vidformer::Config {
+        decoders: 1,
+        filterers: 1,
+        ..test_config()
+    });
+
+    let spec: std::sync::Arc<Box<dyn spec::Spec>> = std::sync::Arc::new(Box::new(MySpec {}));
+    let output_path = test_output_path!(test_filter_batch);
+    let stats = run(&spec, output_path, &context, &dve_config, &None).unwrap();
+    assert_eq!(stats.frames_written, 24 * 3);
+    assert_eq!(stats.filters["BatchedPlaceholder"].calls, 24 * 3);
+
+    // Every frame is ready at once, so the lone filterer gets them in full batches
+    assert_eq!(*batch_sizes.lock().unwrap(), vec![16, 16, 16, 16, 8]);
+}
+
+#[test]
+fn test_analyze() {
+    struct MySpec {}
+    impl spec::DataSpec for MySpec {
+        fn timestamps(&self, _context: &dyn spec::SpecContext) -> Vec<num_rational::Rational64> {
+            (0..24).map(|i| Rational64::new(i, 24)).collect()
+        }
+
+        fn render(
+            &self,
+            _context: &dyn spec::SpecContext,
+            _t: &num_rational::Rational64,
+        ) -> sir::FilterExpr {
+            let placeholder = filter!(PlaceholderFrame; ; width=int!(64), height=int!(64));
+            sir::FilterExpr {
+                name: "MeanLuma".to_string(),
+                args: vec![sir::Expr::Frame(placeholder)],
+                kwargs: BTreeMap::new(),
+            }
+        }
+    }
+
+    let context = std::sync::Arc::new(
+        vidformer::Context::new(vec![], filter::builtin::filters(), None)
+            .with_data_filters(filter::builtin::data_filters()),
+    );
+    let dve_config = std::sync::Arc::new(vidformer::Config {
+        decoders: 1,
+        output_width: 64,
+        output_height: 64,
+        ..test_config()
+    });
+
+    let spec: std::sync::Arc<Box<dyn spec::DataSpec>> = std::sync::Arc::new(Box::new(MySpec {}));
+    let analysis = analyze(&spec, &context, &dve_config, &None).unwrap();
+    assert_eq!(analysis.rows.len(), 24);
+    for (i, (t, val)) in analysis.rows.iter().enumerate() {
+        assert_eq!(*t, Rational64::new(i as i64, 24));
+        // The placeholder's luma is (x + y) % 256
+        assert!(matches!(val, filter::Val::Float(mean) if *mean == 63.0));
+    }
+    assert_eq!(analysis.stats.filters["MeanLuma"].calls, 24);
+    assert_eq!(analysis.stats.frames_written, 0);
+}
+
+#[test]
+fn test_no_filterers() {
+    struct MySpec {}
+    impl spec::Spec for MySpec {
+        fn timestamps(&self, _context: &dyn spec::SpecContext) -> Vec<num_rational::Rational64> {
+            vec![Rational64::new(0, 24)]
+        }
+
+        fn render(
+            &self,
+            _context: &dyn spec::SpecContext,
+            _t: &num_rational::Rational64,
+        ) -> sir::FrameExpr {
+            filter!(PlaceholderFrame; ; width=int!(1920), height=int!(1080))
+        }
+    }
+
+    let context = std::sync::Arc::new(vidformer::Context::new(
+        vec![],
+        filter::builtin::filters(),
+        None,
+    ));
+    let dve_config = std::sync::Arc::new(vidformer::Config {
+        filterers: 0,
+        ..test_config()
+    });
+
+    let spec: std::sync::Arc<Box<dyn spec::Spec>> = std::sync::Arc::new(Box::new(MySpec {}));
+    let output_path = test_output_path!(test_no_filterers);
+    assert!(matches!(
+        run(&spec, output_path, &context, &dve_config, &None),
+        Err(Error::ConfigError(..))
+    ));
+}
+
 #[test]
 fn test_bad_resolution() {
     struct MySpec {}
@@ -147,19 +335,7 @@ fn test_bad_resolution() {
     let sources = vec![];
     let context: vidformer::Context = vidformer::Context::new(sources, filters, None);
 
-    let dve_config = vidformer::Config {
-        decode_pool_size: 10,
-        decoder_view: usize::MAX,
-        decoders: u16::MAX as usize,
-        filterers: 4,
-
-        output_width: 1920,
-        output_height: 1080,
-        output_pix_fmt: "yuv420p".to_string(),
-
-        encoder: None,
-        format: None,
-    };
+    let dve_config = test_config();
 
     let spec: Box<dyn spec::Spec> = Box::new(MySpec {});
 
@@ -204,19 +380,7 @@ fn test_non_existant_source() {
     let sources = vec![];
     let context: vidformer::Context = vidformer::Context::new(sources, filters, None);
 
-    let dve_config = vidformer::Config {
-        decode_pool_size: 10,
-        decoder_view: usize::MAX,
-        decoders: u16::MAX as usize,
-        filterers: 4,
-
-        output_width: 1920,
-        output_height: 1080,
-        output_pix_fmt: "yuv420p".to_string(),
-
-        encoder: None,
-        format: None,
-    };
+    let dve_config = test_config();
 
     let spec: Box<dyn spec::Spec> = Box::new(MySpec {});
 
@@ -263,32 +427,13 @@ fn test_no_source_file() {
 
     let sources = vec![source::SourceVideoStreamMeta {
         name: "gone".to_string(),
-        codec: "h264".to_string(),
-        stream_idx: 0,
-        service: vidformer::service::Service::default(),
-        file_size: 4 * 1024 * 1024,
-        resolution: (1920, 1080),
-        pix_fmt: "yuv420p".to_string(),
         ts: vec![Rational64::new(0, 1)],
         keys: vec![Rational64::new(0, 1)],
-        file_path: "something_fake.mp4".to_string(),
-        fuid: None,
+        ..fake_source_meta()
     }];
     let context: vidformer::Context = vidformer::Context::new(sources, filters, None);
 
-    let dve_config = vidformer::Config {
-        decode_pool_size: 10,
-        decoder_view: usize::MAX,
-        decoders: u16::MAX as usize,
-        filterers: 4,
-
-        output_width: 1920,
-        output_height: 1080,
-        output_pix_fmt: "yuv420p".to_string(),
-
-        encoder: None,
-        format: None,
-    };
+    let dve_config = test_config();
 
     let spec: Box<dyn spec::Spec> = Box::new(MySpec {});
 
@@ -349,6 +494,43 @@ fn test_audio_stream_error() {
     assert!(err_msg.contains("audio stream"));
 }
 
+/// A source which is never decoded: 8 frames of 1920x1080 h264 at 24 fps, with keyframes at 0 and 4/24
+fn fake_source_meta() -> source::SourceVideoStreamMeta {
+    source::SourceVideoStreamMeta {
+        name: "src".to_string(),
+        codec: "h264".to_string(),
+        stream_idx: 0,
+        service: vidformer::service::Service::default(),
+        file_size: 8 * 1024 * 1024,
+        resolution: (1920, 1080),
+        pix_fmt: "yuv420p".to_string(),
+        ts: (0..8).map(|i| Rational64::new(i, 24)).collect(),
+        keys: vec![Rational64::new(0, 1), Rational64::new(4, 24)],
+        file_path: "something_fake.mp4".to_string(),
+        fuid: None,
+        timecode: None,
+    }
+}
+
+/// A config for 1920x1080 yuv420p output, for tests to override the fields they exercise
+fn test_config() -> vidformer::Config {
+    vidformer::Config {
+        decode_pool_size: 10,
+        decode_pool_bytes: None,
+        decoder_view: usize::MAX,
+        decoders: u16::MAX as usize,
+        filterers: 4,
+        filter_cache_bytes: 0,
+
+        output_width: 1920,
+        output_height: 1080,
+        output_pix_fmt: "yuv420p".to_string(),
+
+        encoder: None,
+        format: None,
+    }
+}
+
 fn tos_context() -> std::sync::Arc<vidformer::Context> {
     let fs_service = vidformer::service::Service::default();
 
@@ -369,17 +551,10 @@ fn tos_context() -> std::sync::Arc<vidformer::Context> {
 fn test_tos_transcode_1dec() {
     let context = tos_context();
     let dve_config = std::sync::Arc::new(vidformer::Config {
-        decode_pool_size: 10,
-        decoder_view: usize::MAX,
         decoders: 1,
-        filterers: 4,
-
         output_width: 1280,
         output_height: 720,
-        output_pix_fmt: "yuv420p".to_string(),
-
-        encoder: None,
-        format: None,
+        ..test_config()
     });
     let spec: std::sync::Arc<Box<dyn spec::Spec>> = std::sync::Arc::new(Box::new(ClipSpec {
         num_frames: NUM_FRAMES,
@@ -390,6 +565,8 @@ fn test_tos_transcode_1dec() {
     assert_eq!(stats.max_decoder_count, 1);
     assert_eq!(stats.frames_written, NUM_FRAMES as usize);
     assert!(stats.frames_decoded >= NUM_FRAMES as usize);
+    assert!(stats.bytes_read["tos"] > 0);
+    assert!(stats.decode.busy > std::time::Duration::ZERO);
 
     assert!(std::path::Path::new(output_path).exists());
 }
@@ -398,17 +575,10 @@ fn test_tos_transcode_1dec() {
 fn test_tos_transcode_2dec() {
     let context = tos_context();
     let dve_config = std::sync::Arc::new(vidformer::Config {
-        decode_pool_size: 10,
-        decoder_view: usize::MAX,
         decoders: 2,
-        filterers: 4,
-
         output_width: 1280,
         output_height: 720,
-        output_pix_fmt: "yuv420p".to_string(),
-
-        encoder: None,
-        format: None,
+        ..test_config()
     });
     let spec: std::sync::Arc<Box<dyn spec::Spec>> = std::sync::Arc::new(Box::new(ClipSpec {
         num_frames: NUM_FRAMES,
@@ -427,17 +597,10 @@ fn test_tos_transcode_2dec() {
 fn test_tos_transcode_4dec() {
     let context = tos_context();
     let dve_config = std::sync::Arc::new(vidformer::Config {
-        decode_pool_size: 10,
-        decoder_view: usize::MAX,
         decoders: 4,
-        filterers: 4,
-
         output_width: 1280,
         output_height: 720,
-        output_pix_fmt: "yuv420p".to_string(),
-
-        encoder: None,
-        format: None,
+        ..test_config()
     });
     let spec: std::sync::Arc<Box<dyn spec::Spec>> = std::sync::Arc::new(Box::new(ClipSpec {
         num_frames: NUM_FRAMES,
@@ -456,17 +619,10 @@ fn test_tos_transcode_4dec() {
 fn test_tos_transcode_manydec() {
     let context = tos_context();
     let dve_config = std::sync::Arc::new(vidformer::Config {
-        decode_pool_size: 10,
-        decoder_view: usize::MAX,
         decoders: 100,
-        filterers: 4,
-
         output_width: 1280,
         output_height: 720,
-        output_pix_fmt: "yuv420p".to_string(),
-
-        encoder: None,
-        format: None,
+        ..test_config()
     });
     let spec: std::sync::Arc<Box<dyn spec::Spec>> = std::sync::Arc::new(Box::new(ClipSpec {
         num_frames: NUM_FRAMES,
@@ -486,16 +642,10 @@ fn test_tos_transcode_1dec_1pool() {
     let context = tos_context();
     let dve_config = std::sync::Arc::new(vidformer::Config {
         decode_pool_size: 1,
-        decoder_view: usize::MAX,
         decoders: 1,
-        filterers: 4,
-
         output_width: 1280,
         output_height: 720,
-        output_pix_fmt: "yuv420p".to_string(),
-
-        encoder: None,
-        format: None,
+        ..test_config()
     });
     let spec: std::sync::Arc<Box<dyn spec::Spec>> =
         std::sync::Arc::new(Box::new(ClipSpec { num_frames: 2 * 24 })); // make sure we only need one source GOP
@@ -588,17 +738,10 @@ fn test_tos_io_wrapper() {
     seek_counter.store(0, std::sync::atomic::Ordering::SeqCst);
 
     let dve_config = std::sync::Arc::new(vidformer::Config {
-        decode_pool_size: 10,
-        decoder_view: usize::MAX,
         decoders: 2,
-        filterers: 4,
-
         output_width: 1280,
         output_height: 720,
-        output_pix_fmt: "yuv420p".to_string(),
-
-        encoder: None,
-        format: None,
+        ..test_config()
     });
     let spec: std::sync::Arc<Box<dyn spec::Spec>> = std::sync::Arc::new(Box::new(ClipSpec {
         num_frames: NUM_FRAMES,
@@ -611,3 +754,499 @@ fn test_tos_io_wrapper() {
     assert!(read_counter.load(std::sync::atomic::Ordering::SeqCst) > 0);
     assert!(seek_counter.load(std::sync::atomic::Ordering::SeqCst) > 0);
 }
+
+#[test]
+fn test_optimizer() {
+    let mut filters = filter::builtin::filters();
+    filters.extend(filter::cv2::filters());
+
+    let sources = vec![fake_source_meta()];
+    let context = std::sync::Arc::new(vidformer::Context::new(sources.clone(), filters, None));
+
+    let dve_config = std::sync::Arc::new(vidformer::Config {
+        filter_cache_bytes: 256 * 1024 * 1024,
+        ..test_config()
+    });
+
+    let frames = [
+        // No-op scale
+        (
+            "Scale(src.iloc[0], width=1920, height=1080)",
+            "src[0]",
+        ),
+        // Scaling twice is kept, since it doesn't give the same pixels as scaling once
+        (
+            "Scale(Scale(src.iloc[1], width=3840, height=2160), width=1920, height=1080)",
+            "Scale(Scale(src[1/24], height=2160, width=3840, ), height=1080, width=1920, )",
+        ),
+        // Shrink-then-grow is kept
+        (
+            "Scale(Scale(src.iloc[2], width=16, height=9), width=1920, height=1080)",
+            "Scale(Scale(src[1/12], height=9, width=16, ), height=1080, width=1920, )",
+        ),
+        // A filled rectangle over the whole frame doesn't need the source
+        (
+            "Scale(cv2.rectangle(Scale(src.iloc[3], pix_fmt=\"rgb24\"), [0, 0], [1919, 1079], [0.0, 0.0, 0.0, 0.0], -1), pix_fmt=\"yuv420p\")",
+            "Scale(cv2.rectangle(_black(height=1080, pix_fmt=\"rgb24\", width=1920, ), [0, 0], [1919, 1079], [0.0, 0.0, 0.0, 0.0], -1, ), pix_fmt=\"yuv420p\", )",
+        ),
+        // Unless its edges are anti-aliased
+        (
+            "Scale(cv2.rectangle(Scale(src.iloc[3], pix_fmt=\"rgb24\"), [0, 0], [1919, 1079], [0.0, 0.0, 0.0, 0.0], -1, 16), pix_fmt=\"yuv420p\")",
+            "Scale(cv2.rectangle(Scale(src[1/8], pix_fmt=\"rgb24\", ), [0, 0], [1919, 1079], [0.0, 0.0, 0.0, 0.0], -1, 16, ), pix_fmt=\"yuv420p\", )",
+        ),
+    ];
+
+    let spec: Box<dyn spec::Spec> = Box::new(spec::JsonSpec {
+        frames: frames
+            .iter()
+            .enumerate()
+            .map(|(i, (frame, _))| (Rational64::new(i as i64, 24), frame.parse().unwrap()))
+            .collect(),
+    });
+    let spec = std::sync::Arc::new(spec);
+
+    let optimized = optimized_spec(&spec, &context, &dve_config).unwrap();
+    for ((_, frame), (_, expected)) in optimized.frames.iter().zip(frames.iter()) {
+        assert_eq!(frame.to_string(), *expected);
+    }
+
+    // Rules don't apply to other filters registered under a builtin's name
+    struct OtherScale;
+    impl filter::Filter for OtherScale {
+        fn filter(
+            &self,
+            args: &[filter::Val],
+            kwargs: &BTreeMap<String, filter::Val>,
+        ) -> Result<filter::Frame, Error> {
+            filter::builtin::Scale {}.filter(args, kwargs)
+        }
+
+        fn filter_type(
+            &self,
+            args: &[filter::Val],
+            kwargs: &BTreeMap<String, filter::Val>,
+        ) -> Result<filter::FrameType, Error> {
+            filter::builtin::Scale {}.filter_type(args, kwargs)
+        }
+    }
+    let mut filters = filter::builtin::filters();
+    filters.insert("Scale".to_string(), Box::new(OtherScale));
+    let context = std::sync::Arc::new(vidformer::Context::new(sources, filters, None));
+    let spec: Box<dyn spec::Spec> = Box::new(spec::JsonSpec {
+        frames: vec![(Rational64::new(0, 1), frames[0].0.parse().unwrap())],
+    });
+    let optimized = optimized_spec(&std::sync::Arc::new(spec), &context, &dve_config).unwrap();
+    assert_eq!(
+        optimized.frames[0].1.to_string(),
+        "Scale(src[0], height=1080, width=1920, )"
+    );
+}
+
+#[test]
+fn test_explain() {
+    let mut filters = filter::builtin::filters();
+    filters.extend(filter::cv2::filters());
+
+    let sources = vec![fake_source_meta()];
+    let context = std::sync::Arc::new(vidformer::Context::new(sources, filters, None));
+
+    let dve_config = std::sync::Arc::new(vidformer::Config {
+        decoders: 1,
+        filter_cache_bytes: 256 * 1024 * 1024,
+        ..test_config()
+    });
+
+    let titled = "Scale(cv2.putText(Scale(src[0], pix_fmt=\"rgb24\"), \"title\", [10, 10], 0, 1.0, [255.0, 0.0, 0.0, 0.0]), pix_fmt=\"yuv420p\")";
+    let frames = [titled, titled, "src[5/24]"];
+    let spec: Box<dyn spec::Spec> = Box::new(spec::JsonSpec {
+        frames: frames
+            .iter()
+            .enumerate()
+            .map(|(i, frame)| (Rational64::new(i as i64, 24), frame.parse().unwrap()))
+            .collect(),
+    });
+    let spec = std::sync::Arc::new(spec);
+
+    let plan = explain(&spec, &context, &dve_config, &None).unwrap();
+    assert_eq!(plan.frames, 3);
+    assert_eq!(plan.sources["src"].gops, vec![0, 1]);
+    // The first GOP is abandoned after one unused frame, the second is decoded up to 5/24
+    assert_eq!(plan.frames_decoded, 4);
+    assert_eq!(plan.frames_used, 2);
+    assert_eq!(plan.max_pool_size, 3);
+    assert_eq!(plan.max_pool_bytes, 3 * 1920 * 1080 * 3 / 2);
+    assert_eq!(plan.max_decoder_count, 1);
+    assert_eq!(plan.decoders_created, 2);
+    assert_eq!(plan.bytes_read, 8 * 1024 * 1024);
+    // The second titled frame comes from the filter cache
+    assert_eq!(plan.filter_calls["Scale"], 2);
+    assert_eq!(plan.filter_calls["cv2.putText"], 1);
+    assert_eq!(plan.filter_cache_hits, 1);
+}
+
+#[test]
+fn test_explain_pinned_filter() {
+    let mut filters = filter::builtin::filters();
+    filters.extend(filter::cv2::filters());
+
+    let sources = vec![fake_source_meta()];
+    let context = std::sync::Arc::new(vidformer::Context::new(sources, filters, None));
+
+    let titled = |title: &str| {
+        format!(
+            "cv2.putText(Scale(src[0], pix_fmt=\"rgb24\"), \"{}\", [10, 10], 0, 1.0, [255.0, 0.0, 0.0, 0.0])",
+            title
+        )
+    };
+    // The pool only has room for one frame, so src[0] is gone by the time the last frame is rendered
+    let frames = [
+        titled("a"),
+        "Scale(src[5/24], pix_fmt=\"rgb24\")".to_string(),
+        titled("b"),
+    ];
+    let spec: Box<dyn spec::Spec> = Box::new(spec::JsonSpec {
+        frames: frames
+            .iter()
+            .enumerate()
+            .map(|(i, frame)| (Rational64::new(i as i64, 24), frame.parse().unwrap()))
+            .collect(),
+    });
+    let spec = std::sync::Arc::new(spec);
+
+    let explain_with_cache = |filter_cache_bytes: usize| {
+        let dve_config = std::sync::Arc::new(vidformer::Config {
+            decode_pool_size: 1,
+            decoders: 1,
+            filterers: 1,
+            filter_cache_bytes,
+            output_pix_fmt: "rgb24".to_string(),
+            ..test_config()
+        });
+        explain(&spec, &context, &dve_config, &None).unwrap()
+    };
+
+    let uncached = explain_with_cache(0);
+    let pinned = explain_with_cache(256 * 1024 * 1024);
+    // The last frame takes the scaled src[0] from the cache, so it isn't decoded again
+    assert!(pinned.frames_decoded < uncached.frames_decoded);
+    assert_eq!(pinned.frames_used, 2);
+    assert_eq!(uncached.frames_used, 2);
+    assert_eq!(pinned.sources["src"].gops, vec![0, 1]);
+    assert_eq!(pinned.filter_calls["Scale"], 2);
+    assert_eq!(pinned.filter_cache_hits, 1);
+}
+
+#[test]
+fn test_filter_signature_check() {
+    let mut filters = filter::builtin::filters();
+    filters.extend(filter::cv2::filters());
+
+    let signatures = filter::signatures(&filters);
+    let circle = &signatures["cv2.circle"];
+    assert_eq!(circle.params[0].name, "img");
+    assert_eq!(circle.params[0].ty, filter::ParamType::Frame);
+    assert!(!circle.params[4].required);
+
+    let sources = vec![fake_source_meta()];
+    let context = std::sync::Arc::new(vidformer::Context::new(sources, filters, None));
+
+    let dve_config = std::sync::Arc::new(vidformer::Config {
+        decoders: 1,
+        filterers: 1,
+        output_pix_fmt: "rgb24".to_string(),
+        ..test_config()
+    });
+
+    let explain_frame = |frame: &str| {
+        let spec: Box<dyn spec::Spec> = Box::new(spec::JsonSpec {
+            frames: vec![(Rational64::new(0, 1), frame.parse().unwrap())],
+        });
+        explain(&std::sync::Arc::new(spec), &context, &dve_config, &None)
+    };
+
+    let circle = "cv2.circle(Scale(src[0], pix_fmt=\"rgb24\"), [10, 10], 5, [255.0, 0.0, 0.0, 0.0]";
+    assert!(explain_frame(&format!("{}, thickness=2)", circle)).is_ok());
+    // A misspelled keyword is caught while type checking
+    assert!(matches!(
+        explain_frame(&format!("{}, thicknes=2)", circle)),
+        Err(vidformer::Error::InvalidFilterArgValue(..))
+    ));
+    assert!(matches!(
+        explain_frame(&format!("{}, thickness=2.0)", circle)),
+        Err(vidformer::Error::InvalidFilterArgType(..))
+    ));
+}
+
+#[test]
+fn test_subtitle_burn_in() {
+    let srt = "1\n00:00:00,000 --> 00:00:00,125\nHello\n\n2\n00:00:00,100 --> 00:00:01,000\n<i>World</i>\n";
+    let track = vidformer::subtitle::SubtitleTrack::parse(
+        "en",
+        vidformer::subtitle::SubtitleFormat::Srt,
+        srt,
+    )
+    .unwrap();
+    assert_eq!(track.text_at(Rational64::new(11, 100)), "Hello\nWorld");
+    // Cues end exclusively
+    assert_eq!(track.text_at(Rational64::new(3, 24)), "World");
+
+    let sources = vec![fake_source_meta()];
+    let fonts = vidformer::filter::text::FontStore::new(vidformer::service::Service::new(
+        "fs".to_string(),
+        [(
+            "root".to_string(),
+            concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fonts").to_string(),
+        )]
+        .into_iter()
+        .collect(),
+    ));
+    let context = std::sync::Arc::new(
+        vidformer::Context::new(sources, filter::builtin::filters(), None)
+            .with_subtitles(vec![std::sync::Arc::new(track)], std::sync::Arc::new(fonts)),
+    );
+
+    let dve_config = std::sync::Arc::new(vidformer::Config {
+        decoders: 1,
+        filterers: 1,
+        output_pix_fmt: "rgb24".to_string(),
+        ..test_config()
+    });
+
+    let explain_frames = |track: &str| {
+        let spec: Box<dyn spec::Spec> = Box::new(spec::JsonSpec {
+            frames: (0..4)
+                .map(|i| {
+                    let frame = format!(
+                        "Subtitle(Scale(src[{}/24], pix_fmt=\"rgb24\"), \"{}\", [{}, 24], font=\"DejaVuSans.ttf\")",
+                        i, track, i
+                    );
+                    (Rational64::new(i, 24), frame.parse().unwrap())
+                })
+                .collect(),
+        });
+        explain(&std::sync::Arc::new(spec), &context, &dve_config, &None)
+    };
+
+    let plan = explain_frames("en").unwrap();
+    assert_eq!(plan.filter_calls["Subtitle"], 4);
+    // Unknown tracks are reported while type checking
+    assert!(matches!(
+        explain_frames("fr"),
+        Err(vidformer::Error::AVError(..))
+    ));
+}
+
+#[test]
+fn test_decode_pool_bytes() {
+    let sources = vec![fake_source_meta()];
+    let context = std::sync::Arc::new(vidformer::Context::new(
+        sources,
+        filter::builtin::filters(),
+        None,
+    ));
+
+    let spec: Box<dyn spec::Spec> = Box::new(spec::JsonSpec {
+        frames: ["src[0]", "src[0]", "src[5/24]"]
+            .iter()
+            .enumerate()
+            .map(|(i, frame)| (Rational64::new(i as i64, 24), frame.parse().unwrap()))
+            .collect(),
+    });
+    let spec = std::sync::Arc::new(spec);
+
+    let config = |decode_pool_bytes| {
+        std::sync::Arc::new(vidformer::Config {
+            decode_pool_bytes,
+            decoders: 2,
+            ..test_config()
+        })
+    };
+    let frame_bytes = 1920 * 1080 * 3 / 2;
+
+    let plan = explain(&spec, &context, &config(None), &None).unwrap();
+    assert_eq!(plan.max_decoder_count, 2);
+    assert_eq!(plan.max_pool_bytes, plan.max_pool_size * frame_bytes);
+
+    // Only one frame fits, so the second decoder waits for the first to be abandoned
+    let plan = explain(&spec, &context, &config(Some(frame_bytes)), &None).unwrap();
+    assert_eq!(plan.max_decoder_count, 1);
+    assert_eq!(plan.decoders_created, 2);
+    assert_eq!(plan.max_pool_bytes, frame_bytes);
+
+    let err = explain(&spec, &context, &config(Some(frame_bytes - 1)), &None);
+    assert!(matches!(err, Err(Error::ConfigError(_))));
+}
+
+#[test]
+fn test_auto_config() {
+    let sources = vec![fake_source_meta()];
+    let context = std::sync::Arc::new(vidformer::Context::new(
+        sources,
+        filter::builtin::filters(),
+        None,
+    ));
+
+    let spec: Box<dyn spec::Spec> = Box::new(spec::JsonSpec {
+        frames: (0..8)
+            .map(|i| {
+                let t = Rational64::new(i, 24);
+                (
+                    t,
+                    sir::FrameExpr::Source(FrameSource::new(
+                        "src".to_string(),
+                        sir::IndexConst::T(t),
+                    )),
+                )
+            })
+            .collect(),
+    });
+    let spec = std::sync::Arc::new(spec);
+    let frame_bytes = 1920 * 1080 * 3 / 2;
+
+    let resources = Resources {
+        cores: 4,
+        memory_bytes: None,
+    };
+    let (config, rationale) = Config::auto(spec.as_ref().as_ref(), &context, &resources).unwrap();
+    assert!(!rationale.is_empty());
+    assert_eq!(
+        (
+            config.output_width,
+            config.output_height,
+            config.output_pix_fmt.as_str()
+        ),
+        (1920, 1080, "yuv420p")
+    );
+    // Two GOPs, so two decoders; no filters, so one filterer
+    assert_eq!(config.decoders, 2);
+    assert_eq!(config.filterers, 1);
+    assert_eq!(config.decoder_view, 2);
+    assert_eq!(config.decode_pool_size, 10);
+    assert_eq!(config.decode_pool_bytes, None);
+    explain(&spec, &context, &std::sync::Arc::new(config), &None).unwrap();
+
+    // Memory for ten frames shrinks the pool
+    let resources = Resources {
+        cores: 4,
+        memory_bytes: Some(10 * frame_bytes),
+    };
+    let (config, _) = Config::auto(spec.as_ref().as_ref(), &context, &resources).unwrap();
+    assert_eq!(config.decode_pool_size, 4);
+    assert_eq!(config.decoders, 2);
+    assert_eq!(config.filter_cache_bytes, 10 * frame_bytes / 8);
+    assert_eq!(
+        config.decode_pool_bytes,
+        Some(10 * frame_bytes - 10 * frame_bytes / 8 - 2 * frame_bytes)
+    );
+    explain(&spec, &context, &std::sync::Arc::new(config), &None).unwrap();
+
+    let resources = Resources {
+        cores: 4,
+        memory_bytes: Some(frame_bytes),
+    };
+    let err = Config::auto(spec.as_ref().as_ref(), &context, &resources);
+    assert!(matches!(err, Err(Error::ConfigError(_))));
+}
+
+#[test]
+fn test_frame_cache() {
+    use vidformer::filter::Filter;
+    use vidformer::frame_cache::FrameCache;
+
+    let sources = vec![source::SourceVideoStreamMeta {
+        fuid: Some("src-fuid".to_string()),
+        ..fake_source_meta()
+    }];
+
+    // Another run already decoded the first frame
+    let frame_cache =
+        std::sync::Arc::new(vidformer::frame_cache::LruFrameCache::new(64 * 1024 * 1024));
+    let kwargs = BTreeMap::from([
+        ("width".to_string(), filter::Val::Int(1920)),
+        ("height".to_string(), filter::Val::Int(1080)),
+    ]);
+    let frame = filter::builtin::PlaceholderFrame {}
+        .filter(&[], &kwargs)
+        .unwrap();
+    frame_cache.insert("src-fuid", &Rational64::new(0, 1), frame);
+
+    let context = std::sync::Arc::new(
+        vidformer::Context::new(sources, filter::builtin::filters(), None)
+            .with_frame_cache(frame_cache),
+    );
+    let dve_config = std::sync::Arc::new(vidformer::Config {
+        decoders: 1,
+        ..test_config()
+    });
+
+    let spec: Box<dyn spec::Spec> = Box::new(spec::JsonSpec {
+        frames: ["src[0]", "src[5/24]"]
+            .iter()
+            .enumerate()
+            .map(|(i, frame)| (Rational64::new(i as i64, 24), frame.parse().unwrap()))
+            .collect(),
+    });
+    let spec = std::sync::Arc::new(spec);
+
+    let plan = explain(&spec, &context, &dve_config, &None).unwrap();
+    assert_eq!(plan.frame_cache_hits, 1);
+    assert_eq!(plan.sources["src"].gops, vec![1]);
+    assert_eq!(plan.frames_decoded, 2);
+}
+
+#[test]
+fn test_tos_decoder_registry() {
+    let fs_service = vidformer::service::Service::default();
+    let mut source =
+        source::SourceVideoStreamMeta::profile("tos", "../tos_720p.mp4", 0, &fs_service, None)
+            .unwrap();
+    source.fuid = Some("tos-fuid".to_string());
+
+    // Split the output partway through the first GOP
+    let gop_end = source.ts.binary_search(&source.keys[1]).unwrap();
+    let split = gop_end / 2;
+    let ts = source.ts.clone();
+
+    let registry = std::sync::Arc::new(vidformer::decoder_registry::DecoderRegistry::new(4));
+    let context = std::sync::Arc::new(
+        vidformer::Context::new(vec![source], BTreeMap::new(), None)
+            .with_decoder_registry(registry.clone()),
+    );
+    let dve_config = std::sync::Arc::new(vidformer::Config {
+        decoders: 1,
+        filter_cache_bytes: 256 * 1024 * 1024,
+        output_width: 1280,
+        output_height: 720,
+        ..test_config()
+    });
+    let spec: std::sync::Arc<Box<dyn spec::Spec>> = std::sync::Arc::new(Box::new(ClipSpec {
+        num_frames: NUM_FRAMES,
+    }));
+
+    let range = |start: usize, end: usize| {
+        Some(Range {
+            start: ts[start],
+            end: ts[end],
+            ts_format: RangeTsFormat::StreamLocal,
+        })
+    };
+    let output_path = test_output_path!(test_tos_decoder_registry);
+
+    let stats = run(&spec, output_path, &context, &dve_config, &range(0, split)).unwrap();
+    assert_eq!(stats.decoders_resumed, 0);
+    assert_eq!(registry.len(), 1);
+
+    let stats = run(
+        &spec,
+        output_path,
+        &context,
+        &dve_config,
+        &range(split + 1, split + 2),
+    )
+    .unwrap();
+    assert_eq!(stats.decoders_resumed, 1);
+    assert_eq!(stats.decoders_created, 1);
+    assert_eq!(stats.frames_written, 2);
+    // Resuming skips decoding from the keyframe
+    assert!(stats.frames_decoded < split);
+}
@@ -0,0 +1,88 @@
+/*
+ * Filter plugin used by vidformer's plugin tests, compiled by build.rs
+ *
+ * TestInvert(frame, fail=false) inverts the first plane of a frame. It rejects
+ * anything but a single positional frame, and fails at render time if fail is set.
+ * Defining TEST_ABI_VERSION builds a plugin reporting another ABI version.
+ */
+
+#include <stdio.h>
+#include <string.h>
+
+#include "vidformer_filter_plugin.h"
+
+#ifndef TEST_ABI_VERSION
+#define TEST_ABI_VERSION VF_ABI_VERSION
+#endif
+
+static uint8_t invert_max = 255;
+
+static int32_t check_args(const VfArg *args, size_t n_args, const VfVal **frame,
+                          int *fail, char *err, size_t err_cap) {
+    *frame = NULL;
+    *fail = 0;
+    for (size_t i = 0; i < n_args; i++) {
+        if (args[i].name == NULL) {
+            if (*frame != NULL || (args[i].val.tag != VF_VAL_FRAME &&
+                                   args[i].val.tag != VF_VAL_FRAME_TYPE)) {
+                snprintf(err, err_cap, "TestInvert takes a single frame");
+                return 1;
+            }
+            *frame = &args[i].val;
+        } else if (strcmp(args[i].name, "fail") == 0 && args[i].val.tag == VF_VAL_BOOL) {
+            *fail = args[i].val.int_val != 0;
+        } else {
+            snprintf(err, err_cap, "TestInvert got an unexpected argument `%s`", args[i].name);
+            return 1;
+        }
+    }
+    if (*frame == NULL) {
+        snprintf(err, err_cap, "TestInvert takes a single frame");
+        return 1;
+    }
+    return 0;
+}
+
+static int32_t invert_type(void *state, const VfArg *args, size_t n_args,
+                           VfFrameType *out, char *err, size_t err_cap) {
+    const VfVal *frame;
+    int fail;
+    (void)state;
+    if (check_args(args, n_args, &frame, &fail, err, err_cap) != 0) {
+        return 1;
+    }
+    *out = frame->frame.frame_type;
+    return 0;
+}
+
+static int32_t invert(void *state, const VfArg *args, size_t n_args, VfFrame *out,
+                      char *err, size_t err_cap) {
+    const VfVal *frame;
+    int fail;
+    uint8_t max = *(const uint8_t *)state;
+    if (check_args(args, n_args, &frame, &fail, err, err_cap) != 0) {
+        return 1;
+    }
+    if (fail) {
+        snprintf(err, err_cap, "failing as asked");
+        return 1;
+    }
+
+    const VfFrame *in = &frame->frame;
+    for (int32_t y = 0; y < in->frame_type.height; y++) {
+        const uint8_t *src = in->data[0] + (ptrdiff_t)y * in->linesize[0];
+        uint8_t *dst = out->data[0] + (ptrdiff_t)y * out->linesize[0];
+        for (int32_t x = 0; x < in->frame_type.width; x++) {
+            dst[x] = max - src[x];
+        }
+    }
+    return 0;
+}
+
+static const VfFilterDef filters[] = {
+    {"TestInvert", &invert_max, invert_type, invert},
+};
+
+static const VfPlugin plugin = {TEST_ABI_VERSION, filters, sizeof(filters) / sizeof(filters[0])};
+
+const VfPlugin *vidformer_filter_plugin(void) { return &plugin; }